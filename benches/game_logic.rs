@@ -4,8 +4,10 @@ use tetris_adapter::adapter::server::build_observation;
 use tetris_adapter_protocol::protocol::parse_message;
 use tetris_core::core::{Board, GameSnapshot, GameState};
 use tetris_core::types::{GameAction, PieceKind};
+use tetris_session::engine::env::{ActionSpace, LinesReward, TetrisEnv};
 use tetris_session::engine::replay::transition_hash;
 use tetris_session::engine::session::{SessionRuntime, StepInput};
+use tetris_session::engine::vec_env::{OBS_LEN, VecEnv, VecStepResult};
 use tetris_terminal::term::{FrameBuffer, GameView, TerminalRenderer, Viewport, encode_diff_into};
 
 #[derive(Default)]
@@ -63,6 +65,37 @@ fn bench_session_command_batch(c: &mut Criterion) {
     });
 }
 
+fn bench_env_tick_step(c: &mut Criterion) {
+    let template = TetrisEnv::new(12345, ActionSpace::Tick, LinesReward);
+
+    c.bench_function("env_tick_step_16ms", |b| {
+        b.iter_batched(
+            || template.clone(),
+            |mut env| {
+                let step = env.step_index(black_box(1));
+                black_box((step.reward, step.terminated));
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_vec_env_step(c: &mut Criterion) {
+    let seeds: Vec<u32> = (0..64).collect();
+    let mut env = VecEnv::new(&seeds, ActionSpace::Tick, 4, LinesReward);
+    let mut obs = vec![0.0; seeds.len() * OBS_LEN];
+    let mut results = vec![VecStepResult::default(); seeds.len()];
+    let actions = vec![1; seeds.len()];
+    env.reset(&mut obs);
+
+    c.bench_function("vec_env_step_64_envs", |b| {
+        b.iter(|| {
+            env.step(black_box(&actions), &mut obs, &mut results);
+            black_box((&obs, &results));
+        })
+    });
+}
+
 fn bench_transition_hash(c: &mut Criterion) {
     let session = SessionRuntime::new(12345);
     c.bench_function("transition_hash", |b| {
@@ -384,6 +417,8 @@ criterion_group!(
     benches,
     bench_tick,
     bench_session_command_batch,
+    bench_env_tick_step,
+    bench_vec_env_step,
     bench_transition_hash,
     bench_line_clear,
     bench_snapshot_meta_into,
@@ -0,0 +1,653 @@
+//! Compact binary framing for game -> client messages.
+//!
+//! Negotiated by listing `binary` in `hello.formats`. The handshake stays JSON;
+//! after the welcome line every server message is one frame:
+//!
+//! ```text
+//! u32 LE payload length | u8 frame kind | payload
+//! ```
+//!
+//! The length counts the kind byte and payload. All integers are little-endian
+//! and fixed width. The board is a 20-row `u16` occupancy bitmask followed by
+//! one 4-bit piece kind per occupied cell in row-major order, so a frame
+//! decodes back into exactly the message the JSON form describes.
+
+use arrayvec::ArrayVec;
+
+use crate::protocol::{
+    AckMessage, AckStatus, AckType, ActivePieceSnapshot, BoardSnapshot, ErrorCode, ErrorMessage,
+    ErrorType, EventList, ObservationMessage, ObservationType, PieceKindLower, RotationLower,
+    StateHash, TSpinLower, TimersSnapshot, TransitionEvent,
+};
+
+/// Length prefix plus kind byte.
+pub const FRAME_HEADER_LEN: usize = 5;
+
+const BOARD_WIDTH: usize = 10;
+const BOARD_HEIGHT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum FrameKind {
+    Observation = 1,
+    Ack = 2,
+    Error = 3,
+}
+
+impl FrameKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Observation),
+            2 => Some(Self::Ack),
+            3 => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded binary frame.
+#[derive(Debug, Clone)]
+// Keeping observations inline avoids an extra heap allocation on every decoded frame.
+#[allow(clippy::large_enum_variant)]
+pub enum BinaryMessage {
+    Observation(ObservationMessage),
+    Ack(AckMessage),
+    Error(ErrorMessage),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// More bytes are needed to complete the frame.
+    Truncated,
+    UnknownKind(u8),
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "truncated binary frame"),
+            DecodeError::UnknownKind(kind) => write!(f, "unknown binary frame kind {kind}"),
+            DecodeError::Invalid(what) => write!(f, "invalid binary frame: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Message types that can be written as a binary frame.
+pub trait BinaryEncode {
+    const KIND: FrameKind;
+
+    fn encode_payload(&self, out: &mut Vec<u8>);
+}
+
+/// Append one complete frame for `value` to `out`.
+///
+/// Reusing `out` with sufficient capacity keeps encoding allocation-free.
+pub fn write_frame<T: BinaryEncode>(value: &T, out: &mut Vec<u8>) {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    out.push(T::KIND as u8);
+    value.encode_payload(out);
+    let len = (out.len() - start - 4) as u32;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+}
+
+/// Decode the frame at the start of `bytes`, returning it and its total size.
+pub fn decode_frame(bytes: &[u8]) -> Result<(BinaryMessage, usize), DecodeError> {
+    let len = bytes
+        .get(..4)
+        .map(|prefix| u32::from_le_bytes(prefix.try_into().expect("four bytes")) as usize)
+        .ok_or(DecodeError::Truncated)?;
+    if len == 0 {
+        return Err(DecodeError::Invalid("empty frame"));
+    }
+    let frame = bytes.get(4..4 + len).ok_or(DecodeError::Truncated)?;
+    let kind = FrameKind::from_u8(frame[0]).ok_or(DecodeError::UnknownKind(frame[0]))?;
+    let mut reader = Reader(&frame[1..]);
+    let message = match kind {
+        FrameKind::Observation => BinaryMessage::Observation(decode_observation(&mut reader)?),
+        FrameKind::Ack => BinaryMessage::Ack(decode_ack(&mut reader)?),
+        FrameKind::Error => BinaryMessage::Error(decode_error(&mut reader)?),
+    };
+    if !reader.0.is_empty() {
+        return Err(DecodeError::Invalid("trailing bytes"));
+    }
+    Ok((message, 4 + len))
+}
+
+// ============== Field encoding ==============
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn piece_code(kind: PieceKindLower) -> u8 {
+    match kind {
+        PieceKindLower::I => 1,
+        PieceKindLower::O => 2,
+        PieceKindLower::T => 3,
+        PieceKindLower::S => 4,
+        PieceKindLower::Z => 5,
+        PieceKindLower::J => 6,
+        PieceKindLower::L => 7,
+    }
+}
+
+fn piece_from_code(code: u8) -> Result<PieceKindLower, DecodeError> {
+    Ok(match code {
+        1 => PieceKindLower::I,
+        2 => PieceKindLower::O,
+        3 => PieceKindLower::T,
+        4 => PieceKindLower::S,
+        5 => PieceKindLower::Z,
+        6 => PieceKindLower::J,
+        7 => PieceKindLower::L,
+        _ => return Err(DecodeError::Invalid("piece kind")),
+    })
+}
+
+fn rotation_code(rotation: RotationLower) -> u8 {
+    match rotation {
+        RotationLower::North => 0,
+        RotationLower::East => 1,
+        RotationLower::South => 2,
+        RotationLower::West => 3,
+    }
+}
+
+fn rotation_from_code(code: u8) -> Result<RotationLower, DecodeError> {
+    Ok(match code {
+        0 => RotationLower::North,
+        1 => RotationLower::East,
+        2 => RotationLower::South,
+        3 => RotationLower::West,
+        _ => return Err(DecodeError::Invalid("rotation")),
+    })
+}
+
+const ERROR_CODES: [ErrorCode; 10] = [
+    ErrorCode::HandshakeRequired,
+    ErrorCode::ProtocolMismatch,
+    ErrorCode::NotController,
+    ErrorCode::ControllerActive,
+    ErrorCode::InvalidCommand,
+    ErrorCode::InvalidPlace,
+    ErrorCode::HoldUnavailable,
+    ErrorCode::SnapshotRequired,
+    ErrorCode::Backpressure,
+    ErrorCode::Unauthorized,
+];
+
+const OBS_PLAYABLE: u8 = 1 << 0;
+const OBS_PAUSED: u8 = 1 << 1;
+const OBS_GAME_OVER: u8 = 1 << 2;
+const OBS_CAN_HOLD: u8 = 1 << 3;
+const OBS_ACTIVE: u8 = 1 << 4;
+const OBS_GHOST: u8 = 1 << 5;
+const OBS_HOLD: u8 = 1 << 6;
+
+const EVENT_LOCKED: u8 = 1 << 0;
+const EVENT_BACK_TO_BACK: u8 = 1 << 1;
+const EVENT_TSPIN_MINI: u8 = 1 << 2;
+const EVENT_TSPIN_FULL: u8 = 1 << 3;
+
+impl BinaryEncode for ObservationMessage {
+    const KIND: FrameKind = FrameKind::Observation;
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        put_u64(out, self.seq);
+        put_u64(out, self.ts);
+        put_u64(out, self.logical_step);
+        let mut flags = 0;
+        for (set, bit) in [
+            (self.playable, OBS_PLAYABLE),
+            (self.paused, OBS_PAUSED),
+            (self.game_over, OBS_GAME_OVER),
+            (self.can_hold, OBS_CAN_HOLD),
+            (self.active.is_some(), OBS_ACTIVE),
+            (self.ghost_y.is_some(), OBS_GHOST),
+            (self.hold.is_some(), OBS_HOLD),
+        ] {
+            if set {
+                flags |= bit;
+            }
+        }
+        out.push(flags);
+        put_u32(out, self.episode_id);
+        put_u32(out, self.seed);
+        put_u32(out, self.piece_id);
+        put_u32(out, self.step_in_piece);
+        put_u32(out, self.board_id);
+
+        for row in &self.board.cells {
+            let mask = row
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| **cell != 0)
+                .fold(0u16, |mask, (x, _)| mask | (1 << x));
+            out.extend_from_slice(&mask.to_le_bytes());
+        }
+        let mut pending: Option<u8> = None;
+        for &cell in self.board.cells.iter().flatten().filter(|cell| **cell != 0) {
+            match pending.take() {
+                None => pending = Some(cell & 0x0f),
+                Some(low) => out.push(low | (cell & 0x0f) << 4),
+            }
+        }
+        if let Some(low) = pending {
+            out.push(low);
+        }
+
+        if let Some(active) = &self.active {
+            out.extend_from_slice(&[
+                piece_code(active.kind),
+                rotation_code(active.rotation),
+                active.x as u8,
+                active.y as u8,
+            ]);
+        }
+        if let Some(ghost_y) = self.ghost_y {
+            out.push(ghost_y as u8);
+        }
+        for &kind in &self.next_queue {
+            out.push(piece_code(kind));
+        }
+        if let Some(hold) = self.hold {
+            out.push(piece_code(hold));
+        }
+
+        out.push(self.events.0.len() as u8);
+        for event in &self.events.0 {
+            let mut flags = 0;
+            if event.locked {
+                flags |= EVENT_LOCKED;
+            }
+            if event.back_to_back {
+                flags |= EVENT_BACK_TO_BACK;
+            }
+            match event.tspin {
+                Some(TSpinLower::Mini) => flags |= EVENT_TSPIN_MINI,
+                Some(TSpinLower::Full) => flags |= EVENT_TSPIN_FULL,
+                None => {}
+            }
+            out.push(flags);
+            out.push(event.lines_cleared.min(u8::MAX as u32) as u8);
+            put_u32(out, event.line_clear_score);
+            out.extend_from_slice(&event.combo.to_le_bytes());
+        }
+
+        put_u64(out, self.state_hash.0);
+        put_u32(out, self.score);
+        put_u32(out, self.level);
+        put_u32(out, self.lines);
+        put_u32(out, self.timers.drop_ms);
+        put_u32(out, self.timers.lock_ms);
+        put_u32(out, self.timers.line_clear_ms);
+    }
+}
+
+const ACK_APPLIED: u8 = 1 << 0;
+
+impl BinaryEncode for AckMessage {
+    const KIND: FrameKind = FrameKind::Ack;
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        put_u64(out, self.seq);
+        put_u64(out, self.ts);
+        put_u64(out, self.correlation_seq);
+        match (self.applied_step, self.state_hash) {
+            (Some(step), Some(hash)) => {
+                out.push(ACK_APPLIED);
+                put_u64(out, step);
+                put_u64(out, hash.0);
+            }
+            _ => out.push(0),
+        }
+    }
+}
+
+const ERROR_RETRY: u8 = 1 << 0;
+
+impl BinaryEncode for ErrorMessage {
+    const KIND: FrameKind = FrameKind::Error;
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        put_u64(out, self.seq);
+        put_u64(out, self.ts);
+        let code = ERROR_CODES
+            .iter()
+            .position(|code| *code == self.code)
+            .expect("every error code has a binary tag");
+        out.push(code as u8);
+        match self.retry_after_ms {
+            Some(retry) => {
+                out.push(ERROR_RETRY);
+                put_u64(out, retry);
+            }
+            None => out.push(0),
+        }
+        // Cut an oversized message at a char boundary so it stays UTF-8.
+        let message = &self.message[..self.message.floor_char_boundary(u16::MAX as usize)];
+        out.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        out.extend_from_slice(message.as_bytes());
+    }
+}
+
+// ============== Field decoding ==============
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::Invalid("payload too short"));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().expect("two")))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("four")))
+    }
+
+    fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().expect("four")))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("eight")))
+    }
+}
+
+fn decode_observation(reader: &mut Reader<'_>) -> Result<ObservationMessage, DecodeError> {
+    let seq = reader.u64()?;
+    let ts = reader.u64()?;
+    let logical_step = reader.u64()?;
+    let flags = reader.u8()?;
+    let episode_id = reader.u32()?;
+    let seed = reader.u32()?;
+    let piece_id = reader.u32()?;
+    let step_in_piece = reader.u32()?;
+    let board_id = reader.u32()?;
+
+    let mut masks = [0u16; BOARD_HEIGHT];
+    for mask in &mut masks {
+        *mask = reader.u16()?;
+        if *mask >> BOARD_WIDTH != 0 {
+            return Err(DecodeError::Invalid("board mask"));
+        }
+    }
+    let occupied: u32 = masks.iter().map(|mask| mask.count_ones()).sum();
+    let kinds = reader.take(occupied.div_ceil(2) as usize)?;
+    let mut cells = [[0u8; BOARD_WIDTH]; BOARD_HEIGHT];
+    let mut index = 0usize;
+    for (row, mask) in cells.iter_mut().zip(masks) {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if mask & (1 << x) != 0 {
+                let byte = kinds[index / 2];
+                let kind = if index.is_multiple_of(2) {
+                    byte & 0x0f
+                } else {
+                    byte >> 4
+                };
+                *cell = piece_code(piece_from_code(kind)?);
+                index += 1;
+            }
+        }
+    }
+
+    let active = if flags & OBS_ACTIVE != 0 {
+        Some(ActivePieceSnapshot {
+            kind: piece_from_code(reader.u8()?)?,
+            rotation: rotation_from_code(reader.u8()?)?,
+            x: reader.u8()? as i8,
+            y: reader.u8()? as i8,
+        })
+    } else {
+        None
+    };
+    let ghost_y = if flags & OBS_GHOST != 0 {
+        Some(reader.u8()? as i8)
+    } else {
+        None
+    };
+    let mut next_queue = [PieceKindLower::I; 5];
+    for kind in &mut next_queue {
+        *kind = piece_from_code(reader.u8()?)?;
+    }
+    let hold = if flags & OBS_HOLD != 0 {
+        Some(piece_from_code(reader.u8()?)?)
+    } else {
+        None
+    };
+
+    let event_count = reader.u8()?;
+    let mut events = ArrayVec::new();
+    for _ in 0..event_count {
+        let flags = reader.u8()?;
+        let tspin = match (flags & EVENT_TSPIN_MINI != 0, flags & EVENT_TSPIN_FULL != 0) {
+            (false, false) => None,
+            (true, false) => Some(TSpinLower::Mini),
+            (false, true) => Some(TSpinLower::Full),
+            (true, true) => return Err(DecodeError::Invalid("tspin")),
+        };
+        let event = TransitionEvent {
+            locked: flags & EVENT_LOCKED != 0,
+            back_to_back: flags & EVENT_BACK_TO_BACK != 0,
+            tspin,
+            lines_cleared: u32::from(reader.u8()?),
+            line_clear_score: reader.u32()?,
+            combo: reader.i32()?,
+        };
+        events
+            .try_push(event)
+            .map_err(|_| DecodeError::Invalid("too many events"))?;
+    }
+
+    Ok(ObservationMessage {
+        msg_type: ObservationType::Observation,
+        seq,
+        ts,
+        logical_step,
+        playable: flags & OBS_PLAYABLE != 0,
+        paused: flags & OBS_PAUSED != 0,
+        game_over: flags & OBS_GAME_OVER != 0,
+        episode_id,
+        seed,
+        piece_id,
+        step_in_piece,
+        board: BoardSnapshot {
+            width: BOARD_WIDTH as u8,
+            height: BOARD_HEIGHT as u8,
+            cells,
+        },
+        board_id,
+        active,
+        ghost_y,
+        next: next_queue[0],
+        next_queue,
+        hold,
+        can_hold: flags & OBS_CAN_HOLD != 0,
+        events: EventList(events),
+        state_hash: StateHash(reader.u64()?),
+        score: reader.u32()?,
+        level: reader.u32()?,
+        lines: reader.u32()?,
+        timers: TimersSnapshot {
+            drop_ms: reader.u32()?,
+            lock_ms: reader.u32()?,
+            line_clear_ms: reader.u32()?,
+        },
+    })
+}
+
+fn decode_ack(reader: &mut Reader<'_>) -> Result<AckMessage, DecodeError> {
+    let seq = reader.u64()?;
+    let ts = reader.u64()?;
+    let correlation_seq = reader.u64()?;
+    let (applied_step, state_hash) = if reader.u8()? & ACK_APPLIED != 0 {
+        (Some(reader.u64()?), Some(StateHash(reader.u64()?)))
+    } else {
+        (None, None)
+    };
+    Ok(AckMessage {
+        msg_type: AckType::Ack,
+        seq,
+        ts,
+        status: AckStatus::Ok,
+        correlation_seq,
+        applied_step,
+        state_hash,
+    })
+}
+
+fn decode_error(reader: &mut Reader<'_>) -> Result<ErrorMessage, DecodeError> {
+    let seq = reader.u64()?;
+    let ts = reader.u64()?;
+    let code = *ERROR_CODES
+        .get(reader.u8()? as usize)
+        .ok_or(DecodeError::Invalid("error code"))?;
+    let retry_after_ms = if reader.u8()? & ERROR_RETRY != 0 {
+        Some(reader.u64()?)
+    } else {
+        None
+    };
+    let len = reader.u16()? as usize;
+    let message = std::str::from_utf8(reader.take(len)?)
+        .map_err(|_| DecodeError::Invalid("error message"))?
+        .to_string();
+    Ok(ErrorMessage {
+        msg_type: ErrorType::Error,
+        seq,
+        ts,
+        code,
+        message,
+        retry_after_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{create_applied_ack, create_backpressure_error, create_error};
+
+    const OBSERVATION_JSON: &str = r#"{"type":"observation","seq":42,"ts":1730000001200,"logical_step":42,"playable":true,"paused":false,"game_over":false,"episode_id":3,"seed":1,"piece_id":12,"step_in_piece":5,"board":{"width":10,"height":20,"cells":[[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,7],[0,0,0,0,0,0,0,0,1,7],[3,3,3,0,0,0,0,2,2,7],[4,4,5,5,6,6,6,0,2,2]]},"board_id":123,"active":{"kind":"t","rotation":"west","x":-1,"y":-2},"ghost_y":17,"next":"i","next_queue":["i","o","t","s","z"],"hold":"l","can_hold":true,"events":[{"locked":true,"lines_cleared":2,"line_clear_score":1200,"tspin":"full","combo":-1,"back_to_back":true},{"locked":false,"lines_cleared":0,"line_clear_score":0,"combo":3,"back_to_back":false}],"state_hash":"e1bca4d1b673b8c2","score":1200,"level":2,"lines":17,"timers":{"drop_ms":320,"lock_ms":120,"line_clear_ms":0}}"#;
+
+    fn roundtrip<T>(value: &T) -> BinaryMessage
+    where
+        T: BinaryEncode,
+    {
+        let mut buf = Vec::new();
+        write_frame(value, &mut buf);
+        let (decoded, used) = decode_frame(&buf).expect("frame decodes");
+        assert_eq!(used, buf.len());
+        decoded
+    }
+
+    #[test]
+    fn observation_roundtrips_to_identical_json() {
+        let observation: ObservationMessage = serde_json::from_str(OBSERVATION_JSON).unwrap();
+        let BinaryMessage::Observation(decoded) = roundtrip(&observation) else {
+            panic!("expected observation frame");
+        };
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&observation).unwrap()
+        );
+
+        let mut json = Vec::new();
+        serde_json::to_writer(&mut json, &observation).unwrap();
+        let mut binary = Vec::new();
+        write_frame(&observation, &mut binary);
+        assert!(binary.len() * 4 < json.len(), "binary frame is compact");
+    }
+
+    #[test]
+    fn sparse_observation_without_optional_fields_roundtrips() {
+        let mut observation: ObservationMessage = serde_json::from_str(OBSERVATION_JSON).unwrap();
+        observation.active = None;
+        observation.ghost_y = None;
+        observation.hold = None;
+        observation.events = EventList::default();
+        observation.board.cells = [[0; BOARD_WIDTH]; BOARD_HEIGHT];
+        let BinaryMessage::Observation(decoded) = roundtrip(&observation) else {
+            panic!("expected observation frame");
+        };
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&observation).unwrap()
+        );
+    }
+
+    #[test]
+    fn ack_and_error_roundtrip_to_identical_json() {
+        for ack in [
+            crate::protocol::create_ack(5, 5),
+            create_applied_ack(6, 6, 99, StateHash(0xdead_beef)),
+        ] {
+            let BinaryMessage::Ack(decoded) = roundtrip(&ack) else {
+                panic!("expected ack frame");
+            };
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&ack).unwrap()
+            );
+        }
+
+        for error in [
+            create_error(7, ErrorCode::HoldUnavailable, "hold unavailable"),
+            create_backpressure_error(8, "Command queue is full", 50),
+        ] {
+            let BinaryMessage::Error(decoded) = roundtrip(&error) else {
+                panic!("expected error frame");
+            };
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&error).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_error_messages_are_cut_at_a_char_boundary() {
+        // Two-byte chars put the odd u16 limit in the middle of one.
+        let message = "é".repeat(u16::MAX as usize);
+        let error = create_error(9, ErrorCode::InvalidCommand, &message);
+        let BinaryMessage::Error(decoded) = roundtrip(&error) else {
+            panic!("expected error frame");
+        };
+        assert_eq!(decoded.message.len(), u16::MAX as usize - 1);
+        assert!(message.starts_with(&decoded.message));
+    }
+
+    #[test]
+    fn decode_reports_truncation_and_unknown_kinds() {
+        let ack = crate::protocol::create_ack(1, 1);
+        let mut buf = Vec::new();
+        write_frame(&ack, &mut buf);
+
+        for cut in 0..buf.len() {
+            assert_eq!(
+                decode_frame(&buf[..cut]).unwrap_err(),
+                DecodeError::Truncated
+            );
+        }
+        buf[4] = 9;
+        assert_eq!(decode_frame(&buf).unwrap_err(), DecodeError::UnknownKind(9));
+    }
+}
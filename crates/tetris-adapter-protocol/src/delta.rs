@@ -0,0 +1,286 @@
+//! Delta-encoded JSON observations.
+//!
+//! Negotiated with `requested.delta_observations`. Observations keep every
+//! field except the board, which is sent in one of three shapes relative to
+//! the previous observation delivered on the same connection:
+//!
+//! - keyframe: full `board` plus `"keyframe":true`;
+//! - unchanged `board_id`: no board fields at all;
+//! - changed `board_id`: `board_delta` with the rows that differ from
+//!   `base_board_id`.
+//!
+//! A [`DeltaEncoder`] holds the per-connection state and yields an
+//! [`ObservationView`] of the shared observation, so encoding adds no copies
+//! or heap allocation. A
+//! [`DeltaDecoder`] rebuilds full [`ObservationMessage`]s on the client side.
+
+use serde::Deserialize;
+
+use crate::protocol::{BoardSnapshot, ObservationMessage};
+use crate::view::{BoardEncoding, Cells, ObservationView};
+
+/// Observations between forced keyframes on one connection.
+pub const DELTA_KEYFRAME_INTERVAL: u32 = 64;
+
+const BOARD_WIDTH: usize = 10;
+const BOARD_HEIGHT: usize = 20;
+
+/// Per-connection delta state: the last board this client received.
+#[derive(Debug, Clone)]
+pub struct DeltaEncoder {
+    keyframe_interval: u32,
+    since_keyframe: u32,
+    keyframe_pending: bool,
+    board_id: u32,
+    cells: Cells,
+}
+
+impl Default for DeltaEncoder {
+    fn default() -> Self {
+        Self::new(DELTA_KEYFRAME_INTERVAL)
+    }
+}
+
+impl DeltaEncoder {
+    /// The first observation is always a keyframe; later ones are forced to
+    /// keyframes every `keyframe_interval` observations.
+    pub fn new(keyframe_interval: u32) -> Self {
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+            since_keyframe: 0,
+            keyframe_pending: true,
+            board_id: 0,
+            cells: [[0; BOARD_WIDTH]; BOARD_HEIGHT],
+        }
+    }
+
+    /// Make the next encoded observation a keyframe (client resync).
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_pending = true;
+    }
+
+    pub fn encode<'a>(&mut self, observation: &'a ObservationMessage) -> ObservationView<'a> {
+        let cells = &observation.board.cells;
+        let board = if self.keyframe_pending || self.since_keyframe + 1 >= self.keyframe_interval {
+            BoardEncoding::Keyframe
+        } else if observation.board_id == self.board_id {
+            BoardEncoding::Unchanged
+        } else {
+            let rows = (0..BOARD_HEIGHT)
+                .filter(|&y| cells[y] != self.cells[y])
+                .fold(0u32, |rows, y| rows | (1 << y));
+            // Line clears shift most rows; a full board is smaller then.
+            if rows.count_ones() as usize > BOARD_HEIGHT / 2 {
+                BoardEncoding::Keyframe
+            } else {
+                BoardEncoding::Rows {
+                    base_board_id: self.board_id,
+                    rows,
+                }
+            }
+        };
+
+        if board == BoardEncoding::Keyframe {
+            self.keyframe_pending = false;
+            self.since_keyframe = 0;
+        } else {
+            self.since_keyframe += 1;
+        }
+        self.board_id = observation.board_id;
+        self.cells = *cells;
+        ObservationView::with_board(observation, board)
+    }
+}
+
+// ============== Client-side reconstruction ==============
+
+#[derive(Debug)]
+pub enum DeltaError {
+    /// The decoder lacks the base board; the client should send `resync`.
+    ResyncRequired,
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeltaError::ResyncRequired => write!(f, "delta base board missing; resync required"),
+            DeltaError::Json(err) => write!(f, "invalid delta observation: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+impl From<serde_json::Error> for DeltaError {
+    fn from(err: serde_json::Error) -> Self {
+        DeltaError::Json(err)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BoardDelta {
+    base_board_id: u32,
+    rows: Vec<BoardRow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BoardRow {
+    y: u8,
+    cells: [u8; BOARD_WIDTH],
+}
+
+/// Rebuilds full observations from a delta-encoded stream.
+#[derive(Debug, Clone, Default)]
+pub struct DeltaDecoder {
+    board: Option<(u32, Cells)>,
+}
+
+impl DeltaDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one observation line and return the full observation.
+    ///
+    /// On [`DeltaError::ResyncRequired`] the decoder drops its base until the
+    /// next keyframe arrives.
+    pub fn apply(&mut self, json: &str) -> Result<ObservationMessage, DeltaError> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let object = value
+            .as_object_mut()
+            .ok_or_else(|| serde::de::Error::custom("observation must be an object"))
+            .map_err(DeltaError::Json)?;
+        let board_id: u32 = serde_json::from_value(
+            object
+                .get("board_id")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+        )?;
+        object.remove("keyframe");
+
+        if !object.contains_key("board") {
+            let cells = match (self.board.take(), object.remove("board_delta")) {
+                (Some((base, cells)), None) if base == board_id => cells,
+                (Some((base, mut cells)), Some(delta)) => {
+                    let delta: BoardDelta = serde_json::from_value(delta)?;
+                    if delta.base_board_id != base {
+                        return Err(DeltaError::ResyncRequired);
+                    }
+                    for row in delta.rows {
+                        let Some(target) = cells.get_mut(row.y as usize) else {
+                            return Err(DeltaError::Json(serde::de::Error::custom(
+                                "board_delta row out of range",
+                            )));
+                        };
+                        *target = row.cells;
+                    }
+                    cells
+                }
+                _ => return Err(DeltaError::ResyncRequired),
+            };
+            let board = BoardSnapshot {
+                width: BOARD_WIDTH as u8,
+                height: BOARD_HEIGHT as u8,
+                cells,
+            };
+            object.insert("board".to_string(), serde_json::to_value(board)?);
+        }
+
+        // Protocol enums deserialize from borrowed strings, so parse from text.
+        let observation: ObservationMessage = serde_json::from_str(&value.to_string())?;
+        self.board = Some((observation.board_id, observation.board.cells));
+        Ok(observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBSERVATION_JSON: &str = r#"{"type":"observation","seq":1,"ts":1730000001200,"logical_step":1,"playable":true,"paused":false,"game_over":false,"episode_id":0,"seed":1,"piece_id":1,"step_in_piece":0,"board":{"width":10,"height":20,"cells":[[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[0,0,0,0,0,0,0,0,0,0],[4,4,5,5,6,6,6,0,2,2]]},"board_id":7,"active":{"kind":"t","rotation":"north","x":3,"y":0},"ghost_y":17,"next":"i","next_queue":["i","o","t","s","z"],"can_hold":true,"events":[],"state_hash":"e1bca4d1b673b8c2","score":0,"level":1,"lines":0,"timers":{"drop_ms":1000,"lock_ms":0,"line_clear_ms":0}}"#;
+
+    fn encode(encoder: &mut DeltaEncoder, observation: &ObservationMessage) -> String {
+        serde_json::to_string(&encoder.encode(observation)).unwrap()
+    }
+
+    fn assert_same(decoded: &ObservationMessage, expected: &ObservationMessage) {
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn delta_stream_reconstructs_every_observation() {
+        let mut observation: ObservationMessage = serde_json::from_str(OBSERVATION_JSON).unwrap();
+        let mut encoder = DeltaEncoder::default();
+        let mut decoder = DeltaDecoder::new();
+
+        let line = encode(&mut encoder, &observation);
+        assert!(line.contains(r#""keyframe":true"#));
+        assert_same(&decoder.apply(&line).unwrap(), &observation);
+
+        observation.seq = 2;
+        observation.active.as_mut().unwrap().y = 5;
+        let line = encode(&mut encoder, &observation);
+        assert!(!line.contains("board\"") && !line.contains("board_delta"));
+        assert_same(&decoder.apply(&line).unwrap(), &observation);
+
+        observation.seq = 3;
+        observation.board_id = 8;
+        observation.board.cells[18] = [0, 0, 0, 3, 3, 3, 0, 0, 0, 0];
+        observation.board.cells[19][7] = 3;
+        let line = encode(&mut encoder, &observation);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(value.get("board").is_none());
+        assert_eq!(value["board_delta"]["base_board_id"], 7);
+        assert_eq!(value["board_delta"]["rows"].as_array().unwrap().len(), 2);
+        assert_same(&decoder.apply(&line).unwrap(), &observation);
+
+        // A line clear shifts most rows and falls back to a keyframe.
+        observation.seq = 4;
+        observation.board_id = 9;
+        for (y, row) in observation.board.cells.iter_mut().enumerate() {
+            *row = [(y % 7) as u8 + 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        }
+        let line = encode(&mut encoder, &observation);
+        assert!(line.contains(r#""keyframe":true"#));
+        assert_same(&decoder.apply(&line).unwrap(), &observation);
+    }
+
+    #[test]
+    fn keyframes_repeat_on_interval_and_request() {
+        let observation: ObservationMessage = serde_json::from_str(OBSERVATION_JSON).unwrap();
+        let mut encoder = DeltaEncoder::new(3);
+        let keyframes: Vec<bool> = (0..7)
+            .map(|_| encoder.encode(&observation).is_keyframe())
+            .collect();
+        assert_eq!(keyframes, [true, false, false, true, false, false, true]);
+
+        encoder.request_keyframe();
+        assert!(encoder.encode(&observation).is_keyframe());
+    }
+
+    #[test]
+    fn decoder_without_matching_base_requires_resync() {
+        let mut observation: ObservationMessage = serde_json::from_str(OBSERVATION_JSON).unwrap();
+        let mut encoder = DeltaEncoder::default();
+        let keyframe = encode(&mut encoder, &observation);
+        observation.board_id = 8;
+        observation.board.cells[0][0] = 1;
+        let delta = encode(&mut encoder, &observation);
+
+        let mut decoder = DeltaDecoder::new();
+        assert!(matches!(
+            decoder.apply(&delta),
+            Err(DeltaError::ResyncRequired)
+        ));
+        decoder.apply(&keyframe).unwrap();
+        decoder.apply(&delta).unwrap();
+        assert!(matches!(
+            decoder.apply(&delta),
+            Err(DeltaError::ResyncRequired)
+        ));
+    }
+}
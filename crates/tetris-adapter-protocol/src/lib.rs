@@ -1,3 +1,6 @@
 #![forbid(unsafe_code)]
 
+pub mod binary;
+pub mod delta;
 pub mod protocol;
+pub mod view;
@@ -7,11 +7,25 @@ use serde::{Deserialize, Serialize};
 
 use tetris_core::types::{CoreLastEvent, PieceKind, Rotation, TSpinKind};
 
+use crate::view::FeatureMask;
+
 use arrayvec::ArrayVec;
 
 /// Protocol version implemented by both the adapter server and bundled clients.
 pub const PROTOCOL_VERSION: &str = "3.0.0";
 
+/// Upper bound on `step.n` so one request cannot monopolize the game loop.
+pub const MAX_LOCKSTEP_STEPS: u32 = 3600;
+
+/// Upper bound on `subscription.hz`, matching the fastest observation cadence.
+pub const MAX_SUBSCRIPTION_HZ: u32 = 60;
+
+/// Name of the room every client starts in: the game hosted by the process.
+pub const DEFAULT_ROOM: &str = "tui-tetris";
+
+/// Upper bound on room name length, in bytes.
+pub const MAX_ROOM_NAME_LEN: usize = 64;
+
 // ============== Client -> Game Messages ==============
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -35,6 +49,34 @@ pub enum ControlType {
     Control,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StepType {
+    #[serde(rename = "step")]
+    #[default]
+    Step,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SubscribeType {
+    #[serde(rename = "subscribe")]
+    #[default]
+    Subscribe,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResyncType {
+    #[serde(rename = "resync")]
+    #[default]
+    Resync,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoomType {
+    #[serde(rename = "room")]
+    #[default]
+    Room,
+}
+
 /// Client hello message (first message to establish connection)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HelloMessage {
@@ -47,11 +89,30 @@ pub struct HelloMessage {
     pub protocol_version: String,
     pub formats: FormatsList,
     pub requested: RequestedCapabilities,
+    /// Shared secret for servers that require authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Reclaim a disconnected session instead of starting a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume: Option<ResumeRequest>,
+}
+
+/// Hello request to resume a session from an earlier welcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeRequest {
+    /// `resume_token` of the earlier welcome.
+    pub token: String,
+    /// Last `logical_step` the client saw; retained observations after it
+    /// are replayed right after the welcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_step: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FormatsList {
     pub json: bool,
+    /// Length-prefixed binary frames for game -> client messages; see [`crate::binary`].
+    pub binary: bool,
 }
 
 impl<'de> Deserialize<'de> for FormatsList {
@@ -71,13 +132,18 @@ impl<'de> Deserialize<'de> for FormatsList {
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                let mut json = false;
+                let mut formats = FormatsList {
+                    json: false,
+                    binary: false,
+                };
                 while let Some(v) = seq.next_element::<&str>()? {
                     if v.eq_ignore_ascii_case("json") {
-                        json = true;
+                        formats.json = true;
+                    } else if v.eq_ignore_ascii_case("binary") {
+                        formats.binary = true;
                     }
                 }
-                Ok(FormatsList { json })
+                Ok(formats)
             }
         }
 
@@ -91,10 +157,14 @@ impl Serialize for FormatsList {
         S: serde::Serializer,
     {
         use serde::ser::SerializeSeq;
-        let mut seq = serializer.serialize_seq(Some(if self.json { 1 } else { 0 }))?;
+        let len = usize::from(self.json) + usize::from(self.binary);
+        let mut seq = serializer.serialize_seq(Some(len))?;
         if self.json {
             seq.serialize_element("json")?;
         }
+        if self.binary {
+            seq.serialize_element("binary")?;
+        }
         seq.end()
     }
 }
@@ -115,6 +185,60 @@ pub struct RequestedCapabilities {
     /// Per spec, this MUST NOT change role unless explicitly supported by the adapter.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub role: Option<RequestedRole>,
+    /// Lockstep mode: while this client controls the game, the session only
+    /// advances on its commands and explicit `step` messages.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lockstep: bool,
+    /// Delta observations: omit unchanged boards and send changed rows only.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub delta_observations: bool,
+    /// Initial observation subscription; omitted means every observation with
+    /// every field.
+    #[serde(default, skip_serializing_if = "Subscription::is_default")]
+    pub subscription: Subscription,
+    /// Room to join at handshake; omitted means the default room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    /// Receive a `control_state` message whenever the room's controller or
+    /// waiting queue changes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub control_events: bool,
+}
+
+/// Per-client observation delivery preferences.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Maximum observations per second for this client; omitted means no limit
+    /// beyond the server cadence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hz: Option<u32>,
+    /// Deliver only observations whose events include a lock or line clear.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub events_only: bool,
+    /// Optional observation fields to include; omitted means all of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<CapabilityFeature>>,
+}
+
+impl Subscription {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.hz {
+            Some(hz) if !(1..=MAX_SUBSCRIPTION_HZ).contains(&hz) => Err(format!(
+                "subscription.hz must be in 1..={MAX_SUBSCRIPTION_HZ}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn field_mask(&self) -> FeatureMask {
+        self.fields
+            .as_deref()
+            .map_or(FeatureMask::ALL, FeatureMask::from_features)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -303,7 +427,7 @@ pub struct PlaceCommand {
     pub use_hold: bool,
 }
 
-/// Control message (claim/release controller status)
+/// Control message (claim/release/handoff controller status)
 #[derive(Debug, Clone, Deserialize)]
 pub struct ControlMessage {
     #[serde(rename = "type")]
@@ -312,12 +436,16 @@ pub struct ControlMessage {
     pub seq: u64,
     pub ts: u64,
     pub action: ControlAction,
+    /// Client receiving control; required by `handoff`.
+    #[serde(default)]
+    pub to_client_id: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ControlAction {
     Claim,
     Release,
+    Handoff,
 }
 
 impl<'de> Deserialize<'de> for ControlAction {
@@ -330,6 +458,8 @@ impl<'de> Deserialize<'de> for ControlAction {
             Ok(Self::Claim)
         } else if s.eq_ignore_ascii_case("release") {
             Ok(Self::Release)
+        } else if s.eq_ignore_ascii_case("handoff") {
+            Ok(Self::Handoff)
         } else {
             Err(serde::de::Error::custom("invalid control action"))
         }
@@ -344,10 +474,117 @@ impl Serialize for ControlAction {
         match self {
             ControlAction::Claim => serializer.serialize_str("claim"),
             ControlAction::Release => serializer.serialize_str("release"),
+            ControlAction::Handoff => serializer.serialize_str("handoff"),
         }
     }
 }
 
+/// Step message (lockstep controller only): advance `n` idle logical steps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StepMessage {
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub msg_type: StepType,
+    pub seq: u64,
+    pub ts: u64,
+    pub n: u32,
+}
+
+/// Subscribe message: replace this client's observation subscription.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscribeMessage {
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub msg_type: SubscribeType,
+    pub seq: u64,
+    pub ts: u64,
+    pub subscription: Subscription,
+}
+
+/// Room message: list, create, join, or leave a game room.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoomMessage {
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub msg_type: RoomType,
+    pub seq: u64,
+    pub ts: u64,
+    pub action: RoomAction,
+    /// Target room for `create` and `join`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Game seed for `create`; omitted means 1.
+    #[serde(default)]
+    pub seed: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoomAction {
+    List,
+    Create,
+    Join,
+    Leave,
+}
+
+impl<'de> Deserialize<'de> for RoomAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <&str>::deserialize(deserializer)?;
+        if s.eq_ignore_ascii_case("list") {
+            Ok(Self::List)
+        } else if s.eq_ignore_ascii_case("create") {
+            Ok(Self::Create)
+        } else if s.eq_ignore_ascii_case("join") {
+            Ok(Self::Join)
+        } else if s.eq_ignore_ascii_case("leave") {
+            Ok(Self::Leave)
+        } else {
+            Err(serde::de::Error::custom("invalid room action"))
+        }
+    }
+}
+
+impl Serialize for RoomAction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            RoomAction::List => serializer.serialize_str("list"),
+            RoomAction::Create => serializer.serialize_str("create"),
+            RoomAction::Join => serializer.serialize_str("join"),
+            RoomAction::Leave => serializer.serialize_str("leave"),
+        }
+    }
+}
+
+/// Check a room name: 1 to [`MAX_ROOM_NAME_LEN`] ASCII letters, digits, `-`,
+/// `_`, or `.`.
+pub fn validate_room_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_ROOM_NAME_LEN {
+        return Err(format!("room name must be 1..={MAX_ROOM_NAME_LEN} bytes"));
+    }
+    if !name
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'))
+    {
+        return Err("room name may only contain ASCII letters, digits, '-', '_', '.'".into());
+    }
+    Ok(())
+}
+
+/// Resync message (delta observations): request a keyframe observation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResyncMessage {
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub msg_type: ResyncType,
+    pub seq: u64,
+    pub ts: u64,
+}
+
 // ============== Game -> Client Messages ==============
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -368,6 +605,18 @@ pub enum AckStatus {
     Ok,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoomStateType {
+    #[serde(rename = "room_state")]
+    RoomState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ControlStateType {
+    #[serde(rename = "control_state")]
+    ControlState,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ErrorType {
     #[serde(rename = "error")]
@@ -394,6 +643,8 @@ pub enum ErrorCode {
     SnapshotRequired,
     #[serde(rename = "backpressure")]
     Backpressure,
+    #[serde(rename = "unauthorized")]
+    Unauthorized,
 }
 
 /// Welcome message (response to hello)
@@ -412,11 +663,30 @@ pub struct WelcomeMessage {
     pub controller_id: Option<u64>,
     pub game_id: String,
     pub capabilities: ServerCapabilities,
+    /// Whether lockstep mode was negotiated for this connection.
+    #[serde(default)]
+    pub lockstep: bool,
+    /// Encoding of every game -> client message after this welcome.
+    #[serde(default)]
+    pub format: CapabilityFormat,
+    /// Whether observations on this connection are delta-encoded.
+    #[serde(default)]
+    pub delta_observations: bool,
+    /// Observation subscription in effect for this connection.
+    #[serde(default, skip_serializing_if = "Subscription::is_default")]
+    pub subscription: Subscription,
+    /// Presented in a later hello to resume this session after a disconnect;
+    /// omitted when the server does not support resume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<String>,
+    /// Whether this welcome continues an earlier session.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resumed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerCapabilities {
-    pub formats: [CapabilityFormat; 1],
+    pub formats: Vec<CapabilityFormat>,
     #[serde(rename = "command_modes")]
     pub command_modes: [CapabilityCommandMode; 2],
 
@@ -441,24 +711,88 @@ pub struct ServerCapabilities {
 
     /// Deterministic controller lifecycle policy.
     pub control_policy: ControlPolicy,
+
+    /// Whether a controller may negotiate lockstep mode in hello.
+    #[serde(default)]
+    pub lockstep: bool,
+
+    /// Whether a client may negotiate delta observations in hello.
+    #[serde(default)]
+    pub delta_observations: bool,
+
+    /// Whether clients may set per-client observation subscriptions.
+    #[serde(default)]
+    pub subscriptions: bool,
+
+    /// Whether clients may list, create, join, and leave game rooms.
+    #[serde(default)]
+    pub rooms: bool,
+
+    /// How long a disconnected session stays resumable, in milliseconds;
+    /// omitted when sessions end with their connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_grace_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ControlPolicy {
     pub auto_promote_on_disconnect: bool,
     pub promotion_order: ControlPromotionOrder,
+    /// Whether the controller may pass control with `handoff`.
+    #[serde(default)]
+    pub handoff: bool,
+    /// Logical steps without a controller command after which control passes
+    /// to a waiting client; omitted means leases never expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_steps: Option<u64>,
+    /// Whether clients may request `control_state` messages in hello.
+    #[serde(default)]
+    pub control_events: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Which waiting client is promoted when control passes on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ControlPromotionOrder {
     #[serde(rename = "lowest_client_id")]
+    #[default]
     LowestClientId,
+    /// Longest-waiting client first; a claim while another client controls
+    /// joins the queue.
+    #[serde(rename = "fifo")]
+    Fifo,
+    /// Highest hello-token priority first, then longest-waiting.
+    #[serde(rename = "token_priority")]
+    TokenPriority,
+}
+
+impl ControlPromotionOrder {
+    /// Whether claims queue behind an active controller and release passes
+    /// control to the next waiting client.
+    pub fn is_queued(self) -> bool {
+        !matches!(self, Self::LowestClientId)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl std::str::FromStr for ControlPromotionOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "lowest_client_id" => Ok(Self::LowestClientId),
+            "fifo" => Ok(Self::Fifo),
+            "token_priority" => Ok(Self::TokenPriority),
+            other => Err(format!("unknown promotion order {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CapabilityFormat {
     #[serde(rename = "json")]
+    #[default]
     Json,
+    #[serde(rename = "binary")]
+    Binary,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -511,6 +845,66 @@ pub struct AckMessage {
     pub state_hash: Option<StateHash>,
 }
 
+/// Reply to a room message: the client's room after the action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomStateMessage {
+    #[serde(rename = "type")]
+    pub msg_type: RoomStateType,
+    pub seq: u64,
+    pub ts: u64,
+    pub correlation_seq: u64,
+    /// Room this client is in; observations and commands refer to its game.
+    pub room: String,
+    pub role: AssignedRole,
+    pub controller_id: Option<u64>,
+    /// Every open room; present only in replies to `list`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rooms: Option<Vec<RoomInfo>>,
+}
+
+/// Controller change in the client's room, sent to clients that requested
+/// `control_events`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlStateMessage {
+    #[serde(rename = "type")]
+    pub msg_type: ControlStateType,
+    /// Per-room control change counter, starting at 1.
+    pub seq: u64,
+    pub ts: u64,
+    pub controller_id: Option<u64>,
+    pub reason: ControlChangeReason,
+    /// Clients waiting for control, in promotion order.
+    pub waiting: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ControlChangeReason {
+    /// A hello took the free controller seat or joined the waiting queue.
+    #[serde(rename = "hello")]
+    Hello,
+    #[serde(rename = "claim")]
+    Claim,
+    #[serde(rename = "release")]
+    Release,
+    #[serde(rename = "handoff")]
+    Handoff,
+    /// The controller or a waiting client left the room.
+    #[serde(rename = "disconnect")]
+    Disconnect,
+    #[serde(rename = "lease_expired")]
+    LeaseExpired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub name: String,
+    /// Seed of a server-hosted room; `null` for the default room, whose game
+    /// belongs to the host process.
+    pub seed: Option<u32>,
+    pub clients: u16,
+    pub controller_id: Option<u64>,
+}
+
 /// Error message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorMessage {
@@ -828,12 +1222,24 @@ pub fn parse_message(json: &str) -> Result<ParsedMessage, serde_json::Error> {
         Command(CommandMessage),
         #[serde(rename = "control")]
         Control(ControlMessage),
+        #[serde(rename = "step")]
+        Step(StepMessage),
+        #[serde(rename = "resync")]
+        Resync(ResyncMessage),
+        #[serde(rename = "subscribe")]
+        Subscribe(SubscribeMessage),
+        #[serde(rename = "room")]
+        Room(RoomMessage),
     }
 
     match serde_json::from_str::<InboundMessage>(json) {
         Ok(InboundMessage::Hello(m)) => Ok(ParsedMessage::Hello(m)),
         Ok(InboundMessage::Command(m)) => Ok(ParsedMessage::Command(m)),
         Ok(InboundMessage::Control(m)) => Ok(ParsedMessage::Control(m)),
+        Ok(InboundMessage::Step(m)) => Ok(ParsedMessage::Step(m)),
+        Ok(InboundMessage::Resync(m)) => Ok(ParsedMessage::Resync(m)),
+        Ok(InboundMessage::Subscribe(m)) => Ok(ParsedMessage::Subscribe(m)),
+        Ok(InboundMessage::Room(m)) => Ok(ParsedMessage::Room(m)),
         Err(e) => {
             // Unknown message type is not a hard parse error for the protocol.
             #[derive(Debug, Deserialize)]
@@ -844,7 +1250,10 @@ pub fn parse_message(json: &str) -> Result<ParsedMessage, serde_json::Error> {
             let msg_type = serde_json::from_str::<TypeOnly>(json)?
                 .msg_type
                 .unwrap_or("unknown");
-            if msg_type != "hello" && msg_type != "command" && msg_type != "control" {
+            if !matches!(
+                msg_type,
+                "hello" | "command" | "control" | "step" | "resync" | "subscribe" | "room"
+            ) {
                 #[derive(Debug, Deserialize)]
                 struct SeqOnly {
                     seq: Option<u64>,
@@ -863,6 +1272,10 @@ pub enum ParsedMessage {
     Hello(HelloMessage),
     Command(CommandMessage),
     Control(ControlMessage),
+    Step(StepMessage),
+    Resync(ResyncMessage),
+    Subscribe(SubscribeMessage),
+    Room(RoomMessage),
     Unknown(UnknownMessage),
 }
 
@@ -884,12 +1297,22 @@ pub fn create_hello(seq: u64, client_name: &str, protocol_version: &str) -> Hell
             version: env!("CARGO_PKG_VERSION").to_string(),
         },
         protocol_version: protocol_version.to_string(),
-        formats: FormatsList { json: true },
+        formats: FormatsList {
+            json: true,
+            binary: false,
+        },
         requested: RequestedCapabilities {
             stream_observations: true,
             command_mode: CommandMode::Action,
             role: Some(RequestedRole::Auto),
+            lockstep: false,
+            delta_observations: false,
+            subscription: Subscription::default(),
+            room: None,
+            control_events: false,
         },
+        token: None,
+        resume: None,
     }
 }
 
@@ -909,9 +1332,9 @@ pub fn create_welcome(
         client_id,
         role,
         controller_id,
-        game_id: "tui-tetris".to_string(),
+        game_id: DEFAULT_ROOM.to_string(),
         capabilities: ServerCapabilities {
-            formats: [CapabilityFormat::Json],
+            formats: vec![CapabilityFormat::Json, CapabilityFormat::Binary],
             command_modes: [CapabilityCommandMode::Action, CapabilityCommandMode::Place],
             features: vec![
                 CapabilityFeature::Hold,
@@ -942,8 +1365,22 @@ pub fn create_welcome(
             control_policy: ControlPolicy {
                 auto_promote_on_disconnect: true,
                 promotion_order: ControlPromotionOrder::LowestClientId,
+                handoff: true,
+                lease_steps: None,
+                control_events: true,
             },
+            lockstep: true,
+            delta_observations: true,
+            subscriptions: true,
+            rooms: true,
+            resume_grace_ms: None,
         },
+        lockstep: false,
+        format: CapabilityFormat::Json,
+        delta_observations: false,
+        subscription: Subscription::default(),
+        resume_token: None,
+        resumed: false,
     }
 }
 
@@ -1001,6 +1438,42 @@ pub fn create_backpressure_error(seq: u64, message: &str, retry_after_ms: u64) -
     }
 }
 
+/// Create the reply to a room message.
+pub fn create_room_state(
+    seq: u64,
+    room: &str,
+    role: AssignedRole,
+    controller_id: Option<u64>,
+) -> RoomStateMessage {
+    RoomStateMessage {
+        msg_type: RoomStateType::RoomState,
+        seq,
+        ts: current_timestamp_ms(),
+        correlation_seq: seq,
+        room: room.to_string(),
+        role,
+        controller_id,
+        rooms: None,
+    }
+}
+
+/// Create a controller change event.
+pub fn create_control_state(
+    seq: u64,
+    controller_id: Option<u64>,
+    reason: ControlChangeReason,
+    waiting: Vec<u64>,
+) -> ControlStateMessage {
+    ControlStateMessage {
+        msg_type: ControlStateType::ControlState,
+        seq,
+        ts: current_timestamp_ms(),
+        controller_id,
+        reason,
+        waiting,
+    }
+}
+
 /// Get current timestamp in milliseconds
 fn current_timestamp_ms() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -1067,6 +1540,165 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_handoff_and_promotion_orders() {
+        let json = r#"{"type":"control","seq":5,"ts":1,"action":"handoff","to_client_id":3}"#;
+        let ParsedMessage::Control(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Control message");
+        };
+        assert_eq!(msg.action, ControlAction::Handoff);
+        assert_eq!(msg.to_client_id, Some(3));
+
+        assert_eq!("fifo".parse(), Ok(ControlPromotionOrder::Fifo));
+        assert_eq!(
+            "token_priority".parse(),
+            Ok(ControlPromotionOrder::TokenPriority)
+        );
+        assert!("random".parse::<ControlPromotionOrder>().is_err());
+        let policy = serde_json::to_value(ControlPolicy {
+            auto_promote_on_disconnect: true,
+            promotion_order: ControlPromotionOrder::Fifo,
+            handoff: true,
+            lease_steps: Some(600),
+            control_events: true,
+        })
+        .unwrap();
+        assert_eq!(policy["promotion_order"], "fifo");
+        assert_eq!(policy["lease_steps"], 600);
+    }
+
+    #[test]
+    fn test_parse_resume_hello() {
+        let json = r#"{"type":"hello","seq":1,"ts":1,"client":{"name":"bot","version":"1"},"protocol_version":"3.0.0","formats":["json"],"requested":{"stream_observations":true,"command_mode":"action"},"resume":{"token":"abc","since_step":42}}"#;
+        let ParsedMessage::Hello(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Hello message");
+        };
+        let resume = msg.resume.unwrap();
+        assert_eq!(resume.token, "abc");
+        assert_eq!(resume.since_step, Some(42));
+
+        let welcome =
+            serde_json::to_value(create_welcome(1, "3.0.0", 1, AssignedRole::Observer, None))
+                .unwrap();
+        assert!(welcome.get("resume_token").is_none());
+        assert!(welcome.get("resumed").is_none());
+        assert!(welcome["capabilities"].get("resume_grace_ms").is_none());
+    }
+
+    #[test]
+    fn test_parse_step_and_lockstep_hello() {
+        let json = r#"{"type":"step","seq":4,"ts":1234567920,"n":3}"#;
+        match parse_message(json).unwrap() {
+            ParsedMessage::Step(msg) => {
+                assert_eq!(msg.seq, 4);
+                assert_eq!(msg.n, 3);
+            }
+            _ => panic!("Expected Step message"),
+        }
+
+        let json = r#"{"type":"hello","seq":1,"ts":1,"client":{"name":"rl","version":"1"},"protocol_version":"3.0.0","formats":["json"],"requested":{"stream_observations":true,"command_mode":"place","lockstep":true}}"#;
+        let ParsedMessage::Hello(hello) = parse_message(json).unwrap() else {
+            panic!("Expected Hello message");
+        };
+        assert!(hello.requested.lockstep);
+
+        let hello = create_hello(1, "legacy", PROTOCOL_VERSION);
+        let json = serde_json::to_string(&hello).unwrap();
+        assert!(!json.contains("lockstep"), "default hello stays unchanged");
+    }
+
+    #[test]
+    fn test_parse_resync_and_delta_hello() {
+        let json = r#"{"type":"resync","seq":9,"ts":1}"#;
+        let ParsedMessage::Resync(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Resync message");
+        };
+        assert_eq!(msg.seq, 9);
+
+        let json = r#"{"type":"hello","seq":1,"ts":1,"client":{"name":"viewer","version":"1"},"protocol_version":"3.0.0","formats":["json"],"requested":{"stream_observations":true,"command_mode":"action","delta_observations":true}}"#;
+        let ParsedMessage::Hello(hello) = parse_message(json).unwrap() else {
+            panic!("Expected Hello message");
+        };
+        assert!(hello.requested.delta_observations);
+
+        let json = serde_json::to_string(&create_hello(1, "legacy", PROTOCOL_VERSION)).unwrap();
+        assert!(!json.contains("delta_observations"));
+    }
+
+    #[test]
+    fn test_parse_room_and_validate_name() {
+        let json = r#"{"type":"room","seq":3,"ts":1,"action":"create","name":"eval-7","seed":42}"#;
+        let ParsedMessage::Room(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Room message");
+        };
+        assert_eq!(msg.action, RoomAction::Create);
+        assert_eq!(msg.name.as_deref(), Some("eval-7"));
+        assert_eq!(msg.seed, Some(42));
+
+        let json = r#"{"type":"room","seq":4,"ts":1,"action":"list"}"#;
+        let ParsedMessage::Room(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Room message");
+        };
+        assert_eq!(msg.action, RoomAction::List);
+        assert_eq!(msg.name, None);
+
+        assert!(validate_room_name("eval_07.a").is_ok());
+        assert!(validate_room_name("").is_err());
+        assert!(validate_room_name("has space").is_err());
+        assert!(validate_room_name(&"r".repeat(MAX_ROOM_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_subscribe_and_validate_rate() {
+        let json = r#"{"type":"subscribe","seq":5,"ts":1,"subscription":{"hz":5,"events_only":true,"fields":["state_hash","score"]}}"#;
+        let ParsedMessage::Subscribe(msg) = parse_message(json).unwrap() else {
+            panic!("Expected Subscribe message");
+        };
+        assert_eq!(msg.subscription.hz, Some(5));
+        assert!(msg.subscription.events_only);
+        assert!(msg.subscription.validate().is_ok());
+        let mask = msg.subscription.field_mask();
+        assert!(mask.contains(CapabilityFeature::Score));
+        assert!(!mask.contains(CapabilityFeature::Timers));
+
+        for hz in [0, MAX_SUBSCRIPTION_HZ + 1] {
+            let subscription = Subscription {
+                hz: Some(hz),
+                ..Subscription::default()
+            };
+            assert!(subscription.validate().is_err());
+        }
+        assert_eq!(Subscription::default().field_mask(), FeatureMask::ALL);
+
+        let json = serde_json::to_string(&create_hello(1, "legacy", PROTOCOL_VERSION)).unwrap();
+        assert!(!json.contains("subscription"));
+    }
+
+    #[test]
+    fn test_formats_list_negotiates_binary() {
+        let formats: FormatsList = serde_json::from_str(r#"["JSON","binary","cbor"]"#).unwrap();
+        assert_eq!(
+            formats,
+            FormatsList {
+                json: true,
+                binary: true
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&formats).unwrap(),
+            r#"["json","binary"]"#
+        );
+
+        let welcome = create_welcome(1, PROTOCOL_VERSION, 1, AssignedRole::Controller, Some(1));
+        assert_eq!(welcome.format, CapabilityFormat::Json);
+        assert!(
+            welcome
+                .capabilities
+                .formats
+                .contains(&CapabilityFormat::Binary)
+        );
+    }
+
     #[test]
     fn test_create_welcome() {
         let welcome = create_welcome(1, PROTOCOL_VERSION, 7, AssignedRole::Controller, Some(7));
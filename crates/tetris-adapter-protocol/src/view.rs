@@ -0,0 +1,272 @@
+//! Per-client views of one shared observation.
+//!
+//! The game builds each [`ObservationMessage`] once. Clients that filter
+//! fields or receive delta boards serialize an [`ObservationView`] borrowing
+//! that message, so per-client encoding needs no rebuild, copy, or heap
+//! allocation.
+
+use serde::ser::{SerializeSeq, SerializeStruct};
+use serde::{Serialize, Serializer};
+
+use crate::protocol::{CapabilityFeature, ObservationMessage};
+
+const BOARD_WIDTH: usize = 10;
+const BOARD_HEIGHT: usize = 20;
+
+pub(crate) type Cells = [[u8; BOARD_WIDTH]; BOARD_HEIGHT];
+
+/// Set of optional observation fields, keyed by [`CapabilityFeature`].
+///
+/// `score` covers `score`, `level`, and `lines`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FeatureMask(u16);
+
+impl FeatureMask {
+    pub const ALL: Self = Self((1 << 11) - 1);
+    pub const NONE: Self = Self(0);
+
+    const fn bit(feature: CapabilityFeature) -> u16 {
+        1 << feature as u16
+    }
+
+    pub fn from_features(features: &[CapabilityFeature]) -> Self {
+        features
+            .iter()
+            .fold(Self::NONE, |mask, &feature| mask.with(feature))
+    }
+
+    pub const fn with(self, feature: CapabilityFeature) -> Self {
+        Self(self.0 | Self::bit(feature))
+    }
+
+    pub const fn contains(self, feature: CapabilityFeature) -> bool {
+        self.0 & Self::bit(feature) != 0
+    }
+}
+
+impl Default for FeatureMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BoardEncoding {
+    Full,
+    Keyframe,
+    Unchanged,
+    /// Bit `y` set for every row that differs from `base_board_id`.
+    Rows {
+        base_board_id: u32,
+        rows: u32,
+    },
+}
+
+/// One observation as serialized for a particular client.
+#[derive(Debug, Clone, Copy)]
+pub struct ObservationView<'a> {
+    observation: &'a ObservationMessage,
+    fields: FeatureMask,
+    board: BoardEncoding,
+}
+
+impl<'a> ObservationView<'a> {
+    /// Full board and every field: serializes exactly like the message.
+    pub fn new(observation: &'a ObservationMessage) -> Self {
+        Self {
+            observation,
+            fields: FeatureMask::ALL,
+            board: BoardEncoding::Full,
+        }
+    }
+
+    pub(crate) fn with_board(observation: &'a ObservationMessage, board: BoardEncoding) -> Self {
+        Self {
+            board,
+            ..Self::new(observation)
+        }
+    }
+
+    /// Keep only the optional fields in `fields`.
+    pub fn with_fields(self, fields: FeatureMask) -> Self {
+        Self { fields, ..self }
+    }
+
+    pub fn is_keyframe(&self) -> bool {
+        self.board == BoardEncoding::Keyframe
+    }
+}
+
+struct BoardRows<'a> {
+    cells: &'a Cells,
+    rows: u32,
+}
+
+impl Serialize for BoardRows<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Row<'a> {
+            y: u8,
+            cells: &'a [u8; BOARD_WIDTH],
+        }
+
+        let mut seq = serializer.serialize_seq(Some(self.rows.count_ones() as usize))?;
+        for y in (0..BOARD_HEIGHT).filter(|&y| self.rows & (1 << y) != 0) {
+            seq.serialize_element(&Row {
+                y: y as u8,
+                cells: &self.cells[y],
+            })?;
+        }
+        seq.end()
+    }
+}
+
+#[derive(Serialize)]
+struct BoardDeltaRef<'a> {
+    base_board_id: u32,
+    rows: BoardRows<'a>,
+}
+
+impl Serialize for ObservationView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use CapabilityFeature as F;
+
+        let obs = self.observation;
+        let has = |feature| self.fields.contains(feature);
+        let mut s = serializer.serialize_struct("ObservationMessage", 27)?;
+        s.serialize_field("type", &obs.msg_type)?;
+        s.serialize_field("seq", &obs.seq)?;
+        s.serialize_field("ts", &obs.ts)?;
+        if has(F::LogicalStep) {
+            s.serialize_field("logical_step", &obs.logical_step)?;
+        }
+        s.serialize_field("playable", &obs.playable)?;
+        s.serialize_field("paused", &obs.paused)?;
+        s.serialize_field("game_over", &obs.game_over)?;
+        s.serialize_field("episode_id", &obs.episode_id)?;
+        s.serialize_field("seed", &obs.seed)?;
+        s.serialize_field("piece_id", &obs.piece_id)?;
+        s.serialize_field("step_in_piece", &obs.step_in_piece)?;
+        match self.board {
+            BoardEncoding::Full => s.serialize_field("board", &obs.board)?,
+            BoardEncoding::Keyframe => {
+                s.serialize_field("keyframe", &true)?;
+                s.serialize_field("board", &obs.board)?;
+            }
+            BoardEncoding::Unchanged => {}
+            BoardEncoding::Rows {
+                base_board_id,
+                rows,
+            } => s.serialize_field(
+                "board_delta",
+                &BoardDeltaRef {
+                    base_board_id,
+                    rows: BoardRows {
+                        cells: &obs.board.cells,
+                        rows,
+                    },
+                },
+            )?,
+        }
+        if has(F::BoardId) {
+            s.serialize_field("board_id", &obs.board_id)?;
+        }
+        if let Some(active) = &obs.active {
+            s.serialize_field("active", active)?;
+        }
+        if let Some(ghost_y) = obs.ghost_y.as_ref().filter(|_| has(F::GhostY)) {
+            s.serialize_field("ghost_y", ghost_y)?;
+        }
+        if has(F::Next) {
+            s.serialize_field("next", &obs.next)?;
+        }
+        if has(F::NextQueue) {
+            s.serialize_field("next_queue", &obs.next_queue)?;
+        }
+        if let Some(hold) = obs.hold.as_ref().filter(|_| has(F::Hold)) {
+            s.serialize_field("hold", hold)?;
+        }
+        if has(F::CanHold) {
+            s.serialize_field("can_hold", &obs.can_hold)?;
+        }
+        if has(F::Events) {
+            s.serialize_field("events", &obs.events)?;
+        }
+        if has(F::StateHash) {
+            s.serialize_field("state_hash", &obs.state_hash)?;
+        }
+        if has(F::Score) {
+            s.serialize_field("score", &obs.score)?;
+            s.serialize_field("level", &obs.level)?;
+            s.serialize_field("lines", &obs.lines)?;
+        }
+        if has(F::Timers) {
+            s.serialize_field("timers", &obs.timers)?;
+        }
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::BoardSnapshot;
+
+    fn observation() -> ObservationMessage {
+        let mut cells = [[0u8; BOARD_WIDTH]; BOARD_HEIGHT];
+        cells[19][0] = 4;
+        let json = serde_json::json!({
+            "type": "observation", "seq": 3, "ts": 1, "logical_step": 2,
+            "playable": true, "paused": false, "game_over": false,
+            "episode_id": 0, "seed": 1, "piece_id": 1, "step_in_piece": 0,
+            "board": BoardSnapshot { width: 10, height: 20, cells },
+            "board_id": 4, "ghost_y": 18, "next": "i", "next_queue": ["i", "o", "t", "s", "z"],
+            "hold": "l", "can_hold": true, "events": [], "state_hash": "00000000000000ff",
+            "score": 10, "level": 1, "lines": 0,
+            "timers": { "drop_ms": 1000, "lock_ms": 0, "line_clear_ms": 0 }
+        });
+        serde_json::from_str(&json.to_string()).unwrap()
+    }
+
+    #[test]
+    fn full_view_serializes_like_the_message() {
+        let observation = observation();
+        assert_eq!(
+            serde_json::to_string(&ObservationView::new(&observation)).unwrap(),
+            serde_json::to_string(&observation).unwrap()
+        );
+    }
+
+    #[test]
+    fn field_mask_drops_only_unselected_optional_fields() {
+        let observation = observation();
+        let mask = FeatureMask::from_features(&[CapabilityFeature::StateHash]);
+        let value =
+            serde_json::to_value(ObservationView::new(&observation).with_fields(mask)).unwrap();
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        for dropped in [
+            "logical_step",
+            "board_id",
+            "ghost_y",
+            "next",
+            "next_queue",
+            "hold",
+            "can_hold",
+            "events",
+            "score",
+            "level",
+            "lines",
+            "timers",
+        ] {
+            assert!(!keys.contains(&dropped), "{dropped} is filtered");
+        }
+        for kept in ["type", "seq", "playable", "board", "state_hash"] {
+            assert!(keys.contains(&kept), "{kept} is kept");
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Optional shared-secret authentication for adapter clients.
+
+use crate::adapter::protocol::WelcomeMessage;
+use crate::adapter::server::ServerConfig;
+use crate::adapter::wire_log::WireRecord;
+
+/// What a client's hello token grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Access {
+    /// `priority` orders promotion under the `token_priority` policy.
+    Full { priority: u32 },
+    /// Never controller: hello, claim, and promotion all keep it observer.
+    ObserverOnly,
+}
+
+/// Check a hello token against the configured tokens.
+///
+/// Without configured tokens every client has full access; otherwise a client
+/// without a matching token is refused. The plain full-access token has
+/// priority 0.
+pub(super) fn authorize(config: &ServerConfig, token: Option<&str>) -> Option<Access> {
+    if config.auth_token.is_none()
+        && config.observer_token.is_none()
+        && config.priority_tokens.is_empty()
+    {
+        return Some(Access::Full { priority: 0 });
+    }
+    let token = token?;
+    // Every priority token is compared, so timing does not reveal which one
+    // matched.
+    let priority = config
+        .priority_tokens
+        .iter()
+        .filter(|(expected, _)| token_eq(expected, token))
+        .map(|&(_, priority)| priority)
+        .fold(None, |best: Option<u32>, priority| {
+            Some(best.map_or(priority, |best| best.max(priority)))
+        });
+    if let Some(priority) = priority {
+        Some(Access::Full { priority })
+    } else if config
+        .auth_token
+        .as_deref()
+        .is_some_and(|expected| token_eq(expected, token))
+    {
+        Some(Access::Full { priority: 0 })
+    } else if config
+        .observer_token
+        .as_deref()
+        .is_some_and(|expected| token_eq(expected, token))
+    {
+        Some(Access::ObserverOnly)
+    } else {
+        None
+    }
+}
+
+/// A hello line with its tokens masked for the wire log, or `None` when the
+/// line carries no token.
+pub(super) fn redact_token(line: &str) -> Option<String> {
+    if !line.contains("\"token\"") {
+        return None;
+    }
+    let mut value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let mut redacted = false;
+    for pointer in ["/token", "/resume/token"] {
+        if let Some(token) = value.pointer_mut(pointer) {
+            *token = serde_json::Value::from("[redacted]");
+            redacted = true;
+        }
+    }
+    redacted.then(|| value.to_string())
+}
+
+/// Wire log record of a welcome, with its resume token masked.
+pub(super) fn redacted_welcome(mut welcome: Box<WelcomeMessage>) -> WireRecord {
+    if welcome.resume_token.is_some() {
+        welcome.resume_token = Some("[redacted]".to_string());
+    }
+    WireRecord::Welcome(welcome)
+}
+
+/// A fresh 128-bit resume token drawn from the OS random source.
+///
+/// # Panics
+///
+/// Panics when the OS random source is unavailable.
+pub(super) fn new_resume_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("OS random source unavailable");
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Compare without an early exit, so response timing does not reveal how
+/// much of a guess matched.
+pub(super) fn token_eq(expected: &str, actual: &str) -> bool {
+    let (expected, actual) = (expected.as_bytes(), actual.as_bytes());
+    let mut diff = expected.len() ^ actual.len();
+    for (index, byte) in expected.iter().enumerate() {
+        diff |= usize::from(byte ^ actual.get(index).copied().unwrap_or(!byte));
+    }
+    diff == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(auth: Option<&str>, observer: Option<&str>) -> ServerConfig {
+        ServerConfig {
+            auth_token: auth.map(str::to_string),
+            observer_token: observer.map(str::to_string),
+            ..ServerConfig::default()
+        }
+    }
+
+    #[test]
+    fn tokens_grant_full_or_observer_access() {
+        let full = Some(Access::Full { priority: 0 });
+        assert_eq!(authorize(&config(None, None), None), full);
+        let mut config = config(Some("lab-secret"), Some("watch"));
+        assert_eq!(authorize(&config, Some("lab-secret")), full);
+        assert_eq!(
+            authorize(&config, Some("watch")),
+            Some(Access::ObserverOnly)
+        );
+        assert_eq!(authorize(&config, Some("lab-secre")), None);
+        assert_eq!(authorize(&config, Some("lab-secret!")), None);
+        assert_eq!(authorize(&config, None), None);
+
+        config.priority_tokens = vec![("gold".to_string(), 10)];
+        assert_eq!(
+            authorize(&config, Some("gold")),
+            Some(Access::Full { priority: 10 })
+        );
+        assert_eq!(authorize(&config, Some("lab-secret")), full);
+    }
+
+    #[test]
+    fn wire_log_lines_never_carry_tokens() {
+        let line = r#"{"type":"hello","seq":1,"token":"lab-secret"}"#;
+        let redacted = redact_token(line).unwrap();
+        assert!(!redacted.contains("lab-secret"));
+        assert!(redacted.contains("[redacted]"));
+        assert_eq!(redact_token(r#"{"type":"command","seq":2}"#), None);
+
+        let line = r#"{"type":"hello","seq":1,"resume":{"token":"0123abcd","since_step":9}}"#;
+        let redacted = redact_token(line).unwrap();
+        assert!(!redacted.contains("0123abcd"));
+        assert!(redacted.contains("since_step"));
+    }
+
+    #[test]
+    fn resume_tokens_are_unique() {
+        let first = new_resume_token();
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, new_resume_token());
+        assert!(token_eq(&first, &first.clone()));
+    }
+}
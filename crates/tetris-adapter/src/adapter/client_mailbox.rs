@@ -4,7 +4,10 @@ use std::sync::Arc;
 
 use tokio::sync::{mpsc, watch};
 
-use crate::adapter::protocol::{AckMessage, ErrorMessage, ObservationMessage, WelcomeMessage};
+use crate::adapter::protocol::{
+    AckMessage, ControlStateMessage, ErrorMessage, ObservationMessage, RoomStateMessage,
+    WelcomeMessage,
+};
 
 pub const CLIENT_RELIABLE_QUEUE_CAPACITY: usize = 32;
 
@@ -12,8 +15,12 @@ pub const CLIENT_RELIABLE_QUEUE_CAPACITY: usize = 32;
 pub(super) enum ClientOutbound {
     Ack(AckMessage),
     Error(ErrorMessage),
-    Welcome(WelcomeMessage),
+    Welcome(Box<WelcomeMessage>),
+    RoomState(RoomStateMessage),
+    ControlState(ControlStateMessage),
     ObservationArc(Arc<ObservationMessage>),
+    /// Observations a resumed client missed, oldest first.
+    Backlog(Vec<Arc<ObservationMessage>>),
 }
 
 #[derive(Clone)]
@@ -24,6 +31,17 @@ pub(super) struct ClientOutboundSender {
 }
 
 impl ClientOutboundSender {
+    /// A sender with no connection behind it, held by a detached session
+    /// until it resumes.
+    pub(super) fn detached() -> Self {
+        let (outbound, ..) = client_outbound_channel(1);
+        outbound
+    }
+
+    pub(super) fn same_channel(&self, other: &Self) -> bool {
+        self.reliable_tx.same_channel(&other.reliable_tx)
+    }
+
     pub(super) fn try_send_reliable(&self, message: ClientOutbound) -> bool {
         match self.reliable_tx.try_send(message) {
             Ok(()) => true,
@@ -44,7 +62,12 @@ impl ClientOutboundSender {
         true
     }
 
-    fn request_shutdown(&self) {
+    /// Drop a published observation the writer has not taken yet.
+    pub(super) fn clear_observation(&self) {
+        self.observation_tx.send_replace(None);
+    }
+
+    pub(super) fn request_shutdown(&self) {
         self.shutdown_tx.send_replace(true);
     }
 
@@ -6,8 +6,12 @@ use arrayvec::ArrayVec;
 
 use crate::adapter::command_apply::map_place_error_code;
 use crate::adapter::observation_schedule::ObservationSchedule;
-use crate::adapter::protocol::{StateHash, create_applied_ack, create_error};
-use crate::adapter::runtime::{Adapter, InboundPayload, OutboundMessage};
+use crate::adapter::protocol::{
+    ErrorCode, ObservationMessage, StateHash, create_applied_ack, create_error,
+};
+use crate::adapter::runtime::{
+    Adapter, ClientResponder, InboundPayload, OutboundMessage, SessionLink,
+};
 use crate::adapter::server::build_observation;
 use tetris_core::types::GameAction;
 use tetris_session::engine::replay::transition_hash;
@@ -15,10 +19,13 @@ use tetris_session::engine::session::{GameCommand, SessionRuntime, StepInput, Tr
 
 pub const MAX_COMMANDS_PER_STEP: usize = 32;
 
+/// Sees every step a game loop applies, in order, with its complete input.
+pub type StepTap<'a> = &'a mut dyn FnMut(&SessionRuntime, &StepInput, &Transition);
+
 struct PendingCommand {
     seq: u64,
     command: GameCommand,
-    responder: crate::adapter::runtime::ClientResponder,
+    responder: ClientResponder,
 }
 
 /// Transport-independent protocol driver used by synchronous runners and tests.
@@ -59,60 +66,141 @@ impl SessionProtocolDriver {
     }
 
     pub fn handle(&mut self, inbound: crate::adapter::runtime::InboundCommand) {
-        match inbound.payload {
+        let observation = match inbound.payload {
             InboundPayload::SnapshotRequest => {
-                let (seq, events) = self.observations.immediate();
-                let observation = build_observation(
-                    seq,
-                    self.session.logical_step(),
-                    self.session.snapshot(),
-                    &events,
-                );
-                let _ = inbound.responder.send_observation(Arc::new(observation));
+                send_snapshot(&self.session, &mut self.observations, &inbound.responder);
+                return;
             }
             InboundPayload::Command(command) => {
-                let input = StepInput::default().with_remote(command);
-                let transition = self.session.transition(&input);
-                match transition.command_outcomes.first() {
-                    Some(Ok(())) => {
-                        let state_hash =
-                            transition_hash(self.session.snapshot(), 0, &transition.events, &[]);
-                        let _ = inbound.responder.send_ack(create_applied_ack(
-                            inbound.seq,
-                            inbound.seq,
-                            self.session.logical_step(),
-                            StateHash(state_hash),
-                        ));
-                    }
-                    Some(Err(error)) => {
-                        let _ = inbound.responder.send_error(create_error(
-                            inbound.seq,
-                            map_place_error_code(*error),
-                            error.message(),
-                        ));
-                    }
-                    None => unreachable!("one command must produce one outcome"),
-                }
-                for &event in &transition.events {
-                    self.observations.capture_event(event);
-                }
-                for _ in 0..self.post_command_steps {
-                    let idle = self.session.transition(&StepInput::default());
-                    for &event in &idle.events {
-                        self.observations.capture_event(event);
-                    }
-                }
-                let (seq, events) = self.observations.immediate();
-                let observation = build_observation(
+                advance_and_reply(
+                    None,
+                    None,
+                    &mut self.session,
+                    &mut self.observations,
+                    inbound.seq,
+                    &inbound.responder,
+                    Some(command),
+                    u32::from(self.post_command_steps),
+                )
+                .1
+            }
+            InboundPayload::Step(steps) => {
+                advance_and_reply(
+                    None,
+                    None,
+                    &mut self.session,
+                    &mut self.observations,
+                    inbound.seq,
+                    &inbound.responder,
+                    None,
+                    steps,
+                )
+                .1
+            }
+            InboundPayload::Lockstep(_) => return,
+        };
+        let _ = inbound.responder.send_observation(observation);
+    }
+}
+
+/// Apply one controller request, answer it, and build the follow-up snapshot.
+///
+/// A command is applied with exactly one core tick and answered with ack or a
+/// typed error before `idle_steps` further ticks run. A bare step request is
+/// acknowledged after its last tick. The returned transition is the last one
+/// applied.
+#[allow(clippy::too_many_arguments)] // Borrowed loop state; one inbound request per call.
+fn advance_and_reply(
+    mut link: Option<&mut SessionLink>,
+    mut tap: Option<StepTap<'_>>,
+    session: &mut SessionRuntime,
+    observations: &mut ObservationSchedule,
+    seq: u64,
+    responder: &ClientResponder,
+    command: Option<GameCommand>,
+    idle_steps: u32,
+) -> (Transition, Arc<ObservationMessage>) {
+    let step_only = command.is_none();
+    let mut last = None;
+    if let Some(command) = command {
+        let input = StepInput::default().with_remote(command);
+        let transition = apply_step(link.as_deref_mut(), reborrow(&mut tap), session, &input);
+        match transition.command_outcomes.first() {
+            Some(Ok(())) => send_applied_ack(session, seq, responder, &transition),
+            Some(Err(error)) => {
+                let _ = responder.send_error(create_error(
                     seq,
-                    self.session.logical_step(),
-                    self.session.snapshot(),
-                    &events,
-                );
-                let _ = inbound.responder.send_observation(Arc::new(observation));
+                    map_place_error_code(*error),
+                    error.message(),
+                ));
             }
+            None => unreachable!("one command must produce one outcome"),
+        }
+        for &event in &transition.events {
+            observations.capture_event(event);
+        }
+        last = Some(transition);
+    }
+    for _ in 0..idle_steps {
+        let idle = apply_step(
+            link.as_deref_mut(),
+            reborrow(&mut tap),
+            session,
+            &StepInput::default(),
+        );
+        for &event in &idle.events {
+            observations.capture_event(event);
         }
+        last = Some(idle);
     }
+    let transition = last.unwrap_or_default();
+    if step_only {
+        send_applied_ack(session, seq, responder, &transition);
+    }
+    let (obs_seq, events) = observations.immediate();
+    let observation =
+        build_observation(obs_seq, session.logical_step(), session.snapshot(), &events);
+    (transition, Arc::new(observation))
+}
+
+/// Apply one step, through the link's replay recording when there is a link,
+/// then show it to the tap.
+fn apply_step(
+    link: Option<&mut SessionLink>,
+    tap: Option<StepTap<'_>>,
+    session: &mut SessionRuntime,
+    input: &StepInput,
+) -> Transition {
+    let transition = match link {
+        Some(link) => link.transition(session, input),
+        None => session.transition(input),
+    };
+    if let Some(tap) = tap {
+        tap(session, input, &transition);
+    }
+    transition
+}
+
+fn reborrow<'a>(tap: &'a mut Option<StepTap<'_>>) -> Option<StepTap<'a>> {
+    match tap {
+        Some(tap) => Some(&mut **tap),
+        None => None,
+    }
+}
+
+fn send_applied_ack(
+    session: &SessionRuntime,
+    seq: u64,
+    responder: &ClientResponder,
+    transition: &Transition,
+) {
+    let state_hash = transition_hash(session.snapshot(), 0, &transition.events, &[]);
+    let _ = responder.send_ack(create_applied_ack(
+        seq,
+        seq,
+        session.logical_step(),
+        StateHash(state_hash),
+    ));
 }
 
 /// Execute the single authoritative application step shared by every runner.
@@ -120,6 +208,14 @@ impl SessionProtocolDriver {
 /// Snapshot requests observe the latest completed step. Gameplay commands are
 /// applied before local actions, followed by exactly one core tick. Correlated
 /// responses are emitted only after that application step has completed.
+///
+/// While a lockstep controller is attached the wall clock does not advance the
+/// session: only its commands and `step` requests do, local actions are
+/// ignored, and each request is answered with an ack and a fresh observation
+/// tagged with the resulting `logical_step`. The returned transition is then
+/// the last one applied, or empty when no request arrived. The loop switches
+/// clocks at the request that changes the mode, so each request runs under
+/// the mode it was accepted in.
 pub fn step_session(
     adapter: &mut Option<Adapter>,
     session: &mut SessionRuntime,
@@ -127,27 +223,106 @@ pub fn step_session(
     local_actions: &[GameAction],
     has_streaming_subscribers: bool,
 ) -> Transition {
+    step_linked(
+        adapter.as_mut().map(|adapter| &mut adapter.link),
+        None,
+        session,
+        observations,
+        local_actions,
+        has_streaming_subscribers,
+    )
+}
+
+/// [`step_session`] that also shows every applied step to `tap`, including
+/// each step a lockstep request advances, so runners can record what their
+/// session actually applied.
+pub fn step_session_tapped(
+    adapter: &mut Option<Adapter>,
+    tap: StepTap<'_>,
+    session: &mut SessionRuntime,
+    observations: &mut ObservationSchedule,
+    local_actions: &[GameAction],
+    has_streaming_subscribers: bool,
+) -> Transition {
+    step_linked(
+        adapter.as_mut().map(|adapter| &mut adapter.link),
+        Some(tap),
+        session,
+        observations,
+        local_actions,
+        has_streaming_subscribers,
+    )
+}
+
+/// [`step_session`] for any room's link, including server-hosted rooms.
+pub(super) fn step_linked(
+    mut link: Option<&mut SessionLink>,
+    tap: Option<StepTap<'_>>,
+    session: &mut SessionRuntime,
+    observations: &mut ObservationSchedule,
+    local_actions: &[GameAction],
+    has_streaming_subscribers: bool,
+) -> Transition {
+    let mut tap = tap;
+    if let Some(link) = link.as_deref_mut()
+        && link.lockstep
+    {
+        let advanced = step_lockstep(
+            link,
+            reborrow(&mut tap),
+            session,
+            observations,
+            has_streaming_subscribers,
+        );
+        if link.lockstep || advanced.is_some() {
+            link.publish_logical_step(session.logical_step());
+            return advanced.unwrap_or_default();
+        }
+    }
+
     let mut pending = ArrayVec::<PendingCommand, MAX_COMMANDS_PER_STEP>::new();
 
-    if let Some(adapter) = adapter.as_mut() {
+    if let Some(link) = link.as_deref_mut() {
         for _ in 0..MAX_COMMANDS_PER_STEP {
-            let Some(inbound) = adapter.try_recv() else {
+            let Some(inbound) = link.try_recv() else {
                 break;
             };
             match inbound.payload {
                 InboundPayload::SnapshotRequest => {
-                    let (seq, events) = observations.immediate();
-                    let observation =
-                        build_observation(seq, session.logical_step(), session.snapshot(), &events);
-                    let _ = inbound.responder.send_observation(Arc::new(observation));
+                    send_snapshot(session, observations, &inbound.responder);
+                }
+                InboundPayload::Lockstep(true) => {
+                    link.lockstep = true;
+                    break;
+                }
+                InboundPayload::Lockstep(false) => {}
+                InboundPayload::Command(_) | InboundPayload::Step(_) if inbound.lockstep => {
+                    link.lockstep = true;
+                    link.defer(inbound);
+                    break;
                 }
                 InboundPayload::Command(command) => pending.push(PendingCommand {
                     seq: inbound.seq,
                     command,
                     responder: inbound.responder,
                 }),
+                InboundPayload::Step(_) => {
+                    // Only a lockstep controller's step is accepted.
+                    let _ = inbound.responder.send_error(create_error(
+                        inbound.seq,
+                        ErrorCode::InvalidCommand,
+                        "step requires lockstep mode",
+                    ));
+                }
             }
         }
+
+        if link.lockstep && pending.is_empty() {
+            let advanced =
+                step_lockstep(link, tap, session, observations, has_streaming_subscribers);
+            link.publish_logical_step(session.logical_step());
+            return advanced.unwrap_or_default();
+        }
     }
 
     let mut input = StepInput::default();
@@ -155,19 +330,11 @@ pub fn step_session(
         .remote
         .extend(pending.iter().map(|item| item.command.clone()));
     input.local.extend(local_actions.iter().copied());
-    let transition = session.transition(&input);
+    let transition = apply_step(link.as_deref_mut(), tap, session, &input);
 
     for (pending, outcome) in pending.iter().zip(transition.command_outcomes.iter()) {
         match outcome {
-            Ok(()) => {
-                let state_hash = transition_hash(session.snapshot(), 0, &transition.events, &[]);
-                let _ = pending.responder.send_ack(create_applied_ack(
-                    pending.seq,
-                    pending.seq,
-                    session.logical_step(),
-                    StateHash(state_hash),
-                ));
-            }
+            Ok(()) => send_applied_ack(session, pending.seq, &pending.responder, &transition),
             Err(error) => {
                 let _ = pending.responder.send_error(create_error(
                     pending.seq,
@@ -181,13 +348,16 @@ pub fn step_session(
     for &event in &transition.events {
         observations.capture_event(event);
     }
+    if let Some(link) = link.as_deref() {
+        link.publish_logical_step(session.logical_step());
+    }
     if let Some((seq, events)) = observations.after_tick(session.game())
         && has_streaming_subscribers
-        && let Some(adapter) = adapter.as_ref()
+        && let Some(link) = link.as_deref()
     {
         let observation =
             build_observation(seq, session.logical_step(), session.snapshot(), &events);
-        let _ = adapter.send(OutboundMessage::BroadcastObservationArc {
+        let _ = link.send(OutboundMessage::BroadcastObservationArc {
             obs: Arc::new(observation),
         });
     }
@@ -195,6 +365,67 @@ pub fn step_session(
     transition
 }
 
+/// Drain lockstep requests until the mode ends; returns the last transition
+/// applied, or `None` when no request advanced the session.
+fn step_lockstep(
+    link: &mut SessionLink,
+    mut tap: Option<StepTap<'_>>,
+    session: &mut SessionRuntime,
+    observations: &mut ObservationSchedule,
+    has_streaming_subscribers: bool,
+) -> Option<Transition> {
+    let mut last = None;
+    for _ in 0..MAX_COMMANDS_PER_STEP {
+        let Some(inbound) = link.try_recv() else {
+            break;
+        };
+        let (command, idle_steps) = match inbound.payload {
+            InboundPayload::SnapshotRequest => {
+                send_snapshot(session, observations, &inbound.responder);
+                continue;
+            }
+            InboundPayload::Lockstep(true) => continue,
+            InboundPayload::Lockstep(false) => {
+                link.lockstep = false;
+                break;
+            }
+            InboundPayload::Command(_) if !inbound.lockstep => {
+                link.lockstep = false;
+                link.defer(inbound);
+                break;
+            }
+            InboundPayload::Command(command) => (Some(command), 0),
+            InboundPayload::Step(steps) => (None, steps),
+        };
+        let (transition, observation) = advance_and_reply(
+            Some(link),
+            reborrow(&mut tap),
+            session,
+            observations,
+            inbound.seq,
+            &inbound.responder,
+            command,
+            idle_steps,
+        );
+        let _ = inbound.responder.send_observation(Arc::clone(&observation));
+        if has_streaming_subscribers {
+            let _ = link.send(OutboundMessage::BroadcastObservationArc { obs: observation });
+        }
+        last = Some(transition);
+    }
+    last
+}
+
+fn send_snapshot(
+    session: &SessionRuntime,
+    observations: &mut ObservationSchedule,
+    responder: &ClientResponder,
+) {
+    let (seq, events) = observations.immediate();
+    let observation = build_observation(seq, session.logical_step(), session.snapshot(), &events);
+    let _ = responder.send_observation(Arc::new(observation));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
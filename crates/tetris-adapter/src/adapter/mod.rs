@@ -19,9 +19,14 @@
 //!
 //! ## Client → Server
 //!
-//! - **hello**: Initial handshake with client info and requested capabilities
+//! - **hello**: Initial handshake with client info and requested capabilities,
+//!   optionally resuming an earlier session
 //! - **command**: Execute game actions or place piece at specific position
-//! - **control**: Claim or release controller status
+//! - **control**: Claim, release, or hand off controller status
+//! - **step**: Advance `n` logical steps (lockstep controllers only)
+//! - **resync**: Request a keyframe (delta observation clients only)
+//! - **subscribe**: Set observation rate, event-only mode, and field filter
+//! - **room**: List, create, join, or leave named game rooms
 //!
 //! ## Server → Client
 //!
@@ -29,6 +34,8 @@
 //! - **observation**: Full game state snapshot (board, active piece, score, etc.)
 //! - **ack**: Command acknowledgment
 //! - **error**: Error response with code and message
+//! - **room_state**: Room membership and role after a room request
+//! - **control_state**: Controller and waiting queue after a control change
 //!
 //! # Command Modes
 //!
@@ -37,6 +44,13 @@
 //! - **action**: Send individual game actions (moveLeft, rotateCw, hardDrop, etc.)
 //! - **place**: Send target position, server calculates actions to reach it
 //!
+//! A controller may also negotiate **lockstep** in hello. The session then
+//! advances only on that controller's commands and `step` messages, so training
+//! runs are deterministic regardless of agent latency.
+//!
+//! Listing `binary` in hello `formats` switches server messages after the
+//! welcome to compact length-prefixed frames (`tetris_adapter_protocol::binary`).
+//!
 //! # Environment Variables
 //!
 //! Configure the adapter using environment variables:
@@ -75,15 +89,20 @@
 //! {"type":"hello","seq":1,"ts":1234567890,"client":{"name":"test","version":"1.0.0"},"protocol_version":"3.0.0","formats":["json"],"requested":{"stream_observations":true,"command_mode":"action"}}
 //! ```
 
+mod auth;
 mod client_mailbox;
 pub mod command_apply;
 pub mod game_loop;
 pub mod observation;
 pub mod observation_schedule;
 pub use tetris_adapter_protocol::protocol;
+mod rooms;
 pub mod runtime;
 pub mod server;
 pub mod server_config;
+mod subscription;
+pub mod transport;
+pub mod websocket;
 mod wire_log;
 
 // Keep the adapter root intentionally small. Wire types and server internals stay
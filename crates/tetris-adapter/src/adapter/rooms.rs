@@ -0,0 +1,330 @@
+//! Named game rooms served by one adapter server.
+//!
+//! Every client starts in the default room, whose game belongs to the host
+//! process and is driven through [`crate::adapter::Adapter`]. Clients may
+//! create further rooms; each has its own [`SessionRuntime`], seed, and
+//! broker (controller and observers). One scheduler task advances every
+//! server-hosted room on the fixed game tick, so rooms add no threads.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+use tokio::sync::{RwLock, mpsc, watch};
+use tokio::time::Duration;
+
+use crate::adapter::client_mailbox::ClientOutboundSender;
+use crate::adapter::game_loop::step_linked;
+use crate::adapter::observation_schedule::ObservationSchedule;
+use crate::adapter::protocol::{ControlPolicy, ObservationMessage, RoomInfo};
+use crate::adapter::runtime::{
+    AdapterStatus, ClientResponder, InboundCommand, InboundPayload, SessionLink, SessionLinkServer,
+    recording_metadata,
+};
+use crate::adapter::server::{
+    BrokerState, OutboundReceiver, spawn_dispatcher, spawn_lease_watchdog,
+};
+use tetris_core::types::TICK_MS;
+use tetris_session::engine::replay::ReplayMetadata;
+use tetris_session::engine::session::SessionRuntime;
+
+/// Observations a room keeps for clients that resume after a disconnect.
+const RESUME_HISTORY_LEN: usize = 256;
+
+pub(super) struct Room {
+    pub(super) name: String,
+    /// Seed of a server-hosted game; `None` for the default room.
+    pub(super) seed: Option<u32>,
+    pub(super) broker: RwLock<BrokerState>,
+    pub(super) command_tx: mpsc::Sender<InboundCommand>,
+    status_tx: Option<watch::Sender<AdapterStatus>>,
+    /// Controller lease in logical steps of this room's game.
+    pub(super) lease_steps: Option<u64>,
+    /// Recent observations, kept only while sessions can resume.
+    history: Option<Mutex<VecDeque<Arc<ObservationMessage>>>>,
+}
+
+impl Room {
+    /// `logical_step` is the clock published by the room's game loop;
+    /// `resumable` rooms keep recent observations for resumed sessions.
+    pub(super) fn new(
+        name: &str,
+        seed: Option<u32>,
+        policy: &ControlPolicy,
+        logical_step: Arc<AtomicU64>,
+        command_tx: mpsc::Sender<InboundCommand>,
+        status_tx: Option<watch::Sender<AdapterStatus>>,
+        resumable: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            seed,
+            broker: RwLock::new(BrokerState::new(policy.promotion_order, logical_step)),
+            command_tx,
+            status_tx,
+            lease_steps: policy.lease_steps,
+            history: resumable.then(|| Mutex::new(VecDeque::with_capacity(RESUME_HISTORY_LEN))),
+        }
+    }
+
+    /// Keep a broadcast observation for clients that resume later.
+    pub(super) fn record(&self, obs: &Arc<ObservationMessage>) {
+        let Some(history) = self.history.as_ref() else {
+            return;
+        };
+        let mut history = history.lock().unwrap_or_else(PoisonError::into_inner);
+        if history.len() == RESUME_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(Arc::clone(obs));
+    }
+
+    /// Retained observations after `since_step`, oldest first.
+    pub(super) fn observations_since(&self, since_step: u64) -> Vec<Arc<ObservationMessage>> {
+        let Some(history) = self.history.as_ref() else {
+            return Vec::new();
+        };
+        let history = history.lock().unwrap_or_else(PoisonError::into_inner);
+        history
+            .iter()
+            .filter(|obs| obs.logical_step > since_step)
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn is_hosted(&self) -> bool {
+        self.seed.is_some()
+    }
+
+    /// Publish this room's broker status to its game loop.
+    ///
+    /// A change of lockstep mode is first queued behind the requests already
+    /// sent, so the game loop switches clocks in request order. The broker
+    /// stays locked until it is queued, keeping announcements in order too.
+    pub(super) async fn emit_status(&self) {
+        let Some(tx) = self.status_tx.as_ref() else {
+            return;
+        };
+        let mut broker = self.broker.write().await;
+        if let Some((controller_id, lockstep)) = broker.take_lockstep_change() {
+            let _ = self
+                .command_tx
+                .send(InboundCommand {
+                    client_id: controller_id.unwrap_or(0),
+                    seq: 0,
+                    payload: InboundPayload::Lockstep(lockstep),
+                    lockstep,
+                    responder: ClientResponder::new(ClientOutboundSender::detached()),
+                })
+                .await;
+        }
+        tx.send_replace(broker.status());
+    }
+
+    async fn info(&self) -> RoomInfo {
+        let status = self.broker.read().await.status();
+        RoomInfo {
+            name: self.name.clone(),
+            seed: self.seed,
+            clients: status.client_count,
+            controller_id: status.controller_id.map(|id| id as u64),
+        }
+    }
+}
+
+/// Open rooms; the default room is always first and never closes.
+pub(super) struct RoomRegistry {
+    rooms: Vec<Arc<Room>>,
+    max_rooms: usize,
+    max_pending_commands: usize,
+    policy: ControlPolicy,
+    resumable: bool,
+    /// Replay tape of the default room; hosted rooms record beside it.
+    record_path: Option<PathBuf>,
+    record_metadata: ReplayMetadata,
+    scheduler: Option<mpsc::UnboundedSender<HostedRoom>>,
+}
+
+impl RoomRegistry {
+    pub(super) fn new(
+        default_room: Room,
+        max_rooms: usize,
+        max_pending_commands: usize,
+        policy: ControlPolicy,
+        resumable: bool,
+        record_path: Option<PathBuf>,
+        record_metadata: ReplayMetadata,
+    ) -> Self {
+        Self {
+            rooms: vec![Arc::new(default_room)],
+            max_rooms: max_rooms.max(1),
+            max_pending_commands,
+            policy,
+            resumable,
+            record_path,
+            record_metadata,
+            scheduler: None,
+        }
+    }
+
+    pub(super) fn default_room(&self) -> Arc<Room> {
+        Arc::clone(&self.rooms[0])
+    }
+
+    pub(super) fn get(&self, name: &str) -> Option<Arc<Room>> {
+        self.rooms.iter().find(|room| room.name == name).cloned()
+    }
+
+    /// The room holding the session that `resume_token` was issued to.
+    pub(super) async fn find_session(&self, resume_token: &str) -> Option<Arc<Room>> {
+        for room in &self.rooms {
+            if room
+                .broker
+                .read()
+                .await
+                .find_session(resume_token)
+                .is_some()
+            {
+                return Some(Arc::clone(room));
+            }
+        }
+        None
+    }
+
+    pub(super) async fn list(&self) -> Vec<RoomInfo> {
+        let mut rooms = Vec::with_capacity(self.rooms.len());
+        for room in &self.rooms {
+            rooms.push(room.info().await);
+        }
+        rooms
+    }
+
+    /// Open a server-hosted room and start its game on the shared scheduler.
+    pub(super) fn create(&mut self, name: &str, seed: u32) -> Result<Arc<Room>, String> {
+        if self.get(name).is_some() {
+            return Err(format!("room {name} already exists"));
+        }
+        if self.rooms.len() >= self.max_rooms {
+            return Err(format!("room limit of {} reached", self.max_rooms));
+        }
+
+        let (mut link, server) = SessionLink::new(self.max_pending_commands);
+        if let Some(base) = self.record_path.as_deref() {
+            let path = room_record_path(base, name);
+            let file = File::create(&path).map_err(|error| {
+                format!(
+                    "failed to create room replay recording {}: {error}",
+                    path.display()
+                )
+            })?;
+            link.record_to(file, recording_metadata(&self.record_metadata, Some(name)));
+        }
+        let SessionLinkServer {
+            command_tx,
+            observation_rx,
+            status_tx,
+            logical_step,
+        } = server;
+        let room = Arc::new(Room::new(
+            name,
+            Some(seed),
+            &self.policy,
+            logical_step,
+            command_tx,
+            Some(status_tx),
+            self.resumable,
+        ));
+        spawn_dispatcher(
+            Arc::downgrade(&room),
+            OutboundReceiver::latest(observation_rx),
+        );
+        spawn_lease_watchdog(Arc::downgrade(&room));
+
+        let session = SessionRuntime::new(seed);
+        let observations = ObservationSchedule::from_env(session.game());
+        let hosted = HostedRoom {
+            room: Arc::downgrade(&room),
+            link,
+            session,
+            observations,
+        };
+        // The scheduler only stops once this registry drops its sender.
+        let _ = self
+            .scheduler
+            .get_or_insert_with(spawn_scheduler)
+            .send(hosted);
+
+        self.rooms.push(Arc::clone(&room));
+        Ok(room)
+    }
+
+    /// Close a hosted room; its game stops at the next scheduler tick once
+    /// the last client handle is dropped.
+    pub(super) fn close(&mut self, room: &Arc<Room>) {
+        if room.is_hosted() {
+            self.rooms.retain(|open| !Arc::ptr_eq(open, room));
+        }
+    }
+}
+
+/// `game.ttr` records room `duel` to `game.duel.ttr`; room names are
+/// alphanumeric with dashes, so they are safe in file names.
+fn room_record_path(base: &Path, room: &str) -> PathBuf {
+    let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+    let mut file_name = format!("{stem}.{room}");
+    if let Some(extension) = base.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    base.with_file_name(file_name)
+}
+
+struct HostedRoom {
+    room: Weak<Room>,
+    link: SessionLink,
+    session: SessionRuntime,
+    observations: ObservationSchedule,
+}
+
+impl HostedRoom {
+    fn step(&mut self) {
+        let streaming = self.link.status().streaming_count > 0;
+        step_linked(
+            Some(&mut self.link),
+            None,
+            &mut self.session,
+            &mut self.observations,
+            &[],
+            streaming,
+        );
+    }
+}
+
+fn spawn_scheduler() -> mpsc::UnboundedSender<HostedRoom> {
+    let (tx, mut new_rooms) = mpsc::unbounded_channel::<HostedRoom>();
+    tokio::spawn(async move {
+        let mut rooms: Vec<HostedRoom> = Vec::new();
+        let mut tick = tokio::time::interval(Duration::from_millis(TICK_MS as u64));
+        let mut accepting = true;
+        loop {
+            tokio::select! {
+                hosted = new_rooms.recv(), if accepting => match hosted {
+                    Some(hosted) => rooms.push(hosted),
+                    None => accepting = false,
+                },
+                _ = tick.tick() => {
+                    rooms.retain(|hosted| hosted.room.strong_count() > 0);
+                    if !accepting && rooms.is_empty() {
+                        break;
+                    }
+                    for hosted in &mut rooms {
+                        hosted.step();
+                    }
+                }
+            }
+        }
+    });
+    tx
+}
@@ -1,25 +1,35 @@
 //! Adapter runtime integration.
 //!
-//! Bridges the sync game loop with the async TCP server.
+//! Bridges the sync game loop with the async adapter server.
 
+use std::fs::File;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use tokio::runtime::Runtime;
 use tokio::sync::{mpsc, oneshot, watch};
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::adapter::client_mailbox::{ClientOutbound, ClientOutboundSender};
 use crate::adapter::protocol::{AckMessage, ErrorMessage, ObservationMessage};
-use crate::adapter::server::{ServerConfig, ServerState, run_server_with_startup};
+use crate::adapter::server::{Endpoint, ServerConfig, ServerState, run_server_with_startup};
+use crate::adapter::transport::Listening;
+use tetris_session::engine::replay::{
+    META_DATE, META_MODE, ReplayMetadata, ReplayRecorder, format_utc,
+};
 pub use tetris_session::engine::session::GameCommand as ClientCommand;
+use tetris_session::engine::session::{SessionRuntime, StepInput, Transition};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct AdapterStatus {
     pub client_count: u16,
     pub controller_id: Option<usize>,
     pub streaming_count: u16,
+    /// The live controller negotiated lockstep. The game loop takes its clock
+    /// from the requests it drains, not from this status.
+    pub lockstep: bool,
 }
 
 /// Message delivered to the game loop.
@@ -28,6 +38,8 @@ pub struct InboundCommand {
     pub client_id: usize,
     pub seq: u64,
     pub payload: InboundPayload,
+    /// The sender was the lockstep controller when the request was accepted.
+    pub lockstep: bool,
     pub(super) responder: ClientResponder,
 }
 
@@ -63,6 +75,10 @@ pub enum InboundPayload {
     Command(ClientCommand),
     /// Request an immediate observation snapshot for this client.
     SnapshotRequest,
+    /// Lockstep controller request to advance idle logical steps.
+    Step(u32),
+    /// The room's lockstep mode changed; queued behind earlier requests.
+    Lockstep(bool),
 }
 
 /// Outbound message to be delivered by the server.
@@ -71,13 +87,182 @@ pub enum OutboundMessage {
     BroadcastObservationArc { obs: Arc<ObservationMessage> },
 }
 
-/// Running adapter instance.
-pub struct Adapter {
-    _rt: Runtime,
+/// Game-loop end of one room's channels: inbound requests, the latest
+/// observation slot, the room's broker status, and the session's logical step.
+pub(super) struct SessionLink {
     cmd_rx: mpsc::Receiver<InboundCommand>,
     observation_tx: watch::Sender<Option<Arc<ObservationMessage>>>,
     status_rx: watch::Receiver<AdapterStatus>,
-    listen_addr: SocketAddr,
+    logical_step: Arc<AtomicU64>,
+    recording: Recording,
+    /// Why the replay recording stopped or never started, until taken.
+    recording_error: Option<String>,
+    /// The game loop's clock is owned by a lockstep controller.
+    pub(super) lockstep: bool,
+    /// Request held back while the game loop switched clocks.
+    deferred: Option<InboundCommand>,
+}
+
+/// Replay recording of a link's session.
+#[derive(Default)]
+enum Recording {
+    #[default]
+    Off,
+    /// Waiting for the session's first step, which fixes the tape's seed.
+    Pending(File, ReplayMetadata),
+    On(ReplayRecorder<File>),
+}
+
+/// Server end of a [`SessionLink`].
+pub(super) struct SessionLinkServer {
+    pub(super) command_tx: mpsc::Sender<InboundCommand>,
+    pub(super) observation_rx: watch::Receiver<Option<Arc<ObservationMessage>>>,
+    pub(super) status_tx: watch::Sender<AdapterStatus>,
+    /// Read by controller leases, which count logical steps.
+    pub(super) logical_step: Arc<AtomicU64>,
+}
+
+impl SessionLink {
+    pub(super) fn new(max_pending_commands: usize) -> (Self, SessionLinkServer) {
+        let (command_tx, cmd_rx) = mpsc::channel(max_pending_commands.max(1));
+        let (observation_tx, observation_rx) = watch::channel(None);
+        let (status_tx, status_rx) = watch::channel(AdapterStatus {
+            client_count: 0,
+            controller_id: None,
+            streaming_count: 0,
+            lockstep: false,
+        });
+        let logical_step = Arc::new(AtomicU64::new(0));
+        (
+            Self {
+                cmd_rx,
+                observation_tx,
+                status_rx,
+                logical_step: Arc::clone(&logical_step),
+                recording: Recording::Off,
+                recording_error: None,
+                lockstep: false,
+                deferred: None,
+            },
+            SessionLinkServer {
+                command_tx,
+                observation_rx,
+                status_tx,
+                logical_step,
+            },
+        )
+    }
+
+    pub(super) fn try_recv(&mut self) -> Option<InboundCommand> {
+        self.deferred.take().or_else(|| self.cmd_rx.try_recv().ok())
+    }
+
+    /// Hand `inbound` back to the next [`SessionLink::try_recv`].
+    pub(super) fn defer(&mut self, inbound: InboundCommand) {
+        self.deferred = Some(inbound);
+    }
+
+    pub(super) fn is_closed(&self) -> bool {
+        self.cmd_rx.is_closed()
+    }
+
+    pub(super) fn try_recv_status(&mut self) -> Option<AdapterStatus> {
+        match self.status_rx.has_changed() {
+            Ok(true) => Some(*self.status_rx.borrow_and_update()),
+            Ok(false) | Err(_) => None,
+        }
+    }
+
+    pub(super) fn status(&self) -> AdapterStatus {
+        *self.status_rx.borrow()
+    }
+
+    pub(super) fn publish_logical_step(&self, step: u64) {
+        self.logical_step.store(step, Ordering::Relaxed);
+    }
+
+    /// Record every step applied through [`SessionLink::transition`] to
+    /// `file` as a replay tape with `metadata` in its header.
+    pub(super) fn record_to(&mut self, file: File, metadata: ReplayMetadata) {
+        self.recording = Recording::Pending(file, metadata);
+    }
+
+    /// Apply one step to the session, appending it to the replay recording.
+    ///
+    /// A recording only starts with the session's first step, so the tape
+    /// replays from its seed, and stops at the first write error, leaving a
+    /// verifiable prefix. Either failure is kept for
+    /// [`SessionLink::take_recording_error`].
+    pub(super) fn transition(
+        &mut self,
+        session: &mut SessionRuntime,
+        input: &StepInput,
+    ) -> Transition {
+        self.recording = match std::mem::take(&mut self.recording) {
+            Recording::Pending(file, metadata) if session.logical_step() == 0 => {
+                match ReplayRecorder::with_metadata(file, session.snapshot().seed, &metadata) {
+                    Ok(recorder) => Recording::On(recorder),
+                    Err(error) => {
+                        self.stop_recording(format!("adapter recording stopped: {error}"))
+                    }
+                }
+            }
+            Recording::Pending(..) => self.stop_recording(format!(
+                "adapter recording not started: the session was already at step {}, \
+                 and tapes replay from step 0",
+                session.logical_step()
+            )),
+            recording => recording,
+        };
+        let transition = session.transition(input);
+        if let Recording::On(recorder) = &mut self.recording
+            && let Err(error) = recorder.record(session, input, &transition)
+        {
+            self.recording = self.stop_recording(format!("adapter recording stopped: {error}"));
+        }
+        transition
+    }
+
+    fn stop_recording(&mut self, error: String) -> Recording {
+        self.recording_error = Some(error);
+        Recording::Off
+    }
+
+    pub(super) fn take_recording_error(&mut self) -> Option<String> {
+        self.recording_error.take()
+    }
+
+    pub(super) fn send(&self, msg: OutboundMessage) -> bool {
+        let OutboundMessage::BroadcastObservationArc { obs } = msg;
+        self.observation_tx.send_replace(Some(obs));
+        true
+    }
+}
+
+/// Metadata for a new adapter tape: `mode`, the start `date`, and the room
+/// for hosted rooms, then the configured entries, which win on conflict.
+pub(super) fn recording_metadata(
+    configured: &ReplayMetadata,
+    room: Option<&str>,
+) -> ReplayMetadata {
+    let mut metadata = ReplayMetadata::default();
+    metadata.set(META_MODE, "adapter");
+    metadata.set(META_DATE, format_utc(SystemTime::now()));
+    if let Some(room) = room {
+        metadata.set("room", room);
+    }
+    for (key, value) in configured.iter() {
+        metadata.set(key, value);
+    }
+    metadata
+}
+
+/// Running adapter instance.
+pub struct Adapter {
+    _rt: Runtime,
+    pub(super) link: SessionLink,
+    endpoint: Endpoint,
+    websocket_addr: Option<SocketAddr>,
 }
 
 impl Adapter {
@@ -95,39 +280,42 @@ impl Adapter {
     /// Start the adapter with an explicit configuration.
     ///
     /// This returns only after the async server has completed its authoritative
-    /// TCP bind, so a successful adapter always has a valid listen address.
+    /// bind, so a successful adapter always has a valid endpoint.
     pub fn start(config: ServerConfig) -> anyhow::Result<Self> {
         if ServerState::is_disabled() {
             return Err(anyhow::anyhow!("AI adapter is disabled"));
         }
 
-        let max_pending = config.max_pending_commands.max(1);
-        let (cmd_tx, cmd_rx) = mpsc::channel::<InboundCommand>(max_pending);
-        let (observation_tx, observation_rx) = watch::channel(None);
-        let (status_tx, status_rx) = watch::channel(AdapterStatus {
-            client_count: 0,
-            controller_id: None,
-            streaming_count: 0,
-        });
-        let (startup_tx, startup_rx) = oneshot::channel::<Result<SocketAddr, String>>();
+        let (mut link, server) = SessionLink::new(config.max_pending_commands);
+        if let Some(path) = config.record_path.as_ref() {
+            let file = File::create(path).map_err(|error| {
+                anyhow::anyhow!(
+                    "failed to create adapter replay recording {}: {error}",
+                    path.display()
+                )
+            })?;
+            link.record_to(file, recording_metadata(&config.record_metadata, None));
+        }
+        let (startup_tx, startup_rx) = oneshot::channel::<Result<Listening, String>>();
 
         let rt = Runtime::new()
             .map_err(|error| anyhow::anyhow!("failed to create adapter runtime: {error}"))?;
         rt.spawn(async move {
             let _ = run_server_with_startup(
                 config,
-                cmd_tx,
-                observation_rx,
+                server.command_tx,
+                server.observation_rx,
                 startup_tx,
-                Some(status_tx),
+                Some(server.status_tx),
+                server.logical_step,
             )
             .await;
         });
 
-        let listen_addr = match rt
+        let listening = match rt
             .block_on(async move { tokio::time::timeout(Duration::from_secs(2), startup_rx).await })
         {
-            Ok(Ok(Ok(address))) => address,
+            Ok(Ok(Ok(listening))) => listening,
             Ok(Ok(Err(error))) => return Err(anyhow::anyhow!(error)),
             Ok(Err(_)) => return Err(anyhow::anyhow!("AI adapter startup task stopped early")),
             Err(_) => return Err(anyhow::anyhow!("AI adapter startup timed out")),
@@ -135,26 +323,52 @@ impl Adapter {
 
         Ok(Self {
             _rt: rt,
-            cmd_rx,
-            observation_tx,
-            status_rx,
-            listen_addr,
+            link,
+            endpoint: listening.endpoint,
+            websocket_addr: listening.websocket,
         })
     }
 
     pub fn try_recv(&mut self) -> Option<InboundCommand> {
-        self.cmd_rx.try_recv().ok()
+        self.link.try_recv()
     }
 
     pub fn try_recv_status(&mut self) -> Option<AdapterStatus> {
-        match self.status_rx.has_changed() {
-            Ok(true) => Some(*self.status_rx.borrow_and_update()),
-            Ok(false) | Err(_) => None,
-        }
+        self.link.try_recv_status()
     }
 
-    pub fn listen_addr(&self) -> SocketAddr {
-        self.listen_addr
+    /// Whether the game clock is currently owned by a lockstep controller,
+    /// as of the last [`step_session`](crate::adapter::game_loop::step_session).
+    pub fn lockstep(&self) -> bool {
+        self.link.lockstep
+    }
+
+    /// Why the configured replay recording stopped or never started, once.
+    ///
+    /// Recording needs the adapter to see the session's first step; a
+    /// session that had already stepped is not recorded.
+    pub fn take_recording_error(&mut self) -> Option<String> {
+        self.link.take_recording_error()
+    }
+
+    /// The bound TCP address; `None` on other transports.
+    pub fn listen_addr(&self) -> Option<SocketAddr> {
+        self.endpoint.tcp()
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// The bound WebSocket listener address, when one was configured.
+    pub fn websocket_addr(&self) -> Option<SocketAddr> {
+        self.websocket_addr
+    }
+
+    /// Whether the server has stopped, as a stdio adapter does once its
+    /// client disconnects.
+    pub fn is_closed(&self) -> bool {
+        self.link.is_closed()
     }
 
     /// Publishes a best-effort outbound message.
@@ -162,8 +376,6 @@ impl Adapter {
     /// Reliable request replies bypass this bridge through [`ClientResponder`].
     /// The boolean only reports whether this best-effort publication was accepted.
     pub fn send(&self, msg: OutboundMessage) -> bool {
-        let OutboundMessage::BroadcastObservationArc { obs } = msg;
-        self.observation_tx.send_replace(Some(obs));
-        true
+        self.link.send(msg)
     }
 }
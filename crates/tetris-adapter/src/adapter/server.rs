@@ -1,29 +1,44 @@
-//! TCP server for AI adapter
+//! Server for AI adapter
 //!
 //! Handles incoming connections and manages client lifecycle.
-//! Uses tokio for async networking.
+//! Uses tokio for async networking over TCP, Unix sockets, or stdio.
 
+use std::cmp::Reverse;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use tokio::io::BufWriter;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::TcpListener;
 use tokio::sync::{RwLock, mpsc, oneshot, watch};
-use tokio::time::{Duration, MissedTickBehavior};
+use tokio::time::{Duration, Instant, MissedTickBehavior};
 
+use crate::adapter::auth::{
+    Access, authorize, new_resume_token, redact_token, redacted_welcome, token_eq,
+};
 use crate::adapter::client_mailbox::{
     ClientOutbound, ClientOutboundSender, client_outbound_channel,
 };
 use crate::adapter::protocol::*;
+use crate::adapter::rooms::{Room, RoomRegistry};
 use crate::adapter::runtime::{
     AdapterStatus, ClientCommand, ClientResponder, InboundCommand, InboundPayload, OutboundMessage,
 };
-use crate::adapter::wire_log::{WireRecord, spawn_wire_logger, try_log as log_wire_record};
-use tetris_core::types::{GameAction, Rotation};
+use crate::adapter::subscription::{Admission, ClientSubscription};
+use crate::adapter::transport::{Connection, Listener, Listening};
+use crate::adapter::websocket;
+use crate::adapter::wire_log::{
+    ClientWireLog, WireEntry, WireRecord, spawn_wire_logger, try_log as log_wire_record,
+};
+use tetris_adapter_protocol::binary::{BinaryEncode, write_frame};
+use tetris_adapter_protocol::delta::DeltaEncoder;
+use tetris_adapter_protocol::view::{FeatureMask, ObservationView};
+use tetris_core::types::{GameAction, Rotation, TICK_MS};
 
 pub use crate::adapter::client_mailbox::CLIENT_RELIABLE_QUEUE_CAPACITY;
 pub use crate::adapter::observation::build_observation;
 pub use crate::adapter::server_config::ServerConfig;
+pub use crate::adapter::transport::{Endpoint, Transport};
 pub use crate::adapter::wire_log::WIRE_LOG_QUEUE_CAPACITY;
 
 use arrayvec::ArrayVec;
@@ -31,6 +46,7 @@ use arrayvec::ArrayVec;
 const BACKPRESSURE_RETRY_AFTER_MS: u64 = 50;
 pub const MAX_INBOUND_LINE_BYTES: usize = 64 * 1024;
 const CLIENT_WRITER_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(100);
+const BINARY_FIELDS_ERROR: &str = "subscription.fields requires the json format";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum BoundedLineRead {
@@ -176,7 +192,7 @@ async fn write_json_and_log<W, T, F>(
     writer: &mut BufWriter<W>,
     buf: &mut Vec<u8>,
     value: T,
-    log_tx: Option<&mpsc::Sender<WireRecord>>,
+    log_tx: Option<&ClientWireLog>,
     wrap: F,
 ) -> std::io::Result<()>
 where
@@ -192,13 +208,32 @@ where
     Ok(())
 }
 
+async fn write_frame_and_log<W, T>(
+    writer: &mut BufWriter<W>,
+    buf: &mut Vec<u8>,
+    value: &T,
+    log_tx: Option<&ClientWireLog>,
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: BinaryEncode,
+{
+    buf.clear();
+    write_frame(value, buf);
+    writer.write_all(buf).await?;
+    if log_tx.is_some() {
+        log_wire_record(log_tx, WireRecord::Frame(Arc::from(buf.as_slice())));
+    }
+    Ok(())
+}
+
 async fn enforce_strict_seq(
-    state: &Arc<ServerState>,
+    room: &Room,
     outbound: &ClientOutboundSender,
     client_id: usize,
     seq: u64,
 ) -> bool {
-    if !check_and_update_seq(state, client_id, seq).await {
+    if !check_and_update_seq(room, client_id, seq).await {
         send_client_error(
             outbound,
             seq,
@@ -211,13 +246,13 @@ async fn enforce_strict_seq(
 }
 
 async fn enforce_handshake_and_seq(
-    state: &Arc<ServerState>,
+    room: &Room,
     outbound: &ClientOutboundSender,
     client_id: usize,
     seq: u64,
     noun: &str,
 ) -> bool {
-    if !is_handshaken(state, client_id).await {
+    if !is_handshaken(room, client_id).await {
         send_client_error(
             outbound,
             seq,
@@ -226,57 +261,394 @@ async fn enforce_handshake_and_seq(
         );
         return false;
     }
-    enforce_strict_seq(state, outbound, client_id, seq).await
+    enforce_strict_seq(room, outbound, client_id, seq).await
 }
 
 /// Shared server state
 pub struct ServerState {
     config: ServerConfig,
-    broker: Arc<RwLock<BrokerState>>,
-    status_tx: Option<watch::Sender<AdapterStatus>>,
+    rooms: RwLock<RoomRegistry>,
+    /// Shared by every listener so ids stay unique across transports.
+    next_client_id: AtomicUsize,
 }
 
+/// Controller and waiting clients in promotion order.
+type ControlView = (Option<usize>, Vec<usize>);
+
+/// Clients and controller of one room.
 #[derive(Default)]
-struct BrokerState {
+pub(super) struct BrokerState {
     clients: Vec<ClientHandle>,
     controller_id: Option<usize>,
+    promotion_order: ControlPromotionOrder,
+    /// Queue tickets handed out so far; a lower ticket has waited longer.
+    next_ticket: u64,
+    /// `seq` of the last `control_state` message.
+    control_seq: u64,
+    /// The room game's logical step, published by its game loop.
+    logical_step: Arc<AtomicU64>,
+    /// Logical step of the controller's assignment or latest request.
+    lease_step: AtomicU64,
+    /// Lockstep mode last announced to the room's game loop.
+    announced_lockstep: bool,
 }
 
 impl BrokerState {
+    pub(super) fn new(
+        promotion_order: ControlPromotionOrder,
+        logical_step: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            promotion_order,
+            logical_step,
+            ..Self::default()
+        }
+    }
+
     fn is_controller(&self, client_id: usize) -> bool {
         self.controller_id == Some(client_id)
     }
 
+    fn client_mut(&mut self, client_id: usize) -> Option<&mut ClientHandle> {
+        self.clients
+            .iter_mut()
+            .find(|client| client.id == client_id)
+    }
+
+    fn set_controller(&mut self, controller_id: Option<usize>) {
+        self.controller_id = controller_id;
+        self.touch_lease();
+    }
+
+    /// Restart the controller's lease from the current logical step.
+    fn touch_lease(&self) {
+        let step = self.logical_step.load(Ordering::Relaxed);
+        self.lease_step.store(step, Ordering::Relaxed);
+    }
+
+    /// Queue a client for control, keeping its place if it already waits.
+    fn enqueue(&mut self, client_id: usize) {
+        self.next_ticket += 1;
+        let ticket = self.next_ticket;
+        if let Some(client) = self.client_mut(client_id) {
+            client.waiting_since.get_or_insert(ticket);
+        }
+    }
+
+    /// Move a client to the back of the queue.
+    fn requeue(&mut self, client_id: usize) {
+        self.next_ticket += 1;
+        let ticket = self.next_ticket;
+        if let Some(client) = self.client_mut(client_id) {
+            client.waiting_since = Some(ticket);
+        }
+    }
+
+    /// Withdraw a client from promotion; reports whether it was waiting.
+    fn dequeue(&mut self, client_id: usize) -> bool {
+        self.client_mut(client_id)
+            .is_some_and(|client| client.waiting_since.take().is_some())
+    }
+
+    /// Live clients waiting for control, in promotion order.
+    fn waiting(&self) -> Vec<usize> {
+        let mut waiting: Vec<&ClientHandle> = self
+            .clients
+            .iter()
+            .filter(|client| {
+                client.waiting_since.is_some()
+                    && !client.observer_only
+                    && client.outbound.is_live()
+                    && !self.is_controller(client.id)
+            })
+            .collect();
+        waiting.sort_by_key(|client| {
+            let ticket = client.waiting_since.unwrap_or_default();
+            match self.promotion_order {
+                ControlPromotionOrder::LowestClientId => (Reverse(0), 0, client.id),
+                ControlPromotionOrder::Fifo => (Reverse(0), ticket, client.id),
+                ControlPromotionOrder::TokenPriority => {
+                    (Reverse(client.priority), ticket, client.id)
+                }
+            }
+        });
+        waiting.into_iter().map(|client| client.id).collect()
+    }
+
+    fn next_controller(&self) -> Option<usize> {
+        self.waiting().first().copied()
+    }
+
+    fn control_view(&self) -> ControlView {
+        (self.controller_id, self.waiting())
+    }
+
+    /// Send `control_state` to subscribed clients if the controller or the
+    /// waiting queue differs from `before`.
+    fn announce(&mut self, before: ControlView, reason: ControlChangeReason) {
+        let (controller_id, waiting) = self.control_view();
+        if (controller_id, &waiting) == (before.0, &before.1) {
+            return;
+        }
+        self.control_seq += 1;
+        let message = create_control_state(
+            self.control_seq,
+            controller_id.map(|id| id as u64),
+            reason,
+            waiting.into_iter().map(|id| id as u64).collect(),
+        );
+        for client in self.clients.iter().filter(|client| client.control_events) {
+            client
+                .outbound
+                .try_send_reliable(ClientOutbound::ControlState(message.clone()));
+        }
+    }
+
+    /// Whether the controller has been idle for `lease_steps` logical steps
+    /// while another client waits.
+    fn lease_expired(&self, lease_steps: u64) -> bool {
+        let idle = self
+            .logical_step
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.lease_step.load(Ordering::Relaxed));
+        self.controller_id.is_some() && idle >= lease_steps && self.next_controller().is_some()
+    }
+
+    /// Pass an expired lease to the next waiting client and send the former
+    /// controller to the back of the queue.
+    fn expire_lease(&mut self, lease_steps: u64) -> bool {
+        if !self.lease_expired(lease_steps) {
+            return false;
+        }
+        let before = self.control_view();
+        if let Some(expired) = self.controller_id {
+            self.requeue(expired);
+        }
+        let next = self.next_controller();
+        self.set_controller(next);
+        self.announce(before, ControlChangeReason::LeaseExpired);
+        true
+    }
+
     fn clear_stale_controller(&mut self) {
         clear_stale_controller_id(&mut self.controller_id, |id| {
             self.clients
                 .iter()
-                .any(|client| client.id == id && client.outbound.is_live())
+                .any(|client| client.id == id && client.is_present())
         });
     }
 
-    fn remove_and_promote(&mut self, client_id: usize) {
+    /// The client holding the session issued `resume_token`.
+    pub(super) fn find_session(&self, resume_token: &str) -> Option<usize> {
+        self.clients
+            .iter()
+            .find(|client| {
+                client
+                    .resume_token
+                    .as_deref()
+                    .is_some_and(|token| token_eq(token, resume_token))
+            })
+            .map(|client| client.id)
+    }
+
+    /// End `outbound`'s hold on `client_id`. A handshaken client is detached
+    /// until `resume_until` when that is set, keeping its role and queue
+    /// place; any other is removed. Returns whether the client was detached.
+    /// Does nothing once the session has resumed on another connection.
+    fn disconnect(
+        &mut self,
+        client_id: usize,
+        outbound: &ClientOutboundSender,
+        resume_until: Option<Instant>,
+    ) -> bool {
+        let Some(client) = self.clients.iter().find(|client| client.id == client_id) else {
+            return false;
+        };
+        if !client.outbound.same_channel(outbound) {
+            return false;
+        }
+        match resume_until {
+            Some(until) if client.handshaken => {
+                let before = self.control_view();
+                if let Some(client) = self.client_mut(client_id) {
+                    client.detached_until = Some(until);
+                    client.outbound = ClientOutboundSender::detached();
+                }
+                self.announce(before, ControlChangeReason::Disconnect);
+                true
+            }
+            _ => {
+                self.remove_and_promote(client_id);
+                false
+            }
+        }
+    }
+
+    /// Attach a new connection to a session, returning the outbound of the
+    /// connection it replaces.
+    fn reattach(
+        &mut self,
+        client_id: usize,
+        outbound: ClientOutboundSender,
+    ) -> Option<ClientOutboundSender> {
+        let before = self.control_view();
+        let client = self.client_mut(client_id)?;
+        client.detached_until = None;
+        let previous = std::mem::replace(&mut client.outbound, outbound);
+        self.announce(before, ControlChangeReason::Hello);
+        Some(previous)
+    }
+
+    /// Remove a client, promoting the next waiting client if it held control.
+    fn remove_and_promote(&mut self, client_id: usize) -> Option<ClientHandle> {
+        let before = self.control_view();
         let was_controller = self.is_controller(client_id);
-        self.clients.retain(|client| client.id != client_id);
+        let index = self
+            .clients
+            .iter()
+            .position(|client| client.id == client_id);
+        let removed = index.map(|index| self.clients.remove(index));
         if was_controller {
-            self.controller_id = self
-                .clients
+            let next = self.next_controller();
+            self.set_controller(next);
+        }
+        self.announce(before, ControlChangeReason::Disconnect);
+        removed
+    }
+
+    /// Role of a resumed session. Resuming with an observer-only token gives
+    /// up the control and queue place the session held.
+    fn resume_role(&mut self, client_id: usize) -> (AssignedRole, Option<usize>) {
+        if self.is_observer_only(client_id) {
+            let before = self.control_view();
+            self.dequeue(client_id);
+            if self.is_controller(client_id) {
+                let next = self.next_controller();
+                self.set_controller(next);
+            }
+            self.announce(before, ControlChangeReason::Hello);
+        }
+        (self.role_of(client_id), self.controller_id)
+    }
+
+    /// Hello-time role policy: a client not requesting `observer` joins the
+    /// waiting queue, and takes control when no controller is assigned.
+    fn assign_role(&mut self, client_id: usize) -> (AssignedRole, Option<usize>) {
+        self.clear_stale_controller();
+        let before = self.control_view();
+
+        let allow_auto_controller = self
+            .clients
+            .iter()
+            .find(|client| client.id == client_id)
+            .is_some_and(|client| {
+                client.requested_role != RequestedRole::Observer && !client.observer_only
+            });
+        if allow_auto_controller {
+            self.enqueue(client_id);
+        } else {
+            self.dequeue(client_id);
+        }
+
+        let mut assigned_role = AssignedRole::Observer;
+        if self.controller_id == Some(client_id) {
+            assigned_role = AssignedRole::Controller;
+        } else if self.controller_id.is_none() && allow_auto_controller {
+            self.set_controller(Some(client_id));
+            assigned_role = AssignedRole::Controller;
+        }
+        self.announce(before, ControlChangeReason::Hello);
+        (assigned_role, self.controller_id)
+    }
+
+    fn is_observer_only(&self, client_id: usize) -> bool {
+        self.clients
+            .iter()
+            .any(|client| client.id == client_id && client.observer_only)
+    }
+
+    fn role_of(&self, client_id: usize) -> AssignedRole {
+        if self.is_controller(client_id) {
+            AssignedRole::Controller
+        } else {
+            AssignedRole::Observer
+        }
+    }
+
+    /// The lockstep mode to announce to the game loop, with the controller
+    /// it belongs to, when it changed since the last announcement.
+    pub(super) fn take_lockstep_change(&mut self) -> Option<(Option<usize>, bool)> {
+        let status = self.status();
+        if status.lockstep == self.announced_lockstep {
+            return None;
+        }
+        self.announced_lockstep = status.lockstep;
+        Some((status.controller_id, status.lockstep))
+    }
+
+    pub(super) fn status(&self) -> AdapterStatus {
+        let live_client_count = self
+            .clients
+            .iter()
+            .filter(|c| c.is_present())
+            .count()
+            .min(u16::MAX as usize) as u16;
+        let controller_id = self.controller_id.and_then(|id| {
+            self.clients
                 .iter()
-                .filter(|client| {
-                    client.outbound.is_live() && client.requested_role != RequestedRole::Observer
-                })
-                .map(|client| client.id)
-                .min();
+                .any(|c| c.id == id && c.is_present())
+                .then_some(id)
+        });
+        let streaming_count = self
+            .clients
+            .iter()
+            .filter(|c| c.stream_observations && c.is_present())
+            .count()
+            .min(u16::MAX as usize) as u16;
+        let lockstep =
+            controller_id.is_some_and(|id| self.clients.iter().any(|c| c.id == id && c.lockstep));
+        AdapterStatus {
+            client_count: live_client_count,
+            controller_id,
+            streaming_count,
+            lockstep,
         }
     }
 }
 
 impl ServerState {
-    pub fn new(config: ServerConfig, status_tx: Option<watch::Sender<AdapterStatus>>) -> Self {
+    /// `command_tx`, `status_tx`, and `logical_step` connect the default room
+    /// to the host process's game loop.
+    pub fn new(
+        config: ServerConfig,
+        command_tx: mpsc::Sender<InboundCommand>,
+        status_tx: Option<watch::Sender<AdapterStatus>>,
+        logical_step: Arc<AtomicU64>,
+    ) -> Self {
+        let policy = config.control_policy();
+        let resumable = config.resume_grace_ms.is_some();
+        let default_room = Room::new(
+            DEFAULT_ROOM,
+            None,
+            &policy,
+            logical_step,
+            command_tx,
+            status_tx,
+            resumable,
+        );
+        let rooms = RoomRegistry::new(
+            default_room,
+            config.max_rooms,
+            config.max_pending_commands,
+            policy,
+            resumable,
+            config.record_path.clone(),
+            config.record_metadata.clone(),
+        );
         Self {
             config,
-            broker: Arc::new(RwLock::new(BrokerState::default())),
-            status_tx,
+            rooms: RwLock::new(rooms),
+            next_client_id: AtomicUsize::new(1),
         }
     }
 
@@ -291,39 +663,8 @@ impl ServerState {
     }
 }
 
-async fn emit_status(state: &Arc<ServerState>) {
-    let Some(tx) = state.status_tx.as_ref() else {
-        return;
-    };
-    let broker = state.broker.read().await;
-    let live_client_count = broker
-        .clients
-        .iter()
-        .filter(|c| c.outbound.is_live())
-        .count()
-        .min(u16::MAX as usize) as u16;
-    let controller_id = broker.controller_id.and_then(|id| {
-        broker
-            .clients
-            .iter()
-            .any(|c| c.id == id && c.outbound.is_live())
-            .then_some(id)
-    });
-    let streaming_count = broker
-        .clients
-        .iter()
-        .filter(|c| c.stream_observations && c.outbound.is_live())
-        .count()
-        .min(u16::MAX as usize) as u16;
-    tx.send_replace(AdapterStatus {
-        client_count: live_client_count,
-        controller_id,
-        streaming_count,
-    });
-}
-
-async fn is_handshaken(state: &Arc<ServerState>, client_id: usize) -> bool {
-    let broker = state.broker.read().await;
+async fn is_handshaken(room: &Room, client_id: usize) -> bool {
+    let broker = room.broker.read().await;
     broker
         .clients
         .iter()
@@ -332,8 +673,8 @@ async fn is_handshaken(state: &Arc<ServerState>, client_id: usize) -> bool {
         .unwrap_or(false)
 }
 
-async fn check_and_update_seq(state: &Arc<ServerState>, client_id: usize, seq: u64) -> bool {
-    let mut broker = state.broker.write().await;
+async fn check_and_update_seq(room: &Room, client_id: usize, seq: u64) -> bool {
+    let mut broker = room.broker.write().await;
     let Some(client) = broker.clients.iter_mut().find(|c| c.id == client_id) else {
         return true;
     };
@@ -354,18 +695,39 @@ async fn check_and_update_seq(state: &Arc<ServerState>, client_id: usize, seq: u
 /// Handle to a connected client
 pub struct ClientHandle {
     pub id: usize,
-    pub addr: SocketAddr,
+    pub addr: Endpoint,
     pub requested_role: RequestedRole,
+    /// Authenticated with the observer-only token.
+    pub observer_only: bool,
+    /// Promotion priority from the hello token.
+    pub priority: u32,
+    /// Queue ticket while the client wants control; lower waited longer.
+    pub waiting_since: Option<u64>,
+    /// Receives `control_state` messages.
+    pub control_events: bool,
     pub command_mode: CommandMode,
     pub stream_observations: bool,
+    pub lockstep: bool,
+    pub delta_observations: bool,
     pub handshaken: bool,
     pub last_seq: Option<u64>,
+    /// Issued in welcome when sessions can resume.
+    pub resume_token: Option<String>,
+    /// Set while the session waits for its client to resume.
+    pub detached_until: Option<Instant>,
     outbound: ClientOutboundSender,
 }
 
+impl ClientHandle {
+    /// Connected, or detached and still resumable.
+    fn is_present(&self) -> bool {
+        self.detached_until.is_some() || self.outbound.is_live()
+    }
+}
+
 enum StartupNotifier {
     Address(Option<oneshot::Sender<SocketAddr>>),
-    Result(oneshot::Sender<Result<SocketAddr, String>>),
+    Result(oneshot::Sender<Result<Listening, String>>),
 }
 
 enum OutboundReceiverKind {
@@ -385,7 +747,7 @@ impl From<mpsc::UnboundedReceiver<OutboundMessage>> for OutboundReceiver {
 }
 
 impl OutboundReceiver {
-    fn latest(observations: watch::Receiver<Option<Arc<ObservationMessage>>>) -> Self {
+    pub(super) fn latest(observations: watch::Receiver<Option<Arc<ObservationMessage>>>) -> Self {
         Self(OutboundReceiverKind::Latest(observations))
     }
 
@@ -407,14 +769,17 @@ impl OutboundReceiver {
 }
 
 impl StartupNotifier {
-    fn success(self, address: SocketAddr) {
+    fn success(self, listening: Listening) {
         match self {
+            // Address-only callers are told nothing for non-TCP transports.
             Self::Address(Some(tx)) => {
-                let _ = tx.send(address);
+                if let Some(address) = listening.endpoint.tcp() {
+                    let _ = tx.send(address);
+                }
             }
             Self::Address(None) => {}
             Self::Result(tx) => {
-                let _ = tx.send(Ok(address));
+                let _ = tx.send(Ok(listening));
             }
         }
     }
@@ -426,10 +791,10 @@ impl StartupNotifier {
     }
 }
 
-/// Start the TCP server.
+/// Start the server on the configured transport.
 ///
 /// The optional readiness channel is retained for callers that only need the
-/// bound address. [`run_server_with_startup`] is used by [`crate::adapter::Adapter`]
+/// bound TCP address; it is dropped unsent for other transports. [`run_server_with_startup`] is used by [`crate::adapter::Adapter`]
 /// when startup errors must be propagated synchronously.
 pub async fn run_server<R>(
     config: ServerConfig,
@@ -447,6 +812,7 @@ where
         out_rx.into(),
         StartupNotifier::Address(ready_tx),
         status_tx,
+        Arc::default(),
     )
     .await
 }
@@ -455,8 +821,9 @@ pub(crate) async fn run_server_with_startup(
     config: ServerConfig,
     command_tx: mpsc::Sender<InboundCommand>,
     observation_rx: watch::Receiver<Option<Arc<ObservationMessage>>>,
-    startup_tx: oneshot::Sender<Result<SocketAddr, String>>,
+    startup_tx: oneshot::Sender<Result<Listening, String>>,
     status_tx: Option<watch::Sender<AdapterStatus>>,
+    logical_step: Arc<AtomicU64>,
 ) -> anyhow::Result<()> {
     run_server_inner(
         config,
@@ -464,6 +831,7 @@ pub(crate) async fn run_server_with_startup(
         OutboundReceiver::latest(observation_rx),
         StartupNotifier::Result(startup_tx),
         status_tx,
+        logical_step,
     )
     .await
 }
@@ -471,9 +839,10 @@ pub(crate) async fn run_server_with_startup(
 async fn run_server_inner(
     config: ServerConfig,
     command_tx: mpsc::Sender<InboundCommand>,
-    mut out_rx: OutboundReceiver,
+    out_rx: OutboundReceiver,
     startup: StartupNotifier,
     status_tx: Option<watch::Sender<AdapterStatus>>,
+    logical_step: Arc<AtomicU64>,
 ) -> anyhow::Result<()> {
     if ServerState::is_disabled() {
         let error = anyhow::anyhow!("AI adapter is disabled");
@@ -481,25 +850,32 @@ async fn run_server_inner(
         return Err(error);
     }
 
-    let addr = match config.socket_addr() {
-        Ok(address) => address,
+    let mut listener = match Listener::bind(&config.transport, || config.socket_addr()).await {
+        Ok(listener) => listener,
         Err(error) => {
             startup.failure(&error);
             return Err(error);
         }
     };
-    let listener = match TcpListener::bind(&addr).await {
-        Ok(listener) => listener,
-        Err(source) => {
-            let error = anyhow::anyhow!("failed to bind AI adapter at {addr}: {source}");
+    let bound = match listener.endpoint() {
+        Ok(endpoint) => endpoint,
+        Err(error) => {
+            startup.failure(&error);
+            return Err(error);
+        }
+    };
+    // A stdio server ends with its one client, so it takes no browser clients.
+    let websocket = match bind_websocket(&config, listener.is_single_client()).await {
+        Ok(websocket) => websocket,
+        Err(error) => {
             startup.failure(&error);
             return Err(error);
         }
     };
-    let bound = match listener.local_addr() {
+    let websocket_addr = match websocket.as_ref().map(TcpListener::local_addr).transpose() {
         Ok(address) => address,
         Err(source) => {
-            let error = anyhow::anyhow!("failed to read AI adapter address: {source}");
+            let error = anyhow::anyhow!("failed to read AI adapter WebSocket address: {source}");
             startup.failure(&error);
             return Err(error);
         }
@@ -514,76 +890,283 @@ async fn run_server_inner(
             client_count: 0,
             controller_id: None,
             streaming_count: 0,
+            lockstep: false,
         });
     }
-    startup.success(bound);
+    startup.success(Listening {
+        endpoint: bound,
+        websocket: websocket_addr,
+    });
+
+    let state = Arc::new(ServerState::new(
+        config,
+        command_tx,
+        status_tx,
+        logical_step,
+    ));
+    let default_room = state.rooms.read().await.default_room();
+    spawn_dispatcher(Arc::downgrade(&default_room), out_rx);
+    spawn_lease_watchdog(Arc::downgrade(&default_room));
+    if let Some(websocket) = websocket {
+        tokio::spawn(accept_websockets(
+            websocket,
+            Arc::clone(&state),
+            wire_log_tx.clone(),
+        ));
+    }
 
-    let state = Arc::new(ServerState::new(config, status_tx));
-    let mut client_id_counter = 0usize;
+    // Accept incoming connections
+    loop {
+        let Some(connection) = listener.accept().await? else {
+            return Ok(());
+        };
+        let client = spawn_client(connection, &state, wire_log_tx.clone()).await;
+        if listener.is_single_client() {
+            let _ = client.await;
+        }
+    }
+}
 
-    // Outbound dispatcher.
-    {
+async fn bind_websocket(
+    config: &ServerConfig,
+    single_client: bool,
+) -> anyhow::Result<Option<TcpListener>> {
+    let Some(addr) = config.websocket_addr()? else {
+        return Ok(None);
+    };
+    if single_client {
+        return Ok(None);
+    }
+    TcpListener::bind(&addr).await.map(Some).map_err(|source| {
+        anyhow::anyhow!("failed to bind AI adapter WebSocket listener at {addr}: {source}")
+    })
+}
+
+/// Upgrade WebSocket connections and serve them like any other client.
+async fn accept_websockets(
+    listener: TcpListener,
+    state: Arc<ServerState>,
+    wire_log_tx: Option<mpsc::Sender<WireEntry>>,
+) {
+    while let Ok((socket, peer)) = listener.accept().await {
         let state = Arc::clone(&state);
+        let wire_log_tx = wire_log_tx.clone();
+        // The handshake runs off the accept loop so a stalled peer blocks no one.
         tokio::spawn(async move {
-            while let Some(msg) = out_rx.recv().await {
-                match msg {
-                    OutboundMessage::BroadcastObservationArc { obs } => {
-                        let broker = state.broker.read().await;
-                        let clients = &broker.clients;
-                        for c in clients.iter() {
-                            if c.stream_observations {
-                                c.outbound
-                                    .publish_observation(ClientOutbound::ObservationArc(
-                                        Arc::clone(&obs),
-                                    ));
-                            }
+            let upgrade = websocket::upgrade(socket, peer);
+            if let Ok(Ok(Some(connection))) =
+                tokio::time::timeout(websocket::HANDSHAKE_TIMEOUT, upgrade).await
+            {
+                spawn_client(connection, &state, wire_log_tx).await;
+            }
+        });
+    }
+}
+
+async fn spawn_client(
+    connection: Connection,
+    state: &Arc<ServerState>,
+    wire_log_tx: Option<mpsc::Sender<WireEntry>>,
+) -> tokio::task::JoinHandle<()> {
+    let client_id = state.next_client_id.fetch_add(1, Ordering::Relaxed);
+
+    state.rooms.read().await.default_room().emit_status().await;
+
+    let state_clone = Arc::clone(state);
+
+    // Spawn task to handle this client
+    tokio::spawn(async move {
+        if let Err(e) = handle_client(connection, client_id, state_clone, wire_log_tx).await {
+            let _ = e;
+        }
+    })
+}
+
+/// Fan one room's observations out to its streaming clients until the room
+/// closes or its game stops publishing.
+pub(super) fn spawn_dispatcher(room: Weak<Room>, mut out_rx: OutboundReceiver) {
+    tokio::spawn(async move {
+        while let Some(msg) = out_rx.recv().await {
+            let Some(room) = room.upgrade() else {
+                break;
+            };
+            match msg {
+                OutboundMessage::BroadcastObservationArc { obs } => {
+                    room.record(&obs);
+                    let broker = room.broker.read().await;
+                    let clients = &broker.clients;
+                    for c in clients.iter() {
+                        if c.stream_observations {
+                            c.outbound
+                                .publish_observation(ClientOutbound::ObservationArc(Arc::clone(
+                                    &obs,
+                                )));
                         }
                     }
                 }
             }
-        });
-    }
+        }
+    });
+}
 
-    // Accept incoming connections
-    loop {
-        let (socket, addr) = listener.accept().await?;
-        client_id_counter += 1;
-        let client_id = client_id_counter;
+/// Pass control on whenever a room's controller lease expires, until the room
+/// closes. Rooms without a lease need no watchdog.
+pub(super) fn spawn_lease_watchdog(room: Weak<Room>) {
+    let Some(lease_steps) = room.upgrade().and_then(|room| room.lease_steps) else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_millis(TICK_MS as u64));
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tick.tick().await;
+            let Some(room) = room.upgrade() else {
+                break;
+            };
+            if !room.broker.read().await.lease_expired(lease_steps) {
+                continue;
+            }
+            if room.broker.write().await.expire_lease(lease_steps) {
+                room.emit_status().await;
+            }
+        }
+    });
+}
 
-        emit_status(&state).await;
+/// Move a client's handle between rooms, closing `from` if it is a hosted room
+/// left empty. The caller holds the registry lock, so no other client can join
+/// a room while it closes.
+async fn move_client(rooms: &mut RoomRegistry, client_id: usize, from: &Arc<Room>, to: &Arc<Room>) {
+    let handle = {
+        let mut broker = from.broker.write().await;
+        let handle = broker.remove_and_promote(client_id);
+        if broker.clients.is_empty() {
+            rooms.close(from);
+        }
+        handle
+    };
+    from.emit_status().await;
+    if let Some(handle) = handle {
+        // Anything still pending in the observation slot belongs to `from`.
+        handle.outbound.clear_observation();
+        to.broker.write().await.clients.push(handle);
+    }
+}
 
-        let state_clone = Arc::clone(&state);
-        let command_tx = command_tx.clone();
-        let wire_log_tx = wire_log_tx.clone();
+/// Remove a detached session that has not resumed by `deadline`, closing its
+/// room if it is a hosted room left empty.
+fn spawn_session_expiry(
+    state: Arc<ServerState>,
+    room: Arc<Room>,
+    client_id: usize,
+    deadline: Instant,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep_until(deadline).await;
+        {
+            let mut rooms = state.rooms.write().await;
+            let mut broker = room.broker.write().await;
+            let expired = broker
+                .clients
+                .iter()
+                .any(|client| client.id == client_id && client.detached_until == Some(deadline));
+            if !expired {
+                return;
+            }
+            broker.remove_and_promote(client_id);
+            if broker.clients.is_empty() {
+                rooms.close(&room);
+            }
+        }
+        room.emit_status().await;
+    });
+}
 
-        // Spawn task to handle this client
-        tokio::spawn(async move {
-            if let Err(e) = handle_client(
-                socket,
-                addr,
-                client_id,
-                state_clone,
-                command_tx,
-                wire_log_tx,
-            )
+/// Move the connection of the provisional client `client_id` in `current`
+/// onto the session issued `resume_token`, returning the session's room and
+/// client id. A connection still attached to the session is shut down.
+async fn resume_session(
+    state: &ServerState,
+    current: &Arc<Room>,
+    client_id: usize,
+    resume_token: &str,
+    outbound: &ClientOutboundSender,
+) -> Option<(Arc<Room>, usize)> {
+    let (target, session_id) = {
+        // The registry lock keeps the session from expiring meanwhile.
+        let rooms = state.rooms.write().await;
+        let target = rooms.find_session(resume_token).await?;
+        let session_id = target.broker.read().await.find_session(resume_token)?;
+        current.broker.write().await.remove_and_promote(client_id);
+        let previous = target
+            .broker
+            .write()
             .await
-            {
-                let _ = e;
-            }
-        });
+            .reattach(session_id, outbound.clone())?;
+        previous.request_shutdown();
+        (target, session_id)
+    };
+    current.emit_status().await;
+    Some((target, session_id))
+}
+
+/// Pass control from the controller `client_id` to another client in its
+/// room; the former controller waits at the back of the queue.
+fn handoff(
+    broker: &mut BrokerState,
+    client_id: usize,
+    to_client_id: Option<u64>,
+) -> Result<(), (ErrorCode, String)> {
+    if !broker.is_controller(client_id) {
+        return Err((
+            ErrorCode::NotController,
+            "Only controller may hand off".to_string(),
+        ));
+    }
+    let Some(target) = to_client_id.map(|id| id as usize) else {
+        return Err((
+            ErrorCode::InvalidCommand,
+            "handoff requires to_client_id".to_string(),
+        ));
+    };
+    if target == client_id {
+        return Ok(());
     }
+    let Some(receiver) = broker
+        .clients
+        .iter()
+        .find(|client| client.id == target && client.handshaken && client.outbound.is_live())
+    else {
+        return Err((
+            ErrorCode::InvalidCommand,
+            format!("client {target} is not in this room"),
+        ));
+    };
+    if receiver.observer_only {
+        return Err((
+            ErrorCode::Unauthorized,
+            "observer-only client cannot take control".to_string(),
+        ));
+    }
+    broker.requeue(client_id);
+    broker.enqueue(target);
+    broker.set_controller(Some(target));
+    Ok(())
 }
 
 /// Handle a single client connection
 async fn handle_client(
-    socket: TcpStream,
-    addr: SocketAddr,
-    client_id: usize,
+    connection: Connection,
+    mut client_id: usize,
     state: Arc<ServerState>,
-    command_tx: mpsc::Sender<InboundCommand>,
-    wire_log_tx: Option<mpsc::Sender<WireRecord>>,
+    wire_log_tx: Option<mpsc::Sender<WireEntry>>,
 ) -> anyhow::Result<()> {
-    let (reader, writer) = tokio::io::split(socket);
+    let supports_binary = connection.supports_binary();
+    let Connection {
+        reader,
+        writer,
+        peer: addr,
+    } = connection;
     let mut writer = BufWriter::with_capacity(16 * 1024, writer);
     let mut reader = BufReader::new(reader);
 
@@ -595,40 +1178,71 @@ async fn handle_client(
         id: client_id,
         addr,
         requested_role: RequestedRole::Auto,
+        observer_only: false,
+        priority: 0,
+        waiting_since: None,
+        control_events: false,
         command_mode: CommandMode::Action,
         stream_observations: false,
+        lockstep: false,
+        delta_observations: false,
         handshaken: false,
         last_seq: None,
+        resume_token: None,
+        detached_until: None,
         outbound: outbound.clone(),
     };
 
+    // Every client starts in the default room; `room` follows joins and leaves.
+    let mut room = state.rooms.read().await.default_room();
     {
-        let mut broker = state.broker.write().await;
+        let mut broker = room.broker.write().await;
         broker.clients.push(client_handle);
     }
 
-    emit_status(&state).await;
+    room.emit_status().await;
 
+    let wire_log_tx = wire_log_tx.map(|tx| ClientWireLog::new(tx, client_id));
     let wire_log_tx_out = wire_log_tx.clone();
+    // Set by `resync`; the writer turns the next observation into a keyframe.
+    let keyframe_requested = Arc::new(AtomicBool::new(false));
+    let writer_keyframe_requested = Arc::clone(&keyframe_requested);
+    // Set by hello and `subscribe`; the writer applies it to later observations.
+    let (subscription_tx, mut subscription_rx) = watch::channel(Subscription::default());
 
     // Spawn task to write messages to client
     let write_task = tokio::spawn(async move {
         let mut buf: Vec<u8> = Vec::with_capacity(4096);
+        // A lockstep reply may reach the same client as a targeted response and
+        // as a broadcast; the shared allocation identifies the duplicate.
+        let mut last_observation: Option<Arc<ObservationMessage>> = None;
+        let mut binary = false;
+        let mut delta: Option<DeltaEncoder> = None;
+        let mut subscription = ClientSubscription::default();
+        // Latest rate-limited observation, delivered when the client is due.
+        let mut deferred: Option<(Arc<ObservationMessage>, Instant)> = None;
         let mut dirty = false;
         let mut flush_tick = tokio::time::interval(Duration::from_millis(16));
         flush_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
+            let deferred_at = deferred.as_ref().map_or_else(Instant::now, |(_, at)| *at);
             let msg = tokio::select! {
                 biased;
                 msg = reliable_rx.recv() => msg,
                 changed = observation_rx.changed() => {
                     if changed.is_err() {
                         None
+                    } else if let Some(msg) = observation_rx.borrow_and_update().clone() {
+                        Some(msg)
                     } else {
-                        observation_rx.borrow_and_update().clone()
+                        // Cleared when the client moved to another room.
+                        continue;
                     }
                 }
+                _ = tokio::time::sleep_until(deferred_at), if deferred.is_some() => {
+                    deferred.take().map(|(obs, _)| ClientOutbound::ObservationArc(obs))
+                }
                 _ = flush_tick.tick(), if dirty => {
                     if writer.flush().await.is_err() {
                         break;
@@ -644,64 +1258,150 @@ async fn handle_client(
 
             let flush_after = matches!(
                 msg,
-                ClientOutbound::Ack(_) | ClientOutbound::Error(_) | ClientOutbound::Welcome(_)
+                ClientOutbound::Ack(_)
+                    | ClientOutbound::Error(_)
+                    | ClientOutbound::Welcome(_)
+                    | ClientOutbound::RoomState(_)
+                    | ClientOutbound::ControlState(_)
+                    | ClientOutbound::Backlog(_)
             );
-
-            match msg {
+            // The welcome is always a JSON line; a binary client switches to
+            // length-prefixed frames right after it.
+            let framed = binary && !matches!(msg, ClientOutbound::Welcome(_));
+            let log_tx = wire_log_tx_out.as_ref();
+
+            let written = match msg {
+                ClientOutbound::Ack(ack) if framed => {
+                    write_frame_and_log(&mut writer, &mut buf, &ack, log_tx).await
+                }
                 ClientOutbound::Ack(ack) => {
-                    if write_json_and_log(
-                        &mut writer,
-                        &mut buf,
-                        ack,
-                        wire_log_tx_out.as_ref(),
-                        WireRecord::Ack,
-                    )
-                    .await
-                    .is_err()
-                    {
-                        break;
-                    }
+                    write_json_and_log(&mut writer, &mut buf, ack, log_tx, WireRecord::Ack).await
+                }
+                ClientOutbound::Error(err) if framed => {
+                    write_frame_and_log(&mut writer, &mut buf, &err, log_tx).await
                 }
                 ClientOutbound::Error(err) => {
-                    if write_json_and_log(
+                    write_json_and_log(&mut writer, &mut buf, err, log_tx, WireRecord::Error).await
+                }
+                ClientOutbound::Welcome(welcome) => {
+                    binary = welcome.format == CapabilityFormat::Binary;
+                    delta = welcome.delta_observations.then(DeltaEncoder::default);
+                    write_json_and_log(&mut writer, &mut buf, welcome, log_tx, redacted_welcome)
+                        .await
+                }
+                ClientOutbound::RoomState(room_state) => {
+                    // A rate-limited observation from the previous room is stale.
+                    deferred = None;
+                    write_json_and_log(
                         &mut writer,
                         &mut buf,
-                        err,
-                        wire_log_tx_out.as_ref(),
-                        WireRecord::Error,
+                        room_state,
+                        log_tx,
+                        WireRecord::RoomState,
                     )
                     .await
-                    .is_err()
-                    {
-                        break;
-                    }
                 }
-                ClientOutbound::Welcome(welcome) => {
-                    if write_json_and_log(
+                // Only JSON clients subscribe to control events.
+                ClientOutbound::ControlState(control_state) => {
+                    write_json_and_log(
                         &mut writer,
                         &mut buf,
-                        welcome,
-                        wire_log_tx_out.as_ref(),
-                        WireRecord::Welcome,
+                        control_state,
+                        log_tx,
+                        WireRecord::ControlState,
                     )
                     .await
-                    .is_err()
-                    {
-                        break;
+                }
+                // Missed observations are replayed whole, bypassing the
+                // subscription; the stream continues from the newest.
+                ClientOutbound::Backlog(observations) => {
+                    let mut written = Ok(());
+                    for (index, obs) in observations.into_iter().enumerate() {
+                        if framed {
+                            written =
+                                write_frame_and_log(&mut writer, &mut buf, obs.as_ref(), log_tx)
+                                    .await;
+                        } else {
+                            if index > 0 {
+                                written = writer.write_all(b"\n").await;
+                            }
+                            if written.is_ok() && encode_json_into_buf(&mut buf, obs.as_ref()) {
+                                written = writer.write_all(&buf).await;
+                            }
+                            log_wire_record(log_tx, WireRecord::ObservationArc(Arc::clone(&obs)));
+                        }
+                        last_observation = Some(obs);
+                        if written.is_err() {
+                            break;
+                        }
                     }
+                    written
                 }
                 ClientOutbound::ObservationArc(obs) => {
-                    if !encode_json_into_buf(&mut buf, obs.as_ref()) {
+                    if last_observation
+                        .as_ref()
+                        .is_some_and(|last| Arc::ptr_eq(last, &obs))
+                    {
                         continue;
                     }
-                    if writer.write_all(&buf).await.is_err() {
-                        break;
+                    if subscription_rx.has_changed().unwrap_or(false) {
+                        subscription =
+                            ClientSubscription::new(&subscription_rx.borrow_and_update());
+                    }
+                    let now = Instant::now();
+                    match subscription.admit(&obs, now) {
+                        Admission::Drop => continue,
+                        Admission::Defer(at) => {
+                            deferred = Some((obs, at));
+                            continue;
+                        }
+                        Admission::Send => {
+                            deferred = None;
+                            subscription.mark_sent(now);
+                        }
+                    }
+                    last_observation = Some(Arc::clone(&obs));
+
+                    let fields = subscription.fields();
+                    if framed {
+                        write_frame_and_log(&mut writer, &mut buf, obs.as_ref(), log_tx).await
+                    } else if delta.is_none() && fields == FeatureMask::ALL {
+                        if !encode_json_into_buf(&mut buf, obs.as_ref()) {
+                            continue;
+                        }
+                        let written = writer.write_all(&buf).await;
+                        log_wire_record(log_tx, WireRecord::ObservationArc(obs));
+                        written
+                    } else {
+                        let view = match delta.as_mut() {
+                            Some(encoder) => {
+                                if writer_keyframe_requested.swap(false, Ordering::AcqRel) {
+                                    encoder.request_keyframe();
+                                }
+                                // Delta clients need board_id to track their base board.
+                                encoder
+                                    .encode(obs.as_ref())
+                                    .with_fields(fields.with(CapabilityFeature::BoardId))
+                            }
+                            None => ObservationView::new(obs.as_ref()).with_fields(fields),
+                        };
+                        if !encode_json_into_buf(&mut buf, &view) {
+                            continue;
+                        }
+                        let written = writer.write_all(&buf).await;
+                        if log_tx.is_some() {
+                            let line = String::from_utf8_lossy(&buf);
+                            log_wire_record(log_tx, WireRecord::LineArc(Arc::from(line.as_ref())));
+                        }
+                        written
                     }
-                    log_wire_record(wire_log_tx_out.as_ref(), WireRecord::ObservationArc(obs));
                 }
+            };
+            if written.is_err() {
+                break;
             }
 
-            if writer.write_all(b"\n").await.is_err() {
+            if !framed && writer.write_all(b"\n").await.is_err() {
                 break;
             }
 
@@ -718,6 +1418,7 @@ async fn handle_client(
 
     // Handle incoming messages
     let mut line = Vec::with_capacity(4096);
+    let mut binary = false;
 
     loop {
         let read_result = tokio::select! {
@@ -753,14 +1454,15 @@ async fn handle_client(
             continue;
         }
 
-        log_wire_record(
-            wire_log_tx.as_ref(),
-            WireRecord::LineArc(Arc::from(raw_line)),
-        );
+        let logged = redact_token(raw_line).map_or_else(|| Arc::from(raw_line), Arc::from);
+        log_wire_record(wire_log_tx.as_ref(), WireRecord::Inbound(logged));
 
         // Parse the message
         match parse_message(trimmed) {
-            Ok(ParsedMessage::Hello(hello)) => {
+            Ok(ParsedMessage::Hello(mut hello)) => {
+                if !supports_binary {
+                    hello.formats.binary = false;
+                }
                 // Require hello to start the per-sender sequence at 1.
                 if hello.seq != 1 {
                     send_client_error(
@@ -773,8 +1475,8 @@ async fn handle_client(
                 }
 
                 // Sequencing: enforce monotonic seq per sender.
-                if is_handshaken(&state, client_id).await
-                    && !enforce_strict_seq(&state, &outbound, client_id, hello.seq).await
+                if is_handshaken(&room, client_id).await
+                    && !enforce_strict_seq(&room, &outbound, client_id, hello.seq).await
                 {
                     continue;
                 }
@@ -790,6 +1492,20 @@ async fn handle_client(
                     break;
                 }
 
+                // A refused token ends the connection, so guesses cost a reconnect.
+                let Some(access) = authorize(&state.config, hello.token.as_deref()) else {
+                    send_client_error(
+                        &outbound,
+                        hello.seq,
+                        ErrorCode::Unauthorized,
+                        "hello token missing or invalid",
+                    );
+                    break;
+                };
+                if access == Access::ObserverOnly {
+                    hello.requested.role = Some(RequestedRole::Observer);
+                }
+
                 if !hello.formats.json {
                     let error = create_error(
                         hello.seq,
@@ -800,59 +1516,160 @@ async fn handle_client(
                     continue;
                 }
 
-                // Mark client as handshaken and store requested capabilities.
+                if let Err(message) = hello.requested.subscription.validate() {
+                    send_client_error(&outbound, hello.seq, ErrorCode::InvalidCommand, message);
+                    continue;
+                }
+                // Binary frames have a fixed layout, so a field filter cannot apply.
+                if hello.formats.binary && hello.requested.subscription.fields.is_some() {
+                    send_client_error(
+                        &outbound,
+                        hello.seq,
+                        ErrorCode::InvalidCommand,
+                        BINARY_FIELDS_ERROR,
+                    );
+                    continue;
+                }
+
+                // A resumed session keeps its id, role, queue place, seq
+                // window, and room; an unknown or expired token starts afresh.
+                let resume_grace_ms = state.config.resume_grace_ms;
+                let mut resumed = false;
+                if resume_grace_ms.is_some()
+                    && let Some(resume) = hello.resume.as_ref()
+                    && let Some((session_room, session_id)) =
+                        resume_session(&state, &room, client_id, &resume.token, &outbound).await
                 {
-                    let mut broker = state.broker.write().await;
-                    if let Some(client) = broker.clients.iter_mut().find(|c| c.id == client_id) {
-                        client.handshaken = true;
-                        client.last_seq = Some(hello.seq);
-                        client.requested_role = hello.requested.role.unwrap_or(RequestedRole::Auto);
+                    room = session_room;
+                    client_id = session_id;
+                    resumed = true;
+                }
+
+                if !resumed
+                    && let Some(name) = hello.requested.room.as_deref()
+                    && name != room.name
+                {
+                    let mut rooms = state.rooms.write().await;
+                    let Some(target) = rooms.get(name) else {
+                        send_client_error(
+                            &outbound,
+                            hello.seq,
+                            ErrorCode::InvalidCommand,
+                            format!("unknown room {name}"),
+                        );
+                        continue;
+                    };
+                    move_client(&mut rooms, client_id, &room, &target).await;
+                    room = target;
+                }
+
+                // Mark client as handshaken and store requested capabilities.
+                let resume_token = {
+                    let mut broker = room.broker.write().await;
+                    let client = broker.client_mut(client_id);
+                    if let Some(client) = client {
+                        if !resumed {
+                            client.handshaken = true;
+                            client.last_seq = Some(hello.seq);
+                            client.requested_role =
+                                hello.requested.role.unwrap_or(RequestedRole::Auto);
+                        }
+                        if resume_grace_ms.is_some() && client.resume_token.is_none() {
+                            client.resume_token = Some(new_resume_token());
+                        }
+                        client.observer_only = access == Access::ObserverOnly;
+                        client.priority = match access {
+                            Access::Full { priority } => priority,
+                            Access::ObserverOnly => 0,
+                        };
                         client.command_mode = hello.requested.command_mode;
-                        client.stream_observations = hello.requested.stream_observations;
+                        client.lockstep = hello.requested.lockstep;
+                        client.delta_observations =
+                            hello.requested.delta_observations && !hello.formats.binary;
+                        client.resume_token.clone()
+                    } else {
+                        None
                     }
-                }
+                };
 
                 // Role/controller assignment:
                 // - Default policy: when no controller is assigned, first hello becomes controller.
                 // - If hello.requested.role == observer: never auto-assign controller as a side-effect of hello.
-                let mut assigned_role = AssignedRole::Observer;
-                let controller_id: Option<usize> = {
-                    let mut broker = state.broker.write().await;
-                    broker.clear_stale_controller();
-
-                    let requested_role = hello.requested.role.unwrap_or(RequestedRole::Auto);
-                    let allow_auto_controller = requested_role != RequestedRole::Observer;
-
-                    if broker.controller_id == Some(client_id) {
-                        assigned_role = AssignedRole::Controller;
-                    } else if broker.controller_id.is_none() && allow_auto_controller {
-                        broker.controller_id = Some(client_id);
-                        assigned_role = AssignedRole::Controller;
+                // - A resumed session keeps the role it had, unless its token is
+                //   now observer-only.
+                let (assigned_role, controller_id) = {
+                    let mut broker = room.broker.write().await;
+                    if resumed {
+                        broker.resume_role(client_id)
+                    } else {
+                        broker.assign_role(client_id)
                     }
-
-                    broker.controller_id
                 };
 
                 // Send welcome (with deterministic role/controller fields).
-                let welcome = create_welcome(
+                let mut welcome = create_welcome(
                     hello.seq,
                     &state.config.protocol_version,
                     client_id as u64,
                     assigned_role,
                     controller_id.map(|id| id as u64),
                 );
-                outbound.try_send_reliable(ClientOutbound::Welcome(welcome));
+                welcome.game_id = room.name.clone();
+                welcome.capabilities.control_policy = state.config.control_policy();
+                welcome.capabilities.resume_grace_ms = resume_grace_ms;
+                welcome.resume_token = resume_token;
+                welcome.resumed = resumed;
+                if !supports_binary {
+                    welcome
+                        .capabilities
+                        .formats
+                        .retain(|format| *format == CapabilityFormat::Json);
+                }
+                // Report the granted mode: an observer that asked for lockstep
+                // only gets it once it holds control.
+                welcome.lockstep =
+                    hello.requested.lockstep && assigned_role == AssignedRole::Controller;
+                binary = hello.formats.binary;
+                if hello.formats.binary {
+                    welcome.format = CapabilityFormat::Binary;
+                }
+                // Binary frames already pack the board; deltas apply to JSON only.
+                welcome.delta_observations =
+                    hello.requested.delta_observations && !hello.formats.binary;
+                welcome.subscription = hello.requested.subscription.clone();
+                subscription_tx.send_replace(hello.requested.subscription);
+                outbound.try_send_reliable(ClientOutbound::Welcome(Box::new(welcome)));
+                if resumed && let Some(since_step) = hello.resume.and_then(|r| r.since_step) {
+                    let missed = room.observations_since(since_step);
+                    if !missed.is_empty() {
+                        outbound.try_send_reliable(ClientOutbound::Backlog(missed));
+                    }
+                }
+
+                // Join the broadcasts only once the welcome is queued, so the
+                // first streamed message can never precede it. Control events
+                // are JSON-only.
+                {
+                    let mut broker = room.broker.write().await;
+                    if let Some(client) = broker.clients.iter_mut().find(|c| c.id == client_id) {
+                        client.stream_observations = hello.requested.stream_observations;
+                        client.control_events =
+                            hello.requested.control_events && !hello.formats.binary;
+                    }
+                }
 
                 // Request an immediate snapshot for this client if desired.
                 if hello.requested.stream_observations {
                     // This is a required handshake consequence, not a best-effort
                     // gameplay command. Waiting here backpressures only this client
                     // task and preserves the bounded game-loop queue.
-                    if command_tx
+                    if room
+                        .command_tx
                         .send(InboundCommand {
                             client_id,
                             seq: hello.seq,
                             payload: InboundPayload::SnapshotRequest,
+                            lockstep: false,
                             responder: ClientResponder::new(outbound.clone()),
                         })
                         .await
@@ -862,24 +1679,22 @@ async fn handle_client(
                     }
                 }
 
-                emit_status(&state).await;
+                room.emit_status().await;
             }
 
             Ok(ParsedMessage::Command(cmd)) => {
                 // Handshake required.
-                if !enforce_handshake_and_seq(&state, &outbound, client_id, cmd.seq, "command")
-                    .await
+                if !enforce_handshake_and_seq(&room, &outbound, client_id, cmd.seq, "command").await
                 {
                     continue;
                 }
 
-                // Check if client is controller
-                let is_controller = {
-                    let broker = state.broker.read().await;
-                    broker.is_controller(client_id)
-                };
-
-                if !is_controller {
+                // Check if client is controller; its requests renew the lease.
+                // The broker stays locked until the command is queued, so a
+                // lockstep change is announced to the game loop after it.
+                let broker = room.broker.read().await;
+                if !broker.is_controller(client_id) {
+                    drop(broker);
                     send_client_error(
                         &outbound,
                         cmd.seq,
@@ -888,23 +1703,32 @@ async fn handle_client(
                     );
                     continue;
                 }
+                broker.touch_lease();
+                let lockstep = broker
+                    .clients
+                    .iter()
+                    .any(|c| c.id == client_id && c.lockstep);
 
                 // Map command into an inbound command for the game loop.
                 let mapped = match map_command(&cmd) {
                     Ok(c) => c,
                     Err((code, message)) => {
+                        drop(broker);
                         send_client_error(&outbound, cmd.seq, code, message);
                         continue;
                     }
                 };
 
                 // Backpressure: bounded queue.
-                match command_tx.try_send(InboundCommand {
+                let queued = room.command_tx.try_send(InboundCommand {
                     client_id,
                     seq: cmd.seq,
                     payload: InboundPayload::Command(mapped),
+                    lockstep,
                     responder: ClientResponder::new(outbound.clone()),
-                }) {
+                });
+                drop(broker);
+                match queued {
                     Ok(()) => {
                         // Ack will be sent by the game loop after the command is applied.
                     }
@@ -920,70 +1744,343 @@ async fn handle_client(
                 }
             }
 
-            Ok(ParsedMessage::Control(ctrl)) => match ctrl.action {
-                ControlAction::Claim => {
-                    if !enforce_handshake_and_seq(&state, &outbound, client_id, ctrl.seq, "control")
-                        .await
-                    {
-                        continue;
-                    }
+            Ok(ParsedMessage::Control(ctrl)) => {
+                if !enforce_handshake_and_seq(&room, &outbound, client_id, ctrl.seq, "control")
+                    .await
+                {
+                    continue;
+                }
 
-                    let mut should_emit_status = false;
-                    {
-                        let mut broker = state.broker.write().await;
-                        broker.clear_stale_controller();
-                        if broker.is_controller(client_id) {
-                            // Idempotent self-claim: already controller.
-                            let ack = create_ack(ctrl.seq, ctrl.seq);
-                            outbound.try_send_reliable(ClientOutbound::Ack(ack));
-                            should_emit_status = true;
-                        } else if broker.controller_id.is_none() {
-                            broker.controller_id = Some(client_id);
+                let mut should_emit_status = false;
+                {
+                    let mut broker = room.broker.write().await;
+                    broker.clear_stale_controller();
+                    let before = broker.control_view();
+                    let queued = broker.promotion_order.is_queued();
+                    let reason = match ctrl.action {
+                        ControlAction::Claim => ControlChangeReason::Claim,
+                        ControlAction::Release => ControlChangeReason::Release,
+                        ControlAction::Handoff => ControlChangeReason::Handoff,
+                    };
+                    let reply = match ctrl.action {
+                        ControlAction::Claim => {
+                            if broker.is_observer_only(client_id) {
+                                Err((
+                                    ErrorCode::Unauthorized,
+                                    "observer-only token cannot claim control".to_string(),
+                                ))
+                            } else if broker.is_controller(client_id) {
+                                // Idempotent self-claim: already controller.
+                                Ok(())
+                            } else if broker.controller_id.is_none() {
+                                broker.enqueue(client_id);
+                                broker.set_controller(Some(client_id));
+                                Ok(())
+                            } else if queued {
+                                // Queued policies: wait for the seat instead.
+                                broker.enqueue(client_id);
+                                Ok(())
+                            } else {
+                                Err((
+                                    ErrorCode::ControllerActive,
+                                    "Controller already assigned".to_string(),
+                                ))
+                            }
+                        }
+                        ControlAction::Release => {
+                            if broker.is_controller(client_id) {
+                                broker.dequeue(client_id);
+                                let next = if queued {
+                                    broker.next_controller()
+                                } else {
+                                    None
+                                };
+                                broker.set_controller(next);
+                                Ok(())
+                            } else if queued && broker.dequeue(client_id) {
+                                // Leaving the waiting queue.
+                                Ok(())
+                            } else {
+                                Err((
+                                    ErrorCode::NotController,
+                                    "Only controller may release".to_string(),
+                                ))
+                            }
+                        }
+                        ControlAction::Handoff => {
+                            handoff(&mut broker, client_id, ctrl.to_client_id)
+                        }
+                    };
+                    match reply {
+                        Ok(()) => {
                             let ack = create_ack(ctrl.seq, ctrl.seq);
                             outbound.try_send_reliable(ClientOutbound::Ack(ack));
+                            broker.announce(before, reason);
                             should_emit_status = true;
-                        } else {
-                            send_client_error(
-                                &outbound,
-                                ctrl.seq,
-                                ErrorCode::ControllerActive,
-                                "Controller already assigned",
-                            );
                         }
-                    }
-                    if should_emit_status {
-                        emit_status(&state).await;
+                        Err((code, message)) => {
+                            send_client_error(&outbound, ctrl.seq, code, message);
+                        }
                     }
                 }
-                ControlAction::Release => {
-                    if !enforce_handshake_and_seq(&state, &outbound, client_id, ctrl.seq, "control")
-                        .await
-                    {
-                        continue;
+                if should_emit_status {
+                    room.emit_status().await;
+                }
+            }
+
+            Ok(ParsedMessage::Step(step)) => {
+                if !enforce_handshake_and_seq(&room, &outbound, client_id, step.seq, "step").await {
+                    continue;
+                }
+
+                // As for commands, queue the step before any lockstep change.
+                let broker = room.broker.read().await;
+                let lockstep = broker
+                    .clients
+                    .iter()
+                    .any(|c| c.id == client_id && c.lockstep);
+                let is_controller = broker.is_controller(client_id);
+                if is_controller {
+                    broker.touch_lease();
+                }
+
+                if !is_controller {
+                    drop(broker);
+                    send_client_error(
+                        &outbound,
+                        step.seq,
+                        ErrorCode::NotController,
+                        "Only controller may step",
+                    );
+                    continue;
+                }
+                if !lockstep {
+                    drop(broker);
+                    send_client_error(
+                        &outbound,
+                        step.seq,
+                        ErrorCode::InvalidCommand,
+                        "step requires lockstep mode",
+                    );
+                    continue;
+                }
+                if !(1..=MAX_LOCKSTEP_STEPS).contains(&step.n) {
+                    drop(broker);
+                    send_client_error(
+                        &outbound,
+                        step.seq,
+                        ErrorCode::InvalidCommand,
+                        format!("step.n must be in 1..={MAX_LOCKSTEP_STEPS}"),
+                    );
+                    continue;
+                }
+
+                let queued = room.command_tx.try_send(InboundCommand {
+                    client_id,
+                    seq: step.seq,
+                    payload: InboundPayload::Step(step.n),
+                    lockstep: true,
+                    responder: ClientResponder::new(outbound.clone()),
+                });
+                drop(broker);
+                if queued.is_err() {
+                    outbound.try_send_reliable(ClientOutbound::Error(create_backpressure_error(
+                        step.seq,
+                        "Command queue is full",
+                        BACKPRESSURE_RETRY_AFTER_MS,
+                    )));
+                }
+            }
+
+            Ok(ParsedMessage::Subscribe(subscribe)) => {
+                if !enforce_handshake_and_seq(
+                    &room,
+                    &outbound,
+                    client_id,
+                    subscribe.seq,
+                    "subscribe",
+                )
+                .await
+                {
+                    continue;
+                }
+                if let Err(message) = subscribe.subscription.validate() {
+                    send_client_error(&outbound, subscribe.seq, ErrorCode::InvalidCommand, message);
+                    continue;
+                }
+                if binary && subscribe.subscription.fields.is_some() {
+                    send_client_error(
+                        &outbound,
+                        subscribe.seq,
+                        ErrorCode::InvalidCommand,
+                        BINARY_FIELDS_ERROR,
+                    );
+                    continue;
+                }
+
+                subscription_tx.send_replace(subscribe.subscription);
+                outbound.try_send_reliable(ClientOutbound::Ack(create_ack(
+                    subscribe.seq,
+                    subscribe.seq,
+                )));
+                // Subscribing also starts the stream for clients that said hello
+                // with `stream_observations=false`.
+                {
+                    let mut broker = room.broker.write().await;
+                    if let Some(client) = broker.clients.iter_mut().find(|c| c.id == client_id) {
+                        client.stream_observations = true;
                     }
+                }
+                room.emit_status().await;
+                // Best effort: deliver the new view now rather than at the next
+                // broadcast.
+                let _ = room.command_tx.try_send(InboundCommand {
+                    client_id,
+                    seq: subscribe.seq,
+                    payload: InboundPayload::SnapshotRequest,
+                    lockstep: false,
+                    responder: ClientResponder::new(outbound.clone()),
+                });
+            }
 
-                    let mut should_emit_status = false;
-                    {
-                        let mut broker = state.broker.write().await;
-                        if broker.is_controller(client_id) {
-                            broker.controller_id = None;
-                            let ack = create_ack(ctrl.seq, ctrl.seq);
-                            outbound.try_send_reliable(ClientOutbound::Ack(ack));
-                            should_emit_status = true;
-                        } else {
-                            send_client_error(
-                                &outbound,
-                                ctrl.seq,
-                                ErrorCode::NotController,
-                                "Only controller may release",
-                            );
+            Ok(ParsedMessage::Room(request)) => {
+                if !enforce_handshake_and_seq(&room, &outbound, client_id, request.seq, "room")
+                    .await
+                {
+                    continue;
+                }
+                if binary {
+                    send_client_error(
+                        &outbound,
+                        request.seq,
+                        ErrorCode::InvalidCommand,
+                        "room messages require the json format; use requested.room",
+                    );
+                    continue;
+                }
+
+                let target = {
+                    let mut rooms = state.rooms.write().await;
+                    let target = match (request.action, request.name.as_deref()) {
+                        (RoomAction::List, _) => Ok(None),
+                        (RoomAction::Leave, _) if !room.is_hosted() => {
+                            Err("already in the default room".to_string())
+                        }
+                        (RoomAction::Leave, _) => Ok(Some(rooms.default_room())),
+                        (RoomAction::Create | RoomAction::Join, None) => {
+                            Err("room name is required".to_string())
+                        }
+                        (RoomAction::Create, Some(name)) => validate_room_name(name)
+                            .and_then(|()| rooms.create(name, request.seed.unwrap_or(1)))
+                            .map(Some),
+                        (RoomAction::Join, Some(name)) if name == room.name => Ok(None),
+                        (RoomAction::Join, Some(name)) => rooms
+                            .get(name)
+                            .map(Some)
+                            .ok_or_else(|| format!("unknown room {name}")),
+                    };
+                    match target {
+                        Ok(Some(target)) => {
+                            move_client(&mut rooms, client_id, &room, &target).await;
+                            Ok(Some(target))
                         }
+                        other => other,
                     }
-                    if should_emit_status {
-                        emit_status(&state).await;
+                };
+                let moved = match target {
+                    Ok(Some(target)) => {
+                        room = target;
+                        true
+                    }
+                    Ok(None) => false,
+                    Err(message) => {
+                        send_client_error(
+                            &outbound,
+                            request.seq,
+                            ErrorCode::InvalidCommand,
+                            message,
+                        );
+                        continue;
                     }
+                };
+
+                let (role, controller_id, stream_observations) = {
+                    let mut broker = room.broker.write().await;
+                    let (role, controller_id) = if moved {
+                        broker.assign_role(client_id)
+                    } else {
+                        (broker.role_of(client_id), broker.controller_id)
+                    };
+                    let streaming = broker
+                        .clients
+                        .iter()
+                        .any(|c| c.id == client_id && c.stream_observations);
+                    (role, controller_id, streaming)
+                };
+                let mut reply = create_room_state(
+                    request.seq,
+                    &room.name,
+                    role,
+                    controller_id.map(|id| id as u64),
+                );
+                if request.action == RoomAction::List {
+                    reply.rooms = Some(state.rooms.read().await.list().await);
+                }
+                outbound.try_send_reliable(ClientOutbound::RoomState(reply));
+
+                if moved {
+                    room.emit_status().await;
+                    // Start the new game's stream from a full, unfiltered state.
+                    keyframe_requested.store(true, Ordering::Release);
+                    subscription_tx.send_modify(|_| {});
+                    if stream_observations {
+                        let _ = room.command_tx.try_send(InboundCommand {
+                            client_id,
+                            seq: request.seq,
+                            payload: InboundPayload::SnapshotRequest,
+                            lockstep: false,
+                            responder: ClientResponder::new(outbound.clone()),
+                        });
+                    }
+                }
+            }
+
+            Ok(ParsedMessage::Resync(resync)) => {
+                if !enforce_handshake_and_seq(&room, &outbound, client_id, resync.seq, "resync")
+                    .await
+                {
+                    continue;
                 }
-            },
+
+                let delta_observations = {
+                    let broker = room.broker.read().await;
+                    broker
+                        .clients
+                        .iter()
+                        .any(|c| c.id == client_id && c.delta_observations)
+                };
+                if !delta_observations {
+                    send_client_error(
+                        &outbound,
+                        resync.seq,
+                        ErrorCode::InvalidCommand,
+                        "resync requires delta observations",
+                    );
+                    continue;
+                }
+
+                keyframe_requested.store(true, Ordering::Release);
+                outbound.try_send_reliable(ClientOutbound::Ack(create_ack(resync.seq, resync.seq)));
+                // Best effort: when the queue is full the next broadcast
+                // observation still arrives as the keyframe.
+                let _ = room.command_tx.try_send(InboundCommand {
+                    client_id,
+                    seq: resync.seq,
+                    payload: InboundPayload::SnapshotRequest,
+                    lockstep: false,
+                    responder: ClientResponder::new(outbound.clone()),
+                });
+            }
 
             Err(e) => {
                 let seq = extract_seq_best_effort(trimmed).unwrap_or(0);
@@ -997,8 +2094,8 @@ async fn handle_client(
 
             Ok(ParsedMessage::Unknown(unknown)) => {
                 let seq = unknown.seq;
-                if is_handshaken(&state, client_id).await
-                    && !enforce_strict_seq(&state, &outbound, client_id, seq).await
+                if is_handshaken(&room, client_id).await
+                    && !enforce_strict_seq(&room, &outbound, client_id, seq).await
                 {
                     continue;
                 }
@@ -1012,13 +2109,25 @@ async fn handle_client(
         }
     }
 
-    // Clean up: remove client and release/promote controller if needed.
+    // Clean up: detach a resumable session, or remove the client and
+    // release/promote controller if needed.
     {
-        let mut broker = state.broker.write().await;
-        broker.remove_and_promote(client_id);
+        let mut rooms = state.rooms.write().await;
+        let mut broker = room.broker.write().await;
+        let resume_until = state
+            .config
+            .resume_grace_ms
+            .map(|grace| Instant::now() + Duration::from_millis(grace));
+        if broker.disconnect(client_id, &outbound, resume_until) {
+            if let Some(deadline) = resume_until {
+                spawn_session_expiry(Arc::clone(&state), Arc::clone(&room), client_id, deadline);
+            }
+        } else if broker.clients.is_empty() {
+            rooms.close(&room);
+        }
     }
 
-    emit_status(&state).await;
+    room.emit_status().await;
 
     // Cancel write task
     drop(outbound);
@@ -1,8 +1,11 @@
 //! Adapter server configuration and listen-address validation.
 
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
-use crate::adapter::protocol::PROTOCOL_VERSION;
+use crate::adapter::protocol::{ControlPolicy, ControlPromotionOrder, PROTOCOL_VERSION};
+use crate::adapter::transport::Transport;
+use tetris_session::engine::replay::ReplayMetadata;
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -13,6 +16,32 @@ pub struct ServerConfig {
     pub log_path: Option<String>,
     pub log_every_n: u64,
     pub log_max_lines: Option<u64>,
+    /// Open rooms, including the default one.
+    pub max_rooms: usize,
+    pub transport: Transport,
+    /// Extra WebSocket listener on `host`, alongside the main transport.
+    pub websocket_port: Option<u16>,
+    /// Hello token granting full access. Once any token is set, clients
+    /// without a matching one are refused.
+    pub auth_token: Option<String>,
+    /// Hello token granting observer-only access.
+    pub observer_token: Option<String>,
+    /// Hello tokens granting full access with a promotion priority, used by
+    /// [`ControlPromotionOrder::TokenPriority`].
+    pub priority_tokens: Vec<(String, u32)>,
+    pub promotion_order: ControlPromotionOrder,
+    /// Logical steps without a controller command after which control passes
+    /// to a waiting client.
+    pub lease_steps: Option<u64>,
+    /// Milliseconds a disconnected session stays resumable; `None` ends
+    /// sessions with their connection.
+    pub resume_grace_ms: Option<u64>,
+    /// Replay tape of the default room's game; hosted rooms record next to
+    /// it (see [`crate::adapter::Adapter`]).
+    pub record_path: Option<PathBuf>,
+    /// Extra metadata written into every recorded tape, such as the bot's
+    /// name and version.
+    pub record_metadata: ReplayMetadata,
 }
 
 impl Default for ServerConfig {
@@ -25,6 +54,17 @@ impl Default for ServerConfig {
             log_path: None,
             log_every_n: 1,
             log_max_lines: None,
+            max_rooms: 64,
+            transport: Transport::Tcp,
+            websocket_port: None,
+            auth_token: None,
+            observer_token: None,
+            priority_tokens: Vec::new(),
+            promotion_order: ControlPromotionOrder::LowestClientId,
+            lease_steps: None,
+            resume_grace_ms: None,
+            record_path: None,
+            record_metadata: ReplayMetadata::default(),
         }
     }
 }
@@ -54,6 +94,47 @@ impl ServerConfig {
             .ok()
             .and_then(|value| value.parse::<u64>().ok())
             .filter(|&value| value >= 1);
+        let max_rooms = std::env::var("TETRIS_AI_MAX_ROOMS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(64);
+        let transport = std::env::var("TETRIS_AI_UNIX_PATH")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map_or(Transport::Tcp, |path| Transport::Unix(path.into()));
+        let websocket_port = std::env::var("TETRIS_AI_WS_PORT")
+            .ok()
+            .and_then(|value| value.parse().ok());
+        let auth_token = std::env::var("TETRIS_AI_TOKEN")
+            .ok()
+            .filter(|value| !value.is_empty());
+        let observer_token = std::env::var("TETRIS_AI_OBSERVER_TOKEN")
+            .ok()
+            .filter(|value| !value.is_empty());
+        let priority_tokens = std::env::var("TETRIS_AI_PRIORITY_TOKENS")
+            .map(|value| parse_priority_tokens(&value))
+            .unwrap_or_default();
+        let promotion_order = std::env::var("TETRIS_AI_PROMOTION_ORDER")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        let lease_steps = std::env::var("TETRIS_AI_LEASE_STEPS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|&value| value >= 1);
+        let resume_grace_ms = std::env::var("TETRIS_AI_RESUME_GRACE_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|&value| value >= 1);
+        let record_path = std::env::var("TETRIS_AI_RECORD_PATH")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        let record_metadata = std::env::var("TETRIS_AI_RECORD_META")
+            .map(|value| parse_record_metadata(&value))
+            .unwrap_or_default();
 
         Self {
             host,
@@ -63,18 +144,103 @@ impl ServerConfig {
             log_path,
             log_every_n,
             log_max_lines,
+            max_rooms,
+            transport,
+            websocket_port,
+            auth_token,
+            observer_token,
+            priority_tokens,
+            promotion_order,
+            lease_steps,
+            resume_grace_ms,
+            record_path,
+            record_metadata,
+        }
+    }
+
+    /// The controller policy advertised in welcome.
+    pub fn control_policy(&self) -> ControlPolicy {
+        ControlPolicy {
+            auto_promote_on_disconnect: true,
+            promotion_order: self.promotion_order,
+            handoff: true,
+            lease_steps: self.lease_steps,
+            control_events: true,
         }
     }
 
     pub fn socket_addr(&self) -> anyhow::Result<SocketAddr> {
-        format!("{}:{}", self.host, self.port)
-            .parse()
-            .map_err(|error| {
-                anyhow::anyhow!(
-                    "invalid adapter listen address {}:{}: {error}",
-                    self.host,
-                    self.port
-                )
-            })
+        self.addr_with_port(self.port)
+    }
+
+    pub fn websocket_addr(&self) -> anyhow::Result<Option<SocketAddr>> {
+        self.websocket_port
+            .map(|port| self.addr_with_port(port))
+            .transpose()
+    }
+
+    fn addr_with_port(&self, port: u16) -> anyhow::Result<SocketAddr> {
+        format!("{}:{}", self.host, port).parse().map_err(|error| {
+            anyhow::anyhow!(
+                "invalid adapter listen address {}:{port}: {error}",
+                self.host
+            )
+        })
+    }
+}
+
+/// Parse `token:priority` pairs separated by commas, skipping malformed ones.
+fn parse_priority_tokens(value: &str) -> Vec<(String, u32)> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (token, priority) = pair.trim().rsplit_once(':')?;
+            let priority = priority.parse().ok()?;
+            (!token.is_empty()).then(|| (token.to_string(), priority))
+        })
+        .collect()
+}
+
+/// `key=value` pairs separated by `;`, so values such as tags may contain
+/// commas.
+fn parse_record_metadata(value: &str) -> ReplayMetadata {
+    let mut metadata = ReplayMetadata::default();
+    for (key, value) in value.split(';').filter_map(|pair| pair.split_once('=')) {
+        let key = key.trim();
+        if !key.is_empty() {
+            metadata.set(key, value.trim());
+        }
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_tokens_parse_token_priority_pairs() {
+        assert_eq!(
+            parse_priority_tokens("gold:10, silver:5,:3,bad,nan:x,a:b:2"),
+            vec![
+                ("gold".to_string(), 10),
+                ("silver".to_string(), 5),
+                ("a:b".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_metadata_parses_semicolon_separated_pairs() {
+        let metadata =
+            parse_record_metadata("player=greedy ; player_version=1.2;tags=eval,nightly;bad;=x");
+        assert_eq!(
+            metadata.iter().collect::<Vec<_>>(),
+            vec![
+                ("player", "greedy"),
+                ("player_version", "1.2"),
+                ("tags", "eval,nightly"),
+            ]
+        );
     }
 }
@@ -97,29 +97,45 @@ fn test_encode_json_into_buf_ack() {
 
 #[test]
 fn broker_disconnect_promotes_lowest_eligible_client() {
-    let addr = "127.0.0.1:9999".parse().unwrap();
+    let addr = Endpoint::Tcp("127.0.0.1:9999".parse().unwrap());
     let (tx1, rx1, obs1, shutdown1) = client_outbound_channel(1);
     let (tx2, rx2, obs2, shutdown2) = client_outbound_channel(1);
     let _receiver_guards = (rx1, obs1, shutdown1, rx2, obs2, shutdown2);
     let clients = vec![
         ClientHandle {
             id: 1,
-            addr,
+            addr: addr.clone(),
             requested_role: RequestedRole::Auto,
+            observer_only: false,
+            priority: 0,
+            waiting_since: Some(1),
+            control_events: false,
             command_mode: CommandMode::Action,
             stream_observations: false,
+            lockstep: false,
+            delta_observations: false,
             handshaken: true,
             last_seq: Some(1),
+            resume_token: None,
+            detached_until: None,
             outbound: tx1,
         },
         ClientHandle {
             id: 2,
             addr,
             requested_role: RequestedRole::Auto,
+            observer_only: false,
+            priority: 0,
+            waiting_since: Some(2),
+            control_events: false,
             command_mode: CommandMode::Action,
             stream_observations: false,
+            lockstep: false,
+            delta_observations: false,
             handshaken: true,
             last_seq: Some(1),
+            resume_token: None,
+            detached_until: None,
             outbound: tx2,
         },
     ];
@@ -127,6 +143,7 @@ fn broker_disconnect_promotes_lowest_eligible_client() {
     let mut broker = BrokerState {
         clients,
         controller_id: Some(1),
+        ..BrokerState::default()
     };
     broker.remove_and_promote(1);
 
@@ -0,0 +1,137 @@
+//! Per-client observation delivery: rate limit, event-only mode, and field mask.
+//!
+//! Every filter runs in the client's writer task against the shared
+//! observation, so one client's subscription never rebuilds or delays
+//! observations for another.
+
+use tokio::time::{Duration, Instant};
+
+use crate::adapter::protocol::{ObservationMessage, Subscription};
+use tetris_adapter_protocol::view::FeatureMask;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Admission {
+    Send,
+    /// Rate-limited: deliver at this instant unless superseded first.
+    Defer(Instant),
+    Drop,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ClientSubscription {
+    fields: FeatureMask,
+    min_interval: Option<Duration>,
+    events_only: bool,
+    next_due: Option<Instant>,
+    force_next: bool,
+}
+
+impl Default for ClientSubscription {
+    fn default() -> Self {
+        Self::new(&Subscription::default())
+    }
+}
+
+impl ClientSubscription {
+    /// The first observation after (re)subscribing is always delivered, so the
+    /// client starts from a known state.
+    pub(super) fn new(subscription: &Subscription) -> Self {
+        Self {
+            fields: subscription.field_mask(),
+            min_interval: subscription.hz.map(|hz| Duration::from_secs(1) / hz.max(1)),
+            events_only: subscription.events_only,
+            next_due: None,
+            force_next: true,
+        }
+    }
+
+    pub(super) fn fields(&self) -> FeatureMask {
+        self.fields
+    }
+
+    pub(super) fn admit(&self, observation: &ObservationMessage, now: Instant) -> Admission {
+        if self.force_next {
+            return Admission::Send;
+        }
+        if self.events_only
+            && !observation
+                .events
+                .0
+                .iter()
+                .any(|event| event.locked || event.lines_cleared > 0)
+        {
+            return Admission::Drop;
+        }
+        match self.next_due {
+            Some(due) if now < due => Admission::Defer(due),
+            _ => Admission::Send,
+        }
+    }
+
+    pub(super) fn mark_sent(&mut self, now: Instant) {
+        self.force_next = false;
+        self.next_due = self.min_interval.map(|interval| now + interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::observation::build_observation;
+    use crate::adapter::protocol::TransitionEvent;
+    use tetris_core::core::GameState;
+
+    fn observation(locked: bool) -> ObservationMessage {
+        let mut game = GameState::new(1);
+        game.start();
+        let events = [TransitionEvent {
+            locked,
+            lines_cleared: 0,
+            line_clear_score: 0,
+            tspin: None,
+            combo: -1,
+            back_to_back: false,
+        }];
+        build_observation(1, 0, &game.snapshot(), &events)
+    }
+
+    #[test]
+    fn rate_limit_defers_until_the_interval_elapses() {
+        let mut subscription = ClientSubscription::new(&Subscription {
+            hz: Some(10),
+            ..Subscription::default()
+        });
+        let start = Instant::now();
+        let observation = observation(false);
+        assert_eq!(subscription.admit(&observation, start), Admission::Send);
+        subscription.mark_sent(start);
+
+        let due = start + Duration::from_millis(100);
+        let early = start + Duration::from_millis(40);
+        assert_eq!(
+            subscription.admit(&observation, early),
+            Admission::Defer(due)
+        );
+        assert_eq!(subscription.admit(&observation, due), Admission::Send);
+    }
+
+    #[test]
+    fn events_only_drops_observations_without_a_lock_after_the_first() {
+        let mut subscription = ClientSubscription::new(&Subscription {
+            events_only: true,
+            ..Subscription::default()
+        });
+        let now = Instant::now();
+        assert_eq!(
+            subscription.admit(&observation(false), now),
+            Admission::Send
+        );
+        subscription.mark_sent(now);
+
+        assert_eq!(
+            subscription.admit(&observation(false), now),
+            Admission::Drop
+        );
+        assert_eq!(subscription.admit(&observation(true), now), Admission::Send);
+    }
+}
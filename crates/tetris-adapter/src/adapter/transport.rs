@@ -0,0 +1,187 @@
+//! Listener transports for the adapter server.
+//!
+//! Every transport yields line-oriented byte streams that share the same
+//! framing, broker, and client mailbox; only accepting connections differs.
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+
+/// How the adapter server accepts clients.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// TCP on [`crate::adapter::server::ServerConfig::socket_addr`].
+    #[default]
+    Tcp,
+    /// A Unix domain socket at this path (Unix platforms only).
+    Unix(PathBuf),
+    /// A single client on the process's stdin and stdout. The server stops
+    /// once that client disconnects.
+    Stdio,
+}
+
+/// Where a bound server is reachable, or where a client connected from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+    Stdio,
+    WebSocket(SocketAddr),
+}
+
+impl Endpoint {
+    pub fn tcp(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(address) => Some(*address),
+            Self::Unix(_) | Self::Stdio | Self::WebSocket(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{address}"),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+            Self::Stdio => f.write_str("stdio"),
+            Self::WebSocket(address) => write!(f, "ws://{address}"),
+        }
+    }
+}
+
+/// Where a started server accepts clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Listening {
+    pub(crate) endpoint: Endpoint,
+    pub(crate) websocket: Option<SocketAddr>,
+}
+
+pub(super) type ConnectionReader = Pin<Box<dyn AsyncRead + Send>>;
+pub(super) type ConnectionWriter = Pin<Box<dyn AsyncWrite + Send>>;
+
+/// One accepted client stream, split for the reader loop and writer task.
+pub(super) struct Connection {
+    pub(super) reader: ConnectionReader,
+    pub(super) writer: ConnectionWriter,
+    pub(super) peer: Endpoint,
+}
+
+impl Connection {
+    /// Whether server messages may switch to length-prefixed binary frames.
+    /// WebSocket messages are text-only.
+    pub(super) fn supports_binary(&self) -> bool {
+        !matches!(self.peer, Endpoint::WebSocket(_))
+    }
+}
+
+pub(super) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+    Stdio {
+        accepted: bool,
+    },
+}
+
+impl Listener {
+    pub(super) async fn bind(
+        transport: &Transport,
+        tcp_addr: impl FnOnce() -> anyhow::Result<SocketAddr>,
+    ) -> anyhow::Result<Self> {
+        match transport {
+            Transport::Tcp => {
+                let addr = tcp_addr()?;
+                TcpListener::bind(&addr)
+                    .await
+                    .map(Self::Tcp)
+                    .map_err(|source| {
+                        anyhow::anyhow!("failed to bind AI adapter at {addr}: {source}")
+                    })
+            }
+            Transport::Unix(path) => bind_unix(path),
+            Transport::Stdio => Ok(Self::Stdio { accepted: false }),
+        }
+    }
+
+    pub(super) fn endpoint(&self) -> anyhow::Result<Endpoint> {
+        match self {
+            Self::Tcp(listener) => listener
+                .local_addr()
+                .map(Endpoint::Tcp)
+                .map_err(|source| anyhow::anyhow!("failed to read AI adapter address: {source}")),
+            #[cfg(unix)]
+            Self::Unix(_, path) => Ok(Endpoint::Unix(path.clone())),
+            Self::Stdio { .. } => Ok(Endpoint::Stdio),
+        }
+    }
+
+    /// Whether the server should stop once its first client disconnects.
+    pub(super) fn is_single_client(&self) -> bool {
+        matches!(self, Self::Stdio { .. })
+    }
+
+    /// Wait for the next client; `None` once a single-client transport is used.
+    pub(super) async fn accept(&mut self) -> std::io::Result<Option<Connection>> {
+        match self {
+            Self::Tcp(listener) => {
+                let (socket, peer) = listener.accept().await?;
+                let (reader, writer) = socket.into_split();
+                Ok(Some(Connection {
+                    reader: Box::pin(reader),
+                    writer: Box::pin(writer),
+                    peer: Endpoint::Tcp(peer),
+                }))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, path) => {
+                let (socket, _) = listener.accept().await?;
+                let (reader, writer) = socket.into_split();
+                Ok(Some(Connection {
+                    reader: Box::pin(reader),
+                    writer: Box::pin(writer),
+                    peer: Endpoint::Unix(path.clone()),
+                }))
+            }
+            Self::Stdio { accepted: true } => Ok(None),
+            Self::Stdio { accepted } => {
+                *accepted = true;
+                Ok(Some(Connection {
+                    reader: Box::pin(tokio::io::stdin()),
+                    writer: Box::pin(tokio::io::stdout()),
+                    peer: Endpoint::Stdio,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path) -> anyhow::Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // A socket left behind by an earlier run would make bind fail; other
+    // files at the path are never removed.
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        let _ = std::fs::remove_file(path);
+    }
+    tokio::net::UnixListener::bind(path)
+        .map(|listener| Listener::Unix(listener, path.to_path_buf()))
+        .map_err(|source| {
+            anyhow::anyhow!(
+                "failed to bind AI adapter at unix:{}: {source}",
+                path.display()
+            )
+        })
+}
+
+#[cfg(not(unix))]
+fn bind_unix(path: &std::path::Path) -> anyhow::Result<Listener> {
+    Err(anyhow::anyhow!(
+        "unix socket {} is not supported on this platform",
+        path.display()
+    ))
+}
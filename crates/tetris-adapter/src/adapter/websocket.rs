@@ -0,0 +1,413 @@
+//! WebSocket transport for browser clients.
+//!
+//! Implements the RFC 6455 server handshake and framing directly on tokio.
+//! Each text message carries one protocol JSON message. An upgraded
+//! connection is bridged onto an in-memory pipe as newline-terminated lines,
+//! so the regular bounded line reader, client mailbox, and writer task serve
+//! it unchanged, including their backpressure.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use crate::adapter::server::MAX_INBOUND_LINE_BYTES;
+use crate::adapter::transport::{Connection, Endpoint};
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_HANDSHAKE_BYTES: usize = 8 * 1024;
+/// Time a peer has to send its request head before the socket is dropped.
+pub(super) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Bytes buffered between a socket and its client tasks in each direction.
+const PIPE_CAPACITY: usize = 64 * 1024;
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+const CLOSE_NORMAL: u16 = 1000;
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_UNSUPPORTED_DATA: u16 = 1003;
+const CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+
+/// `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+    let mut input = Vec::with_capacity(key.len() + WEBSOCKET_GUID.len());
+    input.extend_from_slice(key.trim().as_bytes());
+    input.extend_from_slice(WEBSOCKET_GUID.as_bytes());
+    base64(&sha1(&input))
+}
+
+/// Complete the opening handshake and bridge the socket to a [`Connection`].
+///
+/// Returns `None` after answering a request that is not a valid upgrade.
+pub(super) async fn upgrade(
+    socket: TcpStream,
+    peer: SocketAddr,
+) -> std::io::Result<Option<Connection>> {
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    let Some(key) = read_handshake(&mut reader).await? else {
+        writer
+            .write_all(
+                b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            )
+            .await?;
+        return Ok(None);
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    writer.write_all(response.as_bytes()).await?;
+
+    let (client, pump) = tokio::io::duplex(PIPE_CAPACITY);
+    let (pump_reader, pump_writer) = tokio::io::split(pump);
+    let (control_tx, control_rx) = mpsc::channel(4);
+    tokio::spawn(pump_inbound(reader, pump_writer, control_tx));
+    tokio::spawn(pump_outbound(
+        BufReader::new(pump_reader),
+        writer,
+        control_rx,
+    ));
+
+    let (client_reader, client_writer) = tokio::io::split(client);
+    Ok(Some(Connection {
+        reader: Box::pin(client_reader),
+        writer: Box::pin(client_writer),
+        peer: Endpoint::WebSocket(peer),
+    }))
+}
+
+/// Read the request head and return its key if it is a valid upgrade.
+async fn read_handshake<R>(reader: &mut R) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufReadExt + AsyncRead + Unpin,
+{
+    // The limit also bounds a single line, so a head without line breaks
+    // cannot grow the buffer past it.
+    let mut reader = reader.take(MAX_HANDSHAKE_BYTES as u64);
+    let mut head = String::new();
+    loop {
+        let start = head.len();
+        if reader.read_line(&mut head).await? == 0 || !head.ends_with('\n') {
+            return Ok(None);
+        }
+        if head[start..].trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut lines = head.lines();
+    let request = lines.next().unwrap_or_default();
+    if !request.starts_with("GET ") || !request.ends_with("HTTP/1.1") {
+        return Ok(None);
+    }
+    let (mut upgrade, mut connection, mut version, mut key) = (false, false, false, None);
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let has_token = |token: &str| {
+            value
+                .split(',')
+                .any(|part| part.trim().eq_ignore_ascii_case(token))
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upgrade" => upgrade = has_token("websocket"),
+            "connection" => connection = has_token("upgrade"),
+            "sec-websocket-version" => version = value == "13",
+            "sec-websocket-key" if !value.is_empty() => key = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(key.filter(|_| upgrade && connection && version))
+}
+
+/// Control frames the inbound side asks the outbound side to send.
+enum Control {
+    Pong(Vec<u8>),
+    Close(u16),
+}
+
+/// Decode client frames into newline-terminated messages for the line reader.
+async fn pump_inbound<R, W>(mut socket: R, mut lines: W, control: mpsc::Sender<Control>)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut message: Vec<u8> = Vec::new();
+    let mut in_message = false;
+    let mut payload: Vec<u8> = Vec::new();
+    let code = loop {
+        let (fin, opcode) = match read_frame(&mut socket, &mut payload).await {
+            Ok(Ok(header)) => header,
+            Ok(Err(code)) => break code,
+            Err(_) => return,
+        };
+        match opcode {
+            OP_TEXT | OP_CONTINUATION => {
+                if (opcode == OP_TEXT) == in_message {
+                    break CLOSE_PROTOCOL_ERROR;
+                }
+                if message.len() + payload.len() > MAX_INBOUND_LINE_BYTES {
+                    break CLOSE_MESSAGE_TOO_BIG;
+                }
+                message.extend_from_slice(&payload);
+                in_message = !fin;
+                if fin {
+                    // JSON never needs a raw line break outside whitespace.
+                    for byte in &mut message {
+                        if matches!(*byte, b'\n' | b'\r') {
+                            *byte = b' ';
+                        }
+                    }
+                    message.push(b'\n');
+                    if lines.write_all(&message).await.is_err() {
+                        return;
+                    }
+                    message.clear();
+                }
+            }
+            OP_BINARY => break CLOSE_UNSUPPORTED_DATA,
+            OP_PING => {
+                let _ = control.send(Control::Pong(payload.clone())).await;
+            }
+            OP_PONG => {}
+            OP_CLOSE => break CLOSE_NORMAL,
+            _ => break CLOSE_PROTOCOL_ERROR,
+        }
+    };
+    let _ = control.send(Control::Close(code)).await;
+}
+
+/// Read one client frame into `payload`, returning `(fin, opcode)` or the
+/// close code for a frame the server refuses.
+async fn read_frame<R>(
+    socket: &mut R,
+    payload: &mut Vec<u8>,
+) -> std::io::Result<Result<(bool, u8), u16>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 2];
+    socket.read_exact(&mut header).await?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    if header[0] & 0x70 != 0 || header[1] & 0x80 == 0 {
+        // Reserved bits without an extension, or an unmasked client frame.
+        return Ok(Err(CLOSE_PROTOCOL_ERROR));
+    }
+    let len = match header[1] & 0x7F {
+        126 => u64::from(socket.read_u16().await?),
+        127 => socket.read_u64().await?,
+        len => u64::from(len),
+    };
+    let control = opcode & 0x8 != 0;
+    if control && (!fin || len > MAX_CONTROL_PAYLOAD as u64) {
+        return Ok(Err(CLOSE_PROTOCOL_ERROR));
+    }
+    if len > MAX_INBOUND_LINE_BYTES as u64 {
+        return Ok(Err(CLOSE_MESSAGE_TOO_BIG));
+    }
+    let mut mask = [0u8; 4];
+    socket.read_exact(&mut mask).await?;
+    payload.resize(len as usize, 0);
+    socket.read_exact(payload).await?;
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+    Ok(Ok((fin, opcode)))
+}
+
+/// Send each line written by the client's writer task as one text message.
+async fn pump_outbound<R, W>(mut lines: R, mut socket: W, mut control: mpsc::Receiver<Control>)
+where
+    R: AsyncBufReadExt + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut line: Vec<u8> = Vec::new();
+    let mut frame: Vec<u8> = Vec::new();
+    let code = loop {
+        tokio::select! {
+            biased;
+            request = control.recv() => match request {
+                Some(Control::Pong(payload)) => {
+                    encode_frame(&mut frame, OP_PONG, &payload);
+                    if socket.write_all(&frame).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Control::Close(code)) => break code,
+                None => break CLOSE_NORMAL,
+            },
+            // A partial read stays in `line` if the select is cancelled.
+            read = lines.read_until(b'\n', &mut line) => {
+                match read {
+                    Ok(0) | Err(_) => break CLOSE_NORMAL,
+                    Ok(_) => {}
+                }
+                if line.last() != Some(&b'\n') {
+                    continue;
+                }
+                line.pop();
+                encode_frame(&mut frame, OP_TEXT, &line);
+                line.clear();
+                if socket.write_all(&frame).await.is_err() {
+                    return;
+                }
+            }
+        }
+    };
+    encode_frame(&mut frame, OP_CLOSE, &code.to_be_bytes());
+    let _ = socket.write_all(&frame).await;
+    let _ = socket.shutdown().await;
+}
+
+fn encode_frame(frame: &mut Vec<u8>, opcode: u8, payload: &[u8]) {
+    frame.clear();
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (index, word) in block.chunks_exact(4).enumerate() {
+            w[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..80 {
+            w[index] = (w[index - 3] ^ w[index - 8] ^ w[index - 14] ^ w[index - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in w.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (slot, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *slot = slot.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_key_matches_rfc_6455_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[tokio::test]
+    async fn oversized_and_unmasked_frames_are_refused() {
+        let mut payload = Vec::new();
+        let unmasked: &[u8] = &[0x81, 0x02, b'{', b'}'];
+        assert_eq!(
+            read_frame(&mut &unmasked[..], &mut payload).await.unwrap(),
+            Err(CLOSE_PROTOCOL_ERROR)
+        );
+        let mut oversized = vec![0x81, 0xFF];
+        oversized.extend_from_slice(&(MAX_INBOUND_LINE_BYTES as u64 + 1).to_be_bytes());
+        assert_eq!(
+            read_frame(&mut &oversized[..], &mut payload).await.unwrap(),
+            Err(CLOSE_MESSAGE_TOO_BIG)
+        );
+    }
+
+    #[tokio::test]
+    async fn handshake_read_stops_at_the_head_limit() {
+        let (mut peer, socket) = tokio::io::duplex(4 * MAX_HANDSHAKE_BYTES);
+        let mut head = b"GET / HTTP/1.1\r\nX-Filler: ".to_vec();
+        head.resize(MAX_HANDSHAKE_BYTES + 1024, b'a');
+        peer.write_all(&head).await.unwrap();
+        // The peer stays open, so only the limit can end the read.
+        let key = tokio::time::timeout(
+            Duration::from_secs(5),
+            read_handshake(&mut BufReader::new(socket)),
+        )
+        .await
+        .expect("read_handshake waited past the head limit")
+        .unwrap();
+        assert_eq!(key, None);
+    }
+}
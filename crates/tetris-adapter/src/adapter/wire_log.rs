@@ -1,26 +1,78 @@
 //! Bounded, best-effort wire logging isolated from protocol delivery.
+//!
+//! Every line wraps one message as it crossed the wire:
+//! `{"t_us":<unix micros>,"client":<connection id>,"dir":"in"|"out","msg":{..}}`.
+//! The connection id is the one assigned on accept, kept across resumes.
 
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc;
 
-use crate::adapter::protocol::{AckMessage, ErrorMessage, ObservationMessage, WelcomeMessage};
+use crate::adapter::protocol::{
+    AckMessage, ControlStateMessage, ErrorMessage, ObservationMessage, RoomStateMessage,
+    WelcomeMessage,
+};
+use tetris_adapter_protocol::binary::{BinaryMessage, decode_frame};
 
 pub const WIRE_LOG_QUEUE_CAPACITY: usize = 1024;
 
 #[derive(Debug, Clone)]
 pub(super) enum WireRecord {
+    /// A line as received from the client.
+    Inbound(Arc<str>),
+    /// A pre-rendered line sent to the client.
     LineArc(Arc<str>),
-    Welcome(WelcomeMessage),
+    Welcome(Box<WelcomeMessage>),
     Ack(AckMessage),
     Error(ErrorMessage),
+    RoomState(RoomStateMessage),
+    ControlState(ControlStateMessage),
     ObservationArc(Arc<ObservationMessage>),
+    /// One binary frame as sent; logged as the equivalent JSON line.
+    Frame(Arc<[u8]>),
 }
 
-pub(super) fn try_log(log_tx: Option<&mpsc::Sender<WireRecord>>, record: WireRecord) {
-    if let Some(tx) = log_tx {
-        let _ = tx.try_send(record);
+impl WireRecord {
+    fn direction(&self) -> &'static str {
+        match self {
+            Self::Inbound(_) => "in",
+            _ => "out",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct WireEntry {
+    at_us: u64,
+    client: usize,
+    record: WireRecord,
+}
+
+/// One connection's handle on the wire logger.
+#[derive(Debug, Clone)]
+pub(super) struct ClientWireLog {
+    tx: mpsc::Sender<WireEntry>,
+    client: usize,
+}
+
+impl ClientWireLog {
+    pub(super) fn new(tx: mpsc::Sender<WireEntry>, client: usize) -> Self {
+        Self { tx, client }
+    }
+}
+
+pub(super) fn try_log(log_tx: Option<&ClientWireLog>, record: WireRecord) {
+    if let Some(log) = log_tx {
+        let at_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+        let _ = log.tx.try_send(WireEntry {
+            at_us,
+            client: log.client,
+            record,
+        });
     }
 }
 
@@ -28,8 +80,8 @@ pub(super) fn spawn_wire_logger(
     path: String,
     log_every_n: u64,
     log_max_lines: Option<u64>,
-) -> mpsc::Sender<WireRecord> {
-    let (tx, mut rx) = mpsc::channel::<WireRecord>(WIRE_LOG_QUEUE_CAPACITY);
+) -> mpsc::Sender<WireEntry> {
+    let (tx, mut rx) = mpsc::channel::<WireEntry>(WIRE_LOG_QUEUE_CAPACITY);
     tokio::spawn(async move {
         let mut file = match tokio::fs::OpenOptions::new()
             .create(true)
@@ -46,7 +98,7 @@ pub(super) fn spawn_wire_logger(
         let mut record_count = 0u64;
         let log_every_n = log_every_n.max(1);
 
-        while let Some(record) = rx.recv().await {
+        while let Some(entry) = rx.recv().await {
             record_count = record_count.wrapping_add(1);
             if !record_count.is_multiple_of(log_every_n)
                 || log_max_lines.is_some_and(|max| line_count >= max)
@@ -54,16 +106,39 @@ pub(super) fn spawn_wire_logger(
                 continue;
             }
 
-            let write_result = match record {
-                WireRecord::LineArc(line) => file.write_all(line.as_bytes()).await,
+            let envelope = format!(
+                r#"{{"t_us":{},"client":{},"dir":"{}","msg":"#,
+                entry.at_us,
+                entry.client,
+                entry.record.direction()
+            );
+            if file.write_all(envelope.as_bytes()).await.is_err() {
+                break;
+            }
+            let write_result = match entry.record {
+                // Malformed client input is kept as a JSON string.
+                WireRecord::Inbound(line)
+                    if serde_json::from_str::<serde::de::IgnoredAny>(&line).is_err() =>
+                {
+                    write_json(&mut file, &mut buf, line.as_ref()).await
+                }
+                WireRecord::Inbound(line) | WireRecord::LineArc(line) => {
+                    file.write_all(line.as_bytes()).await
+                }
                 WireRecord::Welcome(value) => write_json(&mut file, &mut buf, &value).await,
                 WireRecord::Ack(value) => write_json(&mut file, &mut buf, &value).await,
                 WireRecord::Error(value) => write_json(&mut file, &mut buf, &value).await,
+                WireRecord::RoomState(value) => write_json(&mut file, &mut buf, &value).await,
+                WireRecord::ControlState(value) => write_json(&mut file, &mut buf, &value).await,
                 WireRecord::ObservationArc(value) => {
                     write_json(&mut file, &mut buf, value.as_ref()).await
                 }
+                WireRecord::Frame(frame) => {
+                    encode_frame_json(&mut buf, &frame);
+                    file.write_all(&buf).await
+                }
             };
-            if write_result.is_err() || file.write_all(b"\n").await.is_err() {
+            if write_result.is_err() || file.write_all(b"}\n").await.is_err() {
                 break;
             }
             line_count = line_count.wrapping_add(1);
@@ -84,6 +159,26 @@ async fn write_json<T: serde::Serialize + ?Sized>(
     file.write_all(buf).await
 }
 
+/// Render a binary frame as the JSON line a JSON client would have received.
+fn encode_frame_json(buf: &mut Vec<u8>, frame: &[u8]) {
+    buf.clear();
+    let encoded = match decode_frame(frame) {
+        Ok((BinaryMessage::Observation(value), _)) => {
+            serde_json::to_writer(&mut *buf, &value).is_ok()
+        }
+        Ok((BinaryMessage::Ack(value), _)) => serde_json::to_writer(&mut *buf, &value).is_ok(),
+        Ok((BinaryMessage::Error(value), _)) => serde_json::to_writer(&mut *buf, &value).is_ok(),
+        Err(_) => false,
+    };
+    if !encoded {
+        buf.clear();
+        let _ = serde_json::to_writer(
+            &mut *buf,
+            &serde_json::json!({"type": "undecodable_frame", "len": frame.len()}),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,13 +186,33 @@ mod tests {
     #[test]
     fn bounded_queue_drops_records_when_full() {
         let (tx, mut rx) = mpsc::channel(1);
-        try_log(Some(&tx), WireRecord::LineArc(Arc::from("first")));
-        try_log(Some(&tx), WireRecord::LineArc(Arc::from("second")));
+        let log = ClientWireLog::new(tx, 4);
+        try_log(Some(&log), WireRecord::Inbound(Arc::from("first")));
+        try_log(Some(&log), WireRecord::LineArc(Arc::from("second")));
 
-        let WireRecord::LineArc(line) = rx.try_recv().unwrap() else {
+        let entry = rx.try_recv().unwrap();
+        let WireRecord::Inbound(line) = &entry.record else {
             panic!("expected first wire record");
         };
         assert_eq!(line.as_ref(), "first");
+        assert_eq!(entry.client, 4);
+        assert_eq!(entry.record.direction(), "in");
         assert!(rx.try_recv().is_err());
     }
+
+    #[test]
+    fn binary_frames_are_logged_as_json_lines() {
+        let ack = crate::adapter::protocol::create_ack(7, 6);
+        let mut frame = Vec::new();
+        tetris_adapter_protocol::binary::write_frame(&ack, &mut frame);
+
+        let mut buf = Vec::new();
+        encode_frame_json(&mut buf, &frame);
+        assert_eq!(buf, serde_json::to_vec(&ack).unwrap());
+
+        encode_frame_json(&mut buf, &frame[..frame.len() - 1]);
+        let logged: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(logged["type"], "undecodable_frame");
+        assert_eq!(logged["len"], frame.len() - 1);
+    }
 }
@@ -186,6 +186,22 @@ impl Board {
         }
     }
 
+    /// Occupancy bitmask per row (bit `x` set when column `x` is filled).
+    ///
+    /// Cheap fixed-size projection for search heuristics and compact encodings.
+    pub fn row_masks(&self) -> [u16; BOARD_HEIGHT as usize] {
+        let mut masks = [0u16; BOARD_HEIGHT as usize];
+        for (y, mask) in masks.iter_mut().enumerate() {
+            let row = &self.cells[(y * BOARD_WIDTH as usize)..((y + 1) * BOARD_WIDTH as usize)];
+            for (x, cell) in row.iter().enumerate() {
+                if cell.is_some() {
+                    *mask |= 1 << x;
+                }
+            }
+        }
+        masks
+    }
+
     /// Get a mutable reference to the internal cells array (for testing)
     #[cfg(test)]
     pub fn cells_mut(&mut self) -> &mut [Cell] {
@@ -385,6 +401,19 @@ mod tests {
         assert_eq!(board.filled_count(), 0);
     }
 
+    #[test]
+    fn row_masks_set_one_bit_per_filled_column() {
+        let mut board = Board::new();
+        board.set(0, 19, Some(PieceKind::I));
+        board.set(9, 19, Some(PieceKind::J));
+        board.set(4, 0, Some(PieceKind::T));
+
+        let masks = board.row_masks();
+        assert_eq!(masks[19], 0b10_0000_0001);
+        assert_eq!(masks[0], 0b1_0000);
+        assert!(masks[1..19].iter().all(|mask| *mask == 0));
+    }
+
     #[test]
     fn cells_exposes_the_complete_fixed_board() {
         assert_eq!(
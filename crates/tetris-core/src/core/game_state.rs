@@ -3,8 +3,9 @@
 //! This module ties together all core components: board, pieces, RNG, and scoring.
 //! It handles game timing, piece movement, rotation, line clears, and game lifecycle.
 
+use crate::core::state_codec::{STATE_CODEC_VERSION, StateDecodeError, StateReader, StateWriter};
 use crate::core::{
-    Board, PieceQueue, calculate_drop_score, calculate_score, get_shape,
+    Board, PieceQueue, calculate_drop_score, calculate_score_under, get_shape,
     scoring::get_drop_interval_ms, try_rotate,
 };
 use crate::types::*;
@@ -89,11 +90,18 @@ pub struct GameState {
     // Tracking for soft drop grace period
     soft_drop_timer_ms: u32,
     is_soft_dropping: bool,
+    /// Not part of the encoded state: it belongs to the session's rule set.
+    rules: Rules,
 }
 
 impl GameState {
     /// Create a new game with the given RNG seed
     pub fn new(seed: u32) -> Self {
+        Self::with_rules(seed, Rules::GUIDELINE)
+    }
+
+    /// Create a new game under `rules` rather than the current rule set.
+    pub fn with_rules(seed: u32, rules: Rules) -> Self {
         let piece_queue = PieceQueue::new(seed);
         let next_queue = piece_queue.peek_5();
 
@@ -126,9 +134,14 @@ impl GameState {
             last_action_was_rotate: false,
             soft_drop_timer_ms: 0,
             is_soft_dropping: false,
+            rules,
         }
     }
 
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
     /// Start the game and spawn the first piece
     pub fn start(&mut self) {
         if self.started {
@@ -198,6 +211,10 @@ impl GameState {
         self.board_id
     }
 
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn board_mut(&mut self) -> &mut Board {
         &mut self.board
@@ -376,7 +393,8 @@ impl GameState {
 
     /// Reset lock timers/counts semantics:
     /// - When not grounded, lock timer and reset count are cleared.
-    /// - When grounded, successful moves/rotations may reset the lock timer up to `LOCK_RESET_LIMIT`.
+    /// - When grounded, successful moves/rotations may reset the lock timer up to the
+    ///   rules' `lock_reset_limit`.
     fn handle_lock_reset(&mut self) {
         if !self.is_grounded() {
             self.lock_timer_ms = 0;
@@ -384,7 +402,7 @@ impl GameState {
             return;
         }
 
-        if self.lock_reset_count < LOCK_RESET_LIMIT {
+        if self.lock_reset_count < self.rules.lock_reset_limit {
             self.lock_timer_ms = 0;
             self.lock_reset_count += 1;
         }
@@ -574,7 +592,8 @@ impl GameState {
 
         // Scoring uses the pre-clear level.
         let combo_after_clear = self.combo.saturating_add(1);
-        let score_result = calculate_score(
+        let score_result = calculate_score_under(
+            &self.rules,
             lines_cleared,
             self.level,
             tspin,
@@ -589,7 +608,7 @@ impl GameState {
         self.score = self.score.saturating_add(score_result.total);
 
         // Start line clear timer.
-        self.line_clear_timer_ms = LINE_CLEAR_PAUSE_MS;
+        self.line_clear_timer_ms = self.rules.line_clear_pause_ms;
         self.landing_flash_ms = LANDING_FLASH_MS;
 
         score_result.line_clear_score
@@ -658,7 +677,7 @@ impl GameState {
     }
 
     /// Calculate the ghost piece Y position (where piece would land)
-    fn ghost_y(&self) -> Option<i8> {
+    pub fn ghost_y(&self) -> Option<i8> {
         let active = self.active?;
         let shape = active.shape();
 
@@ -738,7 +757,7 @@ impl GameState {
 
         if self.is_grounded() {
             self.lock_timer_ms = self.lock_timer_ms.saturating_add(elapsed_ms);
-            if self.lock_timer_ms >= LOCK_DELAY_MS {
+            if self.lock_timer_ms >= self.rules.lock_delay_ms {
                 self.lock_timer_ms = 0;
                 self.drop_timer_ms = 0;
                 self.lock_piece();
@@ -808,12 +827,140 @@ impl GameState {
     /// to guarantee determinism for training/evaluation.
     pub fn restart_with_seed(&mut self, seed: u32) -> bool {
         let next_episode = self.episode_id.wrapping_add(1);
-        *self = Self::new(seed);
+        *self = Self::with_rules(seed, self.rules);
         self.episode_id = next_episode;
         self.start();
         true
     }
 
+    /// Append the complete state to `out`; see [`crate::core::state_codec`].
+    pub fn encode_state(&self, out: &mut Vec<u8>) {
+        let mut out = StateWriter(out);
+        out.u8(STATE_CODEC_VERSION);
+        for &cell in self.board.cells() {
+            out.cell(cell);
+        }
+        out.u32(self.board_id);
+        match self.active {
+            Some(active) => {
+                out.cell(Some(active.kind));
+                out.rotation(active.rotation);
+                out.u8(active.x as u8);
+                out.u8(active.y as u8);
+            }
+            None => out.cell(None),
+        }
+        out.cell(self.hold);
+        for &kind in &self.next_queue {
+            out.piece(kind);
+        }
+        self.piece_queue.encode_state(&mut out);
+        for value in [
+            self.episode_id,
+            self.piece_id,
+            self.active_id,
+            self.step_in_piece,
+        ] {
+            out.u32(value);
+        }
+        out.last_event(self.last_event);
+        for value in [self.score, self.level, self.lines] {
+            out.u32(value);
+        }
+        out.i32(self.combo);
+        out.bool(self.back_to_back);
+        for value in [
+            self.drop_timer_ms,
+            self.lock_timer_ms,
+            self.lock_reset_count as u32,
+            self.line_clear_timer_ms,
+            self.landing_flash_ms,
+        ] {
+            out.u32(value);
+        }
+        for flag in [
+            self.paused,
+            self.game_over,
+            self.started,
+            self.can_hold,
+            self.last_action_was_rotate,
+        ] {
+            out.bool(flag);
+        }
+        out.u32(self.soft_drop_timer_ms);
+        out.bool(self.is_soft_dropping);
+    }
+
+    /// Restore a state written by [`GameState::encode_state`].
+    pub fn decode_state(bytes: &[u8]) -> Result<Self, StateDecodeError> {
+        Self::decode_state_under(bytes, Rules::GUIDELINE)
+    }
+
+    /// [`GameState::decode_state`] for a game running under `rules`.
+    pub fn decode_state_under(bytes: &[u8], rules: Rules) -> Result<Self, StateDecodeError> {
+        let mut input = StateReader::new(bytes);
+        let version = input.u8()?;
+        if version != STATE_CODEC_VERSION {
+            return Err(StateDecodeError::UnsupportedVersion(version));
+        }
+        let mut board = Board::new();
+        for y in 0..BOARD_HEIGHT as i8 {
+            for x in 0..BOARD_WIDTH as i8 {
+                board.set(x, y, input.cell("board")?);
+            }
+        }
+        let board_id = input.u32()?;
+        let active = match input.cell("active.kind")? {
+            Some(kind) => Some(Tetromino {
+                kind,
+                rotation: input.rotation()?,
+                x: input.u8()? as i8,
+                y: input.u8()? as i8,
+            }),
+            None => None,
+        };
+        let hold = input.cell("hold")?;
+        let mut next_queue = [PieceKind::I; 5];
+        for kind in &mut next_queue {
+            *kind = input.piece("next_queue")?;
+        }
+        let piece_queue = PieceQueue::decode_state(&mut input)?;
+        let state = Self {
+            board,
+            board_id,
+            active,
+            hold,
+            next_queue,
+            piece_queue,
+            episode_id: input.u32()?,
+            piece_id: input.u32()?,
+            active_id: input.u32()?,
+            step_in_piece: input.u32()?,
+            last_event: input.last_event()?,
+            score: input.u32()?,
+            level: input.u32()?,
+            lines: input.u32()?,
+            combo: input.i32()?,
+            back_to_back: input.bool("back_to_back")?,
+            drop_timer_ms: input.u32()?,
+            lock_timer_ms: input.u32()?,
+            lock_reset_count: u8::try_from(input.u32()?)
+                .map_err(|_| StateDecodeError::InvalidValue("lock_reset_count"))?,
+            line_clear_timer_ms: input.u32()?,
+            landing_flash_ms: input.u32()?,
+            paused: input.bool("paused")?,
+            game_over: input.bool("game_over")?,
+            started: input.bool("started")?,
+            can_hold: input.bool("can_hold")?,
+            last_action_was_rotate: input.bool("last_action_was_rotate")?,
+            soft_drop_timer_ms: input.u32()?,
+            is_soft_dropping: input.bool("is_soft_dropping")?,
+            rules,
+        };
+        input.finish()?;
+        Ok(state)
+    }
+
     /// Get the shape of the active piece (for rendering)
     #[cfg(test)]
     pub(crate) fn active_shape(&self) -> Option<[(i8, i8); 4]> {
@@ -622,6 +622,42 @@ fn test_lock_timer_starts_immediately_after_landing() {
     assert_eq!(state.lock_timer_ms, 16);
 }
 
+#[test]
+fn test_lock_delay_follows_the_game_rules() {
+    let rules = Rules {
+        lock_delay_ms: 32,
+        ..Rules::GUIDELINE
+    };
+    let mut state = GameState::with_rules(12345, rules);
+    state.started = true;
+    state.active = Some(Tetromino {
+        kind: PieceKind::O,
+        rotation: Rotation::North,
+        x: 3,
+        y: 18,
+    });
+
+    state.tick(16, false);
+    assert!(state.active.is_some());
+    state.tick(16, false);
+    assert_eq!(
+        state.piece_id(),
+        1,
+        "locked after 32ms and spawned the next piece"
+    );
+
+    assert!(state.restart_with_seed(7));
+    assert_eq!(state.rules(), &rules);
+    let mut encoded = Vec::new();
+    state.encode_state(&mut encoded);
+    assert_eq!(
+        GameState::decode_state_under(&encoded, rules)
+            .unwrap()
+            .rules(),
+        &rules
+    );
+}
+
 #[test]
 fn test_apply_action_move() {
     let mut state = GameState::new(12345);
@@ -1772,3 +1808,63 @@ fn test_landing_flash_ticks_down_during_line_clear_pause() {
     assert!(state.landing_flash_ms < flash_before);
     assert!(state.line_clear_timer_ms < 32);
 }
+
+#[test]
+fn encoded_state_round_trips_and_continues_identically() {
+    let mut original = GameState::new(21);
+    original.start();
+    for step in 0..400 {
+        match step % 23 {
+            0 => {
+                original.apply_action(GameAction::HardDrop);
+            }
+            7 => {
+                original.apply_action(GameAction::Hold);
+            }
+            11 => {
+                original.apply_action(GameAction::RotateCw);
+            }
+            _ => {}
+        }
+        original.tick(16, step % 5 == 0);
+    }
+    let mut encoded = Vec::new();
+    original.encode_state(&mut encoded);
+    let mut restored = GameState::decode_state(&encoded).unwrap();
+    let mut reencoded = Vec::new();
+    restored.encode_state(&mut reencoded);
+    assert_eq!(reencoded, encoded);
+
+    for step in 0..300 {
+        if step % 17 == 0 {
+            original.apply_action(GameAction::HardDrop);
+            restored.apply_action(GameAction::HardDrop);
+        }
+        original.tick(16, false);
+        restored.tick(16, false);
+        assert_eq!(restored.snapshot(), original.snapshot());
+    }
+    original.apply_action(GameAction::Restart);
+    restored.apply_action(GameAction::Restart);
+    assert_eq!(restored.snapshot(), original.snapshot());
+}
+
+#[test]
+fn decoding_rejects_truncated_and_unknown_states() {
+    let mut encoded = Vec::new();
+    GameState::new(3).encode_state(&mut encoded);
+    assert_eq!(
+        GameState::decode_state(&encoded[..encoded.len() - 1]).unwrap_err(),
+        StateDecodeError::Truncated
+    );
+    encoded.push(0);
+    assert_eq!(
+        GameState::decode_state(&encoded).unwrap_err(),
+        StateDecodeError::TrailingBytes
+    );
+    encoded[0] = STATE_CODEC_VERSION + 1;
+    assert!(matches!(
+        GameState::decode_state(&encoded),
+        Err(StateDecodeError::UnsupportedVersion(_))
+    ));
+}
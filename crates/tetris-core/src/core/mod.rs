@@ -15,6 +15,7 @@
 //! - [`pieces`]: Tetromino shape definitions and SRS rotation with wall kicks
 //! - [`rng`]: 7-bag random piece generation for fair distribution
 //! - [`scoring`]: Score calculation with T-spins, combos, and back-to-back bonuses
+//! - [`state_codec`]: Binary encoding of a complete game state for replay keyframes
 //!
 //! # Game Rules
 //!
@@ -63,6 +64,7 @@ pub mod pieces;
 pub mod rng;
 pub mod scoring;
 pub mod snapshot;
+pub mod state_codec;
 pub mod state_hash;
 
 // Re-export commonly used types for convenience
@@ -70,6 +72,7 @@ pub use board::Board;
 pub use game_state::{GameState, Tetromino};
 pub use pieces::{get_shape, try_rotate};
 pub use rng::{PieceQueue, SimpleRng};
-pub use scoring::{ScoreResult, calculate_drop_score, calculate_score};
+pub use scoring::{ScoreResult, calculate_drop_score, calculate_score, calculate_score_under};
 pub use snapshot::{ActiveSnapshot, GameSnapshot};
+pub use state_codec::{STATE_CODEC_VERSION, StateDecodeError};
 pub use state_hash::stable_state_hash;
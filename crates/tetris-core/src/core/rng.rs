@@ -6,6 +6,7 @@
 //!
 //! Also provides a simple LCG for deterministic testing.
 
+use crate::core::state_codec::{StateDecodeError, StateReader, StateWriter};
 use crate::types::PieceKind;
 
 /// Simple LCG (Linear Congruential Generator) RNG
@@ -189,6 +190,33 @@ impl PieceQueue {
     pub fn rng_state(&self) -> u32 {
         self.rng.state
     }
+
+    pub(crate) fn encode_state(&self, out: &mut StateWriter<'_>) {
+        out.u32(self.episode_seed);
+        for &kind in &self.bag {
+            out.piece(kind);
+        }
+        out.u8(self.bag_index as u8);
+        out.u32(self.rng.state);
+    }
+
+    pub(crate) fn decode_state(input: &mut StateReader<'_>) -> Result<Self, StateDecodeError> {
+        let episode_seed = input.u32()?;
+        let mut bag = [PieceKind::I; 7];
+        for kind in &mut bag {
+            *kind = input.piece("piece_queue.bag")?;
+        }
+        let bag_index = input.u8()? as usize;
+        if bag_index > bag.len() {
+            return Err(StateDecodeError::InvalidValue("piece_queue.bag_index"));
+        }
+        Ok(Self {
+            episode_seed,
+            bag,
+            bag_index,
+            rng: SimpleRng::new(input.u32()?),
+        })
+    }
 }
 
 impl Default for PieceQueue {
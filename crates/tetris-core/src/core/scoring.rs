@@ -7,7 +7,7 @@
 //! - B2B applies a 3/2 multiplier to the base clear points (before combo bonus).
 //! - Combo bonus is `combo_base * combo_index` with no level multiplier.
 
-use crate::types::{B2B_DENOMINATOR, B2B_NUMERATOR, COMBO_BASE, LINE_SCORES, TSpinKind};
+use crate::types::{B2B_DENOMINATOR, B2B_NUMERATOR, COMBO_BASE, LINE_SCORES, Rules, TSpinKind};
 
 /// Score calculation result
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -26,11 +26,14 @@ pub struct ScoreResult {
 /// lines: number of lines cleared (1-4)
 /// level: current level (0-based)
 pub fn calculate_line_score(lines: usize, level: u32) -> u32 {
+    line_score(&LINE_SCORES, lines, level)
+}
+
+fn line_score(table: &[u32; 5], lines: usize, level: u32) -> u32 {
     if lines == 0 || lines > 4 {
         return 0;
     }
-    let base_score = LINE_SCORES[lines];
-    base_score.saturating_mul(level.saturating_add(1))
+    table[lines].saturating_mul(level.saturating_add(1))
 }
 
 /// Calculate T-spin score (Modern rules)
@@ -55,10 +58,14 @@ pub fn calculate_tspin_score(tspin: TSpinKind, lines: usize, level: u32) -> u32
 /// - `0`: first clear in chain (no bonus)
 /// - `1+`: bonus applies as `combo_base * combo_index`
 pub fn calculate_combo_bonus(combo_index: i32) -> u32 {
+    combo_bonus(COMBO_BASE, combo_index)
+}
+
+fn combo_bonus(combo_base: u32, combo_index: i32) -> u32 {
     if combo_index <= 0 {
         return 0;
     }
-    COMBO_BASE.saturating_mul(combo_index as u32)
+    combo_base.saturating_mul(combo_index as u32)
 }
 
 /// Check if this clear qualifies for back-to-back
@@ -93,12 +100,31 @@ pub fn calculate_score(
     tspin: TSpinKind,
     combo_index: i32,
     previous_b2b: bool,
+) -> ScoreResult {
+    calculate_score_under(
+        &Rules::GUIDELINE,
+        lines,
+        level,
+        tspin,
+        combo_index,
+        previous_b2b,
+    )
+}
+
+/// [`calculate_score`] with the line and combo tables of `rules`.
+pub fn calculate_score_under(
+    rules: &Rules,
+    lines: usize,
+    level: u32,
+    tspin: TSpinKind,
+    combo_index: i32,
+    previous_b2b: bool,
 ) -> ScoreResult {
     let qualifies_b2b = qualifies_for_b2b(tspin, lines);
 
     let base_points = match tspin {
         TSpinKind::Full | TSpinKind::Mini => calculate_tspin_score(tspin, lines, level),
-        TSpinKind::None => calculate_line_score(lines, level),
+        TSpinKind::None => line_score(&rules.line_scores, lines, level),
     };
 
     let b2b_applied = qualifies_b2b && previous_b2b;
@@ -108,7 +134,7 @@ pub fn calculate_score(
         base_points
     };
 
-    let combo_bonus = calculate_combo_bonus(combo_index);
+    let combo_bonus = combo_bonus(rules.combo_base, combo_index);
     let total = line_clear_score.saturating_add(combo_bonus);
 
     ScoreResult {
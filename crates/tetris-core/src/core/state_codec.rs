@@ -0,0 +1,213 @@
+//! Binary encoding of a complete [`GameState`](crate::core::GameState).
+//!
+//! Unlike [`GameSnapshot`](crate::core::GameSnapshot), an encoded state keeps
+//! everything the simulation depends on (timers, lock resets, the 7-bag and
+//! its RNG), so a decoded state continues exactly like the original. Replay
+//! keyframes use it to start playback in the middle of a tape.
+//!
+//! The layout is fixed-width little endian, versioned by
+//! [`STATE_CODEC_VERSION`].
+
+use std::fmt;
+
+use crate::types::{CoreLastEvent, PieceKind, Rotation, TSpinKind};
+
+pub const STATE_CODEC_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateDecodeError {
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidValue(&'static str),
+    TrailingBytes,
+}
+
+impl fmt::Display for StateDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported game state encoding version {version}")
+            }
+            Self::Truncated => f.write_str("truncated game state"),
+            Self::InvalidValue(field) => write!(f, "invalid game state field: {field}"),
+            Self::TrailingBytes => f.write_str("trailing bytes after game state"),
+        }
+    }
+}
+
+impl std::error::Error for StateDecodeError {}
+
+pub(crate) struct StateWriter<'a>(pub(crate) &'a mut Vec<u8>);
+
+impl StateWriter<'_> {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn i32(&mut self, value: i32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn piece(&mut self, kind: PieceKind) {
+        self.u8(piece_code(kind));
+    }
+
+    /// `0` for `None`, otherwise the piece code plus one.
+    pub(crate) fn cell(&mut self, kind: Option<PieceKind>) {
+        self.u8(kind.map_or(0, |kind| piece_code(kind) + 1));
+    }
+
+    pub(crate) fn rotation(&mut self, rotation: Rotation) {
+        self.u8(match rotation {
+            Rotation::North => 0,
+            Rotation::East => 1,
+            Rotation::South => 2,
+            Rotation::West => 3,
+        });
+    }
+
+    pub(crate) fn last_event(&mut self, event: Option<CoreLastEvent>) {
+        let Some(event) = event else {
+            self.bool(false);
+            return;
+        };
+        self.bool(true);
+        self.bool(event.locked);
+        self.u32(event.lines_cleared);
+        self.u32(event.line_clear_score);
+        self.u8(match event.tspin {
+            None => 0,
+            Some(TSpinKind::None) => 1,
+            Some(TSpinKind::Mini) => 2,
+            Some(TSpinKind::Full) => 3,
+        });
+        self.i32(event.combo);
+        self.bool(event.back_to_back);
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn finish(self) -> Result<(), StateDecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(StateDecodeError::TrailingBytes)
+        }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], StateDecodeError> {
+        let (head, tail) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(StateDecodeError::Truncated)?;
+        self.bytes = tail;
+        Ok(*head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, StateDecodeError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn bool(&mut self, field: &'static str) -> Result<bool, StateDecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateDecodeError::InvalidValue(field)),
+        }
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, StateDecodeError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, StateDecodeError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn piece(&mut self, field: &'static str) -> Result<PieceKind, StateDecodeError> {
+        piece_from_code(self.u8()?).ok_or(StateDecodeError::InvalidValue(field))
+    }
+
+    pub(crate) fn cell(
+        &mut self,
+        field: &'static str,
+    ) -> Result<Option<PieceKind>, StateDecodeError> {
+        match self.u8()? {
+            0 => Ok(None),
+            code => piece_from_code(code - 1)
+                .map(Some)
+                .ok_or(StateDecodeError::InvalidValue(field)),
+        }
+    }
+
+    pub(crate) fn rotation(&mut self) -> Result<Rotation, StateDecodeError> {
+        match self.u8()? {
+            0 => Ok(Rotation::North),
+            1 => Ok(Rotation::East),
+            2 => Ok(Rotation::South),
+            3 => Ok(Rotation::West),
+            _ => Err(StateDecodeError::InvalidValue("rotation")),
+        }
+    }
+
+    pub(crate) fn last_event(&mut self) -> Result<Option<CoreLastEvent>, StateDecodeError> {
+        if !self.bool("last_event")? {
+            return Ok(None);
+        }
+        Ok(Some(CoreLastEvent {
+            locked: self.bool("last_event.locked")?,
+            lines_cleared: self.u32()?,
+            line_clear_score: self.u32()?,
+            tspin: match self.u8()? {
+                0 => None,
+                1 => Some(TSpinKind::None),
+                2 => Some(TSpinKind::Mini),
+                3 => Some(TSpinKind::Full),
+                _ => return Err(StateDecodeError::InvalidValue("last_event.tspin")),
+            },
+            combo: self.i32()?,
+            back_to_back: self.bool("last_event.back_to_back")?,
+        }))
+    }
+}
+
+fn piece_code(kind: PieceKind) -> u8 {
+    match kind {
+        PieceKind::I => 0,
+        PieceKind::O => 1,
+        PieceKind::T => 2,
+        PieceKind::S => 3,
+        PieceKind::Z => 4,
+        PieceKind::J => 5,
+        PieceKind::L => 6,
+    }
+}
+
+fn piece_from_code(code: u8) -> Option<PieceKind> {
+    Some(match code {
+        0 => PieceKind::I,
+        1 => PieceKind::O,
+        2 => PieceKind::T,
+        3 => PieceKind::S,
+        4 => PieceKind::Z,
+        5 => PieceKind::J,
+        6 => PieceKind::L,
+        _ => return None,
+    })
+}
@@ -470,3 +470,35 @@ pub const B2B_NUMERATOR: u32 = 3;
 
 /// Back-to-back bonus denominator
 pub const B2B_DENOMINATOR: u32 = 2;
+
+/// Rules a game runs under, fixed for its lifetime.
+///
+/// [`Rules::GUIDELINE`] is the current rule set. A game built with other
+/// values reproduces a retired rule set, so tapes recorded under it still
+/// replay exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// Base points per line count, multiplied by (level + 1).
+    pub line_scores: [u32; 5],
+    /// Combo bonus per combo step.
+    pub combo_base: u32,
+    pub lock_delay_ms: u32,
+    pub lock_reset_limit: u8,
+    pub line_clear_pause_ms: u32,
+}
+
+impl Rules {
+    pub const GUIDELINE: Self = Self {
+        line_scores: LINE_SCORES,
+        combo_base: COMBO_BASE,
+        lock_delay_ms: LOCK_DELAY_MS,
+        lock_reset_limit: LOCK_RESET_LIMIT,
+        line_clear_pause_ms: LINE_CLEAR_PAUSE_MS,
+    };
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::GUIDELINE
+    }
+}
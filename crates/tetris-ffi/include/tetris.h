@@ -0,0 +1,269 @@
+#ifndef TETRIS_FFI_H
+#define TETRIS_FFI_H
+
+/* Generated by cbindgen from crates/tetris-ffi/src/lib.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Incremented on any incompatible change to this API.
+ */
+#define TT_ABI_VERSION 1
+
+#define TT_BOARD_WIDTH 10
+
+#define TT_BOARD_HEIGHT 20
+
+#define TT_NEXT_QUEUE_LEN 5
+
+#define TT_OK 0
+
+/**
+ * A required pointer argument was null.
+ */
+#define TT_ERR_NULL -1
+
+/**
+ * An action, rotation, or count was out of range.
+ */
+#define TT_ERR_INVALID_ARGUMENT -2
+
+/**
+ * The placement was rejected by the rules; the step still advanced.
+ */
+#define TT_ERR_PLACE_REJECTED -3
+
+/**
+ * Replay bytes could not be decoded.
+ */
+#define TT_ERR_DECODE -4
+
+/**
+ * Replay decoded but a recorded state hash did not reproduce.
+ */
+#define TT_ERR_REPLAY_MISMATCH -5
+
+/**
+ * The output buffer is too small; the required size was reported.
+ */
+#define TT_ERR_BUFFER_TOO_SMALL -6
+
+/**
+ * The session was created without recording, so it has no replay.
+ */
+#define TT_ERR_NOT_RECORDING -7
+
+#define TT_ACTION_MOVE_LEFT 0
+
+#define TT_ACTION_MOVE_RIGHT 1
+
+#define TT_ACTION_SOFT_DROP 2
+
+#define TT_ACTION_HARD_DROP 3
+
+#define TT_ACTION_ROTATE_CW 4
+
+#define TT_ACTION_ROTATE_CCW 5
+
+#define TT_ACTION_HOLD 6
+
+#define TT_ACTION_PAUSE 7
+
+#define TT_ACTION_RESTART 8
+
+/**
+ * Opaque session handle.
+ */
+typedef struct TtSession TtSession;
+
+/**
+ * Result of one logical step.
+ */
+typedef struct {
+  uint64_t logical_step;
+  /**
+   * Replay transition hash of this step.
+   */
+  uint64_t transition_hash;
+  uint32_t lines_cleared;
+  uint8_t locked;
+  uint8_t changed;
+  uint8_t game_over;
+} TtStepResult;
+
+/**
+ * Placement target; `rotation` is 0..=3 (north, east, south, west).
+ */
+typedef struct {
+  int8_t x;
+  uint8_t rotation;
+  uint8_t use_hold;
+} TtPlacement;
+
+/**
+ * Observable state. Piece kinds use board cell codes: 0 none, 1..=7 I O T S Z J L.
+ */
+typedef struct {
+  uint8_t board[TT_BOARD_HEIGHT][TT_BOARD_WIDTH];
+  uint8_t active_kind;
+  uint8_t active_rotation;
+  int8_t active_x;
+  int8_t active_y;
+  /**
+   * Landing row of the active piece, -1 without one.
+   */
+  int8_t ghost_y;
+  uint8_t hold;
+  uint8_t next_queue[TT_NEXT_QUEUE_LEN];
+  uint8_t can_hold;
+  uint8_t paused;
+  uint8_t game_over;
+  uint32_t episode_id;
+  uint32_t seed;
+  uint32_t piece_id;
+  uint32_t step_in_piece;
+  uint32_t score;
+  uint32_t level;
+  uint32_t lines;
+  uint64_t logical_step;
+  uint64_t state_hash;
+} TtSnapshot;
+
+/**
+ * Summary of a verified (or mismatching) replay.
+ */
+typedef struct {
+  uint32_t seed;
+  uint64_t steps;
+  /**
+   * First mismatching step; only meaningful with `TT_ERR_REPLAY_MISMATCH`.
+   */
+  uint64_t mismatch_step;
+  uint64_t expected_hash;
+  uint64_t actual_hash;
+} TtReplayInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+uint32_t tt_abi_version(void);
+
+/**
+ * Ruleset identifier as a static NUL-terminated string.
+ */
+const char *tt_ruleset_version(void);
+
+/**
+ * Create a started session that keeps no history. Free it with
+ * `tt_session_free`.
+ */
+TtSession *tt_session_new(uint32_t seed);
+
+/**
+ * Create a started session that records every step for
+ * `tt_session_encode_replay`. The history grows by one input per step for
+ * the life of the session.
+ */
+TtSession *tt_session_new_recording(uint32_t seed);
+
+/**
+ * # Safety
+ * `session` must be null or a handle returned by this library that has not
+ * been freed yet.
+ */
+void tt_session_free(TtSession *session);
+
+/**
+ * Apply up to 64 `TT_ACTION_*` codes and advance one 16ms logical step.
+ *
+ * # Safety
+ * `session` must be a live handle, `actions` must point to `action_count`
+ * bytes (or be null when the count is zero), and `out` must be null or
+ * writable.
+ */
+int32_t tt_session_step(TtSession *session,
+                        const uint8_t *actions,
+                        size_t action_count,
+                        TtStepResult *out);
+
+/**
+ * Apply one atomic placement and advance one logical step.
+ *
+ * Returns `TT_ERR_PLACE_REJECTED` when the rules refuse the placement; the
+ * step is still taken and recorded, exactly like a rejected adapter `place`.
+ *
+ * # Safety
+ * `session` must be a live handle and `out` must be null or writable.
+ */
+int32_t tt_session_place(TtSession *session, TtPlacement placement, TtStepResult *out);
+
+/**
+ * Copy the current observable state into `out`.
+ *
+ * # Safety
+ * `session` must be a live handle and `out` must be writable.
+ */
+int32_t tt_session_snapshot(const TtSession *session, TtSnapshot *out);
+
+/**
+ * Write in-bounds placement targets for the active piece into `out`.
+ *
+ * Returns the total number of targets; at most `capacity` are written, so
+ * calling with `capacity == 0` queries the required size.
+ *
+ * # Safety
+ * `session` must be a live handle and `out` must be null or point to
+ * `capacity` writable elements.
+ */
+size_t tt_session_placements(const TtSession *session,
+                             uint8_t include_hold,
+                             TtPlacement *out,
+                             size_t capacity);
+
+/**
+ * Encode the session history as a TTR2 replay.
+ *
+ * `*written` receives the encoded length. When it exceeds `capacity` nothing
+ * is copied and `TT_ERR_BUFFER_TOO_SMALL` is returned. Sessions that do not
+ * record return `TT_ERR_NOT_RECORDING` with `*written` set to 0.
+ *
+ * # Safety
+ * `session` must be a live handle, `out` must be null or point to `capacity`
+ * writable bytes, and `written` must be writable.
+ */
+int32_t tt_session_encode_replay(const TtSession *session,
+                                 uint8_t *out,
+                                 size_t capacity,
+                                 size_t *written);
+
+/**
+ * Decode and verify a replay without keeping a session.
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `info` must be null or
+ * writable.
+ */
+int32_t tt_replay_verify(const uint8_t *data, size_t len, TtReplayInfo *info);
+
+/**
+ * Rebuild a session by verifying a replay; it continues from the last step
+ * and keeps recording on top of the replay's history.
+ *
+ * Returns null on failure and stores the reason in `*status` when non-null.
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `status` must be null or
+ * writable.
+ */
+TtSession *tt_session_from_replay(const uint8_t *data, size_t len, int32_t *status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TETRIS_FFI_H */
@@ -0,0 +1,671 @@
+//! Stable C ABI over `tetris-core` and `tetris-session`.
+//!
+//! Every entry point is prefixed `tt_`, takes plain `#[repr(C)]` values or an
+//! opaque `TtSession` handle, and reports failures with `TT_*` status codes
+//! instead of unwinding. The matching header lives in `include/tetris.h` and is
+//! kept in sync with this file by a cbindgen test.
+//!
+//! Sessions created with `tt_session_new_recording` (or resumed from a replay)
+//! keep every step input so the exact history can be exported as a TTR2
+//! replay and verified or resumed elsewhere. That history grows by one input
+//! per step and is never trimmed, so long training loops should use
+//! `tt_session_new`, which records nothing.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use std::ffi::c_char;
+use std::ptr;
+
+use tetris_core::core::{GameSnapshot, stable_state_hash};
+use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH, GameAction, PieceKind, Rotation};
+use tetris_session::engine::place::placement_targets;
+use tetris_session::engine::replay::{
+    RULESET_VERSION_CSTR, ReplayTape, replay_and_verify, transition_hash,
+};
+use tetris_session::engine::session::{
+    GameCommand, MAX_LOCAL_ACTIONS_PER_STEP, SessionRuntime, StepInput,
+};
+
+/// Incremented on any incompatible change to this API.
+pub const TT_ABI_VERSION: u32 = 1;
+
+pub const TT_BOARD_WIDTH: usize = 10;
+pub const TT_BOARD_HEIGHT: usize = 20;
+pub const TT_NEXT_QUEUE_LEN: usize = 5;
+
+const _: () = assert!(TT_BOARD_WIDTH == BOARD_WIDTH as usize);
+const _: () = assert!(TT_BOARD_HEIGHT == BOARD_HEIGHT as usize);
+
+pub const TT_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const TT_ERR_NULL: i32 = -1;
+/// An action, rotation, or count was out of range.
+pub const TT_ERR_INVALID_ARGUMENT: i32 = -2;
+/// The placement was rejected by the rules; the step still advanced.
+pub const TT_ERR_PLACE_REJECTED: i32 = -3;
+/// Replay bytes could not be decoded.
+pub const TT_ERR_DECODE: i32 = -4;
+/// Replay decoded but a recorded state hash did not reproduce.
+pub const TT_ERR_REPLAY_MISMATCH: i32 = -5;
+/// The output buffer is too small; the required size was reported.
+pub const TT_ERR_BUFFER_TOO_SMALL: i32 = -6;
+/// The session was created without recording, so it has no replay.
+pub const TT_ERR_NOT_RECORDING: i32 = -7;
+
+pub const TT_ACTION_MOVE_LEFT: u8 = 0;
+pub const TT_ACTION_MOVE_RIGHT: u8 = 1;
+pub const TT_ACTION_SOFT_DROP: u8 = 2;
+pub const TT_ACTION_HARD_DROP: u8 = 3;
+pub const TT_ACTION_ROTATE_CW: u8 = 4;
+pub const TT_ACTION_ROTATE_CCW: u8 = 5;
+pub const TT_ACTION_HOLD: u8 = 6;
+pub const TT_ACTION_PAUSE: u8 = 7;
+pub const TT_ACTION_RESTART: u8 = 8;
+
+/// Opaque session handle.
+pub struct TtSession {
+    runtime: SessionRuntime,
+    seed: u32,
+    /// Every applied input, kept only for recording sessions.
+    history: Option<Vec<StepInput>>,
+}
+
+/// Placement target; `rotation` is 0..=3 (north, east, south, west).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtPlacement {
+    pub x: i8,
+    pub rotation: u8,
+    pub use_hold: u8,
+}
+
+/// Observable state. Piece kinds use board cell codes: 0 none, 1..=7 I O T S Z J L.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtSnapshot {
+    pub board: [[u8; TT_BOARD_WIDTH]; TT_BOARD_HEIGHT],
+    pub active_kind: u8,
+    pub active_rotation: u8,
+    pub active_x: i8,
+    pub active_y: i8,
+    /// Landing row of the active piece, -1 without one.
+    pub ghost_y: i8,
+    pub hold: u8,
+    pub next_queue: [u8; TT_NEXT_QUEUE_LEN],
+    pub can_hold: u8,
+    pub paused: u8,
+    pub game_over: u8,
+    pub episode_id: u32,
+    pub seed: u32,
+    pub piece_id: u32,
+    pub step_in_piece: u32,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    pub logical_step: u64,
+    pub state_hash: u64,
+}
+
+/// Result of one logical step.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtStepResult {
+    pub logical_step: u64,
+    /// Replay transition hash of this step.
+    pub transition_hash: u64,
+    pub lines_cleared: u32,
+    pub locked: u8,
+    pub changed: u8,
+    pub game_over: u8,
+}
+
+/// Summary of a verified (or mismatching) replay.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtReplayInfo {
+    pub seed: u32,
+    pub steps: u64,
+    /// First mismatching step; only meaningful with `TT_ERR_REPLAY_MISMATCH`.
+    pub mismatch_step: u64,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+}
+
+fn action_from_code(code: u8) -> Option<GameAction> {
+    Some(match code {
+        TT_ACTION_MOVE_LEFT => GameAction::MoveLeft,
+        TT_ACTION_MOVE_RIGHT => GameAction::MoveRight,
+        TT_ACTION_SOFT_DROP => GameAction::SoftDrop,
+        TT_ACTION_HARD_DROP => GameAction::HardDrop,
+        TT_ACTION_ROTATE_CW => GameAction::RotateCw,
+        TT_ACTION_ROTATE_CCW => GameAction::RotateCcw,
+        TT_ACTION_HOLD => GameAction::Hold,
+        TT_ACTION_PAUSE => GameAction::Pause,
+        TT_ACTION_RESTART => GameAction::Restart,
+        _ => return None,
+    })
+}
+
+fn rotation_from_code(code: u8) -> Option<Rotation> {
+    Some(match code {
+        0 => Rotation::North,
+        1 => Rotation::East,
+        2 => Rotation::South,
+        3 => Rotation::West,
+        _ => return None,
+    })
+}
+
+fn rotation_code(rotation: Rotation) -> u8 {
+    match rotation {
+        Rotation::North => 0,
+        Rotation::East => 1,
+        Rotation::South => 2,
+        Rotation::West => 3,
+    }
+}
+
+fn kind_code(kind: PieceKind) -> u8 {
+    match kind {
+        PieceKind::I => 1,
+        PieceKind::O => 2,
+        PieceKind::T => 3,
+        PieceKind::S => 4,
+        PieceKind::Z => 5,
+        PieceKind::J => 6,
+        PieceKind::L => 7,
+    }
+}
+
+fn snapshot_to_c(snapshot: &GameSnapshot, logical_step: u64) -> TtSnapshot {
+    let mut next_queue = [0; TT_NEXT_QUEUE_LEN];
+    for (slot, &kind) in next_queue.iter_mut().zip(snapshot.next_queue.iter()) {
+        *slot = kind_code(kind);
+    }
+    let active = snapshot.active;
+    TtSnapshot {
+        board: snapshot.board,
+        active_kind: active.map_or(0, |active| kind_code(active.kind)),
+        active_rotation: active.map_or(0, |active| rotation_code(active.rotation)),
+        active_x: active.map_or(0, |active| active.x),
+        active_y: active.map_or(0, |active| active.y),
+        ghost_y: snapshot.ghost_y.unwrap_or(-1),
+        hold: snapshot.hold.map_or(0, kind_code),
+        next_queue,
+        can_hold: u8::from(snapshot.can_hold),
+        paused: u8::from(snapshot.paused),
+        game_over: u8::from(snapshot.game_over),
+        episode_id: snapshot.episode_id,
+        seed: snapshot.seed,
+        piece_id: snapshot.piece_id,
+        step_in_piece: snapshot.step_in_piece,
+        score: snapshot.score,
+        level: snapshot.level,
+        lines: snapshot.lines,
+        logical_step,
+        state_hash: stable_state_hash(snapshot, None),
+    }
+}
+
+impl TtSession {
+    fn new(seed: u32, record: bool) -> Self {
+        Self {
+            runtime: SessionRuntime::new(seed),
+            seed,
+            history: record.then(Vec::new),
+        }
+    }
+
+    fn step(&mut self, input: StepInput) -> (TtStepResult, bool) {
+        let transition = self.runtime.transition(&input);
+        let rejected = transition.command_outcomes.iter().any(Result::is_err);
+        let result = TtStepResult {
+            logical_step: self.runtime.logical_step(),
+            transition_hash: transition_hash(
+                self.runtime.snapshot(),
+                self.runtime.logical_step(),
+                &transition.events,
+                &transition.command_outcomes,
+            ),
+            lines_cleared: transition.events.iter().map(|e| e.lines_cleared).sum(),
+            locked: u8::from(transition.events.iter().any(|e| e.locked)),
+            changed: u8::from(transition.changed),
+            game_over: u8::from(self.runtime.game().game_over()),
+        };
+        if let Some(history) = &mut self.history {
+            history.push(input);
+        }
+        (result, rejected)
+    }
+}
+
+/// # Safety
+/// `data` must point to `len` readable bytes (or be null with `len == 0`).
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    // SAFETY: the caller guarantees `data` points to `len` readable bytes.
+    Some(unsafe { std::slice::from_raw_parts(data, len) })
+}
+
+fn decode_replay(bytes: &[u8]) -> Result<ReplayTape, i32> {
+    ReplayTape::decode(bytes).map_err(|_| TT_ERR_DECODE)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn tt_abi_version() -> u32 {
+    TT_ABI_VERSION
+}
+
+/// Ruleset identifier as a static NUL-terminated string.
+#[unsafe(no_mangle)]
+pub extern "C" fn tt_ruleset_version() -> *const c_char {
+    RULESET_VERSION_CSTR.as_ptr()
+}
+
+/// Create a started session that keeps no history. Free it with
+/// `tt_session_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn tt_session_new(seed: u32) -> *mut TtSession {
+    Box::into_raw(Box::new(TtSession::new(seed, false)))
+}
+
+/// Create a started session that records every step for
+/// `tt_session_encode_replay`. The history grows by one input per step for
+/// the life of the session.
+#[unsafe(no_mangle)]
+pub extern "C" fn tt_session_new_recording(seed: u32) -> *mut TtSession {
+    Box::into_raw(Box::new(TtSession::new(seed, true)))
+}
+
+/// # Safety
+/// `session` must be null or a handle returned by this library that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_free(session: *mut TtSession) {
+    if !session.is_null() {
+        // SAFETY: the caller passes an owned, not yet freed handle.
+        drop(unsafe { Box::from_raw(session) });
+    }
+}
+
+/// Apply up to 64 `TT_ACTION_*` codes and advance one 16ms logical step.
+///
+/// # Safety
+/// `session` must be a live handle, `actions` must point to `action_count`
+/// bytes (or be null when the count is zero), and `out` must be null or
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_step(
+    session: *mut TtSession,
+    actions: *const u8,
+    action_count: usize,
+    out: *mut TtStepResult,
+) -> i32 {
+    // SAFETY: the caller guarantees a live, exclusive handle.
+    let Some(session) = (unsafe { session.as_mut() }) else {
+        return TT_ERR_NULL;
+    };
+    // SAFETY: forwarded caller contract for `actions`.
+    let Some(codes) = (unsafe { bytes(actions, action_count) }) else {
+        return TT_ERR_NULL;
+    };
+    if codes.len() > MAX_LOCAL_ACTIONS_PER_STEP {
+        return TT_ERR_INVALID_ARGUMENT;
+    }
+    let mut input = StepInput::default();
+    for &code in codes {
+        let Some(action) = action_from_code(code) else {
+            return TT_ERR_INVALID_ARGUMENT;
+        };
+        input.local.push(action);
+    }
+    let (result, _) = session.step(input);
+    // SAFETY: `out` is null or writable per the caller contract.
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = result;
+    }
+    TT_OK
+}
+
+/// Apply one atomic placement and advance one logical step.
+///
+/// Returns `TT_ERR_PLACE_REJECTED` when the rules refuse the placement; the
+/// step is still taken and recorded, exactly like a rejected adapter `place`.
+///
+/// # Safety
+/// `session` must be a live handle and `out` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_place(
+    session: *mut TtSession,
+    placement: TtPlacement,
+    out: *mut TtStepResult,
+) -> i32 {
+    // SAFETY: the caller guarantees a live, exclusive handle.
+    let Some(session) = (unsafe { session.as_mut() }) else {
+        return TT_ERR_NULL;
+    };
+    let Some(rotation) = rotation_from_code(placement.rotation) else {
+        return TT_ERR_INVALID_ARGUMENT;
+    };
+    let input = StepInput::default().with_remote(GameCommand::Place {
+        x: placement.x,
+        rotation,
+        use_hold: placement.use_hold != 0,
+    });
+    let (result, rejected) = session.step(input);
+    // SAFETY: `out` is null or writable per the caller contract.
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = result;
+    }
+    if rejected {
+        TT_ERR_PLACE_REJECTED
+    } else {
+        TT_OK
+    }
+}
+
+/// Copy the current observable state into `out`.
+///
+/// # Safety
+/// `session` must be a live handle and `out` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_snapshot(
+    session: *const TtSession,
+    out: *mut TtSnapshot,
+) -> i32 {
+    // SAFETY: the caller guarantees both pointers are valid when non-null.
+    let (Some(session), Some(out)) = (unsafe { session.as_ref() }, unsafe { out.as_mut() }) else {
+        return TT_ERR_NULL;
+    };
+    *out = snapshot_to_c(session.runtime.snapshot(), session.runtime.logical_step());
+    TT_OK
+}
+
+/// Write in-bounds placement targets for the active piece into `out`.
+///
+/// Returns the total number of targets; at most `capacity` are written, so
+/// calling with `capacity == 0` queries the required size.
+///
+/// # Safety
+/// `session` must be a live handle and `out` must be null or point to
+/// `capacity` writable elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_placements(
+    session: *const TtSession,
+    include_hold: u8,
+    out: *mut TtPlacement,
+    capacity: usize,
+) -> usize {
+    // SAFETY: the caller guarantees a live handle.
+    let Some(session) = (unsafe { session.as_ref() }) else {
+        return 0;
+    };
+    let targets = placement_targets(session.runtime.game(), include_hold != 0);
+    if !out.is_null() {
+        // SAFETY: `out` points to `capacity` writable elements.
+        let out = unsafe { std::slice::from_raw_parts_mut(out, capacity) };
+        for (slot, target) in out.iter_mut().zip(&targets) {
+            *slot = TtPlacement {
+                x: target.x,
+                rotation: rotation_code(target.rotation),
+                use_hold: u8::from(target.use_hold),
+            };
+        }
+    }
+    targets.len()
+}
+
+/// Encode the session history as a TTR2 replay.
+///
+/// `*written` receives the encoded length. When it exceeds `capacity` nothing
+/// is copied and `TT_ERR_BUFFER_TOO_SMALL` is returned. Sessions that do not
+/// record return `TT_ERR_NOT_RECORDING` with `*written` set to 0.
+///
+/// # Safety
+/// `session` must be a live handle, `out` must be null or point to `capacity`
+/// writable bytes, and `written` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_encode_replay(
+    session: *const TtSession,
+    out: *mut u8,
+    capacity: usize,
+    written: *mut usize,
+) -> i32 {
+    // SAFETY: the caller guarantees both pointers are valid when non-null.
+    let (Some(session), Some(written)) = (unsafe { session.as_ref() }, unsafe { written.as_mut() })
+    else {
+        return TT_ERR_NULL;
+    };
+    let Some(history) = &session.history else {
+        *written = 0;
+        return TT_ERR_NOT_RECORDING;
+    };
+    let encoded = ReplayTape::record(session.seed, history.iter().cloned()).encode();
+    *written = encoded.len();
+    if encoded.len() > capacity || out.is_null() {
+        return TT_ERR_BUFFER_TOO_SMALL;
+    }
+    // SAFETY: `out` has room for `capacity >= encoded.len()` bytes.
+    unsafe { ptr::copy_nonoverlapping(encoded.as_ptr(), out, encoded.len()) };
+    TT_OK
+}
+
+/// Decode and verify a replay without keeping a session.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `info` must be null or
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_replay_verify(
+    data: *const u8,
+    len: usize,
+    info: *mut TtReplayInfo,
+) -> i32 {
+    // SAFETY: forwarded caller contract for `data`.
+    let Some(data) = (unsafe { bytes(data, len) }) else {
+        return TT_ERR_NULL;
+    };
+    let tape = match decode_replay(data) {
+        Ok(tape) => tape,
+        Err(status) => return status,
+    };
+    let mut summary = TtReplayInfo {
+        seed: tape.seed(),
+        steps: tape.records().len() as u64,
+        ..TtReplayInfo::default()
+    };
+    let status = match replay_and_verify(&tape) {
+        Ok(_) => TT_OK,
+        Err(mismatch) => {
+            summary.mismatch_step = mismatch.step as u64;
+            summary.expected_hash = mismatch.expected;
+            summary.actual_hash = mismatch.actual;
+            TT_ERR_REPLAY_MISMATCH
+        }
+    };
+    // SAFETY: `info` is null or writable per the caller contract.
+    if let Some(info) = unsafe { info.as_mut() } {
+        *info = summary;
+    }
+    status
+}
+
+/// Rebuild a session by verifying a replay; it continues from the last step
+/// and keeps recording on top of the replay's history.
+///
+/// Returns null on failure and stores the reason in `*status` when non-null.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `status` must be null or
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tt_session_from_replay(
+    data: *const u8,
+    len: usize,
+    status: *mut i32,
+) -> *mut TtSession {
+    // SAFETY: forwarded caller contract for `data`.
+    let result = match unsafe { bytes(data, len) } {
+        None => Err(TT_ERR_NULL),
+        Some(data) => decode_replay(data).and_then(|tape| {
+            let runtime = replay_and_verify(&tape).map_err(|_| TT_ERR_REPLAY_MISMATCH)?;
+            Ok(TtSession {
+                runtime,
+                seed: tape.seed(),
+                history: Some(
+                    tape.records()
+                        .iter()
+                        .map(|record| record.input.clone())
+                        .collect(),
+                ),
+            })
+        }),
+    };
+    let (session, code) = match result {
+        Ok(session) => (Box::into_raw(Box::new(session)), TT_OK),
+        Err(code) => (ptr::null_mut(), code),
+    };
+    // SAFETY: `status` is null or writable per the caller contract.
+    if let Some(status) = unsafe { status.as_mut() } {
+        *status = code;
+    }
+    session
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use tetris_session::engine::replay::RULESET_VERSION;
+
+    #[test]
+    fn ruleset_string_matches_the_session_crate() {
+        // SAFETY: the pointer is a static NUL-terminated string.
+        let ruleset = unsafe { CStr::from_ptr(tt_ruleset_version()) };
+        assert_eq!(ruleset.to_str(), Ok(RULESET_VERSION));
+    }
+
+    #[test]
+    fn session_steps_places_and_round_trips_through_a_replay() {
+        let session = tt_session_new_recording(7);
+        let mut result = TtStepResult::default();
+        let actions = [TT_ACTION_MOVE_LEFT, TT_ACTION_ROTATE_CW];
+        // SAFETY: live handle and valid buffers throughout this test.
+        unsafe {
+            assert_eq!(
+                tt_session_step(session, actions.as_ptr(), actions.len(), &mut result),
+                TT_OK
+            );
+            assert_eq!(result.logical_step, 1);
+            assert_eq!(
+                tt_session_step(session, [99].as_ptr(), 1, &mut result),
+                TT_ERR_INVALID_ARGUMENT
+            );
+
+            let count = tt_session_placements(session, 1, ptr::null_mut(), 0);
+            let mut targets = vec![TtPlacement::default(); count];
+            assert_eq!(
+                tt_session_placements(session, 1, targets.as_mut_ptr(), count),
+                count
+            );
+            assert_eq!(tt_session_place(session, targets[0], &mut result), TT_OK);
+            assert_eq!(result.locked, 1);
+
+            let mut snapshot = std::mem::zeroed::<TtSnapshot>();
+            assert_eq!(tt_session_snapshot(session, &mut snapshot), TT_OK);
+            assert_eq!(snapshot.seed, 7);
+            assert_eq!(snapshot.logical_step, 2);
+            assert!(snapshot.board.iter().flatten().any(|&cell| cell != 0));
+
+            let mut needed = 0;
+            assert_eq!(
+                tt_session_encode_replay(session, ptr::null_mut(), 0, &mut needed),
+                TT_ERR_BUFFER_TOO_SMALL
+            );
+            let mut replay = vec![0; needed];
+            assert_eq!(
+                tt_session_encode_replay(session, replay.as_mut_ptr(), replay.len(), &mut needed),
+                TT_OK
+            );
+
+            let mut info = TtReplayInfo::default();
+            assert_eq!(
+                tt_replay_verify(replay.as_ptr(), replay.len(), &mut info),
+                TT_OK
+            );
+            assert_eq!((info.seed, info.steps), (7, 2));
+
+            let mut status = TT_ERR_NULL;
+            let resumed = tt_session_from_replay(replay.as_ptr(), replay.len(), &mut status);
+            assert_eq!(status, TT_OK);
+            let mut resumed_snapshot = std::mem::zeroed::<TtSnapshot>();
+            tt_session_snapshot(resumed, &mut resumed_snapshot);
+            assert_eq!(resumed_snapshot, snapshot);
+
+            assert!(tt_session_from_replay(b"junk".as_ptr(), 4, &mut status).is_null());
+            assert_eq!(status, TT_ERR_DECODE);
+
+            tt_session_free(resumed);
+            tt_session_free(session);
+        }
+    }
+
+    #[test]
+    fn plain_sessions_keep_no_history() {
+        let session = tt_session_new(7);
+        // SAFETY: live handle and valid buffers throughout this test.
+        unsafe {
+            for _ in 0..3 {
+                tt_session_step(session, ptr::null(), 0, ptr::null_mut());
+            }
+            assert!((*session).history.is_none());
+            let mut written = usize::MAX;
+            assert_eq!(
+                tt_session_encode_replay(session, ptr::null_mut(), 0, &mut written),
+                TT_ERR_NOT_RECORDING
+            );
+            assert_eq!(written, 0);
+            tt_session_free(session);
+        }
+    }
+
+    #[test]
+    fn null_handles_are_reported_not_dereferenced() {
+        // SAFETY: null pointers are part of the documented contract.
+        unsafe {
+            assert_eq!(
+                tt_session_step(ptr::null_mut(), ptr::null(), 0, ptr::null_mut()),
+                TT_ERR_NULL
+            );
+            assert_eq!(
+                tt_session_snapshot(ptr::null(), ptr::null_mut()),
+                TT_ERR_NULL
+            );
+            assert_eq!(tt_session_placements(ptr::null(), 0, ptr::null_mut(), 0), 0);
+            tt_session_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn checked_in_header_matches_cbindgen_output() {
+        let crate_dir = env!("CARGO_MANIFEST_DIR");
+        let header_path = std::path::Path::new(crate_dir).join("include/tetris.h");
+        let mut generated = Vec::new();
+        cbindgen::Builder::new()
+            .with_config(cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap())
+            .with_crate(crate_dir)
+            .generate()
+            .expect("generate header")
+            .write(&mut generated);
+        if std::env::var_os("TETRIS_FFI_BLESS").is_some() {
+            std::fs::write(&header_path, &generated).unwrap();
+        }
+        let checked_in = std::fs::read(&header_path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "include/tetris.h is stale; rerun with TETRIS_FFI_BLESS=1"
+        );
+    }
+}
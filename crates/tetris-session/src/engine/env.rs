@@ -0,0 +1,449 @@
+//! Gym-style reinforcement-learning environment over [`SessionRuntime`].
+//!
+//! `TetrisEnv` keeps the session's deterministic `StepInput → Transition`
+//! boundary and adds the pieces an RL loop needs: episode reset, reward,
+//! termination/truncation flags, per-step info, and discrete action spaces.
+//! Stepping borrows the session snapshot as the observation and performs no
+//! heap allocation.
+
+use tetris_core::core::{GameSnapshot, GameState, get_shape};
+use tetris_core::types::{BOARD_WIDTH, CoreLastEvent, GameAction, Rotation, TSpinKind};
+
+use crate::engine::place::{MAX_PLACEMENTS, Placement};
+use crate::engine::session::{SessionRuntime, StepInput};
+
+/// Per-tick actions exposed by [`ActionSpace::Tick`]; index 0 is a no-op.
+pub const TICK_ACTIONS: [Option<GameAction>; 8] = [
+    None,
+    Some(GameAction::MoveLeft),
+    Some(GameAction::MoveRight),
+    Some(GameAction::SoftDrop),
+    Some(GameAction::HardDrop),
+    Some(GameAction::RotateCw),
+    Some(GameAction::RotateCcw),
+    Some(GameAction::Hold),
+];
+
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::North,
+    Rotation::East,
+    Rotation::South,
+    Rotation::West,
+];
+
+/// How agent decisions map onto logical steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSpace {
+    /// One optional raw `GameAction` per 16ms logical step.
+    Tick,
+    /// One complete placement per step, applied atomically like
+    /// `GameCommand::Place`.
+    Placement,
+}
+
+impl ActionSpace {
+    /// Number of discrete action indices.
+    ///
+    /// Placement indices are `(use_hold * 4 + rotation) * BOARD_WIDTH + column`,
+    /// where `column` is the leftmost board column covered by the piece.
+    pub fn size(self) -> usize {
+        match self {
+            ActionSpace::Tick => TICK_ACTIONS.len(),
+            ActionSpace::Placement => MAX_PLACEMENTS,
+        }
+    }
+}
+
+/// A decoded agent action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvAction {
+    Tick(Option<GameAction>),
+    Place(Placement),
+}
+
+/// Diagnostics for one environment step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepInfo {
+    pub lines_cleared: u32,
+    pub score_delta: u32,
+    /// Garbage lines this step would send in a versus game.
+    pub attack: u32,
+    pub pieces_locked: u32,
+    /// The action did not match the action space or was rejected by the rules.
+    pub invalid_action: bool,
+    /// Steps taken in the current episode, including this one.
+    pub episode_step: u64,
+}
+
+/// Pluggable reward shaping.
+pub trait RewardFn {
+    fn reward(&mut self, info: &StepInfo, terminated: bool) -> f32;
+}
+
+impl<F: FnMut(&StepInfo, bool) -> f32> RewardFn for F {
+    fn reward(&mut self, info: &StepInfo, terminated: bool) -> f32 {
+        self(info, terminated)
+    }
+}
+
+/// Reward equal to the number of cleared lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinesReward;
+
+impl RewardFn for LinesReward {
+    fn reward(&mut self, info: &StepInfo, _terminated: bool) -> f32 {
+        info.lines_cleared as f32
+    }
+}
+
+/// Reward equal to the in-game score gained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScoreDeltaReward;
+
+impl RewardFn for ScoreDeltaReward {
+    fn reward(&mut self, info: &StepInfo, _terminated: bool) -> f32 {
+        info.score_delta as f32
+    }
+}
+
+/// Constant reward per surviving step and a penalty on top-out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurvivalReward {
+    pub per_step: f32,
+    pub game_over: f32,
+}
+
+impl Default for SurvivalReward {
+    fn default() -> Self {
+        Self {
+            per_step: 1.0,
+            game_over: -1.0,
+        }
+    }
+}
+
+impl RewardFn for SurvivalReward {
+    fn reward(&mut self, _info: &StepInfo, terminated: bool) -> f32 {
+        if terminated {
+            self.game_over
+        } else {
+            self.per_step
+        }
+    }
+}
+
+/// Reward equal to garbage lines sent (see [`attack_lines`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AttackReward;
+
+impl RewardFn for AttackReward {
+    fn reward(&mut self, info: &StepInfo, _terminated: bool) -> f32 {
+        info.attack as f32
+    }
+}
+
+/// Guideline garbage sent by one lock event.
+///
+/// `back_to_back_before` is the B2B state before this clear; the bonus line
+/// applies only when a qualifying clear continues an existing chain.
+pub fn attack_lines(event: &CoreLastEvent, back_to_back_before: bool) -> u32 {
+    if event.lines_cleared == 0 {
+        return 0;
+    }
+    let base = match (event.tspin.unwrap_or(TSpinKind::None), event.lines_cleared) {
+        (TSpinKind::Full, lines) => lines * 2,
+        (TSpinKind::Mini, lines) => lines - 1,
+        (TSpinKind::None, 4) => 4,
+        (TSpinKind::None, lines) => lines - 1,
+    };
+    let b2b = u32::from(back_to_back_before && event.back_to_back);
+    let combo = match event.combo {
+        i32::MIN..=0 => 0,
+        1..=2 => 1,
+        3..=4 => 2,
+        5..=6 => 3,
+        7..=9 => 4,
+        _ => 5,
+    };
+    base + b2b + combo
+}
+
+/// Result of [`TetrisEnv::step`], mirroring Gym's
+/// `(observation, reward, terminated, truncated, info)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvStep<'a> {
+    pub observation: &'a GameSnapshot,
+    pub reward: f32,
+    /// The game ended (top-out).
+    pub terminated: bool,
+    /// The episode hit `max_episode_steps` without ending.
+    pub truncated: bool,
+    pub info: StepInfo,
+}
+
+/// In-process single-session RL environment.
+#[derive(Debug, Clone)]
+pub struct TetrisEnv<R = LinesReward> {
+    session: SessionRuntime,
+    action_space: ActionSpace,
+    reward: R,
+    max_episode_steps: Option<u64>,
+    episode_step: u64,
+    back_to_back: bool,
+}
+
+impl<R: RewardFn> TetrisEnv<R> {
+    pub fn new(seed: u32, action_space: ActionSpace, reward: R) -> Self {
+        Self {
+            session: SessionRuntime::new(seed),
+            action_space,
+            reward,
+            max_episode_steps: None,
+            episode_step: 0,
+            back_to_back: false,
+        }
+    }
+
+    /// Truncate episodes after `steps` environment steps.
+    pub fn with_max_episode_steps(mut self, steps: u64) -> Self {
+        self.max_episode_steps = Some(steps);
+        self
+    }
+
+    pub fn action_space(&self) -> ActionSpace {
+        self.action_space
+    }
+
+    pub fn session(&self) -> &SessionRuntime {
+        &self.session
+    }
+
+    pub fn game(&self) -> &GameState {
+        self.session.game()
+    }
+
+    pub fn observation(&self) -> &GameSnapshot {
+        self.session.snapshot()
+    }
+
+    /// Start a new episode from `seed` and return its first observation.
+    pub fn reset(&mut self, seed: u32) -> &GameSnapshot {
+        self.session = SessionRuntime::new(seed);
+        self.episode_step = 0;
+        self.back_to_back = false;
+        self.session.snapshot()
+    }
+
+    /// Decode a discrete action index for the configured action space.
+    pub fn action_from_index(&self, index: usize) -> Option<EnvAction> {
+        match self.action_space {
+            ActionSpace::Tick => TICK_ACTIONS.get(index).copied().map(EnvAction::Tick),
+            ActionSpace::Placement => {
+                placement_from_index(self.session.game(), index).map(EnvAction::Place)
+            }
+        }
+    }
+
+    /// Step with a discrete action index; unknown indices count as invalid.
+    pub fn step_index(&mut self, index: usize) -> EnvStep<'_> {
+        let action = self.action_from_index(index);
+        self.step_action(action)
+    }
+
+    /// Advance one environment step.
+    ///
+    /// Actions that do not belong to the configured action space are
+    /// reported through `info.invalid_action` and still advance one logical
+    /// step, so the episode always makes progress.
+    pub fn step(&mut self, action: EnvAction) -> EnvStep<'_> {
+        self.step_action(Some(action))
+    }
+
+    fn step_action(&mut self, action: Option<EnvAction>) -> EnvStep<'_> {
+        let score_before = self.session.game().score();
+        let mut info = StepInfo::default();
+        let input = match (self.action_space, action) {
+            (ActionSpace::Tick, Some(EnvAction::Tick(Some(action)))) => {
+                StepInput::default().with_local(action)
+            }
+            (ActionSpace::Tick, Some(EnvAction::Tick(None))) => StepInput::default(),
+            (ActionSpace::Placement, Some(EnvAction::Place(placement))) => {
+                StepInput::default().with_remote(placement.into())
+            }
+            _ => {
+                info.invalid_action = true;
+                StepInput::default()
+            }
+        };
+
+        let transition = self.session.transition(&input);
+        info.invalid_action |= transition.command_outcomes.iter().any(Result::is_err);
+        for event in &transition.events {
+            info.lines_cleared += event.lines_cleared;
+            info.pieces_locked += u32::from(event.locked);
+            info.attack += attack_lines(event, self.back_to_back);
+            // The core clears B2B on every lock that clears no lines too.
+            if event.locked {
+                self.back_to_back = event.back_to_back;
+            }
+        }
+        self.episode_step += 1;
+        info.episode_step = self.episode_step;
+        info.score_delta = self.session.game().score().saturating_sub(score_before);
+
+        let terminated = self.session.game().game_over();
+        let truncated = !terminated
+            && self
+                .max_episode_steps
+                .is_some_and(|max| self.episode_step >= max);
+        let reward = self.reward.reward(&info, terminated);
+        EnvStep {
+            observation: self.session.snapshot(),
+            reward,
+            terminated,
+            truncated,
+            info,
+        }
+    }
+}
+
+/// Decode a fixed placement index against the current active (or hold) piece.
+///
+/// Returns `None` when the index is out of range, no piece is active, hold is
+/// unavailable, or the footprint does not fit at that column.
+pub fn placement_from_index(game: &GameState, index: usize) -> Option<Placement> {
+    if index >= MAX_PLACEMENTS {
+        return None;
+    }
+    let width = BOARD_WIDTH as usize;
+    let column = (index % width) as i8;
+    let rotation = ROTATIONS[(index / width) % 4];
+    let use_hold = index >= width * 4;
+    let active = game.active()?;
+    let kind = if use_hold {
+        if !game.can_hold() {
+            return None;
+        }
+        game.hold_piece().unwrap_or(game.next_queue()[0])
+    } else {
+        active.kind
+    };
+    let shape = get_shape(kind, rotation);
+    let min_dx = shape.iter().map(|(dx, _)| *dx).min().unwrap_or(0);
+    let max_dx = shape.iter().map(|(dx, _)| *dx).max().unwrap_or(0);
+    let x = column - min_dx;
+    if x + max_dx >= BOARD_WIDTH as i8 {
+        return None;
+    }
+    Some(Placement {
+        x,
+        rotation,
+        use_hold,
+    })
+}
+
+/// Write a validity mask for every placement index into `mask`.
+///
+/// The mask covers footprint and hold availability only; reachability is
+/// decided when the placement is applied.
+pub fn placement_mask(game: &GameState, mask: &mut [bool; MAX_PLACEMENTS]) {
+    for (index, slot) in mask.iter_mut().enumerate() {
+        *slot = placement_from_index(game, index).is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::place::{apply_place, placement_targets};
+    use tetris_core::types::{BOARD_HEIGHT, PieceKind};
+
+    fn clear_event(lines: u32, tspin: Option<TSpinKind>, combo: i32, b2b: bool) -> CoreLastEvent {
+        CoreLastEvent {
+            locked: true,
+            lines_cleared: lines,
+            line_clear_score: 0,
+            tspin,
+            combo,
+            back_to_back: b2b,
+        }
+    }
+
+    #[test]
+    fn attack_follows_guideline_table() {
+        assert_eq!(attack_lines(&clear_event(1, None, 0, false), false), 0);
+        assert_eq!(attack_lines(&clear_event(2, None, 0, false), false), 1);
+        assert_eq!(attack_lines(&clear_event(4, None, 0, true), false), 4);
+        assert_eq!(attack_lines(&clear_event(4, None, 0, true), true), 5);
+        assert_eq!(
+            attack_lines(&clear_event(2, Some(TSpinKind::Full), 2, true), true),
+            4 + 1 + 1
+        );
+        assert_eq!(attack_lines(&clear_event(0, None, -1, false), true), 0);
+    }
+
+    #[test]
+    fn a_lock_without_lines_ends_the_back_to_back_chain() {
+        let mut env = TetrisEnv::new(1, ActionSpace::Placement, LinesReward);
+        let mut tetris_attacks = Vec::new();
+        let mut locks_between = 0;
+        while tetris_attacks.len() < 2 {
+            let mut game = env.game().clone();
+            let tetris = game.active().unwrap().kind == PieceKind::I
+                && (tetris_attacks.is_empty() || locks_between > 0);
+            game.board_mut().clear();
+            if tetris {
+                for y in BOARD_HEIGHT as i8 - 4..BOARD_HEIGHT as i8 {
+                    for x in 1..BOARD_WIDTH as i8 {
+                        game.board_mut().set(x, y, Some(PieceKind::O));
+                    }
+                }
+            }
+            env.session = SessionRuntime::from_game(game);
+
+            let lines = if tetris { 4 } else { 0 };
+            let placement = placement_targets(env.game(), false)
+                .into_iter()
+                .find(|target| {
+                    let mut state = env.game().clone();
+                    apply_place(&mut state, target.x, target.rotation, false).is_ok()
+                        && state.lines() == env.game().lines() + lines
+                })
+                .unwrap();
+            let step = env.step(EnvAction::Place(placement));
+            assert_eq!(step.info.lines_cleared, lines);
+            if tetris {
+                tetris_attacks.push(step.info.attack);
+            } else if !tetris_attacks.is_empty() {
+                locks_between += 1;
+            }
+        }
+        assert_eq!(tetris_attacks, [4, 4]);
+    }
+
+    #[test]
+    fn placement_indices_round_trip_through_the_leftmost_column() {
+        let mut game = GameState::new(1);
+        game.start();
+        let mut mask = [false; MAX_PLACEMENTS];
+        placement_mask(&game, &mut mask);
+        assert!(mask[0]);
+        for (index, valid) in mask.iter().enumerate() {
+            let Some(placement) = placement_from_index(&game, index) else {
+                assert!(!valid);
+                continue;
+            };
+            let kind = if placement.use_hold {
+                game.next_queue()[0]
+            } else {
+                game.active().unwrap().kind
+            };
+            let left = get_shape(kind, placement.rotation)
+                .iter()
+                .map(|(dx, _)| placement.x + dx)
+                .min()
+                .unwrap();
+            assert_eq!(left as usize, index % BOARD_WIDTH as usize);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Deterministic board evaluation used by placement search.
+//!
+//! Features are computed from fixed-size row bitmasks and scored with integer
+//! weights, so equal boards always rank identically on every platform.
+
+use tetris_core::core::Board;
+use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH};
+
+/// Structural board features consumed by [`HeuristicWeights::score`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardFeatures {
+    /// Sum of all column heights.
+    pub aggregate_height: i32,
+    /// Height of the tallest column.
+    pub max_height: i32,
+    /// Empty cells with at least one filled cell above them in the same column.
+    pub holes: i32,
+    /// Sum of absolute height differences between neighbouring columns.
+    pub bumpiness: i32,
+    /// Sum of well depths (columns lower than both neighbours, walls count as filled).
+    pub wells: i32,
+}
+
+impl BoardFeatures {
+    pub fn from_board(board: &Board) -> Self {
+        Self::from_row_masks(&board.row_masks())
+    }
+
+    pub fn from_row_masks(rows: &[u16; BOARD_HEIGHT as usize]) -> Self {
+        let mut heights = [0i32; BOARD_WIDTH as usize];
+        let mut holes = 0;
+        for (x, height) in heights.iter_mut().enumerate() {
+            let bit = 1u16 << x;
+            let mut seen_filled = false;
+            for (y, row) in rows.iter().enumerate() {
+                if row & bit != 0 {
+                    if !seen_filled {
+                        *height = BOARD_HEIGHT as i32 - y as i32;
+                        seen_filled = true;
+                    }
+                } else if seen_filled {
+                    holes += 1;
+                }
+            }
+        }
+
+        let mut features = Self {
+            holes,
+            ..Self::default()
+        };
+        for (x, &height) in heights.iter().enumerate() {
+            features.aggregate_height += height;
+            features.max_height = features.max_height.max(height);
+            if let Some(&right) = heights.get(x + 1) {
+                features.bumpiness += (height - right).abs();
+            }
+            let left = if x == 0 {
+                BOARD_HEIGHT as i32
+            } else {
+                heights[x - 1]
+            };
+            let right = heights.get(x + 1).copied().unwrap_or(BOARD_HEIGHT as i32);
+            let depth = left.min(right) - height;
+            if depth > 0 {
+                features.wells += depth;
+            }
+        }
+        features
+    }
+}
+
+/// Integer feature weights; positive values are rewarded, negative penalised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeuristicWeights {
+    pub aggregate_height: i32,
+    pub max_height: i32,
+    pub holes: i32,
+    pub bumpiness: i32,
+    pub wells: i32,
+    /// Reward per cleared line along the evaluated path.
+    pub lines_cleared: i32,
+    /// Score assigned to any path that ends the game.
+    pub game_over: i32,
+}
+
+impl Default for HeuristicWeights {
+    /// Hand-tuned weights in thousandths, derived from the classic
+    /// height/lines/holes/bumpiness linear evaluator.
+    fn default() -> Self {
+        Self {
+            aggregate_height: -510,
+            max_height: -60,
+            holes: -3_560,
+            bumpiness: -185,
+            wells: -120,
+            lines_cleared: 760,
+            game_over: -1_000_000_000,
+        }
+    }
+}
+
+impl HeuristicWeights {
+    /// Score a board plus the number of lines cleared on the way to it.
+    pub fn score(&self, features: &BoardFeatures, lines_cleared: u32) -> i32 {
+        self.aggregate_height
+            .saturating_mul(features.aggregate_height)
+            .saturating_add(self.max_height.saturating_mul(features.max_height))
+            .saturating_add(self.holes.saturating_mul(features.holes))
+            .saturating_add(self.bumpiness.saturating_mul(features.bumpiness))
+            .saturating_add(self.wells.saturating_mul(features.wells))
+            .saturating_add(
+                self.lines_cleared
+                    .saturating_mul(lines_cleared.min(i32::MAX as u32) as i32),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris_core::types::PieceKind;
+
+    #[test]
+    fn features_measure_heights_holes_and_bumpiness() {
+        let mut board = Board::new();
+        // Column 0: height 3 with one covered hole; column 1: height 1.
+        board.set(0, 17, Some(PieceKind::I));
+        board.set(0, 19, Some(PieceKind::I));
+        board.set(1, 19, Some(PieceKind::O));
+
+        let features = BoardFeatures::from_board(&board);
+        assert_eq!(features.aggregate_height, 4);
+        assert_eq!(features.max_height, 3);
+        assert_eq!(features.holes, 1);
+        assert_eq!(features.bumpiness, 2 + 1);
+    }
+
+    #[test]
+    fn holes_score_worse_than_flat_stacks() {
+        let weights = HeuristicWeights::default();
+        let mut flat = Board::new();
+        let mut holed = Board::new();
+        for x in 0..4 {
+            flat.set(x, 19, Some(PieceKind::I));
+            holed.set(x, 18, Some(PieceKind::I));
+        }
+
+        let flat_score = weights.score(&BoardFeatures::from_board(&flat), 0);
+        let holed_score = weights.score(&BoardFeatures::from_board(&holed), 0);
+        assert!(flat_score > holed_score);
+    }
+}
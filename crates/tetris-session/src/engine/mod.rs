@@ -1,4 +1,9 @@
+pub mod env;
 pub mod fixed_step;
+pub mod heuristic;
 pub mod place;
+pub mod planner;
 pub mod replay;
+pub mod replay_cursor;
 pub mod session;
+pub mod vec_env;
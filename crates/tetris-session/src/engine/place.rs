@@ -1,5 +1,6 @@
-use tetris_core::core::GameState;
-use tetris_core::types::{BOARD_WIDTH, GameAction, Rotation};
+use arrayvec::ArrayVec;
+use tetris_core::core::{ActiveSnapshot, GameState, get_shape};
+use tetris_core::types::{BOARD_WIDTH, GameAction, PieceKind, Rotation};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlaceError {
@@ -35,6 +36,66 @@ impl PlaceError {
     }
 }
 
+/// A complete placement target as accepted by `GameCommand::Place`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub x: i8,
+    pub rotation: Rotation,
+    pub use_hold: bool,
+}
+
+/// Upper bound of distinct `(x, rotation, use_hold)` targets for one piece.
+pub const MAX_PLACEMENTS: usize = (BOARD_WIDTH as usize) * 4 * 2;
+
+/// Enumerate every placement target whose piece footprint fits horizontally.
+///
+/// Targets are not validated against the board; apply them to a cloned state
+/// (or through [`apply_place`]) to learn whether they are reachable.
+pub fn placement_targets(
+    state: &GameState,
+    include_hold: bool,
+) -> ArrayVec<Placement, MAX_PLACEMENTS> {
+    let mut targets = ArrayVec::new();
+    let Some(active) = state.active() else {
+        return targets;
+    };
+    let hold_options: &[bool] = if include_hold && state.can_hold() {
+        &[false, true]
+    } else {
+        &[false]
+    };
+    for &use_hold in hold_options {
+        let kind = if use_hold {
+            state.hold_piece().unwrap_or(state.next_queue()[0])
+        } else {
+            active.kind
+        };
+        let rotations: &[Rotation] = if kind == PieceKind::O {
+            &[Rotation::North]
+        } else {
+            &[
+                Rotation::North,
+                Rotation::East,
+                Rotation::South,
+                Rotation::West,
+            ]
+        };
+        for &rotation in rotations {
+            let shape = get_shape(kind, rotation);
+            let min_dx = shape.iter().map(|(dx, _)| *dx).min().unwrap_or(0);
+            let max_dx = shape.iter().map(|(dx, _)| *dx).max().unwrap_or(0);
+            for x in -min_dx..(BOARD_WIDTH as i8 - max_dx) {
+                targets.push(Placement {
+                    x,
+                    rotation,
+                    use_hold,
+                });
+            }
+        }
+    }
+    targets
+}
+
 pub fn apply_place(
     state: &mut GameState,
     target_x: i8,
@@ -47,11 +108,50 @@ pub fn apply_place(
     Ok(())
 }
 
+/// Where `placement` would lock if it were applied to `state` now.
+///
+/// The piece is steered on a clone exactly as [`apply_place`] would, then
+/// reported at its ghost row instead of being hard-dropped. Returns `None`
+/// when the placement is not reachable.
+pub fn placement_landing(state: &GameState, placement: Placement) -> Option<ActiveSnapshot> {
+    let mut candidate = state.clone();
+    steer(
+        &mut candidate,
+        placement.x,
+        placement.rotation,
+        placement.use_hold,
+    )
+    .ok()?;
+    let mut active = ActiveSnapshot::from(candidate.active()?);
+    active.y = candidate.ghost_y()?;
+    Some(active)
+}
+
 fn apply_place_in_place(
     state: &mut GameState,
     target_x: i8,
     target_rot: Rotation,
     use_hold: bool,
+) -> Result<(), PlaceError> {
+    steer(state, target_x, target_rot, use_hold)?;
+
+    if !state.apply_action(GameAction::HardDrop) {
+        return Err(if state.active().is_none() {
+            PlaceError::NoActive
+        } else {
+            PlaceError::NotPlayable
+        });
+    }
+
+    Ok(())
+}
+
+/// Hold, rotate, and shift the active piece onto the target without dropping it.
+fn steer(
+    state: &mut GameState,
+    target_x: i8,
+    target_rot: Rotation,
+    use_hold: bool,
 ) -> Result<(), PlaceError> {
     if state.paused() || state.game_over() {
         return Err(PlaceError::NotPlayable);
@@ -145,21 +245,49 @@ fn apply_place_in_place(
         }
     }
 
-    if !state.apply_action(GameAction::HardDrop) {
-        return Err(if state.active().is_none() {
-            PlaceError::NoActive
-        } else {
-            PlaceError::NotPlayable
-        });
-    }
-
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tetris_core::types::{BOARD_HEIGHT, PieceKind};
+    use tetris_core::types::BOARD_HEIGHT;
+
+    #[test]
+    fn placement_targets_cover_every_in_bounds_column_and_rotation() {
+        let mut gs = GameState::new(1);
+        gs.start();
+
+        let targets = placement_targets(&gs, false);
+        assert!(!targets.is_empty());
+        assert!(targets.iter().all(|target| !target.use_hold));
+        for target in &targets {
+            let mut candidate = gs.clone();
+            let result = apply_place(&mut candidate, target.x, target.rotation, false);
+            assert_ne!(result, Err(PlaceError::XOutOfBounds), "{target:?}");
+        }
+
+        let with_hold = placement_targets(&gs, true);
+        assert!(with_hold.iter().any(|target| target.use_hold));
+        assert!(with_hold.len() > targets.len());
+    }
+
+    #[test]
+    fn placement_landing_matches_the_locked_cells_of_apply_place() {
+        let mut gs = GameState::new(3);
+        gs.start();
+        let target = placement_targets(&gs, true)[5];
+
+        let landing = placement_landing(&gs, target).expect("reachable target");
+        let mut placed = gs.clone();
+        apply_place(&mut placed, target.x, target.rotation, target.use_hold).unwrap();
+        for (dx, dy) in get_shape(landing.kind, landing.rotation) {
+            let cell = placed.board().get(landing.x + dx, landing.y + dy);
+            assert_eq!(cell, Some(Some(landing.kind)), "{landing:?}");
+        }
+        // Previewing never mutates the source state.
+        assert!(gs.board().row_masks().iter().all(|&mask| mask == 0));
+    }
 
     #[test]
     fn place_rejected_when_paused() {
@@ -0,0 +1,261 @@
+//! Beam-search placement planner over cloned game states.
+//!
+//! The planner looks ahead through the active piece, the visible `next_queue`,
+//! and optional hold swaps. Every candidate is simulated on a cloned
+//! `GameState` through the same `apply_place` rules the session uses, so a
+//! returned placement is always accepted by `GameCommand::Place`.
+//!
+//! Search time is budgeted in logical steps rather than wall-clock time: a
+//! configured number of simulated placements fits in one step, so equal inputs
+//! produce equal plans, taking equal steps, on any machine. Node buffers are
+//! reserved once per [`Planner`], so repeated planning does not allocate.
+
+use std::cmp::Reverse;
+
+use arrayvec::ArrayVec;
+use tetris_core::core::GameState;
+
+use crate::engine::heuristic::{BoardFeatures, HeuristicWeights};
+use crate::engine::place::{MAX_PLACEMENTS, Placement, apply_place, placement_targets};
+
+/// Deepest useful search: the active piece plus the five previewed pieces.
+pub const MAX_LOOKAHEAD_DEPTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannerConfig {
+    /// Nodes kept after each ply.
+    pub beam_width: usize,
+    /// Plies searched, clamped to `1..=MAX_LOOKAHEAD_DEPTH`.
+    pub depth: usize,
+    /// Logical steps one decision may take. The root ply is always expanded
+    /// in full so a legal placement is returned whenever one exists, even if
+    /// that overruns the budget.
+    pub step_budget: u64,
+    /// Simulated placements the planner gets through in one logical step.
+    pub placements_per_step: u64,
+    /// Consider hold swaps at every ply.
+    pub use_hold: bool,
+    pub weights: HeuristicWeights,
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self {
+            beam_width: 8,
+            depth: 3,
+            step_budget: 4,
+            placements_per_step: 2_048,
+            use_hold: true,
+            weights: HeuristicWeights::default(),
+        }
+    }
+}
+
+impl PlannerConfig {
+    /// Single-ply, single-node search without hold.
+    pub fn greedy() -> Self {
+        Self {
+            beam_width: 1,
+            depth: 1,
+            use_hold: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Planner decision for the current active piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plan {
+    pub placement: Placement,
+    /// Heuristic score of the best leaf reached through `placement`.
+    pub score: i32,
+    /// Plies fully or partially expanded before the budget ran out.
+    pub depth_reached: usize,
+    /// Placements simulated during the search.
+    pub placements: u64,
+    /// Logical steps the search took, at least one; a caller that plays in
+    /// game time commits `placement` this many steps after planning began.
+    pub steps_used: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    state: GameState,
+    first: Option<Placement>,
+    lines: u32,
+    score: i32,
+    order: u32,
+}
+
+/// Reusable beam-search planner.
+#[derive(Debug)]
+pub struct Planner {
+    config: PlannerConfig,
+    beam: Vec<Node>,
+    children: Vec<Node>,
+}
+
+impl Planner {
+    pub fn new(config: PlannerConfig) -> Self {
+        let config = PlannerConfig {
+            beam_width: config.beam_width.max(1),
+            depth: config.depth.clamp(1, MAX_LOOKAHEAD_DEPTH),
+            placements_per_step: config.placements_per_step.max(1),
+            ..config
+        };
+        Self {
+            config,
+            beam: Vec::with_capacity(config.beam_width),
+            children: Vec::with_capacity(config.beam_width * MAX_PLACEMENTS),
+        }
+    }
+
+    pub fn config(&self) -> &PlannerConfig {
+        &self.config
+    }
+
+    /// Choose a placement for the active piece of `game`.
+    ///
+    /// Returns `None` when the game is paused, over, or has no active piece.
+    pub fn plan(&mut self, game: &GameState) -> Option<Plan> {
+        if game.paused() || game.game_over() || game.active().is_none() {
+            return None;
+        }
+
+        self.beam.clear();
+        self.beam.push(Node {
+            state: game.clone(),
+            first: None,
+            lines: 0,
+            score: 0,
+            order: 0,
+        });
+
+        let placement_budget = self
+            .config
+            .step_budget
+            .saturating_mul(self.config.placements_per_step);
+        let mut placements = 0u64;
+        let mut depth_reached = 0;
+        for ply in 0..self.config.depth {
+            let mut exhausted = false;
+            self.children.clear();
+            'parents: for parent in &self.beam {
+                let mut seen = ArrayVec::<u64, MAX_PLACEMENTS>::new();
+                for target in placement_targets(&parent.state, self.config.use_hold) {
+                    if ply > 0 && placements >= placement_budget {
+                        exhausted = true;
+                        break 'parents;
+                    }
+                    let mut state = parent.state.clone();
+                    placements += 1;
+                    if apply_place(&mut state, target.x, target.rotation, target.use_hold).is_err()
+                    {
+                        continue;
+                    }
+                    let key = state_key(&state);
+                    if seen.contains(&key) {
+                        continue;
+                    }
+                    seen.push(key);
+
+                    let lines = parent
+                        .lines
+                        .saturating_add(state.lines().saturating_sub(parent.state.lines()));
+                    let score = if state.game_over() {
+                        self.config.weights.game_over
+                    } else {
+                        self.config
+                            .weights
+                            .score(&BoardFeatures::from_board(state.board()), lines)
+                    };
+                    let order = self.children.len() as u32;
+                    self.children.push(Node {
+                        state,
+                        first: parent.first.or(Some(target)),
+                        lines,
+                        score,
+                        order,
+                    });
+                }
+            }
+            // A partially expanded ply still ranks deeper leaves, but only when
+            // it produced any; otherwise the previous ply remains the answer.
+            if self.children.is_empty() {
+                break;
+            }
+            depth_reached = ply + 1;
+            self.keep_best_children();
+            if exhausted {
+                break;
+            }
+        }
+
+        let best = self.beam.first()?;
+        Some(Plan {
+            placement: best.first?,
+            score: best.score,
+            depth_reached,
+            placements,
+            steps_used: placements.div_ceil(self.config.placements_per_step).max(1),
+        })
+    }
+
+    fn keep_best_children(&mut self) {
+        self.children
+            .sort_unstable_by_key(|node| (Reverse(node.score), node.order));
+        self.children.truncate(self.config.beam_width);
+        // Move rather than swap so each buffer keeps its reserved capacity.
+        self.beam.clear();
+        self.beam.append(&mut self.children);
+    }
+}
+
+/// Identity of a search node for duplicate pruning within one parent.
+fn state_key(state: &GameState) -> u64 {
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = 0xcbf29ce484222325_u64;
+    let mut write = |value: u64| {
+        hash ^= value;
+        hash = hash.wrapping_mul(PRIME);
+    };
+    for mask in state.board().row_masks() {
+        write(u64::from(mask));
+    }
+    write(state.hold_piece().map_or(0, |kind| kind as u64 + 1));
+    write(state.active().map_or(0, |active| active.kind as u64 + 1));
+    write(u64::from(state.game_over()));
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_clamps_degenerate_configs() {
+        let planner = Planner::new(PlannerConfig {
+            beam_width: 0,
+            depth: 99,
+            ..PlannerConfig::default()
+        });
+        assert_eq!(planner.config().beam_width, 1);
+        assert_eq!(planner.config().depth, MAX_LOOKAHEAD_DEPTH);
+        let planner = Planner::new(PlannerConfig {
+            placements_per_step: 0,
+            ..PlannerConfig::default()
+        });
+        assert_eq!(planner.config().placements_per_step, 1);
+    }
+
+    #[test]
+    fn plan_is_none_without_a_playable_active_piece() {
+        let mut planner = Planner::new(PlannerConfig::greedy());
+        let mut game = GameState::new(1);
+        assert!(planner.plan(&game).is_none());
+        game.start();
+        assert!(planner.plan(&game).is_some());
+        game.apply_action(tetris_core::types::GameAction::Pause);
+        assert!(planner.plan(&game).is_none());
+    }
+}
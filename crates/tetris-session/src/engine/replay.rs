@@ -1,15 +1,55 @@
 //! Stable command recording and deterministic replay verification.
 
-use crate::engine::session::{CommandOutcome, GameCommand, SessionRuntime, StepInput};
+use std::ffi::CStr;
+use std::io::{self, Write};
+
+use crate::engine::session::{CommandOutcome, GameCommand, SessionRuntime, StepInput, Transition};
 use arrayvec::ArrayVec;
 use tetris_core::core::{GameSnapshot, stable_state_hash};
 use tetris_core::types::{GameAction, Rotation};
 
 pub const REPLAY_FORMAT_VERSION: u16 = 2;
 pub const RULESET_VERSION: &str = "tui-guideline-2026.1";
+/// [`RULESET_VERSION`] as a NUL-terminated string for C callers.
+pub const RULESET_VERSION_CSTR: &CStr = c"tui-guideline-2026.1";
+
+const _: () = assert!(
+    bytes_eq(RULESET_VERSION_CSTR.to_bytes(), RULESET_VERSION.as_bytes()),
+    "RULESET_VERSION_CSTR must spell RULESET_VERSION"
+);
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < a.len() {
+        if a[index] != b[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
 
 const HASH_PRIME: u64 = 0x100000001b3;
 
+mod binary;
+mod metadata;
+mod ruleset;
+
+pub use binary::{
+    BINARY_REPLAY_FORMAT_VERSION, DEFAULT_KEYFRAME_INTERVAL, binary_keyframe_interval, seek_binary,
+};
+pub use metadata::{
+    META_BRANCH_OF, META_BRANCH_STEP, META_DATE, META_FINAL_SCORE, META_MODE, META_PLAYER,
+    META_PLAYER_VERSION, META_TAGS, ReplayAnnotation, ReplayMetadata, format_utc,
+};
+pub use ruleset::{
+    CURRENT_RULESET, META_MIGRATED_FROM, OutcomeChange, RULESETS, ReplayMigration, Ruleset,
+    find_ruleset, migrate,
+};
+
 fn hash_bytes(hash: &mut u64, bytes: &[u8]) {
     for byte in bytes {
         *hash ^= u64::from(*byte);
@@ -58,9 +98,13 @@ pub struct StepRecord {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReplayTape {
+    ruleset: &'static Ruleset,
     seed: u32,
     records: Vec<StepRecord>,
     final_snapshot: GameSnapshot,
+    metadata: ReplayMetadata,
+    /// Sorted by step; notes on the same step keep their order.
+    annotations: Vec<ReplayAnnotation>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -72,7 +116,16 @@ pub struct ReplayMismatch {
 
 impl ReplayTape {
     pub fn record(seed: u32, inputs: impl IntoIterator<Item = StepInput>) -> Self {
-        let mut session = SessionRuntime::new(seed);
+        Self::record_under(&CURRENT_RULESET, seed, inputs)
+    }
+
+    /// [`ReplayTape::record`] under `ruleset` rather than the current rules.
+    pub fn record_under(
+        ruleset: &'static Ruleset,
+        seed: u32,
+        inputs: impl IntoIterator<Item = StepInput>,
+    ) -> Self {
+        let mut session = ruleset.session(seed);
         let mut records = Vec::new();
         for (step, input) in inputs.into_iter().enumerate() {
             let transition = session.transition(&input);
@@ -88,9 +141,12 @@ impl ReplayTape {
             });
         }
         Self {
+            ruleset,
             seed,
             records,
             final_snapshot: *session.snapshot(),
+            metadata: ReplayMetadata::default(),
+            annotations: Vec::new(),
         }
     }
 
@@ -102,67 +158,213 @@ impl ReplayTape {
         self.seed
     }
 
+    pub fn ruleset(&self) -> &'static Ruleset {
+        self.ruleset
+    }
+
     pub fn ruleset_version(&self) -> &'static str {
-        RULESET_VERSION
+        self.ruleset.version
     }
 
     pub fn final_snapshot(&self) -> &GameSnapshot {
         &self.final_snapshot
     }
 
+    pub fn metadata(&self) -> &ReplayMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut ReplayMetadata {
+        &mut self.metadata
+    }
+
+    pub fn annotations(&self) -> &[ReplayAnnotation] {
+        &self.annotations
+    }
+
+    /// Attach `text` to logical `step`, after any notes already on it.
+    pub fn annotate(&mut self, step: u64, text: impl Into<String>) {
+        let index = self
+            .annotations
+            .partition_point(|annotation| annotation.step <= step);
+        self.annotations.insert(
+            index,
+            ReplayAnnotation {
+                step,
+                text: text.into(),
+            },
+        );
+    }
+
+    /// The tape up to and including the mismatching step.
     pub fn minimal_failure_prefix(&self, mismatch: &ReplayMismatch) -> Self {
-        let records = self.records[..=mismatch.step].to_vec();
-        let mut prefix = Self::record(self.seed, records.iter().map(|record| record.input.clone()));
+        self.truncated(mismatch.step as u64 + 1)
+    }
+
+    /// The first `steps` steps, with recorded hashes kept as they are, the
+    /// metadata, and the annotations that still fall inside them.
+    pub fn truncated(&self, steps: u64) -> Self {
+        let records = self.records[..(steps as usize).min(self.records.len())].to_vec();
+        let mut prefix = Self::record_under(
+            self.ruleset,
+            self.seed,
+            records.iter().map(|record| record.input.clone()),
+        );
         prefix.records = records;
+        prefix.metadata = self.metadata.clone();
+        prefix.annotations = self
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.step <= prefix.records.len() as u64)
+            .cloned()
+            .collect();
         prefix
     }
 
+    /// This tape's first `at` steps followed by `other`'s steps from `at` on,
+    /// for trying one tape's later decisions after another's opening.
+    ///
+    /// Hashes are recomputed, since `other`'s inputs now apply to this tape's
+    /// state. Metadata comes from this tape, annotations from whichever tape
+    /// covers their step.
+    pub fn spliced(&self, at: u64, other: &ReplayTape) -> Result<Self, String> {
+        self.check_compatible(other)?;
+        let at = at.min(self.records.len() as u64);
+        let inputs = self.records[..at as usize]
+            .iter()
+            .chain(other.records.iter().skip(at as usize))
+            .map(|record| record.input.clone());
+        let mut tape = Self::record_under(self.ruleset, self.seed, inputs);
+        tape.metadata = self.metadata.clone();
+        let notes = self
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.step <= at)
+            .chain(
+                other
+                    .annotations
+                    .iter()
+                    .filter(|annotation| annotation.step > at),
+            );
+        for annotation in notes {
+            tape.annotate(annotation.step, annotation.text.clone());
+        }
+        Ok(tape)
+    }
+
+    /// This tape followed by the steps `other` played after this tape ends,
+    /// for joining a cut opening with a tape branched from it.
+    ///
+    /// `other` must continue this tape: its state after this tape's last step
+    /// has to match this tape's final state hash, so the joined steps replay
+    /// exactly as `other` recorded them. Metadata comes from this tape,
+    /// annotations from whichever tape covers their step.
+    pub fn concatenated(&self, other: &ReplayTape) -> Result<Self, String> {
+        self.check_compatible(other)?;
+        let at = self.records.len();
+        if let Some(last) = self.records.last() {
+            match other.records.get(at - 1) {
+                None => {
+                    return Err(format!(
+                        "the other tape ends after {} steps, before this one does at {at}",
+                        other.records.len()
+                    ));
+                }
+                Some(record) if record.state_hash != last.state_hash => {
+                    return Err(format!(
+                        "the other tape does not continue this one: its state after step {} differs",
+                        last.step
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        let inputs = self
+            .records
+            .iter()
+            .chain(&other.records[at..])
+            .map(|record| record.input.clone());
+        let mut tape = Self::record_under(self.ruleset, self.seed, inputs);
+        tape.metadata = self.metadata.clone();
+        tape.annotations = self.annotations.clone();
+        for annotation in other
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.step > at as u64)
+        {
+            tape.annotate(annotation.step, annotation.text.clone());
+        }
+        Ok(tape)
+    }
+
+    /// The session after the first `steps` steps, for resuming play there.
+    pub fn replay_to(&self, steps: u64) -> SessionRuntime {
+        let mut session = self.ruleset.session(self.seed);
+        for record in self.records.iter().take(steps as usize) {
+            session.transition(&record.input);
+        }
+        session
+    }
+
+    /// Whether `other` was recorded with the same seed and ruleset, so the
+    /// two tapes' steps can be combined or compared.
+    pub fn check_compatible(&self, other: &ReplayTape) -> Result<(), String> {
+        if self.seed != other.seed {
+            return Err(format!(
+                "tapes use different seeds: {} and {}",
+                self.seed, other.seed
+            ));
+        }
+        if self.ruleset != other.ruleset {
+            return Err(format!(
+                "tapes use different rulesets: {} and {}",
+                self.ruleset.version, other.ruleset.version
+            ));
+        }
+        Ok(())
+    }
+
     #[doc(hidden)]
     pub fn replace_record_for_test(&mut self, index: usize, record: StepRecord) {
         self.records[index] = record;
     }
 
     pub fn encode(&self) -> Vec<u8> {
-        let mut output = format!(
-            "TTR{REPLAY_FORMAT_VERSION}\t{RULESET_VERSION}\t{}\n",
-            self.seed
-        );
+        let mut output = encode_header(self.ruleset.version, self.seed);
+        for (key, value) in self.metadata.iter() {
+            encode_metadata(&mut output, key, value);
+        }
+        let mut annotations = self.annotations.iter().peekable();
         for record in &self.records {
-            output.push_str(&format!("S\t{}\t{}\n", record.step, record.state_hash));
-            for command in &record.input.remote {
-                match command {
-                    GameCommand::Actions {
-                        actions,
-                        restart_seed,
-                    } => {
-                        let seed = restart_seed.map_or_else(|| "-".into(), |v| v.to_string());
-                        let actions = actions
-                            .iter()
-                            .map(GameAction::as_str)
-                            .collect::<Vec<_>>()
-                            .join(",");
-                        output.push_str(&format!("R\tA\t{seed}\t{actions}\n"));
-                    }
-                    GameCommand::Place {
-                        x,
-                        rotation,
-                        use_hold,
-                    } => output.push_str(&format!(
-                        "R\tP\t{x}\t{}\t{}\n",
-                        rotation.as_str(),
-                        use_hold
-                    )),
-                }
-            }
-            for action in &record.input.local {
-                output.push_str(&format!("L\t{}\n", action.as_str()));
+            while let Some(annotation) =
+                annotations.next_if(|annotation| annotation.step <= record.step)
+            {
+                encode_annotation(&mut output, annotation);
             }
-            output.push_str("E\n");
+            encode_step(&mut output, record.step, record.state_hash, &record.input);
+        }
+        for annotation in annotations {
+            encode_annotation(&mut output, annotation);
         }
         output.into_bytes()
     }
 
+    /// Encode as TTR3 with a state keyframe every `keyframe_interval` steps;
+    /// `0` writes no keyframes.
+    pub fn encode_binary(&self, keyframe_interval: u64) -> Vec<u8> {
+        binary::encode(self, keyframe_interval)
+    }
+
+    /// Whether `bytes` is a TTR3 tape rather than TTR2 text.
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        binary::is_binary(bytes)
+    }
+
+    /// Decode a TTR2 or TTR3 tape.
     pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if binary::is_binary(bytes) {
+            return binary::decode(bytes);
+        }
         let text = std::str::from_utf8(bytes).map_err(|error| error.to_string())?;
         let mut lines = text.lines();
         let header = lines.next().ok_or("missing replay header")?;
@@ -172,9 +374,7 @@ impl ReplayTape {
             return Err(format!("unsupported replay format: {format}"));
         }
         let ruleset = header.next().ok_or("missing replay ruleset")?;
-        if ruleset != RULESET_VERSION {
-            return Err(format!("unsupported ruleset: {ruleset}"));
-        }
+        let ruleset = find_ruleset(ruleset).ok_or_else(|| unsupported_ruleset(ruleset))?;
         let seed = header
             .next()
             .ok_or("missing replay seed")?
@@ -184,10 +384,24 @@ impl ReplayTape {
             return Err("invalid replay header".into());
         }
         let mut records = Vec::new();
+        let mut metadata = ReplayMetadata::default();
+        let mut annotations = Vec::new();
         while let Some(line) = lines.next() {
             let fields = line.split('\t').collect::<Vec<_>>();
-            if fields.len() != 3 || fields[0] != "S" {
-                return Err("invalid step record".into());
+            match fields.as_slice() {
+                ["M", key, value] => {
+                    metadata.set(metadata::unescape(key)?, metadata::unescape(value)?);
+                    continue;
+                }
+                ["N", step, text] => {
+                    annotations.push((
+                        step.parse::<u64>().map_err(|error| error.to_string())?,
+                        metadata::unescape(text)?,
+                    ));
+                    continue;
+                }
+                ["S", _, _] => {}
+                _ => return Err("invalid step record".into()),
             }
             let step = fields[1]
                 .parse::<u64>()
@@ -250,14 +464,162 @@ impl ReplayTape {
                 state_hash,
             });
         }
-        let mut tape = Self::record(seed, records.iter().map(|record| record.input.clone()));
+        let mut tape = Self::record_under(
+            ruleset,
+            seed,
+            records.iter().map(|record| record.input.clone()),
+        );
         tape.records = records;
+        tape.metadata = metadata;
+        for (step, text) in annotations {
+            tape.annotate(step, text);
+        }
         Ok(tape)
     }
 }
 
+fn encode_header(ruleset: &str, seed: u32) -> String {
+    format!("TTR{REPLAY_FORMAT_VERSION}\t{ruleset}\t{seed}\n")
+}
+
+pub(super) fn unsupported_ruleset(ruleset: &str) -> String {
+    let known: Vec<_> = RULESETS.iter().map(|ruleset| ruleset.version).collect();
+    format!(
+        "unsupported ruleset: {ruleset} (known: {})",
+        known.join(", ")
+    )
+}
+
+fn encode_metadata(output: &mut String, key: &str, value: &str) {
+    output.push_str(&format!(
+        "M\t{}\t{}\n",
+        metadata::escape(key),
+        metadata::escape(value)
+    ));
+}
+
+fn encode_annotation(output: &mut String, annotation: &ReplayAnnotation) {
+    output.push_str(&format!(
+        "N\t{}\t{}\n",
+        annotation.step,
+        metadata::escape(&annotation.text)
+    ));
+}
+
+fn encode_step(output: &mut String, step: u64, state_hash: u64, input: &StepInput) {
+    output.push_str(&format!("S\t{step}\t{state_hash}\n"));
+    for command in &input.remote {
+        match command {
+            GameCommand::Actions {
+                actions,
+                restart_seed,
+            } => {
+                let seed = restart_seed.map_or_else(|| "-".into(), |v| v.to_string());
+                let actions = actions
+                    .iter()
+                    .map(GameAction::as_str)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                output.push_str(&format!("R\tA\t{seed}\t{actions}\n"));
+            }
+            GameCommand::Place {
+                x,
+                rotation,
+                use_hold,
+            } => output.push_str(&format!("R\tP\t{x}\t{}\t{}\n", rotation.as_str(), use_hold)),
+        }
+    }
+    for action in &input.local {
+        output.push_str(&format!("L\t{}\n", action.as_str()));
+    }
+    output.push_str("E\n");
+}
+
+/// Streams a tape while a session runs, for games whose inputs are not known
+/// up front.
+///
+/// Each step record is written with one `write_all` and flushed, so a
+/// recording cut short by a crash still decodes and verifies up to its last
+/// complete step.
+#[derive(Debug)]
+pub struct ReplayRecorder<W: Write> {
+    writer: W,
+    steps: u64,
+    buffer: String,
+}
+
+impl<W: Write> ReplayRecorder<W> {
+    /// Start a tape for a session created with `seed` that has not stepped.
+    pub fn new(writer: W, seed: u32) -> io::Result<Self> {
+        Self::with_metadata(writer, seed, &ReplayMetadata::default())
+    }
+
+    /// Continue `prefix`: its steps are written first, and recorded steps
+    /// follow them, for a session resumed with [`ReplayTape::replay_to`] at
+    /// the prefix's end.
+    pub fn continuing(mut writer: W, prefix: &ReplayTape) -> io::Result<Self> {
+        writer.write_all(&prefix.encode())?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            steps: prefix.records.len() as u64,
+            buffer: String::new(),
+        })
+    }
+
+    /// [`ReplayRecorder::new`] with `metadata` in the tape's header.
+    pub fn with_metadata(mut writer: W, seed: u32, metadata: &ReplayMetadata) -> io::Result<Self> {
+        let mut header = encode_header(RULESET_VERSION, seed);
+        for (key, value) in metadata.iter() {
+            encode_metadata(&mut header, key, value);
+        }
+        writer.write_all(header.as_bytes())?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            steps: 0,
+            buffer: String::new(),
+        })
+    }
+
+    /// Append the step `session` just applied from `input`.
+    pub fn record(
+        &mut self,
+        session: &SessionRuntime,
+        input: &StepInput,
+        transition: &Transition,
+    ) -> io::Result<()> {
+        let state_hash = transition_hash(
+            session.snapshot(),
+            session.logical_step(),
+            &transition.events,
+            &transition.command_outcomes,
+        );
+        self.buffer.clear();
+        encode_step(&mut self.buffer, self.steps, state_hash, input);
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.writer.flush()?;
+        self.steps += 1;
+        Ok(())
+    }
+
+    /// Steps recorded so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Replay `tape` under the ruleset it was recorded with.
 pub fn replay_and_verify(tape: &ReplayTape) -> Result<SessionRuntime, ReplayMismatch> {
-    let mut session = SessionRuntime::new(tape.seed);
+    let mut session = tape.ruleset.session(tape.seed);
     for (index, record) in tape.records.iter().enumerate() {
         let transition = session.transition(&record.input);
         let actual = transition_hash(
@@ -276,3 +638,20 @@ pub fn replay_and_verify(tape: &ReplayTape) -> Result<SessionRuntime, ReplayMism
     }
     Ok(session)
 }
+
+/// First step where two tapes of the same seed disagree on input or state
+/// hash, with `expected` from `left` and `actual` from `right`.
+///
+/// Only the steps both tapes have are compared, so `None` means one tape is a
+/// prefix of the other.
+pub fn first_divergence(left: &ReplayTape, right: &ReplayTape) -> Option<ReplayMismatch> {
+    left.records
+        .iter()
+        .zip(&right.records)
+        .position(|(left, right)| left.input != right.input || left.state_hash != right.state_hash)
+        .map(|step| ReplayMismatch {
+            step,
+            expected: left.records[step].state_hash,
+            actual: right.records[step].state_hash,
+        })
+}
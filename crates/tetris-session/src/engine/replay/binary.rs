@@ -0,0 +1,480 @@
+//! TTR3: compact binary replay tapes with keyframes and an index footer.
+//!
+//! ```text
+//! "TTR3" | varint ruleset length | ruleset | varint seed | varint keyframe interval
+//! chunk* | END
+//! index: varint keyframe count, then per keyframe varint step, varint offset
+//! trailer: u64 LE index offset | "T3IX"
+//! ```
+//!
+//! Chunks start with a tag byte:
+//!
+//! - `IDLE`: varint run length, u64 LE hash of the run's last step. Runs of
+//!   steps without input collapse into one chunk; decoding re-simulates the
+//!   steps inside a run and only the last one keeps its recorded hash.
+//! - `STEP`: varint remote command count, commands, varint local action count,
+//!   action codes, u64 LE state hash.
+//! - `KEYFRAME`: varint logical step, varint length, and the session's game
+//!   encoded by [`GameState::encode_state`], written before the step it
+//!   starts at. The index footer lists every keyframe's byte offset, so
+//!   [`seek_binary`] restores the nearest one instead of replaying from 0.
+//! - `META`: varint key length, key, varint value length, value; written
+//!   right after the header.
+//! - `NOTE`: varint logical step, varint length, text; written before the
+//!   step it is attached to.
+
+use tetris_core::core::GameState;
+use tetris_core::types::{GameAction, Rotation};
+
+use super::{ReplayMetadata, ReplayTape, Ruleset, find_ruleset, unsupported_ruleset};
+use crate::engine::session::{GameCommand, SessionRuntime, StepInput};
+
+pub const BINARY_REPLAY_FORMAT_VERSION: u16 = 3;
+/// Default steps between keyframes, a little over one minute of play.
+pub const DEFAULT_KEYFRAME_INTERVAL: u64 = 4096;
+
+const MAGIC: &[u8; 4] = b"TTR3";
+const INDEX_MAGIC: &[u8; 4] = b"T3IX";
+const TRAILER_LEN: usize = 12;
+
+const TAG_END: u8 = 0;
+const TAG_IDLE: u8 = 1;
+const TAG_STEP: u8 = 2;
+const TAG_KEYFRAME: u8 = 3;
+const TAG_META: u8 = 4;
+const TAG_NOTE: u8 = 5;
+
+const COMMAND_ACTIONS: u8 = 0;
+const COMMAND_PLACE: u8 = 1;
+
+pub(super) fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Encode `tape` as TTR3 with a keyframe every `keyframe_interval` steps, or
+/// none when it is 0.
+pub(super) fn encode(tape: &ReplayTape, keyframe_interval: u64) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    put_bytes(&mut out, tape.ruleset.version.as_bytes());
+    put_varint(&mut out, u64::from(tape.seed));
+    put_varint(&mut out, keyframe_interval);
+    for (key, value) in tape.metadata.iter() {
+        out.push(TAG_META);
+        put_bytes(&mut out, key.as_bytes());
+        put_bytes(&mut out, value.as_bytes());
+    }
+
+    let mut annotations = tape.annotations.iter().peekable();
+    let mut session = tape.ruleset.session(tape.seed);
+    let mut index = Vec::new();
+    let mut idle = IdleRun::default();
+    let mut state = Vec::new();
+    for (step, record) in tape.records.iter().enumerate() {
+        let step = step as u64;
+        if keyframe_interval > 0 && step > 0 && step.is_multiple_of(keyframe_interval) {
+            idle.flush(&mut out);
+            index.push((step, out.len() as u64));
+            state.clear();
+            session.game().encode_state(&mut state);
+            out.push(TAG_KEYFRAME);
+            put_varint(&mut out, step);
+            put_varint(&mut out, state.len() as u64);
+            out.extend_from_slice(&state);
+        }
+        if annotations
+            .peek()
+            .is_some_and(|annotation| annotation.step <= step)
+        {
+            idle.flush(&mut out);
+        }
+        while let Some(annotation) = annotations.next_if(|annotation| annotation.step <= step) {
+            out.push(TAG_NOTE);
+            put_varint(&mut out, annotation.step);
+            put_bytes(&mut out, annotation.text.as_bytes());
+        }
+        session.transition(&record.input);
+
+        if record.input.remote.is_empty() && record.input.local.is_empty() {
+            idle.steps += 1;
+            idle.last_hash = record.state_hash;
+            continue;
+        }
+        idle.flush(&mut out);
+        out.push(TAG_STEP);
+        encode_input(&mut out, &record.input);
+        out.extend_from_slice(&record.state_hash.to_le_bytes());
+    }
+    idle.flush(&mut out);
+    for annotation in annotations {
+        out.push(TAG_NOTE);
+        put_varint(&mut out, annotation.step);
+        put_bytes(&mut out, annotation.text.as_bytes());
+    }
+    out.push(TAG_END);
+
+    let index_offset = out.len() as u64;
+    put_varint(&mut out, index.len() as u64);
+    for (step, offset) in index {
+        put_varint(&mut out, step);
+        put_varint(&mut out, offset);
+    }
+    out.extend_from_slice(&index_offset.to_le_bytes());
+    out.extend_from_slice(INDEX_MAGIC);
+    out
+}
+
+pub(super) fn decode(bytes: &[u8]) -> Result<ReplayTape, String> {
+    let mut reader = Reader::new(bytes);
+    let (ruleset, seed, _) = reader.header()?;
+    read_index(bytes)?;
+    let mut inputs = Vec::new();
+    // Recorded hashes by step; idle steps inside a run have none.
+    let mut hashes = Vec::new();
+    let mut metadata = ReplayMetadata::default();
+    let mut annotations = Vec::new();
+    loop {
+        match reader.u8()? {
+            TAG_END => break,
+            TAG_META => metadata.set(reader.text()?, reader.text()?),
+            TAG_NOTE => annotations.push((reader.varint()?, reader.text()?)),
+            TAG_IDLE => {
+                let steps = reader.varint()?;
+                if steps == 0 {
+                    return Err("empty idle run".into());
+                }
+                let hash = reader.u64()?;
+                for _ in 1..steps {
+                    inputs.push(StepInput::default());
+                    hashes.push(None);
+                }
+                inputs.push(StepInput::default());
+                hashes.push(Some(hash));
+            }
+            TAG_STEP => {
+                inputs.push(reader.input()?);
+                hashes.push(Some(reader.u64()?));
+            }
+            TAG_KEYFRAME => {
+                let step = reader.varint()?;
+                if step != inputs.len() as u64 {
+                    return Err(format!("keyframe for step {step} out of place"));
+                }
+                let len = reader.varint()? as usize;
+                GameState::decode_state(reader.take(len)?).map_err(|error| error.to_string())?;
+            }
+            tag => return Err(format!("unknown replay chunk tag {tag}")),
+        }
+    }
+
+    let mut tape = ReplayTape::record_under(ruleset, seed, inputs);
+    for (record, hash) in tape.records.iter_mut().zip(hashes) {
+        if let Some(hash) = hash {
+            record.state_hash = hash;
+        }
+    }
+    tape.metadata = metadata;
+    for (step, text) in annotations {
+        tape.annotate(step, text);
+    }
+    Ok(tape)
+}
+
+/// Restore the session after `step` steps of a TTR3 tape, starting from the
+/// nearest keyframe at or before it. `step` is clamped to the tape's length.
+pub fn seek_binary(bytes: &[u8], step: u64) -> Result<SessionRuntime, String> {
+    let mut reader = Reader::new(bytes);
+    let (ruleset, seed, _) = reader.header()?;
+    let keyframe = read_index(bytes)?
+        .into_iter()
+        .take_while(|&(keyframe_step, _)| keyframe_step <= step)
+        .last();
+    let mut session = ruleset.session(seed);
+    if let Some((_, offset)) = keyframe {
+        reader.seek(offset)?;
+    }
+    while session.logical_step() < step {
+        match reader.u8()? {
+            TAG_END => break,
+            TAG_IDLE => {
+                let steps = reader.varint()?;
+                reader.u64()?;
+                let idle = StepInput::default();
+                for _ in 0..steps.min(step - session.logical_step()) {
+                    session.transition(&idle);
+                }
+            }
+            TAG_STEP => {
+                let input = reader.input()?;
+                reader.u64()?;
+                session.transition(&input);
+            }
+            TAG_KEYFRAME => {
+                let keyframe_step = reader.varint()?;
+                let len = reader.varint()? as usize;
+                let game = GameState::decode_state_under(reader.take(len)?, ruleset.rules)
+                    .map_err(|error| error.to_string())?;
+                session = SessionRuntime::resume(game, keyframe_step);
+            }
+            TAG_META => {
+                reader.text()?;
+                reader.text()?;
+            }
+            TAG_NOTE => {
+                reader.varint()?;
+                reader.text()?;
+            }
+            tag => return Err(format!("unknown replay chunk tag {tag}")),
+        }
+    }
+    Ok(session)
+}
+
+/// The keyframe interval a TTR3 tape was written with, so it can be
+/// re-encoded the same way.
+pub fn binary_keyframe_interval(bytes: &[u8]) -> Result<u64, String> {
+    Ok(Reader::new(bytes).header()?.2)
+}
+
+/// `(step, byte offset)` of every keyframe, in step order.
+fn read_index(bytes: &[u8]) -> Result<Vec<(u64, u64)>, String> {
+    let trailer = bytes
+        .len()
+        .checked_sub(TRAILER_LEN)
+        .map(|start| &bytes[start..])
+        .filter(|trailer| trailer.ends_with(INDEX_MAGIC))
+        .ok_or("missing replay index")?;
+    let offset = u64::from_le_bytes(trailer[..8].try_into().expect("8-byte offset"));
+    let mut reader = Reader::new(bytes);
+    reader.seek(offset)?;
+    let count = reader.varint()?;
+    let mut index = Vec::new();
+    for _ in 0..count {
+        index.push((reader.varint()?, reader.varint()?));
+    }
+    Ok(index)
+}
+
+#[derive(Default)]
+struct IdleRun {
+    steps: u64,
+    last_hash: u64,
+}
+
+impl IdleRun {
+    fn flush(&mut self, out: &mut Vec<u8>) {
+        if self.steps == 0 {
+            return;
+        }
+        out.push(TAG_IDLE);
+        put_varint(out, self.steps);
+        out.extend_from_slice(&self.last_hash.to_le_bytes());
+        self.steps = 0;
+    }
+}
+
+fn encode_input(out: &mut Vec<u8>, input: &StepInput) {
+    put_varint(out, input.remote.len() as u64);
+    for command in &input.remote {
+        match command {
+            GameCommand::Actions {
+                actions,
+                restart_seed,
+            } => {
+                out.push(COMMAND_ACTIONS);
+                put_varint(out, restart_seed.map_or(0, |seed| u64::from(seed) + 1));
+                put_varint(out, actions.len() as u64);
+                out.extend(actions.iter().map(|&action| action_code(action)));
+            }
+            GameCommand::Place {
+                x,
+                rotation,
+                use_hold,
+            } => {
+                out.push(COMMAND_PLACE);
+                out.push(*x as u8);
+                out.push(rotation_code(*rotation));
+                out.push(*use_hold as u8);
+            }
+        }
+    }
+    put_varint(out, input.local.len() as u64);
+    out.extend(input.local.iter().map(|&action| action_code(action)));
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Unsigned LEB128.
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Check the magic and return the ruleset, seed, and keyframe interval.
+    fn header(&mut self) -> Result<(&'static Ruleset, u32, u64), String> {
+        if self.take(MAGIC.len())? != MAGIC {
+            return Err("unsupported replay format".into());
+        }
+        let ruleset = self.text()?;
+        let ruleset = find_ruleset(&ruleset).ok_or_else(|| unsupported_ruleset(&ruleset))?;
+        let seed = u32::try_from(self.varint()?).map_err(|_| "invalid replay seed")?;
+        Ok((ruleset, seed, self.varint()?))
+    }
+
+    fn seek(&mut self, offset: u64) -> Result<(), String> {
+        if offset > self.bytes.len() as u64 {
+            return Err("replay offset out of range".into());
+        }
+        self.pos = offset as usize;
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("truncated replay")?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid varint".into())
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        let len = self.varint()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|error| error.to_string())
+    }
+
+    fn action(&mut self) -> Result<GameAction, String> {
+        action_from_code(self.u8()?).ok_or_else(|| "invalid action".into())
+    }
+
+    fn input(&mut self) -> Result<StepInput, String> {
+        let mut input = StepInput::default();
+        for _ in 0..self.varint()? {
+            let command = match self.u8()? {
+                COMMAND_ACTIONS => {
+                    let restart_seed = match self.varint()? {
+                        0 => None,
+                        seed => Some(u32::try_from(seed - 1).map_err(|_| "invalid restart seed")?),
+                    };
+                    let mut actions = arrayvec::ArrayVec::new();
+                    for _ in 0..self.varint()? {
+                        actions
+                            .try_push(self.action()?)
+                            .map_err(|_| "too many remote actions")?;
+                    }
+                    GameCommand::Actions {
+                        actions,
+                        restart_seed,
+                    }
+                }
+                COMMAND_PLACE => GameCommand::Place {
+                    x: self.u8()? as i8,
+                    rotation: rotation_from_code(self.u8()?).ok_or("invalid rotation")?,
+                    use_hold: match self.u8()? {
+                        0 => false,
+                        1 => true,
+                        _ => return Err("invalid use_hold".into()),
+                    },
+                },
+                kind => return Err(format!("unknown command kind {kind}")),
+            };
+            input
+                .remote
+                .try_push(command)
+                .map_err(|_| "too many commands")?;
+        }
+        for _ in 0..self.varint()? {
+            input
+                .local
+                .try_push(self.action()?)
+                .map_err(|_| "too many local actions")?;
+        }
+        Ok(input)
+    }
+}
+
+fn action_code(action: GameAction) -> u8 {
+    match action {
+        GameAction::MoveLeft => 0,
+        GameAction::MoveRight => 1,
+        GameAction::SoftDrop => 2,
+        GameAction::HardDrop => 3,
+        GameAction::RotateCw => 4,
+        GameAction::RotateCcw => 5,
+        GameAction::Hold => 6,
+        GameAction::Pause => 7,
+        GameAction::Restart => 8,
+    }
+}
+
+fn action_from_code(code: u8) -> Option<GameAction> {
+    Some(match code {
+        0 => GameAction::MoveLeft,
+        1 => GameAction::MoveRight,
+        2 => GameAction::SoftDrop,
+        3 => GameAction::HardDrop,
+        4 => GameAction::RotateCw,
+        5 => GameAction::RotateCcw,
+        6 => GameAction::Hold,
+        7 => GameAction::Pause,
+        8 => GameAction::Restart,
+        _ => return None,
+    })
+}
+
+fn rotation_code(rotation: Rotation) -> u8 {
+    match rotation {
+        Rotation::North => 0,
+        Rotation::East => 1,
+        Rotation::South => 2,
+        Rotation::West => 3,
+    }
+}
+
+fn rotation_from_code(code: u8) -> Option<Rotation> {
+    Some(match code {
+        0 => Rotation::North,
+        1 => Rotation::East,
+        2 => Rotation::South,
+        3 => Rotation::West,
+        _ => return None,
+    })
+}
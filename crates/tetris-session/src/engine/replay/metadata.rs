@@ -0,0 +1,150 @@
+//! Tape metadata and step annotations.
+//!
+//! Metadata is an ordered list of free-form `key = value` pairs; the well-known
+//! keys below are what the recorders fill in and what `replay inspect` and the
+//! viewer show first. Annotations attach a note to a logical step: the number
+//! of steps applied when it applies, so step 0 is the initial state.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Who played: a bot name or a human player's name.
+pub const META_PLAYER: &str = "player";
+pub const META_PLAYER_VERSION: &str = "player_version";
+/// When recording started, as `YYYY-MM-DDTHH:MM:SSZ`.
+pub const META_DATE: &str = "date";
+/// How the tape was produced: `interactive`, `adapter`, `headless`, or `bot`.
+pub const META_MODE: &str = "mode";
+pub const META_FINAL_SCORE: &str = "final_score";
+/// Comma-separated tags.
+pub const META_TAGS: &str = "tags";
+/// The tape a branch was cut from, and the step its own play starts at.
+pub const META_BRANCH_OF: &str = "branch_of";
+pub const META_BRANCH_STEP: &str = "branch_step";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayMetadata {
+    entries: Vec<(String, String)>,
+}
+
+impl ReplayMetadata {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set `key`, replacing its value in place if it is already present.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let (key, value) = (key.into(), value.into());
+        match self.entries.iter_mut().find(|(entry, _)| *entry == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(entry, _)| entry == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.get(META_TAGS)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+
+    /// Append `tag` to [`META_TAGS`] unless it is already there.
+    pub fn add_tag(&mut self, tag: &str) {
+        if self.tags().any(|existing| existing == tag) {
+            return;
+        }
+        let tags = match self.get(META_TAGS).filter(|tags| !tags.is_empty()) {
+            Some(tags) => format!("{tags},{tag}"),
+            None => tag.to_string(),
+        };
+        self.set(META_TAGS, tags);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayAnnotation {
+    pub step: u64,
+    pub text: String,
+}
+
+/// `time` as `YYYY-MM-DDTHH:MM:SSZ`, the [`META_DATE`] format.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Escape tabs, newlines, and backslashes for one TTR2 field.
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(super) fn unescape(text: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next() {
+            Some('\\') => '\\',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            _ => return Err("invalid escape in replay metadata".into()),
+        });
+    }
+    Ok(unescaped)
+}
@@ -0,0 +1,129 @@
+//! Rulesets tapes can be verified under, and migration between them.
+//!
+//! A tape names the ruleset it was recorded under and only ever verifies
+//! against that one. [`RULESETS`] keeps every ruleset whose tapes we still
+//! accept: when a change to the core's [`Rules`] bumps [`RULESET_VERSION`],
+//! the outgoing entry stays with the values it had, so its tapes keep
+//! replaying exactly, and [`migrate`] re-records old tapes under the current
+//! rules. A rules change the [`Rules`] values cannot express needs a new
+//! field there first.
+
+use tetris_core::types::{CoreLastEvent, Rules};
+
+use super::{META_FINAL_SCORE, RULESET_VERSION, ReplayTape, transition_hash};
+use crate::engine::session::SessionRuntime;
+
+/// Metadata key naming the ruleset a migrated tape was recorded under.
+pub const META_MIGRATED_FROM: &str = "migrated_from";
+
+#[derive(Debug)]
+pub struct Ruleset {
+    pub version: &'static str,
+    pub rules: Rules,
+}
+
+impl Ruleset {
+    /// A fresh session under these rules.
+    pub fn session(&self, seed: u32) -> SessionRuntime {
+        SessionRuntime::with_rules(seed, self.rules)
+    }
+}
+
+/// Rulesets are identified by their version alone.
+impl PartialEq for Ruleset {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+    }
+}
+
+impl Eq for Ruleset {}
+
+pub static CURRENT_RULESET: Ruleset = Ruleset {
+    version: RULESET_VERSION,
+    rules: Rules::GUIDELINE,
+};
+
+/// Every ruleset tapes may name, current first.
+pub static RULESETS: &[&Ruleset] = &[&CURRENT_RULESET];
+
+pub fn find_ruleset(version: &str) -> Option<&'static Ruleset> {
+    RULESETS
+        .iter()
+        .copied()
+        .find(|ruleset| ruleset.version == version)
+}
+
+/// A tape re-recorded under another ruleset and how its outcomes moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayMigration {
+    pub tape: ReplayTape,
+    /// First step whose state hash differs between the two rulesets.
+    pub first_divergence: Option<u64>,
+    /// Steps whose lock events differ, in step order.
+    pub changes: Vec<OutcomeChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutcomeChange {
+    pub step: u64,
+    pub before: Option<CoreLastEvent>,
+    pub after: Option<CoreLastEvent>,
+}
+
+/// Replay `tape`'s inputs under its own ruleset and under `target` side by
+/// side, recording the `target` run as a new tape.
+///
+/// The new tape keeps the metadata, with [`META_MIGRATED_FROM`] set and any
+/// final score updated, and the annotations.
+pub fn migrate(tape: &ReplayTape, target: &'static Ruleset) -> ReplayMigration {
+    let mut before = tape.ruleset().session(tape.seed());
+    let mut after = target.session(tape.seed());
+    let mut first_divergence = None;
+    let mut changes = Vec::new();
+    for record in tape.records() {
+        let old = before.transition(&record.input);
+        let new = after.transition(&record.input);
+        let hashes = [(&before, &old), (&after, &new)].map(|(session, transition)| {
+            transition_hash(
+                session.snapshot(),
+                session.logical_step(),
+                &transition.events,
+                &transition.command_outcomes,
+            )
+        });
+        if hashes[0] != hashes[1] {
+            first_divergence.get_or_insert(record.step);
+        }
+        if old.events != new.events {
+            changes.push(OutcomeChange {
+                step: record.step,
+                before: old.events.first().copied(),
+                after: new.events.first().copied(),
+            });
+        }
+    }
+
+    let mut migrated = ReplayTape::record_under(
+        target,
+        tape.seed(),
+        tape.records().iter().map(|record| record.input.clone()),
+    );
+    *migrated.metadata_mut() = tape.metadata().clone();
+    migrated
+        .metadata_mut()
+        .set(META_MIGRATED_FROM, tape.ruleset_version());
+    if migrated.metadata().get(META_FINAL_SCORE).is_some() {
+        let score = migrated.final_snapshot().score;
+        migrated
+            .metadata_mut()
+            .set(META_FINAL_SCORE, score.to_string());
+    }
+    for annotation in tape.annotations() {
+        migrated.annotate(annotation.step, annotation.text.clone());
+    }
+    ReplayMigration {
+        tape: migrated,
+        first_divergence,
+        changes,
+    }
+}
@@ -0,0 +1,158 @@
+//! Seekable playback position over a replay tape.
+//!
+//! Moving forward applies the next recorded input to a live session. Moving
+//! backward restores the nearest earlier keyframe, a session clone taken every
+//! [`KEYFRAME_INTERVAL`] steps the first time playback passes it, and replays
+//! forward from there, so no seek re-simulates more than one interval.
+
+use crate::engine::replay::ReplayTape;
+use crate::engine::session::SessionRuntime;
+
+/// Steps between keyframes.
+pub const KEYFRAME_INTERVAL: u64 = 256;
+
+#[derive(Debug, Clone)]
+pub struct ReplayCursor {
+    tape: ReplayTape,
+    session: SessionRuntime,
+    /// `keyframes[i]` is the session after `i * KEYFRAME_INTERVAL` steps.
+    keyframes: Vec<SessionRuntime>,
+}
+
+impl ReplayCursor {
+    /// Start at step 0 of `tape`.
+    pub fn new(tape: ReplayTape) -> Self {
+        let session = tape.ruleset().session(tape.seed());
+        Self {
+            keyframes: vec![session.clone()],
+            tape,
+            session,
+        }
+    }
+
+    pub fn tape(&self) -> &ReplayTape {
+        &self.tape
+    }
+
+    /// The session after [`ReplayCursor::position`] steps.
+    pub fn session(&self) -> &SessionRuntime {
+        &self.session
+    }
+
+    /// Steps applied so far.
+    pub fn position(&self) -> u64 {
+        self.session.logical_step()
+    }
+
+    /// Steps on the tape.
+    pub fn len(&self) -> u64 {
+        self.tape.records().len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tape.records().is_empty()
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.position() >= self.len()
+    }
+
+    /// Apply the next recorded step; `false` at the end of the tape.
+    pub fn step_forward(&mut self) -> bool {
+        let Some(record) = self.tape.records().get(self.position() as usize) else {
+            return false;
+        };
+        self.session.transition(&record.input);
+        let position = self.position();
+        if position.is_multiple_of(KEYFRAME_INTERVAL)
+            && position / KEYFRAME_INTERVAL == self.keyframes.len() as u64
+        {
+            self.keyframes.push(self.session.clone());
+        }
+        true
+    }
+
+    /// Return to the previous step; `false` at step 0.
+    pub fn step_back(&mut self) -> bool {
+        let Some(target) = self.position().checked_sub(1) else {
+            return false;
+        };
+        self.seek(target);
+        true
+    }
+
+    /// Move to `step`, clamped to the end of the tape.
+    pub fn seek(&mut self, step: u64) {
+        let target = step.min(self.len());
+        let keyframe = ((target / KEYFRAME_INTERVAL) as usize).min(self.keyframes.len() - 1);
+        let keyframe_step = keyframe as u64 * KEYFRAME_INTERVAL;
+        // Replaying from the current position is cheaper whenever it is not
+        // behind the target and not before the best keyframe.
+        if self.position() > target || self.position() < keyframe_step {
+            self.session = self.keyframes[keyframe].clone();
+        }
+        while self.position() < target {
+            self.step_forward();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::session::StepInput;
+    use tetris_core::types::GameAction;
+
+    fn tape(steps: u64) -> ReplayTape {
+        ReplayTape::record(
+            5,
+            (0..steps).map(|step| match step % 40 {
+                0 => StepInput::default().with_local(GameAction::HardDrop),
+                13 => StepInput::default().with_local(GameAction::MoveLeft),
+                _ => StepInput::default(),
+            }),
+        )
+    }
+
+    #[test]
+    fn seeking_matches_straight_playback_in_both_directions() {
+        let steps = KEYFRAME_INTERVAL * 2 + 37;
+        let tape = tape(steps);
+        let mut straight = Vec::new();
+        let mut cursor = ReplayCursor::new(tape.clone());
+        straight.push(*cursor.session().snapshot());
+        while cursor.step_forward() {
+            straight.push(*cursor.session().snapshot());
+        }
+        assert!(cursor.at_end());
+        assert_eq!(cursor.position(), steps);
+        assert_eq!(cursor.session().snapshot(), tape.final_snapshot());
+
+        for target in [
+            steps - 1,
+            3,
+            KEYFRAME_INTERVAL,
+            KEYFRAME_INTERVAL + 1,
+            0,
+            steps,
+        ] {
+            cursor.seek(target);
+            assert_eq!(cursor.position(), target);
+            assert_eq!(cursor.session().snapshot(), &straight[target as usize]);
+        }
+        assert!(!cursor.step_forward());
+        assert!(cursor.step_back());
+        assert_eq!(cursor.session().snapshot(), &straight[steps as usize - 1]);
+    }
+
+    #[test]
+    fn seeking_ahead_of_playback_builds_keyframes_on_the_way() {
+        let mut cursor = ReplayCursor::new(tape(KEYFRAME_INTERVAL * 3));
+        cursor.seek(u64::MAX);
+        assert_eq!(cursor.position(), KEYFRAME_INTERVAL * 3);
+        assert_eq!(cursor.keyframes.len(), 4);
+        cursor.seek(0);
+        assert!(!cursor.step_back());
+        assert_eq!(cursor.position(), 0);
+    }
+}
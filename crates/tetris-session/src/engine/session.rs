@@ -6,9 +6,9 @@
 
 use arrayvec::ArrayVec;
 
-use crate::engine::place::{PlaceError, apply_place};
-use tetris_core::core::{GameSnapshot, GameState};
-use tetris_core::types::{CoreLastEvent, GameAction, Rotation, TICK_MS};
+use crate::engine::place::{PlaceError, Placement, apply_place, placement_landing};
+use tetris_core::core::{ActiveSnapshot, GameSnapshot, GameState};
+use tetris_core::types::{CoreLastEvent, GameAction, Rotation, Rules, TICK_MS};
 
 pub const MAX_COMMANDS_PER_STEP: usize = 32;
 pub const MAX_LOCAL_ACTIONS_PER_STEP: usize = 64;
@@ -39,6 +39,16 @@ impl GameCommand {
     }
 }
 
+impl From<Placement> for GameCommand {
+    fn from(value: Placement) -> Self {
+        Self::Place {
+            x: value.x,
+            rotation: value.rotation,
+            use_hold: value.use_hold,
+        }
+    }
+}
+
 pub type CommandOutcome = Result<(), PlaceError>;
 
 /// Complete input accepted at one authoritative logical-step boundary.
@@ -61,13 +71,21 @@ impl StepInput {
 }
 
 /// Observable result of one authoritative logical transition.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Transition {
     pub command_outcomes: ArrayVec<CommandOutcome, MAX_COMMANDS_PER_STEP>,
     pub events: ArrayVec<CoreLastEvent, MAX_EVENTS_PER_STEP>,
     pub changed: bool,
 }
 
+/// A piece that locked during a transition: where it came to rest and the
+/// event its lock raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockedPiece {
+    pub piece: ActiveSnapshot,
+    pub event: CoreLastEvent,
+}
+
 /// Coherent reusable projection of core state.
 #[derive(Debug, Clone)]
 pub struct SnapshotStore {
@@ -109,7 +127,12 @@ pub struct SessionRuntime {
 
 impl SessionRuntime {
     pub fn new(seed: u32) -> Self {
-        let mut game = GameState::new(seed);
+        Self::with_rules(seed, Rules::GUIDELINE)
+    }
+
+    /// A fresh session whose game runs under `rules`.
+    pub fn with_rules(seed: u32, rules: Rules) -> Self {
+        let mut game = GameState::with_rules(seed, rules);
         game.start();
         let snapshots = SnapshotStore::new(&game);
         Self {
@@ -129,6 +152,15 @@ impl SessionRuntime {
         }
     }
 
+    /// Restores a session whose game has already applied `logical_step`
+    /// steps, such as a replay keyframe.
+    pub fn resume(game: GameState, logical_step: u64) -> Self {
+        Self {
+            logical_step,
+            ..Self::from_game(game)
+        }
+    }
+
     pub fn game(&self) -> &GameState {
         &self.game
     }
@@ -143,20 +175,47 @@ impl SessionRuntime {
 
     /// Apply one complete input and advance exactly one logical transition.
     pub fn transition(&mut self, input: &StepInput) -> Transition {
+        self.apply(input, LockWatch::default())
+    }
+
+    /// [`SessionRuntime::transition`], also passing every piece that locks
+    /// during the step to `on_lock`, in order.
+    ///
+    /// A step can lock more than one piece, for example a placement followed
+    /// by a hard drop; [`Transition::events`] reports only the last lock.
+    pub fn transition_with_locks(
+        &mut self,
+        input: &StepInput,
+        mut on_lock: impl FnMut(LockedPiece),
+    ) -> Transition {
+        self.apply(
+            input,
+            LockWatch {
+                on_lock: Some(&mut on_lock),
+                last_event: None,
+            },
+        )
+    }
+
+    fn apply(&mut self, input: &StepInput, mut watch: LockWatch<'_>) -> Transition {
         let before_snapshot = *self.snapshots.get();
         let mut command_outcomes = ArrayVec::new();
 
         for command in &input.remote {
-            command_outcomes.push(apply_game_command(&mut self.game, command));
+            command_outcomes.push(apply_game_command(&mut self.game, command, &mut watch));
         }
 
         for &action in &input.local {
-            let _ = self.game.apply_action(action);
+            watch.run(&mut self.game, ghost_landing, |game| {
+                game.apply_action(action)
+            });
         }
 
-        let _ = self.game.tick(TICK_MS, false);
+        watch.run(&mut self.game, ghost_landing, |game| {
+            game.tick(TICK_MS, false)
+        });
         let mut events = ArrayVec::new();
-        if let Some(event) = self.game.take_last_event() {
+        if let Some(event) = watch.finish(&mut self.game) {
             events.push(event);
         }
         self.logical_step = self.logical_step.wrapping_add(1);
@@ -171,7 +230,60 @@ impl SessionRuntime {
     }
 }
 
-fn apply_game_command(game: &mut GameState, command: &GameCommand) -> CommandOutcome {
+/// Runs the core operations of one step, reporting each lock to `on_lock`.
+///
+/// Without a callback the operations run untouched and the step's event is
+/// whatever the core holds at the end. With one, the event is taken after
+/// every operation, so the landing position computed beforehand can be paired
+/// with it; a restart forgets earlier events, as it does in the core.
+#[derive(Default)]
+struct LockWatch<'a> {
+    on_lock: Option<&'a mut dyn FnMut(LockedPiece)>,
+    last_event: Option<CoreLastEvent>,
+}
+
+impl LockWatch<'_> {
+    fn run<T>(
+        &mut self,
+        game: &mut GameState,
+        landing: impl FnOnce(&GameState) -> Option<ActiveSnapshot>,
+        operation: impl FnOnce(&mut GameState) -> T,
+    ) -> T {
+        let Some(on_lock) = self.on_lock.as_mut() else {
+            return operation(game);
+        };
+        let landing = landing(game);
+        let episode = game.episode_id();
+        let result = operation(game);
+        if game.episode_id() != episode {
+            self.last_event = None;
+        }
+        if let Some(event) = game.take_last_event() {
+            self.last_event = Some(event);
+            if let Some(piece) = landing.filter(|_| event.locked) {
+                on_lock(LockedPiece { piece, event });
+            }
+        }
+        result
+    }
+
+    fn finish(self, game: &mut GameState) -> Option<CoreLastEvent> {
+        game.take_last_event().or(self.last_event)
+    }
+}
+
+/// The active piece at its ghost row, where a hard drop or lock leaves it.
+fn ghost_landing(game: &GameState) -> Option<ActiveSnapshot> {
+    let mut active = ActiveSnapshot::from(game.active()?);
+    active.y = game.ghost_y()?;
+    Some(active)
+}
+
+fn apply_game_command(
+    game: &mut GameState,
+    command: &GameCommand,
+    watch: &mut LockWatch<'_>,
+) -> CommandOutcome {
     match command {
         GameCommand::Actions {
             actions,
@@ -182,17 +294,28 @@ fn apply_game_command(game: &mut GameState, command: &GameCommand) -> CommandOut
                 if action == GameAction::Restart
                     && let Some(seed) = restart_seed.take()
                 {
-                    let _ = game.restart_with_seed(seed);
+                    watch.run(game, |_| None, |game| game.restart_with_seed(seed));
                     continue;
                 }
-                let _ = game.apply_action(action);
+                watch.run(game, ghost_landing, |game| game.apply_action(action));
             }
             Ok(())
         }
-        GameCommand::Place {
+        &GameCommand::Place {
             x,
             rotation,
             use_hold,
-        } => apply_place(game, *x, *rotation, *use_hold),
+        } => {
+            let placement = Placement {
+                x,
+                rotation,
+                use_hold,
+            };
+            watch.run(
+                game,
+                |game| placement_landing(game, placement),
+                |game| apply_place(game, x, rotation, use_hold),
+            )
+        }
     }
 }
@@ -0,0 +1,380 @@
+//! Vectorized lockstep environments for parallel simulation.
+//!
+//! A [`VecEnv`] partitions N independent [`TetrisEnv`]s across persistent
+//! worker threads. Each call to [`VecEnv::step`] releases every worker once,
+//! waits for all of them, and copies flattened observations into one
+//! caller-provided contiguous buffer. Worker slots are sized at construction,
+//! so stepping performs no heap allocation.
+//!
+//! Every environment is seeded independently and advanced by its own action,
+//! so results do not depend on the number of worker threads.
+//!
+//! A panic inside a worker, such as from a reward function, is caught there
+//! and resumed on the calling thread; the `VecEnv` then refuses further steps.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use tetris_core::core::GameSnapshot;
+use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH};
+
+use crate::engine::env::{ActionSpace, RewardFn, StepInfo, TetrisEnv};
+
+/// Flattened board cells (row-major, `0` empty, `1..=7` piece kind).
+pub const OBS_BOARD: usize = 0;
+/// Five previewed piece kinds (`1..=7`).
+pub const OBS_QUEUE: usize = OBS_BOARD + BOARD_WIDTH as usize * BOARD_HEIGHT as usize;
+/// Held piece kind, `0` when empty.
+pub const OBS_HOLD: usize = OBS_QUEUE + 5;
+/// Active piece kind, rotation (`0..=3`), x, and y; all zero when absent.
+pub const OBS_ACTIVE: usize = OBS_HOLD + 1;
+/// Score, lines, level, can-hold flag, and game-over flag.
+pub const OBS_SCALARS: usize = OBS_ACTIVE + 4;
+/// Values per environment in the observation buffer.
+pub const OBS_LEN: usize = OBS_SCALARS + 5;
+
+/// Flatten one snapshot into `out[..OBS_LEN]`.
+pub fn write_observation(snapshot: &GameSnapshot, out: &mut [f32]) {
+    let out = &mut out[..OBS_LEN];
+    for (slot, &cell) in out[OBS_BOARD..OBS_QUEUE]
+        .iter_mut()
+        .zip(snapshot.board.iter().flatten())
+    {
+        *slot = f32::from(cell);
+    }
+    for (slot, &kind) in out[OBS_QUEUE..OBS_HOLD]
+        .iter_mut()
+        .zip(snapshot.next_queue.iter())
+    {
+        *slot = f32::from(kind as u8 + 1);
+    }
+    out[OBS_HOLD] = snapshot.hold.map_or(0.0, |kind| f32::from(kind as u8 + 1));
+    let active = match snapshot.active {
+        Some(active) => [
+            f32::from(active.kind as u8 + 1),
+            f32::from(active.rotation as u8),
+            f32::from(active.x),
+            f32::from(active.y),
+        ],
+        None => [0.0; 4],
+    };
+    out[OBS_ACTIVE..OBS_SCALARS].copy_from_slice(&active);
+    out[OBS_SCALARS..OBS_LEN].copy_from_slice(&[
+        snapshot.score as f32,
+        snapshot.lines as f32,
+        snapshot.level as f32,
+        f32::from(u8::from(snapshot.can_hold)),
+        f32::from(u8::from(snapshot.game_over)),
+    ]);
+}
+
+/// Per-environment outcome of one vectorized step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VecStepResult {
+    pub reward: f32,
+    pub terminated: bool,
+    pub truncated: bool,
+    pub info: StepInfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Step,
+    Reset,
+}
+
+/// Preallocated exchange area between the caller and one worker.
+#[derive(Debug)]
+struct Slot {
+    command: Command,
+    actions: Vec<usize>,
+    obs: Vec<f32>,
+    results: Vec<VecStepResult>,
+    /// Payload of a panic raised while the worker ran `command`.
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    slots: Vec<Mutex<Slot>>,
+    start: Barrier,
+    done: Barrier,
+    shutdown: AtomicBool,
+}
+
+/// N lockstep environments stepped by persistent worker threads.
+#[derive(Debug)]
+pub struct VecEnv {
+    num_envs: usize,
+    shared: Arc<Shared>,
+    ranges: Vec<(usize, usize)>,
+    workers: Vec<JoinHandle<()>>,
+    /// Set once a worker panicked; its environments are no longer trusted.
+    poisoned: bool,
+}
+
+impl VecEnv {
+    /// Create one environment per seed, spread over `threads` workers.
+    ///
+    /// Finished episodes reset automatically with their seed advanced by
+    /// `seeds.len()`, so every environment walks a distinct seed sequence.
+    pub fn new<R>(seeds: &[u32], action_space: ActionSpace, threads: usize, reward: R) -> Self
+    where
+        R: RewardFn + Clone + Send + 'static,
+    {
+        Self::with_max_episode_steps(seeds, action_space, threads, reward, None)
+    }
+
+    /// Like [`VecEnv::new`], truncating episodes after `max_episode_steps`.
+    pub fn with_max_episode_steps<R>(
+        seeds: &[u32],
+        action_space: ActionSpace,
+        threads: usize,
+        reward: R,
+        max_episode_steps: Option<u64>,
+    ) -> Self
+    where
+        R: RewardFn + Clone + Send + 'static,
+    {
+        let num_envs = seeds.len();
+        let threads = threads.clamp(1, num_envs.max(1));
+        let chunk = num_envs.div_ceil(threads).max(1);
+        let ranges: Vec<(usize, usize)> = (0..threads)
+            .map(|worker| {
+                let start = (worker * chunk).min(num_envs);
+                (start, ((worker + 1) * chunk).min(num_envs))
+            })
+            .collect();
+
+        let shared = Arc::new(Shared {
+            slots: ranges
+                .iter()
+                .map(|&(start, end)| {
+                    Mutex::new(Slot {
+                        command: Command::Step,
+                        actions: vec![0; end - start],
+                        obs: vec![0.0; (end - start) * OBS_LEN],
+                        results: vec![VecStepResult::default(); end - start],
+                        panic: None,
+                    })
+                })
+                .collect(),
+            start: Barrier::new(threads + 1),
+            done: Barrier::new(threads + 1),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let stride = num_envs as u32;
+        let workers = ranges
+            .iter()
+            .enumerate()
+            .map(|(worker, &(start, end))| {
+                let mut envs: Vec<(TetrisEnv<R>, u32)> = seeds[start..end]
+                    .iter()
+                    .map(|&seed| {
+                        let env = TetrisEnv::new(seed, action_space, reward.clone());
+                        let env = match max_episode_steps {
+                            Some(steps) => env.with_max_episode_steps(steps),
+                            None => env,
+                        };
+                        (env, seed)
+                    })
+                    .collect();
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("tetris-vec-env-{worker}"))
+                    .spawn(move || run_worker(&shared, worker, &mut envs, stride))
+                    .expect("spawn vec env worker")
+            })
+            .collect();
+
+        Self {
+            num_envs,
+            shared,
+            ranges,
+            workers,
+            poisoned: false,
+        }
+    }
+
+    pub fn num_envs(&self) -> usize {
+        self.num_envs
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Reset every environment to its current seed and write observations.
+    pub fn reset(&mut self, obs: &mut [f32]) {
+        self.run(Command::Reset, &[], obs, &mut []);
+    }
+
+    /// Step every environment with its discrete action index.
+    ///
+    /// `actions` holds one index per environment, `obs` receives
+    /// `num_envs * OBS_LEN` values, and `results` one entry per environment.
+    ///
+    /// # Panics
+    ///
+    /// Resumes a panic raised by any worker, and panics on every call after
+    /// that.
+    pub fn step(&mut self, actions: &[usize], obs: &mut [f32], results: &mut [VecStepResult]) {
+        assert_eq!(actions.len(), self.num_envs, "one action per environment");
+        assert_eq!(results.len(), self.num_envs, "one result per environment");
+        self.run(Command::Step, actions, obs, results);
+    }
+
+    fn run(
+        &mut self,
+        command: Command,
+        actions: &[usize],
+        obs: &mut [f32],
+        results: &mut [VecStepResult],
+    ) {
+        assert!(
+            !self.poisoned,
+            "a VecEnv worker panicked in an earlier step"
+        );
+        assert_eq!(
+            obs.len(),
+            self.num_envs * OBS_LEN,
+            "observation buffer size"
+        );
+        for (slot, &(start, end)) in self.shared.slots.iter().zip(&self.ranges) {
+            let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+            slot.command = command;
+            if command == Command::Step {
+                slot.actions.copy_from_slice(&actions[start..end]);
+            }
+        }
+
+        self.shared.start.wait();
+        self.shared.done.wait();
+
+        for slot in &self.shared.slots {
+            let payload = slot
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .panic
+                .take();
+            if let Some(payload) = payload {
+                self.poisoned = true;
+                panic::resume_unwind(payload);
+            }
+        }
+        for (slot, &(start, end)) in self.shared.slots.iter().zip(&self.ranges) {
+            let slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+            obs[start * OBS_LEN..end * OBS_LEN].copy_from_slice(&slot.obs);
+            if command == Command::Step {
+                results[start..end].copy_from_slice(&slot.results);
+            }
+        }
+    }
+}
+
+impl Drop for VecEnv {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        self.shared.start.wait();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_worker<R: RewardFn>(
+    shared: &Shared,
+    worker: usize,
+    envs: &mut [(TetrisEnv<R>, u32)],
+    stride: u32,
+) {
+    loop {
+        shared.start.wait();
+        if shared.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+        {
+            let mut slot = shared.slots[worker]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let Slot {
+                command,
+                actions,
+                obs,
+                results,
+                panic: payload,
+            } = &mut *slot;
+            // Reach the `done` barrier even if an environment panics, so the
+            // caller is never left waiting for this worker.
+            *payload = panic::catch_unwind(AssertUnwindSafe(|| {
+                run_command(*command, actions, obs, results, envs, stride)
+            }))
+            .err();
+        }
+        shared.done.wait();
+    }
+}
+
+fn run_command<R: RewardFn>(
+    command: Command,
+    actions: &[usize],
+    obs: &mut [f32],
+    results: &mut [VecStepResult],
+    envs: &mut [(TetrisEnv<R>, u32)],
+    stride: u32,
+) {
+    for (index, (env, seed)) in envs.iter_mut().enumerate() {
+        let out = &mut obs[index * OBS_LEN..(index + 1) * OBS_LEN];
+        if command == Command::Reset {
+            write_observation(env.reset(*seed), out);
+            continue;
+        }
+        let step = env.step_index(actions[index]);
+        let result = VecStepResult {
+            reward: step.reward,
+            terminated: step.terminated,
+            truncated: step.truncated,
+            info: step.info,
+        };
+        if result.terminated || result.truncated {
+            *seed = seed.wrapping_add(stride);
+            write_observation(env.reset(*seed), out);
+        } else {
+            write_observation(env.observation(), out);
+        }
+        results[index] = result;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris_core::core::GameState;
+
+    #[test]
+    fn observation_layout_covers_board_queue_hold_active_and_scalars() {
+        let mut game = GameState::new(1);
+        game.start();
+        let mut snapshot = game.snapshot();
+        snapshot.board[19][0] = 3;
+        let mut out = [-1.0; OBS_LEN];
+        write_observation(&snapshot, &mut out);
+
+        assert_eq!(out[OBS_BOARD + 19 * BOARD_WIDTH as usize], 3.0);
+        assert_eq!(out[OBS_QUEUE], f32::from(snapshot.next_queue[0] as u8 + 1));
+        assert_eq!(out[OBS_HOLD], 0.0);
+        assert_eq!(
+            out[OBS_ACTIVE],
+            f32::from(snapshot.active.unwrap().kind as u8 + 1)
+        );
+        assert_eq!(out[OBS_LEN - 2], 1.0);
+        assert!(
+            out.iter().all(|&value| value != -1.0),
+            "every value is written"
+        );
+    }
+}
@@ -214,6 +214,7 @@ impl InputHandler {
         }
     }
 
+    #[allow(clippy::collapsible_match)] // Arms mirror the per-layout key groups of `handle_key_press`.
     pub fn handle_key_release(&mut self, code: KeyCode) {
         match code {
             KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') => {
@@ -7,17 +7,71 @@ use tetris_core::types::GameAction;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputCommand {
     Action(GameAction),
+    /// Show or hide the planner's suggested placement.
+    ToggleHint,
+    /// Save the game played so far as a replay tape.
+    SaveReplay,
     Quit,
 }
 
 pub fn map_input_command(key: KeyEvent) -> Option<InputCommand> {
     if should_quit(key) {
         Some(InputCommand::Quit)
+    } else if matches!(key.code, KeyCode::Char('g') | KeyCode::Char('G')) {
+        Some(InputCommand::ToggleHint)
+    } else if matches!(key.code, KeyCode::Char('v') | KeyCode::Char('V')) {
+        Some(InputCommand::SaveReplay)
     } else {
         handle_key_event(key).map(InputCommand::Action)
     }
 }
 
+/// Intent of a key pressed in the replay viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayInputCommand {
+    TogglePlay,
+    Faster,
+    Slower,
+    StepForward,
+    StepBack,
+    Start,
+    End,
+    /// A digit of the step number to jump to.
+    Digit(u8),
+    DeleteDigit,
+    /// Jump to the typed step number.
+    Jump,
+    /// Discard the typed step number.
+    CancelJump,
+    Quit,
+}
+
+pub fn map_replay_command(key: KeyEvent) -> Option<ReplayInputCommand> {
+    if should_quit(key) {
+        return Some(ReplayInputCommand::Quit);
+    }
+    match key.code {
+        KeyCode::Char(' ') => Some(ReplayInputCommand::TogglePlay),
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Char(']') => {
+            Some(ReplayInputCommand::Faster)
+        }
+        KeyCode::Char('-') | KeyCode::Char('[') => Some(ReplayInputCommand::Slower),
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('L') => {
+            Some(ReplayInputCommand::StepForward)
+        }
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('H') => {
+            Some(ReplayInputCommand::StepBack)
+        }
+        KeyCode::Home => Some(ReplayInputCommand::Start),
+        KeyCode::End => Some(ReplayInputCommand::End),
+        KeyCode::Char(digit @ '0'..='9') => Some(ReplayInputCommand::Digit(digit as u8 - b'0')),
+        KeyCode::Backspace => Some(ReplayInputCommand::DeleteDigit),
+        KeyCode::Enter => Some(ReplayInputCommand::Jump),
+        KeyCode::Esc => Some(ReplayInputCommand::CancelJump),
+        _ => None,
+    }
+}
+
 /// Map keyboard input to game actions.
 pub fn handle_key_event(key: KeyEvent) -> Option<GameAction> {
     match key.code {
@@ -150,4 +204,54 @@ mod tests {
         )));
         assert!(!should_quit(KeyEvent::from(KeyCode::Char('x'))));
     }
+
+    #[test]
+    fn test_hint_toggle_key() {
+        assert_eq!(
+            map_input_command(KeyEvent::from(KeyCode::Char('g'))),
+            Some(InputCommand::ToggleHint)
+        );
+        assert_eq!(
+            map_input_command(KeyEvent::from(KeyCode::Char('G'))),
+            Some(InputCommand::ToggleHint)
+        );
+        assert_eq!(handle_key_event(KeyEvent::from(KeyCode::Char('g'))), None);
+    }
+
+    #[test]
+    fn test_save_replay_key() {
+        assert_eq!(
+            map_input_command(KeyEvent::from(KeyCode::Char('v'))),
+            Some(InputCommand::SaveReplay)
+        );
+        assert_eq!(
+            map_input_command(KeyEvent::from(KeyCode::Char('V'))),
+            Some(InputCommand::SaveReplay)
+        );
+        assert_eq!(handle_key_event(KeyEvent::from(KeyCode::Char('v'))), None);
+    }
+
+    #[test]
+    fn test_replay_keys() {
+        assert_eq!(
+            map_replay_command(KeyEvent::from(KeyCode::Char(' '))),
+            Some(ReplayInputCommand::TogglePlay)
+        );
+        assert_eq!(
+            map_replay_command(KeyEvent::from(KeyCode::Left)),
+            Some(ReplayInputCommand::StepBack)
+        );
+        assert_eq!(
+            map_replay_command(KeyEvent::from(KeyCode::Char('7'))),
+            Some(ReplayInputCommand::Digit(7))
+        );
+        assert_eq!(
+            map_replay_command(KeyEvent::from(KeyCode::Enter)),
+            Some(ReplayInputCommand::Jump)
+        );
+        assert_eq!(
+            map_replay_command(KeyEvent::from(KeyCode::Char('q'))),
+            Some(ReplayInputCommand::Quit)
+        );
+    }
 }
@@ -9,4 +9,7 @@ pub mod handler;
 pub mod map;
 
 pub use handler::InputHandler;
-pub use map::{InputCommand, handle_key_event, map_input_command, should_quit};
+pub use map::{
+    InputCommand, ReplayInputCommand, handle_key_event, map_input_command, map_replay_command,
+    should_quit,
+};
@@ -151,6 +151,27 @@ impl FrameBuffer {
         self.put_str(x, y, s, style);
     }
 
+    /// Like [`FrameBuffer::put_u32`]; returns the column after the last digit.
+    pub fn put_u64(&mut self, x: u16, y: u16, mut v: u64, style: CellStyle) -> u16 {
+        // Max 20 digits for u64.
+        let mut buf = [0u8; 20];
+        let mut i = 20;
+        if v == 0 {
+            i -= 1;
+            buf[i] = b'0';
+        } else {
+            while v != 0 {
+                let digit = (v % 10) as u8;
+                v /= 10;
+                i -= 1;
+                buf[i] = b'0' + digit;
+            }
+        }
+        let s = std::str::from_utf8(&buf[i..]).expect("digits are valid utf8");
+        self.put_str(x, y, s, style);
+        x.saturating_add(s.len() as u16)
+    }
+
     pub fn fill_rect(&mut self, x: u16, y: u16, w: u16, h: u16, ch: char, style: CellStyle) {
         let end_x = x.saturating_add(w).min(self.width);
         let end_y = y.saturating_add(h).min(self.height);
@@ -3,7 +3,7 @@
 //! This module is pure (no I/O). It can be unit-tested.
 
 use crate::term::fb::{CellStyle, FrameBuffer, Rgb};
-use tetris_core::core::{GameSnapshot, get_shape};
+use tetris_core::core::{ActiveSnapshot, GameSnapshot, get_shape};
 use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH, PieceKind};
 
 /// Terminal viewport dimensions.
@@ -44,16 +44,41 @@ impl AdapterStatusView {
     }
 }
 
+/// Replay playback position shown in the side panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStatusView {
+    pub step: u64,
+    pub total_steps: u64,
+    pub playing: bool,
+    /// Playback speed as a `numerator/denominator` multiplier of real time.
+    pub speed: (u16, u16),
+    /// Step number being typed for a jump, if any.
+    pub jump_target: Option<u64>,
+}
+
 /// Immutable terminal projection decoupled from the authoritative session.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GameViewModel {
     snapshot: GameSnapshot,
     adapter: Option<AdapterStatusView>,
+    hint: Option<ActiveSnapshot>,
+    replay: Option<ReplayStatusView>,
 }
 
 impl GameViewModel {
     pub fn new(snapshot: GameSnapshot, adapter: Option<AdapterStatusView>) -> Self {
-        Self { snapshot, adapter }
+        Self {
+            snapshot,
+            adapter,
+            hint: None,
+            replay: None,
+        }
+    }
+
+    /// Attach a suggested landing position, drawn as a second ghost.
+    pub fn with_hint(mut self, hint: Option<ActiveSnapshot>) -> Self {
+        self.hint = hint;
+        self
     }
 
     pub fn snapshot(&self) -> &GameSnapshot {
@@ -63,6 +88,20 @@ impl GameViewModel {
     pub fn adapter(&self) -> Option<&AdapterStatusView> {
         self.adapter.as_ref()
     }
+
+    pub fn hint(&self) -> Option<&ActiveSnapshot> {
+        self.hint.as_ref()
+    }
+
+    /// Show replay playback status and a progress bar in the side panel.
+    pub fn with_replay(mut self, replay: Option<ReplayStatusView>) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    pub fn replay(&self) -> Option<&ReplayStatusView> {
+        self.replay.as_ref()
+    }
 }
 
 impl Viewport {
@@ -125,7 +164,14 @@ impl GameView {
         viewport: Viewport,
         fb: &mut FrameBuffer,
     ) {
-        self.render_into_with_adapter(model.snapshot(), model.adapter(), viewport, fb);
+        self.render_parts(
+            model.snapshot(),
+            model.adapter(),
+            model.hint(),
+            model.replay(),
+            viewport,
+            fb,
+        );
     }
 
     pub fn render_into_with_adapter(
@@ -134,6 +180,31 @@ impl GameView {
         adapter: Option<&AdapterStatusView>,
         viewport: Viewport,
         fb: &mut FrameBuffer,
+    ) {
+        self.render_into_with_hint(snap, adapter, None, viewport, fb);
+    }
+
+    /// Render with an optional coaching hint: a suggested landing position
+    /// drawn beneath the active piece in a style distinct from the ghost.
+    pub fn render_into_with_hint(
+        &self,
+        snap: &GameSnapshot,
+        adapter: Option<&AdapterStatusView>,
+        hint: Option<&ActiveSnapshot>,
+        viewport: Viewport,
+        fb: &mut FrameBuffer,
+    ) {
+        self.render_parts(snap, adapter, hint, None, viewport, fb);
+    }
+
+    fn render_parts(
+        &self,
+        snap: &GameSnapshot,
+        adapter: Option<&AdapterStatusView>,
+        hint: Option<&ActiveSnapshot>,
+        replay: Option<&ReplayStatusView>,
+        viewport: Viewport,
+        fb: &mut FrameBuffer,
     ) {
         fb.resize(viewport.width, viewport.height);
         fb.clear(CellStyle::default().into_cell(' '));
@@ -198,6 +269,23 @@ impl GameView {
             }
         }
 
+        // Hint piece (suggested landing), drawn over the ghost.
+        if let Some(hint) = hint {
+            let hint_style = CellStyle {
+                fg: Rgb::new(120, 230, 160),
+                bg: Rgb::new(30, 30, 40),
+                bold: true,
+                dim: false,
+            };
+            for &(dx, dy) in get_shape(hint.kind, hint.rotation).iter() {
+                let x = hint.x + dx;
+                let y = hint.y + dy;
+                if x >= 0 && x < BOARD_WIDTH as i8 && y >= 0 && y < BOARD_HEIGHT as i8 {
+                    self.fill_cell_rect(fb, start_x, start_y, x as u16, y as u16, '▒', hint_style);
+                }
+            }
+        }
+
         // Active piece.
         if let Some(active) = snap.active {
             for &(dx, dy) in get_shape(active.kind, active.rotation).iter() {
@@ -218,7 +306,9 @@ impl GameView {
         }
 
         // Side panel (score/next/hold).
-        self.draw_side_panel(fb, snap, adapter, viewport, start_x, start_y, frame_w);
+        self.draw_side_panel(
+            fb, snap, adapter, replay, viewport, start_x, start_y, frame_w,
+        );
 
         // Overlays.
         if snap.paused {
@@ -327,6 +417,7 @@ impl GameView {
         fb: &mut FrameBuffer,
         snap: &GameSnapshot,
         adapter: Option<&AdapterStatusView>,
+        replay: Option<&ReplayStatusView>,
         viewport: Viewport,
         start_x: u16,
         start_y: u16,
@@ -382,7 +473,12 @@ impl GameView {
 
         // Reserve space for the AI status block only when adapter status is available.
         // In observe mode there is no local adapter status source, so hide the whole block.
-        let reserve_ai_lines: u16 = if adapter.is_some() { 7 } else { 0 };
+        let reserve_ai_lines: u16 = if adapter.is_some() { 7 } else { 0 }
+            + if replay.is_some() {
+                REPLAY_PANEL_LINES
+            } else {
+                0
+            };
 
         fb.put_str(panel_x, y, "NEXT", label);
         y = y.saturating_add(1);
@@ -435,6 +531,67 @@ impl GameView {
             fb.put_str(panel_x, y, "PID", value);
             fb.put_u32(value_x, y, st.pid, value);
         }
+
+        if let Some(replay) = replay {
+            y = y.saturating_add(1);
+            self.draw_replay_status(fb, replay, panel_x, y, panel_w, label, value);
+        }
+    }
+
+    /// Replay block: title, step counter, playback state, and progress bar.
+    #[allow(clippy::too_many_arguments)] // Layout inputs are borrowed/scalar and grouped at the call site.
+    fn draw_replay_status(
+        &self,
+        fb: &mut FrameBuffer,
+        replay: &ReplayStatusView,
+        panel_x: u16,
+        mut y: u16,
+        panel_w: u16,
+        label: CellStyle,
+        value: CellStyle,
+    ) {
+        fb.put_str(panel_x, y, "REPLAY", label);
+        y = y.saturating_add(1);
+        let mut x = panel_x;
+        x = fb.put_u64(x, y, replay.step, value);
+        fb.put_char(x, y, '/', value);
+        fb.put_u64(x + 1, y, replay.total_steps, value);
+
+        y = y.saturating_add(1);
+        fb.put_str(
+            panel_x,
+            y,
+            if replay.playing { "PLAY" } else { "PAUSE" },
+            value,
+        );
+        let (numerator, denominator) = replay.speed;
+        let mut x = panel_x + 6;
+        fb.put_char(x, y, 'x', value);
+        x = fb.put_u64(x + 1, y, numerator as u64, value);
+        if denominator > 1 {
+            fb.put_char(x, y, '/', value);
+            fb.put_u64(x + 1, y, denominator as u64, value);
+        }
+
+        y = y.saturating_add(1);
+        let bar_w = panel_w.saturating_sub(2).min(REPLAY_BAR_MAX_WIDTH);
+        let filled = if replay.total_steps == 0 {
+            bar_w
+        } else {
+            (replay.step.min(replay.total_steps) as u128 * bar_w as u128
+                / replay.total_steps as u128) as u16
+        };
+        fb.put_char(panel_x, y, '[', value);
+        fb.fill_rect(panel_x + 1, y, filled, 1, '█', value);
+        let dim = CellStyle { dim: true, ..value };
+        fb.fill_rect(panel_x + 1 + filled, y, bar_w - filled, 1, '·', dim);
+        fb.put_char(panel_x + 1 + bar_w, y, ']', value);
+
+        if let Some(target) = replay.jump_target {
+            y = y.saturating_add(1);
+            fb.put_str(panel_x, y, "GO ", label);
+            fb.put_u64(panel_x + 3, y, target, value);
+        }
     }
 
     fn draw_overlay_text(
@@ -459,6 +616,10 @@ impl GameView {
     }
 }
 
+/// Lines drawn by the replay block, including its leading gap.
+const REPLAY_PANEL_LINES: u16 = 6;
+const REPLAY_BAR_MAX_WIDTH: u16 = 20;
+
 fn piece_from_cell(v: u8) -> Option<PieceKind> {
     match v {
         1 => Some(PieceKind::I),
@@ -15,6 +15,8 @@ pub mod render_throttle;
 pub mod renderer;
 
 pub use fb::{Cell, CellStyle, FrameBuffer, Rgb};
-pub use game_view::{AdapterStatusView, AnchorY, GameView, GameViewModel, Viewport};
+pub use game_view::{
+    AdapterStatusView, AnchorY, GameView, GameViewModel, ReplayStatusView, Viewport,
+};
 pub use render_throttle::RenderThrottle;
 pub use renderer::{TerminalRenderer, encode_diff_into, encode_full_into};
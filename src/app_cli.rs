@@ -1,5 +1,7 @@
 //! Top-level non-interactive application commands.
 
+use std::path::PathBuf;
+
 use tetris_adapter_protocol::protocol::PROTOCOL_VERSION;
 use tetris_session::engine::replay::{REPLAY_FORMAT_VERSION, RULESET_VERSION, transition_hash};
 use tetris_session::engine::session::{SessionRuntime, StepInput};
@@ -8,6 +10,8 @@ use tetris_session::engine::session::{SessionRuntime, StepInput};
 pub struct HeadlessConfig {
     pub seed: u32,
     pub steps: Option<u64>,
+    /// Serve the adapter to one client over stdin/stdout instead of TCP.
+    pub stdio: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,8 +27,14 @@ pub fn parse_app_args(args: &[String]) -> Result<Option<AppCommand>, String> {
         Some("headless") => {
             let mut seed = 1;
             let mut steps = None;
+            let mut stdio = false;
             let mut index = 1;
             while index < args.len() {
+                if args[index] == "--stdio" {
+                    stdio = true;
+                    index += 1;
+                    continue;
+                }
                 let value = args.get(index + 1).ok_or("missing headless option value")?;
                 match args[index].as_str() {
                     "--seed" => seed = value.parse().map_err(|_| "invalid --seed")?,
@@ -33,12 +43,44 @@ pub fn parse_app_args(args: &[String]) -> Result<Option<AppCommand>, String> {
                 }
                 index += 2;
             }
-            Ok(Some(AppCommand::Headless(HeadlessConfig { seed, steps })))
+            if stdio && steps.is_some() {
+                return Err("--stdio cannot be combined with --steps".into());
+            }
+            Ok(Some(AppCommand::Headless(HeadlessConfig {
+                seed,
+                steps,
+                stdio,
+            })))
         }
         _ => Ok(None),
     }
 }
 
+/// Options of the interactive game, the default command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayConfig {
+    /// Stream every applied step to this replay tape.
+    pub record: Option<PathBuf>,
+    /// Player name recorded in replay metadata; defaults to `$USER`.
+    pub player: Option<String>,
+}
+
+/// Parse the interactive game's options; call after every subcommand parser.
+pub fn parse_play_args(args: &[String]) -> Result<PlayConfig, String> {
+    let mut config = PlayConfig::default();
+    let mut index = 0;
+    while index < args.len() {
+        let value = args.get(index + 1).ok_or("missing option value")?;
+        match args[index].as_str() {
+            "--record" => config.record = Some(PathBuf::from(value)),
+            "--player" => config.player = Some(value.clone()),
+            option => return Err(format!("unknown option: {option}")),
+        }
+        index += 2;
+    }
+    Ok(config)
+}
+
 pub fn run_batch_headless(config: HeadlessConfig) -> Result<String, String> {
     let steps = config
         .steps
@@ -0,0 +1,146 @@
+//! Headless planner-driven bot command surface.
+
+use tetris_session::engine::planner::{MAX_LOOKAHEAD_DEPTH, Planner, PlannerConfig};
+use tetris_session::engine::replay::transition_hash;
+use tetris_session::engine::session::{SessionRuntime, StepInput, Transition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotConfig {
+    pub seed: u32,
+    pub pieces: u64,
+    pub planner: PlannerConfig,
+}
+
+/// Final result of one bot run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotRun {
+    pub pieces: u64,
+    pub lines: u32,
+    pub score: u32,
+    pub game_over: bool,
+    pub logical_steps: u64,
+    pub state_hash: u64,
+}
+
+pub fn parse_bot_args(args: &[String]) -> Result<Option<BotConfig>, String> {
+    if args.first().map(String::as_str) != Some("bot") {
+        return Ok(None);
+    }
+    let mut config = BotConfig {
+        seed: 1,
+        pieces: 100,
+        planner: PlannerConfig::default(),
+    };
+    let mut index = 1;
+    while index < args.len() {
+        if args[index] == "--no-hold" {
+            config.planner.use_hold = false;
+            index += 1;
+            continue;
+        }
+        let value = args.get(index + 1).ok_or("missing bot option value")?;
+        match args[index].as_str() {
+            "--seed" => config.seed = value.parse().map_err(|_| "invalid --seed")?,
+            "--pieces" => config.pieces = value.parse().map_err(|_| "invalid --pieces")?,
+            "--beam" => {
+                config.planner.beam_width = value
+                    .parse()
+                    .ok()
+                    .filter(|&width| width > 0)
+                    .ok_or("invalid --beam")?
+            }
+            "--depth" => {
+                config.planner.depth = value
+                    .parse()
+                    .ok()
+                    .filter(|depth| (1..=MAX_LOOKAHEAD_DEPTH).contains(depth))
+                    .ok_or_else(|| format!("--depth must be 1..={MAX_LOOKAHEAD_DEPTH}"))?
+            }
+            "--budget" => {
+                config.planner.step_budget = value.parse().map_err(|_| "invalid --budget")?
+            }
+            "--placements-per-step" => {
+                config.planner.placements_per_step = value
+                    .parse()
+                    .ok()
+                    .filter(|&placements| placements > 0)
+                    .ok_or("invalid --placements-per-step")?
+            }
+            option => return Err(format!("unknown bot option: {option}")),
+        }
+        index += 2;
+    }
+    Ok(Some(config))
+}
+
+/// Play `config.pieces` placements (or until game over) on a fresh session.
+pub fn play_bot(config: BotConfig) -> BotRun {
+    let mut session = SessionRuntime::new(config.seed);
+    drive_bot(&mut session, config, |_, _, _| {})
+}
+
+/// Play `config.pieces` placements (or until game over) from wherever
+/// `session` stands, showing every applied step to `on_step`.
+///
+/// Planning runs in game time: the game keeps stepping for the
+/// [`Plan::steps_used`](tetris_session::engine::planner::Plan::steps_used) a
+/// decision took, and the placement goes in with the last of those steps.
+pub fn drive_bot(
+    session: &mut SessionRuntime,
+    config: BotConfig,
+    mut on_step: impl FnMut(&SessionRuntime, &StepInput, &Transition),
+) -> BotRun {
+    let mut planner = Planner::new(config.planner);
+    let mut pieces = 0;
+    let mut hash = transition_hash(session.snapshot(), session.logical_step(), &[], &[]);
+    // The decision being thought through and the steps it still needs.
+    let mut thinking = None;
+    while pieces < config.pieces && !session.game().game_over() {
+        if thinking.is_none() {
+            thinking = planner
+                .plan(session.game())
+                .map(|plan| (plan.placement, plan.steps_used));
+        }
+        let input = match thinking.take() {
+            Some((placement, steps)) if steps > 1 => {
+                thinking = Some((placement, steps - 1));
+                StepInput::default()
+            }
+            Some((placement, _)) => {
+                pieces += 1;
+                StepInput::default().with_remote(placement.into())
+            }
+            None => StepInput::default(),
+        };
+        let transition = session.transition(&input);
+        on_step(session, &input, &transition);
+        hash = transition_hash(
+            session.snapshot(),
+            session.logical_step(),
+            &transition.events,
+            &transition.command_outcomes,
+        );
+    }
+    BotRun {
+        pieces,
+        lines: session.game().lines(),
+        score: session.game().score(),
+        game_over: session.game().game_over(),
+        logical_steps: session.logical_step(),
+        state_hash: hash,
+    }
+}
+
+pub fn run_bot(config: BotConfig) -> String {
+    let run = play_bot(config);
+    format!(
+        "seed={} pieces={} lines={} score={} game_over={} steps={} state_hash={:016x}",
+        config.seed,
+        run.pieces,
+        run.lines,
+        run.score,
+        run.game_over,
+        run.logical_steps,
+        run.state_hash
+    )
+}
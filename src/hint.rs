@@ -0,0 +1,170 @@
+//! Interactive placement coach.
+//!
+//! Planning runs on a dedicated worker thread. The fixed-step loop only hands
+//! over a cloned `GameState` through a one-slot channel and polls finished
+//! hints, so a slow search can never stall a tick or a frame.
+
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::thread;
+
+use tetris_core::core::{ActiveSnapshot, GameState};
+use tetris_session::engine::place::placement_landing;
+use tetris_session::engine::planner::{Planner, PlannerConfig};
+
+/// Identity of the piece a hint was computed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HintKey {
+    episode_id: u32,
+    piece_id: u32,
+    active_id: u32,
+}
+
+impl HintKey {
+    fn of(game: &GameState) -> Self {
+        Self {
+            episode_id: game.episode_id(),
+            piece_id: game.piece_id(),
+            active_id: game.active_id(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HintWorker {
+    requests: SyncSender<GameState>,
+    results: Receiver<(HintKey, Option<ActiveSnapshot>)>,
+}
+
+impl HintWorker {
+    fn spawn(config: PlannerConfig) -> std::io::Result<Self> {
+        let (requests, request_rx) = mpsc::sync_channel::<GameState>(1);
+        let (result_tx, results) = mpsc::channel();
+        thread::Builder::new()
+            .name("tetris-hint".into())
+            .spawn(move || {
+                let mut planner = Planner::new(config);
+                // Exits once the coach (and its request sender) is dropped.
+                while let Ok(game) = request_rx.recv() {
+                    let landing = planner
+                        .plan(&game)
+                        .and_then(|plan| placement_landing(&game, plan.placement));
+                    if result_tx.send((HintKey::of(&game), landing)).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self { requests, results })
+    }
+}
+
+/// Toggleable hint state owned by the interactive loop.
+#[derive(Debug)]
+pub struct HintCoach {
+    config: PlannerConfig,
+    enabled: bool,
+    worker: Option<HintWorker>,
+    requested: Option<HintKey>,
+    current: Option<(HintKey, ActiveSnapshot)>,
+}
+
+impl HintCoach {
+    pub fn new(config: PlannerConfig) -> Self {
+        Self {
+            config,
+            enabled: false,
+            worker: None,
+            requested: None,
+            current: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Flip the overlay; the worker thread is started on first use.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if self.enabled && self.worker.is_none() {
+            self.worker = HintWorker::spawn(self.config).ok();
+            self.requested = None;
+        }
+    }
+
+    /// Collect finished hints and request one for a new active piece.
+    ///
+    /// Never blocks: a busy worker simply gets the request on a later call.
+    pub fn update(&mut self, game: &GameState) {
+        if !self.enabled {
+            return;
+        }
+        let Some(worker) = self.worker.as_ref() else {
+            return;
+        };
+        loop {
+            match worker.results.try_recv() {
+                Ok((key, landing)) => self.current = landing.map(|landing| (key, landing)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.worker = None;
+                    return;
+                }
+            }
+        }
+
+        let key = HintKey::of(game);
+        if self.requested == Some(key) || game.active().is_none() || game.paused() {
+            return;
+        }
+        match worker.requests.try_send(game.clone()) {
+            Ok(()) => self.requested = Some(key),
+            Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => self.worker = None,
+        }
+    }
+
+    /// Landing position to draw for `game`, if a fresh hint is available.
+    pub fn overlay(&self, game: &GameState) -> Option<ActiveSnapshot> {
+        if !self.enabled || game.game_over() {
+            return None;
+        }
+        self.current
+            .filter(|(key, _)| *key == HintKey::of(game))
+            .map(|(_, landing)| landing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn coach_delivers_a_hint_for_the_current_piece_without_blocking() {
+        let mut game = GameState::new(5);
+        game.start();
+        let mut coach = HintCoach::new(PlannerConfig::default());
+        coach.update(&game);
+        assert_eq!(coach.overlay(&game), None);
+
+        coach.toggle();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let hint = loop {
+            coach.update(&game);
+            if let Some(hint) = coach.overlay(&game) {
+                break hint;
+            }
+            assert!(Instant::now() < deadline, "hint never arrived");
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert!(hint.kind == game.active().unwrap().kind || game.can_hold());
+
+        // A hint for a previous piece is never shown for the next one.
+        game.apply_action(tetris_core::types::GameAction::HardDrop);
+        assert_eq!(coach.overlay(&game), None);
+
+        coach.toggle();
+        assert!(!coach.enabled());
+        assert_eq!(coach.overlay(&game), None);
+    }
+}
@@ -6,7 +6,8 @@
 //!
 //! Gameplay, session, adapter, and terminal APIs live in their dedicated
 //! workspace crates. This root library owns only application commands, replay
-//! commands, and the observer client.
+//! and bot commands, the replay viewer, the interactive hint coach and play
+//! recording, the observer client, and wire log analysis.
 //!
 //! # Quick Start
 //!
@@ -49,5 +50,12 @@
 //! - Diff-based terminal rendering (dirty-cell flush)
 
 pub mod app_cli;
+pub mod bot_cli;
+pub mod hint;
 pub mod observe;
+pub mod recording;
 pub mod replay_cli;
+pub mod replay_diff;
+pub mod replay_export;
+pub mod replay_view;
+pub mod wirelog;
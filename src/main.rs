@@ -12,35 +12,81 @@ use arrayvec::ArrayVec;
 use crossterm::event::{self, Event, KeyEventKind};
 
 use tetris_adapter::adapter::Adapter;
-use tetris_adapter::adapter::game_loop::step_session;
+use tetris_adapter::adapter::game_loop::{step_session, step_session_tapped};
 use tetris_adapter::adapter::observation_schedule::ObservationSchedule;
-use tetris_core::core::{GameSnapshot, GameState};
+use tetris_adapter::adapter::server::{ServerConfig, Transport};
+use tetris_core::core::{ActiveSnapshot, GameSnapshot, GameState};
 use tetris_core::types::{GameAction, TICK_MS};
 use tetris_session::engine::fixed_step::FixedStepClock;
-use tetris_session::engine::session::SessionRuntime;
-use tetris_terminal::input::{InputCommand, InputHandler, map_input_command};
+use tetris_session::engine::planner::PlannerConfig;
+use tetris_session::engine::session::{SessionRuntime, StepInput};
+use tetris_terminal::input::{InputCommand, InputHandler, map_input_command, map_replay_command};
 use tetris_terminal::term::AdapterStatusView;
 use tetris_terminal::term::{
     AnchorY, CellStyle, GameView, GameViewModel, RenderThrottle, Rgb, TerminalRenderer, Viewport,
 };
-use tui_tetris::app_cli::{AppCommand, diagnostic_report, parse_app_args, run_batch_headless};
+use tui_tetris::app_cli::{
+    AppCommand, PlayConfig, diagnostic_report, parse_app_args, parse_play_args, run_batch_headless,
+};
+use tui_tetris::bot_cli::{parse_bot_args, run_bot};
+use tui_tetris::hint::HintCoach;
 use tui_tetris::observe::{
-    ObserveEvent, ObserveReconnectPolicy, connect_observer_with_retry, observe_status_lines,
-    parse_observe_args, snapshot_from_observation,
+    ObserveEvent, ObserveReconnectPolicy, ObserveResume, connect_observer_with_retry,
+    observe_status_lines, parse_observe_args, snapshot_from_observation,
+};
+use tui_tetris::recording::{PlayRecording, interactive_metadata};
+use tui_tetris::replay_cli::{
+    ReplayCommand, branch_prefix, parse_replay_args, read_tape, run_replay_command,
 };
-use tui_tetris::replay_cli::{parse_replay_args, run_replay_command};
+use tui_tetris::replay_view::ReplayViewer;
+use tui_tetris::wirelog::{parse_wirelog_args, run_wirelog_command};
 
 const MAX_CATCH_UP_STEPS: u32 = 8;
+/// How long a recording notice stays on screen.
+const NOTICE_MS: u64 = 3_000;
 
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().skip(1).collect();
     if let Some(command) = parse_replay_args(&args).map_err(anyhow::Error::msg)? {
+        if let ReplayCommand::Play { path } = command {
+            let tape = read_tape(&path).map_err(anyhow::Error::msg)?;
+            return with_terminal(|term| run_replay_viewer(term, ReplayViewer::new(tape)));
+        }
+        if let ReplayCommand::Branch {
+            path,
+            output,
+            at,
+            bot: None,
+        } = command
+        {
+            let tape = read_tape(&path).map_err(anyhow::Error::msg)?;
+            let mut prefix = branch_prefix(&tape, &path, at);
+            for (key, value) in interactive_metadata(&local_player(None)).iter() {
+                prefix.metadata_mut().set(key, value);
+            }
+            let recording = PlayRecording::branching(&prefix, &output).map_err(|error| {
+                anyhow::anyhow!("failed to create replay {}: {error}", output.display())
+            })?;
+            let session = tape.replay_to(at);
+            return with_terminal(|term| play_session(term, session, recording));
+        }
         println!(
             "{}",
             run_replay_command(command).map_err(anyhow::Error::msg)?
         );
         return Ok(());
     }
+    if let Some(command) = parse_wirelog_args(&args).map_err(anyhow::Error::msg)? {
+        println!(
+            "{}",
+            run_wirelog_command(command).map_err(anyhow::Error::msg)?
+        );
+        return Ok(());
+    }
+    if let Some(config) = parse_bot_args(&args).map_err(anyhow::Error::msg)? {
+        println!("{}", run_bot(config));
+        return Ok(());
+    }
     if let Some(command) = parse_app_args(&args).map_err(anyhow::Error::msg)? {
         match command {
             AppCommand::Diagnostic => {
@@ -55,7 +101,7 @@ fn main() -> Result<()> {
                     );
                     return Ok(());
                 }
-                return run_headless(config.seed);
+                return run_headless(config.seed, config.stdio);
             }
         }
     }
@@ -63,11 +109,13 @@ fn main() -> Result<()> {
         return run_observe(config);
     }
 
+    let play = parse_play_args(&args).map_err(anyhow::Error::msg)?;
+
     if headless_enabled() {
-        return run_headless(1);
+        return run_headless(1, false);
     }
 
-    with_terminal(run)
+    with_terminal(|term| run(term, &play))
 }
 
 fn with_terminal(run: impl FnOnce(&mut TerminalRenderer) -> Result<()>) -> Result<()> {
@@ -80,7 +128,9 @@ fn with_terminal(run: impl FnOnce(&mut TerminalRenderer) -> Result<()>) -> Resul
 
 fn run_observe(config: tui_tetris::observe::ObserveConfig) -> Result<()> {
     let reconnect_policy = ObserveReconnectPolicy::default();
-    let (mut rx, first_obs) = connect_observer_with_retry(&config, reconnect_policy)?;
+    // Reconnects resume the session and replay the observations it missed.
+    let mut resume = ObserveResume::default();
+    let (mut rx, first_obs) = connect_observer_with_retry(&config, reconnect_policy, &mut resume)?;
 
     with_terminal(|term| {
         let view = game_view_from_env();
@@ -97,12 +147,13 @@ fn run_observe(config: tui_tetris::observe::ObserveConfig) -> Result<()> {
             while let Ok(event) = rx.try_recv() {
                 match event {
                     ObserveEvent::Observation(obs) => {
+                        resume.observed(&obs);
                         latest_obs = Some(obs.clone());
                         snap = snapshot_from_observation(&obs);
                         dirty = true;
                     }
                     ObserveEvent::Error(_) | ObserveEvent::Closed => {
-                        match connect_observer_with_retry(&config, reconnect_policy) {
+                        match connect_observer_with_retry(&config, reconnect_policy, &mut resume) {
                             Ok((new_rx, first_obs_after_reconnect)) => {
                                 rx = new_rx;
                                 latest_obs = first_obs_after_reconnect;
@@ -117,7 +168,7 @@ fn run_observe(config: tui_tetris::observe::ObserveConfig) -> Result<()> {
                             }
                         }
                     }
-                    ObserveEvent::Welcome => {}
+                    ObserveEvent::Welcome { .. } => {}
                 }
             }
 
@@ -131,12 +182,7 @@ fn run_observe(config: tui_tetris::observe::ObserveConfig) -> Result<()> {
             if dirty {
                 let model = GameViewModel::new(snap, None);
                 view.render_model_into(&model, Viewport::new(w, h), &mut fb);
-                let observe_label = CellStyle {
-                    fg: Rgb::new(220, 220, 220),
-                    bg: Rgb::new(0, 0, 0),
-                    bold: true,
-                    dim: false,
-                };
+                let observe_label = overlay_label_style();
                 for (i, line) in observe_status_lines(&config, latest_obs.as_ref())
                     .iter()
                     .enumerate()
@@ -170,6 +216,64 @@ fn run_observe(config: tui_tetris::observe::ObserveConfig) -> Result<()> {
     })
 }
 
+fn run_replay_viewer(term: &mut TerminalRenderer, mut viewer: ReplayViewer) -> Result<()> {
+    let view = game_view_from_env();
+    let mut fb = tetris_terminal::term::FrameBuffer::new(80, 24);
+    let mut last_term_size: (u16, u16) = (0, 0);
+    let mut last_sample = Instant::now();
+    let mut drawn = None;
+    let title = viewer.title();
+
+    loop {
+        let (w, h) = crossterm::terminal::size().unwrap_or((80, 24));
+        if (w, h) != last_term_size {
+            last_term_size = (w, h);
+            term.invalidate();
+            drawn = None;
+        }
+
+        let status = viewer.status();
+        if drawn != Some(status) {
+            let model = GameViewModel::new(*viewer.snapshot(), None).with_replay(Some(status));
+            view.render_model_into(&model, Viewport::new(w, h), &mut fb);
+            if let Some(title) = title.as_deref() {
+                fb.put_str(0, 0, title, overlay_label_style());
+            }
+            if let Some(note) = viewer.annotation() {
+                let line = format!("@{} {}", note.step, note.text.replace(['\n', '\t'], " "));
+                fb.put_str(0, h.saturating_sub(1), &line, overlay_label_style());
+            }
+            term.draw_swap(&mut fb)?;
+            drawn = Some(status);
+        }
+
+        // Paused playback only waits for keys.
+        let timeout = viewer
+            .until_next_step()
+            .unwrap_or(Duration::from_millis(250));
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Resize(_, _) => {
+                    term.invalidate();
+                    drawn = None;
+                }
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    if let Some(command) = map_replay_command(key)
+                        && !viewer.handle(command)
+                    {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        viewer.advance(now.saturating_duration_since(last_sample));
+        last_sample = now;
+    }
+}
+
 fn headless_enabled() -> bool {
     std::env::var("TUI_TETRIS_HEADLESS")
         .ok()
@@ -180,10 +284,19 @@ fn headless_enabled() -> bool {
         .unwrap_or(false)
 }
 
-fn run_headless(seed: u32) -> Result<()> {
+fn run_headless(seed: u32, stdio: bool) -> Result<()> {
     let mut session = SessionRuntime::new(seed);
 
-    let mut adapter = Adapter::start_from_env()?;
+    // Stdout carries the protocol in stdio mode, so nothing else may print.
+    let mut adapter = if stdio {
+        let config = ServerConfig {
+            transport: Transport::Stdio,
+            ..ServerConfig::from_env()
+        };
+        Some(Adapter::start(config)?)
+    } else {
+        Adapter::start_from_env()?
+    };
     let mut adapter_streaming_count: u16 = 0;
 
     let mut observations = ObservationSchedule::from_env(session.game());
@@ -198,6 +311,9 @@ fn run_headless(seed: u32) -> Result<()> {
             while let Some(st) = ad.try_recv_status() {
                 adapter_streaming_count = st.streaming_count;
             }
+            if ad.is_closed() {
+                return Ok(());
+            }
         }
 
         let now = Instant::now();
@@ -217,11 +333,40 @@ fn run_headless(seed: u32) -> Result<()> {
                 adapter_streaming_count > 0,
             );
         }
+        if let Some(error) = adapter.as_mut().and_then(Adapter::take_recording_error) {
+            eprintln!("{error}");
+        }
     }
 }
 
-fn run(term: &mut TerminalRenderer) -> Result<()> {
-    let mut session = SessionRuntime::new(1);
+fn run(term: &mut TerminalRenderer, play: &PlayConfig) -> Result<()> {
+    const SEED: u32 = 1;
+    let session = SessionRuntime::new(SEED);
+    let metadata = interactive_metadata(&local_player(play.player.clone()));
+    let recording = match play.record.as_deref() {
+        Some(path) => PlayRecording::streaming_to(SEED, &metadata, path).map_err(|error| {
+            anyhow::anyhow!("failed to create replay {}: {error}", path.display())
+        })?,
+        None => PlayRecording::new(SEED, &metadata),
+    };
+    play_session(term, session, recording)
+}
+
+/// `player`, else `$USER`, else `player`.
+fn local_player(player: Option<String>) -> String {
+    player
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "player".to_string())
+}
+
+/// The interactive loop on `session`, which `recording` has recorded so far.
+fn play_session(
+    term: &mut TerminalRenderer,
+    mut session: SessionRuntime,
+    mut recording: PlayRecording,
+) -> Result<()> {
+    // Latest recording message and when it expires, in render-epoch ms.
+    let mut notice: Option<(String, u64)> = None;
 
     let view = game_view_from_env();
     let mut fb = tetris_terminal::term::FrameBuffer::new(80, 24);
@@ -247,25 +392,8 @@ fn run(term: &mut TerminalRenderer) -> Result<()> {
     let mut render_throttle = RenderThrottle::new(250);
 
     let mut adapter = Adapter::start_from_env()?;
-    let listen_addr = if adapter.is_some() {
-        adapter.as_ref().map(|a| a.listen_addr()).or_else(|| {
-            // Fallback to configured env, mirroring adapter defaults.
-            let host_s = std::env::var("TETRIS_AI_HOST")
-                .ok()
-                .unwrap_or_else(|| "127.0.0.1".to_string());
-            let port = std::env::var("TETRIS_AI_PORT")
-                .ok()
-                .and_then(|s| s.parse::<u16>().ok())
-                .unwrap_or(7777);
-            host_s
-                .trim()
-                .parse::<std::net::IpAddr>()
-                .ok()
-                .map(|ip| std::net::SocketAddr::new(ip, port))
-        })
-    } else {
-        None
-    };
+    // Unix socket and stdio adapters have no address to show.
+    let listen_addr = adapter.as_ref().and_then(Adapter::listen_addr);
     let mut adapter_view = AdapterStatusView {
         enabled: adapter.is_some(),
         client_count: 0,
@@ -276,6 +404,7 @@ fn run(term: &mut TerminalRenderer) -> Result<()> {
     };
     let mut observations = ObservationSchedule::from_env(session.game());
     let mut pending_local_actions = ArrayVec::<GameAction, 64>::new();
+    let mut coach = HintCoach::new(PlannerConfig::default());
 
     let tick_duration = Duration::from_millis(TICK_MS as u64);
     let mut clock = FixedStepClock::new(tick_duration, MAX_CATCH_UP_STEPS);
@@ -300,12 +429,25 @@ fn run(term: &mut TerminalRenderer) -> Result<()> {
         }
 
         let now_ms = render_epoch.elapsed().as_millis() as u64;
+        if notice
+            .as_ref()
+            .is_some_and(|&(_, until_ms)| now_ms >= until_ms)
+        {
+            notice = None;
+        }
         let is_static = session.game().paused() || session.game().game_over();
-        let fingerprint = render_fingerprint(session.game(), &adapter_view, Viewport::new(w, h));
+        coach.update(session.game());
+        let hint = coach.overlay(session.game());
+        let fingerprint =
+            render_fingerprint(session.game(), &adapter_view, hint, Viewport::new(w, h))
+                ^ notice.as_ref().map_or(0, |&(_, until_ms)| until_ms);
 
         if render_throttle.should_render(now_ms, fingerprint, is_static) {
-            let model = GameViewModel::new(*session.snapshot(), Some(adapter_view));
+            let model = GameViewModel::new(*session.snapshot(), Some(adapter_view)).with_hint(hint);
             view.render_model_into(&model, Viewport::new(w, h), &mut fb);
+            if let Some((message, _)) = notice.as_ref() {
+                fb.put_str(0, h.saturating_sub(1), message, overlay_label_style());
+            }
             term.draw_swap(&mut fb)?;
         }
 
@@ -326,6 +468,15 @@ fn run(term: &mut TerminalRenderer) -> Result<()> {
                         if command == Some(InputCommand::Quit) {
                             return Ok(());
                         }
+                        if command == Some(InputCommand::ToggleHint) {
+                            coach.toggle();
+                            continue;
+                        }
+                        if command == Some(InputCommand::SaveReplay) {
+                            let message = save_replay(&recording);
+                            notice = Some((message, now_ms + NOTICE_MS));
+                            continue;
+                        }
 
                         // While paused/game over, input repeats are released and only Pause/Restart
                         // are accepted.
@@ -416,20 +567,51 @@ fn run(term: &mut TerminalRenderer) -> Result<()> {
                 let _ = local_actions.try_push(action);
             }
 
-            step_session(
+            let mut record = |session: &SessionRuntime, input: &StepInput, transition: &_| {
+                if let Err(error) = recording.record(session, input, transition) {
+                    let message = format!("replay recording stopped: {error}");
+                    notice = Some((message, now_ms + NOTICE_MS));
+                }
+            };
+            step_session_tapped(
                 &mut adapter,
+                &mut record,
                 &mut session,
                 &mut observations,
                 &local_actions,
                 adapter_view.streaming_count > 0,
             );
         }
+        if let Some(error) = adapter.as_mut().and_then(Adapter::take_recording_error) {
+            notice = Some((error, now_ms + NOTICE_MS));
+        }
+    }
+}
+
+/// Save the game so far and describe the outcome for the notice line.
+fn save_replay(recording: &PlayRecording) -> String {
+    let path = recording.default_save_path();
+    match recording.save(&path) {
+        Ok(()) => format!("saved {} steps to {}", recording.steps(), path.display()),
+        Err(error) => format!("failed to save {}: {error}", path.display()),
+    }
+}
+
+/// Text drawn over the game view: observer status, recording notices, and
+/// replay titles and annotations.
+fn overlay_label_style() -> CellStyle {
+    CellStyle {
+        fg: Rgb::new(220, 220, 220),
+        bg: Rgb::new(0, 0, 0),
+        bold: true,
+        dim: false,
     }
 }
 
 fn render_fingerprint(
     game_state: &GameState,
     adapter: &AdapterStatusView,
+    hint: Option<ActiveSnapshot>,
     viewport: Viewport,
 ) -> u64 {
     // FNV-1a 64-bit over render-relevant fields only.
@@ -464,6 +646,15 @@ fn render_fingerprint(
         push_u64(piece as u64);
     }
 
+    if let Some(hint) = hint {
+        push_u64(hint.kind as u64);
+        push_u64(hint.rotation as u64);
+        push_u64(hint.x as u8 as u64);
+        push_u64(hint.y as u8 as u64);
+    } else {
+        push_u64(u64::MAX);
+    }
+
     push_u64(adapter.enabled as u64);
     push_u64(adapter.client_count as u64);
     push_u64(adapter.streaming_count as u64);
@@ -526,11 +717,11 @@ mod tests {
         game.start();
         let adapter = adapter_view();
         let viewport = Viewport::new(80, 24);
-        let before = render_fingerprint(&game, &adapter, viewport);
+        let before = render_fingerprint(&game, &adapter, None, viewport);
 
         game.tick(TICK_MS, false);
 
-        assert_ne!(render_fingerprint(&game, &adapter, viewport), before);
+        assert_ne!(render_fingerprint(&game, &adapter, None, viewport), before);
     }
 
     #[test]
@@ -538,18 +729,32 @@ mod tests {
         let mut game = GameState::new(1);
         game.start();
         let adapter = adapter_view();
-        let baseline = render_fingerprint(&game, &adapter, Viewport::new(80, 24));
+        let baseline = render_fingerprint(&game, &adapter, None, Viewport::new(80, 24));
 
         let mut connected = adapter;
         connected.client_count = 1;
 
         assert_ne!(
-            render_fingerprint(&game, &connected, Viewport::new(80, 24)),
+            render_fingerprint(&game, &connected, None, Viewport::new(80, 24)),
             baseline
         );
         assert_ne!(
-            render_fingerprint(&game, &adapter, Viewport::new(100, 30)),
+            render_fingerprint(&game, &adapter, None, Viewport::new(100, 30)),
             baseline
         );
     }
+
+    #[test]
+    fn render_fingerprint_includes_hint_overlay() {
+        let mut game = GameState::new(1);
+        game.start();
+        let adapter = adapter_view();
+        let viewport = Viewport::new(80, 24);
+        let hint = ActiveSnapshot::from(game.active().unwrap());
+
+        assert_ne!(
+            render_fingerprint(&game, &adapter, Some(hint), viewport),
+            render_fingerprint(&game, &adapter, None, viewport)
+        );
+    }
 }
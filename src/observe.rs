@@ -8,7 +8,7 @@ use anyhow::{Result, anyhow};
 
 use tetris_adapter_protocol::protocol::{
     CommandMode, ObservationMessage, PROTOCOL_VERSION, PieceKindLower, RequestedRole,
-    RotationLower, create_hello,
+    ResumeRequest, RotationLower, create_hello,
 };
 use tetris_core::core::snapshot::{ActiveSnapshot, GameSnapshot, TimersSnapshot};
 use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH, PieceKind, Rotation};
@@ -17,18 +17,41 @@ use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH, PieceKind, Rotation};
 pub struct ObserveConfig {
     pub host: String,
     pub port: u16,
+    /// Hello token for adapters that require authentication.
+    pub token: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 // Keeping observations inline avoids an extra heap allocation on every parsed frame.
 #[allow(clippy::large_enum_variant)]
 pub enum ObserveEvent {
-    Welcome,
+    Welcome { resume_token: Option<String> },
     Observation(ObservationMessage),
     Error(String),
     Closed,
 }
 
+/// Session an observer resumes after a reconnect, so the adapter keeps its
+/// client id and replays the observations it missed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObserveResume {
+    token: Option<String>,
+    last_step: Option<u64>,
+}
+
+impl ObserveResume {
+    pub fn observed(&mut self, obs: &ObservationMessage) {
+        self.last_step = Some(obs.logical_step);
+    }
+
+    fn request(&self) -> Option<ResumeRequest> {
+        self.token.clone().map(|token| ResumeRequest {
+            token,
+            since_step: self.last_step,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ObserveReconnectPolicy {
     pub max_attempts: u32,
@@ -53,6 +76,7 @@ pub fn parse_observe_args(args: &[String]) -> Result<Option<ObserveConfig>> {
 
     let mut host = String::from("127.0.0.1");
     let mut port: u16 = 7777;
+    let mut token = None;
     let mut i = 1usize;
     while i < args.len() {
         match args[i].as_str() {
@@ -72,6 +96,13 @@ pub fn parse_observe_args(args: &[String]) -> Result<Option<ObserveConfig>> {
                     .parse::<u16>()
                     .map_err(|_| anyhow!("observe: invalid --port value: {}", v))?;
             }
+            "--token" => {
+                i += 1;
+                let v = args
+                    .get(i)
+                    .ok_or_else(|| anyhow!("observe: missing value for --token"))?;
+                token = Some(v.clone());
+            }
             other => {
                 return Err(anyhow!("observe: unknown argument: {}", other));
             }
@@ -79,10 +110,13 @@ pub fn parse_observe_args(args: &[String]) -> Result<Option<ObserveConfig>> {
         i += 1;
     }
 
-    Ok(Some(ObserveConfig { host, port }))
+    Ok(Some(ObserveConfig { host, port, token }))
 }
 
-pub fn connect_observer(config: &ObserveConfig) -> Result<mpsc::Receiver<ObserveEvent>> {
+pub fn connect_observer(
+    config: &ObserveConfig,
+    resume: Option<ResumeRequest>,
+) -> Result<mpsc::Receiver<ObserveEvent>> {
     let mut stream = TcpStream::connect((config.host.as_str(), config.port)).map_err(|e| {
         anyhow!(
             "observe: connect {}:{} failed: {}",
@@ -99,6 +133,8 @@ pub fn connect_observer(config: &ObserveConfig) -> Result<mpsc::Receiver<Observe
     hello.requested.stream_observations = true;
     hello.requested.command_mode = CommandMode::Action;
     hello.requested.role = Some(RequestedRole::Observer);
+    hello.token = config.token.clone();
+    hello.resume = resume;
     let line = serde_json::to_string(&hello)?;
     stream.write_all(line.as_bytes())?;
     stream.write_all(b"\n")?;
@@ -129,16 +165,19 @@ pub fn connect_observer(config: &ObserveConfig) -> Result<mpsc::Receiver<Observe
     Ok(rx)
 }
 
+/// Connect, resuming the session in `resume` when it holds one, and keep
+/// `resume` up to date with the new welcome.
 pub fn connect_observer_with_retry(
     config: &ObserveConfig,
     policy: ObserveReconnectPolicy,
+    resume: &mut ObserveResume,
 ) -> Result<(mpsc::Receiver<ObserveEvent>, Option<ObservationMessage>)> {
     let max_attempts = policy.max_attempts.max(1);
     let mut last_error = anyhow!("observe: reconnect attempts exhausted");
 
     for attempt in 1..=max_attempts {
-        match connect_observer(config) {
-            Ok(rx) => match wait_for_welcome(&rx, policy.welcome_timeout) {
+        match connect_observer(config, resume.request()) {
+            Ok(rx) => match wait_for_welcome(&rx, policy.welcome_timeout, resume) {
                 Ok(first_obs) => return Ok((rx, first_obs)),
                 Err(e) => {
                     last_error = e;
@@ -268,6 +307,7 @@ fn rotation_from_lower(value: RotationLower) -> Rotation {
 pub fn wait_for_welcome(
     rx: &mpsc::Receiver<ObserveEvent>,
     timeout: Duration,
+    resume: &mut ObserveResume,
 ) -> Result<Option<ObservationMessage>> {
     let deadline = std::time::Instant::now() + timeout;
     let mut got_welcome = false;
@@ -275,13 +315,15 @@ pub fn wait_for_welcome(
 
     while std::time::Instant::now() < deadline {
         match rx.recv_timeout(Duration::from_millis(50)) {
-            Ok(ObserveEvent::Welcome) => {
+            Ok(ObserveEvent::Welcome { resume_token }) => {
                 got_welcome = true;
+                resume.token = resume_token;
                 if first_obs.is_some() {
                     break;
                 }
             }
             Ok(ObserveEvent::Observation(obs)) => {
+                resume.observed(&obs);
                 first_obs = Some(obs);
                 if got_welcome {
                     break;
@@ -309,7 +351,12 @@ fn parse_server_line(line: &str) -> Option<ObserveEvent> {
     };
     let msg_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
     match msg_type {
-        "welcome" => Some(ObserveEvent::Welcome),
+        "welcome" => Some(ObserveEvent::Welcome {
+            resume_token: value
+                .get("resume_token")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        }),
         "observation" => match serde_json::from_str::<ObservationMessage>(line) {
             Ok(obs) => Some(ObserveEvent::Observation(obs)),
             Err(e) => Some(ObserveEvent::Error(format!(
@@ -353,7 +400,8 @@ mod tests {
             cfg,
             ObserveConfig {
                 host: "0.0.0.0".to_string(),
-                port: 9001
+                port: 9001,
+                token: None,
             }
         );
     }
@@ -366,7 +414,8 @@ mod tests {
             cfg,
             ObserveConfig {
                 host: "127.0.0.1".to_string(),
-                port: 7777
+                port: 7777,
+                token: None,
             }
         );
     }
@@ -452,11 +501,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn welcome_resume_token_feeds_the_next_hello() {
+        let (tx, rx) = mpsc::channel();
+        let line = r#"{"type":"welcome","seq":1,"resume_token":"00ff"}"#;
+        tx.send(parse_server_line(line).unwrap()).unwrap();
+        let mut resume = ObserveResume::default();
+        assert_eq!(resume.request(), None);
+        assert!(
+            wait_for_welcome(&rx, Duration::from_millis(100), &mut resume)
+                .unwrap()
+                .is_none()
+        );
+        resume.last_step = Some(42);
+        assert_eq!(
+            resume.request(),
+            Some(ResumeRequest {
+                token: "00ff".to_string(),
+                since_step: Some(42),
+            })
+        );
+    }
+
     #[test]
     fn observe_status_lines_include_mode_target_and_episode_fields() {
         let cfg = ObserveConfig {
             host: "127.0.0.1".to_string(),
             port: 7780,
+            token: None,
         };
         let obs = ObservationMessage {
             msg_type: ObservationType::Observation,
@@ -513,13 +585,15 @@ mod tests {
         let cfg = ObserveConfig {
             host: "127.0.0.1".to_string(),
             port: 1,
+            token: None,
         };
         let policy = ObserveReconnectPolicy {
             max_attempts: 2,
             retry_delay: Duration::from_millis(1),
             welcome_timeout: Duration::from_millis(50),
         };
-        let err = connect_observer_with_retry(&cfg, policy).unwrap_err();
+        let err =
+            connect_observer_with_retry(&cfg, policy, &mut ObserveResume::default()).unwrap_err();
         assert!(err.to_string().contains("after 2 attempts"));
     }
 }
@@ -0,0 +1,132 @@
+//! Replay recording of interactive games.
+//!
+//! The interactive loop shows every step it applies to a [`PlayRecording`],
+//! local keys and adapter commands alike. The whole game stays in memory as a
+//! TTR2 tape for the save key; with `--record`, or when branching from another
+//! tape, each step is also appended to a file as soon as it is applied. Saved
+//! tapes also carry the final score in their metadata.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tetris_session::engine::replay::{
+    META_DATE, META_FINAL_SCORE, META_MODE, META_PLAYER, ReplayMetadata, ReplayRecorder,
+    ReplayTape, format_utc,
+};
+use tetris_session::engine::session::{SessionRuntime, StepInput, Transition};
+
+#[derive(Debug)]
+pub struct PlayRecording {
+    seed: u32,
+    tape: ReplayRecorder<Vec<u8>>,
+    stream: Option<File>,
+    /// Tape bytes already appended to `stream`.
+    streamed: usize,
+}
+
+impl PlayRecording {
+    /// Record a session created with `seed` that has not stepped yet.
+    pub fn new(seed: u32, metadata: &ReplayMetadata) -> Self {
+        let tape = ReplayRecorder::with_metadata(Vec::new(), seed, metadata)
+            .expect("writing to a Vec cannot fail");
+        Self {
+            seed,
+            tape,
+            stream: None,
+            streamed: 0,
+        }
+    }
+
+    /// [`PlayRecording::new`] that also streams the tape to `path`.
+    pub fn streaming_to(seed: u32, metadata: &ReplayMetadata, path: &Path) -> io::Result<Self> {
+        let mut recording = Self::new(seed, metadata);
+        let mut file = File::create(path)?;
+        file.write_all(recording.tape.get_ref())?;
+        file.flush()?;
+        recording.streamed = recording.tape.get_ref().len();
+        recording.stream = Some(file);
+        Ok(recording)
+    }
+
+    /// Continue `prefix` in a new tape at `path`, for a session resumed with
+    /// [`ReplayTape::replay_to`] at the prefix's end.
+    pub fn branching(prefix: &ReplayTape, path: &Path) -> io::Result<Self> {
+        let tape = ReplayRecorder::continuing(Vec::new(), prefix)?;
+        let mut file = File::create(path)?;
+        file.write_all(tape.get_ref())?;
+        file.flush()?;
+        Ok(Self {
+            seed: prefix.seed(),
+            streamed: tape.get_ref().len(),
+            tape,
+            stream: Some(file),
+        })
+    }
+
+    /// Append the step `session` just applied from `input`.
+    ///
+    /// A stream write error stops streaming, leaving the file a verifiable
+    /// prefix, and is returned once; the in-memory tape keeps recording.
+    pub fn record(
+        &mut self,
+        session: &SessionRuntime,
+        input: &StepInput,
+        transition: &Transition,
+    ) -> io::Result<()> {
+        self.tape
+            .record(session, input, transition)
+            .expect("writing to a Vec cannot fail");
+        let Some(file) = self.stream.as_mut() else {
+            return Ok(());
+        };
+        let tape = self.tape.get_ref();
+        if let Err(error) = file
+            .write_all(&tape[self.streamed..])
+            .and_then(|()| file.flush())
+        {
+            self.stream = None;
+            return Err(error);
+        }
+        self.streamed = tape.len();
+        Ok(())
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Steps recorded so far.
+    pub fn steps(&self) -> u64 {
+        self.tape.steps()
+    }
+
+    /// The encoded tape of the game so far.
+    pub fn tape_bytes(&self) -> &[u8] {
+        self.tape.get_ref()
+    }
+
+    /// Write the game so far to `path` as a complete tape, with its final
+    /// score.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tape = ReplayTape::decode(self.tape_bytes()).expect("recorded tapes decode");
+        let score = tape.final_snapshot().score;
+        tape.metadata_mut().set(META_FINAL_SCORE, score.to_string());
+        std::fs::write(path, tape.encode())
+    }
+
+    /// File name the save key uses, unique per seed and step.
+    pub fn default_save_path(&self) -> PathBuf {
+        PathBuf::from(format!("tui-tetris-{}-{}.ttr", self.seed, self.steps()))
+    }
+}
+
+/// Metadata for an interactive game started now by `player`.
+pub fn interactive_metadata(player: &str) -> ReplayMetadata {
+    let mut metadata = ReplayMetadata::default();
+    metadata.set(META_PLAYER, player);
+    metadata.set(META_MODE, "interactive");
+    metadata.set(META_DATE, format_utc(SystemTime::now()));
+    metadata
+}
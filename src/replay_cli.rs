@@ -1,10 +1,27 @@
-//! Replay recording, verification, and inspection command surface.
+//! Replay recording, verification, inspection, annotation, conversion,
+//! diffing, export, editing, branching, migration, and playback command
+//! surface.
+//!
+//! Commands that write a new tape from existing ones keep the first input's
+//! format; branches are always streamed as TTR2.
 
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use tetris_session::engine::replay::{ReplayTape, replay_and_verify};
+use tetris_core::types::CoreLastEvent;
+use tetris_session::engine::planner::PlannerConfig;
+use tetris_session::engine::replay::{
+    CURRENT_RULESET, DEFAULT_KEYFRAME_INTERVAL, META_BRANCH_OF, META_BRANCH_STEP, META_DATE,
+    META_FINAL_SCORE, META_MODE, META_PLAYER, META_PLAYER_VERSION, ReplayRecorder, ReplayTape,
+    binary_keyframe_interval, format_utc, migrate, replay_and_verify,
+};
 use tetris_session::engine::session::StepInput;
 
+use crate::bot_cli::{BotConfig, drive_bot};
+use crate::replay_diff::diff_tapes;
+use crate::replay_export::{ExportFormat, ExportOptions, ExportRows, export_tape};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ReplayCommand {
     Record {
@@ -18,16 +35,104 @@ pub enum ReplayCommand {
     Inspect {
         path: PathBuf,
     },
+    /// Interactive playback in the terminal, run by the binary.
+    Play {
+        path: PathBuf,
+    },
+    /// Edit a tape's metadata and annotations in place, keeping its format.
+    Annotate {
+        path: PathBuf,
+        /// `(key, value)` metadata entries to set.
+        set: Vec<(String, String)>,
+        tags: Vec<String>,
+        /// `(step, text)` annotations to add.
+        notes: Vec<(u64, String)>,
+    },
+    /// Re-encode a tape; `keyframe_interval` only applies to TTR3.
+    Convert {
+        path: PathBuf,
+        output: PathBuf,
+        format: ReplayFormat,
+        keyframe_interval: u64,
+    },
+    /// Report where two tapes of the same seed first disagree.
+    Diff {
+        path: PathBuf,
+        other: PathBuf,
+    },
+    /// Re-simulate a tape into JSON Lines or CSV rows, printed when `output`
+    /// is `None`.
+    Export {
+        path: PathBuf,
+        options: ExportOptions,
+        output: Option<PathBuf>,
+    },
+    /// Keep only the first `steps` steps.
+    Cut {
+        path: PathBuf,
+        output: PathBuf,
+        steps: u64,
+    },
+    /// `path`'s first `at` steps followed by `other`'s inputs from `at` on.
+    Splice {
+        path: PathBuf,
+        other: PathBuf,
+        output: PathBuf,
+        at: u64,
+    },
+    /// `path` followed by all of `other`'s inputs.
+    Concat {
+        path: PathBuf,
+        other: PathBuf,
+        output: PathBuf,
+    },
+    /// Resume play after `path`'s first `at` steps into a new tape at
+    /// `output`: interactively, run by the binary, when `bot` is `None`.
+    Branch {
+        path: PathBuf,
+        output: PathBuf,
+        at: u64,
+        bot: Option<BotConfig>,
+    },
+    /// Re-record a tape under the current ruleset and report what changed.
+    Migrate {
+        path: PathBuf,
+        output: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFormat {
+    /// Tab-separated text, one line per step.
+    Ttr2,
+    /// Binary with idle-step runs, keyframes, and an index.
+    Ttr3,
+}
+
+impl ReplayFormat {
+    pub fn of(bytes: &[u8]) -> Self {
+        if ReplayTape::is_binary(bytes) {
+            Self::Ttr3
+        } else {
+            Self::Ttr2
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ttr2 => "ttr2",
+            Self::Ttr3 => "ttr3",
+        }
+    }
 }
 
 pub fn parse_replay_args(args: &[String]) -> Result<Option<ReplayCommand>, String> {
     if args.first().map(String::as_str) != Some("replay") {
         return Ok(None);
     }
-    let operation = args
-        .get(1)
-        .map(String::as_str)
-        .ok_or("usage: tui-tetris replay <record|verify|inspect> <path> [--seed N] [--steps N]")?;
+    let operation = args.get(1).map(String::as_str).ok_or(
+        "usage: tui-tetris replay <record|verify|inspect|annotate|play|convert|diff|export|cut|splice|concat|branch|migrate> <path> [--seed N] [--steps N]",
+    )?;
     let path = args
         .get(2)
         .map(PathBuf::from)
@@ -48,9 +153,191 @@ pub fn parse_replay_args(args: &[String]) -> Result<Option<ReplayCommand>, Strin
             }
             Ok(Some(ReplayCommand::Record { path, seed, steps }))
         }
+        "annotate" => {
+            let mut set = Vec::new();
+            let mut tags = Vec::new();
+            let mut notes = Vec::new();
+            let mut index = 3;
+            while index < args.len() {
+                let value = args.get(index + 1).ok_or("missing replay option value")?;
+                match args[index].as_str() {
+                    "--set" => {
+                        let (key, value) = value
+                            .split_once('=')
+                            .filter(|(key, _)| !key.is_empty())
+                            .ok_or("--set expects key=value")?;
+                        set.push((key.to_string(), value.to_string()));
+                    }
+                    "--tag" => tags.push(value.clone()),
+                    "--note" => {
+                        let (step, text) =
+                            value.split_once('=').ok_or("--note expects step=text")?;
+                        let step = step.parse().map_err(|_| "invalid --note step")?;
+                        notes.push((step, text.to_string()));
+                    }
+                    option => return Err(format!("unknown replay option: {option}")),
+                }
+                index += 2;
+            }
+            Ok(Some(ReplayCommand::Annotate {
+                path,
+                set,
+                tags,
+                notes,
+            }))
+        }
+        "convert" => {
+            let output = args
+                .get(3)
+                .map(PathBuf::from)
+                .ok_or("missing replay output path")?;
+            let mut format = ReplayFormat::Ttr3;
+            let mut keyframe_interval = DEFAULT_KEYFRAME_INTERVAL;
+            let mut index = 4;
+            while index < args.len() {
+                let value = args.get(index + 1).ok_or("missing replay option value")?;
+                match args[index].as_str() {
+                    "--format" => {
+                        format = match value.as_str() {
+                            "ttr2" => ReplayFormat::Ttr2,
+                            "ttr3" => ReplayFormat::Ttr3,
+                            _ => return Err("invalid --format".into()),
+                        }
+                    }
+                    "--keyframe-interval" => {
+                        keyframe_interval =
+                            value.parse().map_err(|_| "invalid --keyframe-interval")?
+                    }
+                    option => return Err(format!("unknown replay option: {option}")),
+                }
+                index += 2;
+            }
+            Ok(Some(ReplayCommand::Convert {
+                path,
+                output,
+                format,
+                keyframe_interval,
+            }))
+        }
+        "export" => {
+            let mut options = ExportOptions::default();
+            let mut output = None;
+            let mut index = 3;
+            while index < args.len() {
+                if args[index] == "--board" {
+                    options.board = true;
+                    index += 1;
+                    continue;
+                }
+                let value = args.get(index + 1).ok_or("missing replay option value")?;
+                match args[index].as_str() {
+                    "--format" => {
+                        options.format = match value.as_str() {
+                            "jsonl" => ExportFormat::Jsonl,
+                            "csv" => ExportFormat::Csv,
+                            _ => return Err("invalid --format".into()),
+                        }
+                    }
+                    "--rows" => {
+                        options.rows = match value.as_str() {
+                            "step" => ExportRows::Step,
+                            "piece" => ExportRows::Piece,
+                            _ => return Err("invalid --rows".into()),
+                        }
+                    }
+                    "--output" => output = Some(PathBuf::from(value)),
+                    option => return Err(format!("unknown replay option: {option}")),
+                }
+                index += 2;
+            }
+            Ok(Some(ReplayCommand::Export {
+                path,
+                options,
+                output,
+            }))
+        }
+        "cut" => {
+            let output = args
+                .get(3)
+                .map(PathBuf::from)
+                .ok_or("missing replay output path")?;
+            let steps = step_option(&args[4..], "--steps")?;
+            Ok(Some(ReplayCommand::Cut {
+                path,
+                output,
+                steps,
+            }))
+        }
+        "splice" => {
+            let (Some(other), Some(output)) = (args.get(3), args.get(4)) else {
+                return Err(
+                    "usage: tui-tetris replay splice <a.ttr> <b.ttr> <out.ttr> --at N".into(),
+                );
+            };
+            let at = step_option(&args[5..], "--at")?;
+            Ok(Some(ReplayCommand::Splice {
+                path,
+                other: PathBuf::from(other),
+                output: PathBuf::from(output),
+                at,
+            }))
+        }
+        "concat" if args.len() == 5 => Ok(Some(ReplayCommand::Concat {
+            path,
+            other: PathBuf::from(&args[3]),
+            output: PathBuf::from(&args[4]),
+        })),
+        "concat" => Err("usage: tui-tetris replay concat <a.ttr> <b.ttr> <out.ttr>".into()),
+        "branch" => {
+            let output = args
+                .get(3)
+                .map(PathBuf::from)
+                .ok_or("missing replay output path")?;
+            let mut at = None;
+            let mut bot = false;
+            let mut pieces = 100;
+            let mut index = 4;
+            while index < args.len() {
+                if args[index] == "--bot" {
+                    bot = true;
+                    index += 1;
+                    continue;
+                }
+                let value = args.get(index + 1).ok_or("missing replay option value")?;
+                match args[index].as_str() {
+                    "--at" => at = Some(value.parse().map_err(|_| "invalid --at")?),
+                    "--pieces" => pieces = value.parse().map_err(|_| "invalid --pieces")?,
+                    option => return Err(format!("unknown replay option: {option}")),
+                }
+                index += 2;
+            }
+            // The branch keeps the source tape's seed; `seed` is unused.
+            let bot = bot.then_some(BotConfig {
+                seed: 0,
+                pieces,
+                planner: PlannerConfig::default(),
+            });
+            Ok(Some(ReplayCommand::Branch {
+                path,
+                output,
+                at: at.ok_or("missing --at")?,
+                bot,
+            }))
+        }
+        "migrate" if args.len() == 4 => Ok(Some(ReplayCommand::Migrate {
+            path,
+            output: PathBuf::from(&args[3]),
+        })),
+        "migrate" => Err("usage: tui-tetris replay migrate <in.ttr> <out.ttr>".into()),
+        "diff" if args.len() == 4 => Ok(Some(ReplayCommand::Diff {
+            path,
+            other: PathBuf::from(&args[3]),
+        })),
+        "diff" => Err("usage: tui-tetris replay diff <a.ttr> <b.ttr>".into()),
         "verify" if args.len() == 3 => Ok(Some(ReplayCommand::Verify { path })),
         "inspect" if args.len() == 3 => Ok(Some(ReplayCommand::Inspect { path })),
-        "verify" | "inspect" => Err("unexpected replay arguments".into()),
+        "play" if args.len() == 3 => Ok(Some(ReplayCommand::Play { path })),
+        "verify" | "inspect" | "play" => Err("unexpected replay arguments".into()),
         _ => Err(format!("unknown replay operation: {operation}")),
     }
 }
@@ -58,7 +345,8 @@ pub fn parse_replay_args(args: &[String]) -> Result<Option<ReplayCommand>, Strin
 pub fn run_replay_command(command: ReplayCommand) -> Result<String, String> {
     match command {
         ReplayCommand::Record { path, seed, steps } => {
-            let tape = ReplayTape::record(seed, (0..steps).map(|_| StepInput::default()));
+            let mut tape = ReplayTape::record(seed, (0..steps).map(|_| StepInput::default()));
+            tape.metadata_mut().set(META_MODE, "headless");
             std::fs::write(&path, tape.encode()).map_err(|error| error.to_string())?;
             Ok(format!("recorded {steps} steps to {}", path.display()))
         }
@@ -77,20 +365,285 @@ pub fn run_replay_command(command: ReplayCommand) -> Result<String, String> {
             ))
         }
         ReplayCommand::Inspect { path } => {
-            let tape = read_tape(&path)?;
-            Ok(format!(
-                "path: {}\nruleset: {}\nseed: {}\nsteps: {}\nfinal_state_hash: {}",
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let tape = ReplayTape::decode(&bytes)?;
+            let mut report = format!(
+                "path: {}\nformat: {}\nbytes: {}\nruleset: {}\nseed: {}\nsteps: {}\nfinal_state_hash: {}\nfinal_score: {}",
                 path.display(),
+                ReplayFormat::of(&bytes).as_str(),
+                bytes.len(),
                 tape.ruleset_version(),
                 tape.seed(),
                 tape.records().len(),
-                tape.records().last().map_or(0, |record| record.state_hash)
+                tape.records().last().map_or(0, |record| record.state_hash),
+                tape.final_snapshot().score
+            );
+            if !tape.metadata().is_empty() {
+                report.push_str("\nmetadata:");
+                for (key, value) in tape.metadata().iter() {
+                    report.push_str(&format!("\n  {key}: {value}"));
+                }
+            }
+            if !tape.annotations().is_empty() {
+                report.push_str("\nannotations:");
+                for note in tape.annotations() {
+                    report.push_str(&format!("\n  {}: {}", note.step, note.text));
+                }
+            }
+            Ok(report)
+        }
+        ReplayCommand::Annotate {
+            path,
+            set,
+            tags,
+            notes,
+        } => {
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let mut tape = ReplayTape::decode(&bytes)?;
+            for (key, value) in set {
+                tape.metadata_mut().set(key, value);
+            }
+            for tag in &tags {
+                tape.metadata_mut().add_tag(tag);
+            }
+            let added = notes.len();
+            for (step, text) in notes {
+                tape.annotate(step, text);
+            }
+            write_like(&path, &tape, &bytes)?;
+            Ok(format!(
+                "annotated {}: {} metadata entries, {} annotations ({added} added)",
+                path.display(),
+                tape.metadata().iter().count(),
+                tape.annotations().len()
+            ))
+        }
+        ReplayCommand::Convert {
+            path,
+            output,
+            format,
+            keyframe_interval,
+        } => {
+            let tape = read_tape(&path)?;
+            let bytes = match format {
+                ReplayFormat::Ttr2 => tape.encode(),
+                ReplayFormat::Ttr3 => tape.encode_binary(keyframe_interval),
+            };
+            std::fs::write(&output, &bytes).map_err(|error| error.to_string())?;
+            Ok(format!(
+                "converted {} steps to {} ({} bytes) at {}",
+                tape.records().len(),
+                format.as_str(),
+                bytes.len(),
+                output.display()
+            ))
+        }
+        ReplayCommand::Diff { path, other } => diff_tapes(
+            &read_tape(&path)?,
+            &path.display().to_string(),
+            &read_tape(&other)?,
+            &other.display().to_string(),
+        ),
+        ReplayCommand::Export {
+            path,
+            options,
+            output,
+        } => {
+            let tape = read_tape(&path)?;
+            let Some(output) = output else {
+                let mut rows = Vec::new();
+                export_tape(&tape, options, &mut rows).map_err(|error| error.to_string())?;
+                let mut rows = String::from_utf8(rows).map_err(|error| error.to_string())?;
+                rows.pop();
+                return Ok(rows);
+            };
+            let mut file = std::io::BufWriter::new(
+                std::fs::File::create(&output).map_err(|error| error.to_string())?,
+            );
+            let rows = export_tape(&tape, options, &mut file)
+                .and_then(|rows| file.flush().map(|()| rows))
+                .map_err(|error| error.to_string())?;
+            Ok(format!("exported {rows} rows to {}", output.display()))
+        }
+        ReplayCommand::Cut {
+            path,
+            output,
+            steps,
+        } => {
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let tape = ReplayTape::decode(&bytes)?.truncated(steps);
+            write_like(&output, &tape, &bytes)?;
+            Ok(format!(
+                "cut {} to {} steps at {}",
+                path.display(),
+                tape.records().len(),
+                output.display()
             ))
         }
+        ReplayCommand::Splice {
+            path,
+            other,
+            output,
+            at,
+        } => {
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let tape = ReplayTape::decode(&bytes)?.spliced(at, &read_tape(&other)?)?;
+            write_like(&output, &tape, &bytes)?;
+            Ok(format!(
+                "spliced {} steps ({} from {}) to {}",
+                tape.records().len(),
+                tape.records().len().saturating_sub(at as usize),
+                other.display(),
+                output.display()
+            ))
+        }
+        ReplayCommand::Concat {
+            path,
+            other,
+            output,
+        } => {
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let tape = ReplayTape::decode(&bytes)?.concatenated(&read_tape(&other)?)?;
+            write_like(&output, &tape, &bytes)?;
+            Ok(format!(
+                "concatenated {} steps to {}",
+                tape.records().len(),
+                output.display()
+            ))
+        }
+        ReplayCommand::Branch {
+            path,
+            output,
+            at,
+            bot,
+        } => {
+            let Some(bot) = bot else {
+                return Err("interactive replay branch needs a terminal".into());
+            };
+            let tape = read_tape(&path)?;
+            let mut prefix = branch_prefix(&tape, &path, at);
+            prefix.metadata_mut().set(META_PLAYER, "planner");
+            prefix.metadata_mut().set(META_MODE, "bot");
+            let mut recorder = ReplayRecorder::continuing(Vec::new(), &prefix)
+                .map_err(|error| error.to_string())?;
+            let mut session = tape.replay_to(at);
+            let run = drive_bot(&mut session, bot, |session, input, transition| {
+                recorder
+                    .record(session, input, transition)
+                    .expect("writing to a Vec cannot fail");
+            });
+            std::fs::write(&output, recorder.get_ref()).map_err(|error| error.to_string())?;
+            Ok(format!(
+                "branched {} at step {}: bot placed {} pieces, score {} (was {}), {} steps to {}",
+                path.display(),
+                prefix.records().len(),
+                run.pieces,
+                run.score,
+                tape.final_snapshot().score,
+                run.logical_steps,
+                output.display()
+            ))
+        }
+        ReplayCommand::Migrate { path, output } => {
+            let bytes = std::fs::read(&path).map_err(|error| error.to_string())?;
+            let tape = ReplayTape::decode(&bytes)?;
+            let migration = migrate(&tape, &CURRENT_RULESET);
+            write_like(&output, &migration.tape, &bytes)?;
+            let mut report = format!(
+                "migrated {} steps from {} to {} at {}",
+                tape.records().len(),
+                tape.ruleset_version(),
+                CURRENT_RULESET.version,
+                output.display()
+            );
+            let Some(step) = migration.first_divergence else {
+                report.push_str("\noutcomes unchanged");
+                return Ok(report);
+            };
+            let (before, after) = (tape.final_snapshot(), migration.tape.final_snapshot());
+            report.push_str(&format!(
+                "\nstate first differs at step {step}; {} lock outcomes changed\nscore {} -> {}, lines {} -> {}, game over {} -> {}",
+                migration.changes.len(),
+                before.score,
+                after.score,
+                before.lines,
+                after.lines,
+                before.game_over,
+                after.game_over
+            ));
+            for change in migration.changes.iter().take(MIGRATE_REPORT_CHANGES) {
+                report.push_str(&format!(
+                    "\n  step {}: {} -> {}",
+                    change.step,
+                    describe_lock(change.before),
+                    describe_lock(change.after)
+                ));
+            }
+            if let Some(more) = migration.changes.len().checked_sub(MIGRATE_REPORT_CHANGES)
+                && more > 0
+            {
+                report.push_str(&format!("\n  ... and {more} more"));
+            }
+            Ok(report)
+        }
+        ReplayCommand::Play { .. } => Err("replay play needs an interactive terminal".into()),
+    }
+}
+
+/// Changed lock outcomes listed by `replay migrate`.
+const MIGRATE_REPORT_CHANGES: usize = 20;
+
+/// A lock event as `lock, 2 lines (+300)`, or `no lock`.
+fn describe_lock(event: Option<CoreLastEvent>) -> String {
+    match event {
+        None => "no lock".into(),
+        Some(event) => format!(
+            "lock, {} lines (+{}){}",
+            event.lines_cleared,
+            event.line_clear_score,
+            event
+                .tspin
+                .and_then(|tspin| tspin.as_str())
+                .map_or(String::new(), |tspin| format!(", tspin {tspin}"))
+        ),
     }
 }
 
-fn read_tape(path: &PathBuf) -> Result<ReplayTape, String> {
+/// The first `at` steps of `tape`, read from `path`, as the start of a
+/// branch: who played and how it ended no longer describe the result.
+pub fn branch_prefix(tape: &ReplayTape, path: &Path, at: u64) -> ReplayTape {
+    let mut prefix = tape.truncated(at);
+    let steps = prefix.records().len();
+    let metadata = prefix.metadata_mut();
+    for key in [META_PLAYER, META_PLAYER_VERSION, META_FINAL_SCORE] {
+        metadata.remove(key);
+    }
+    metadata.set(META_DATE, format_utc(SystemTime::now()));
+    metadata.set(META_BRANCH_OF, path.display().to_string());
+    metadata.set(META_BRANCH_STEP, steps.to_string());
+    prefix
+}
+
+/// The value of the only option in `args`, which must be `option`.
+fn step_option(args: &[String], option: &str) -> Result<u64, String> {
+    match args {
+        [name, value] if name == option => value.parse().map_err(|_| format!("invalid {option}")),
+        [] => Err(format!("missing {option}")),
+        [name, ..] if name != option => Err(format!("unknown replay option: {name}")),
+        _ => Err("unexpected replay arguments".into()),
+    }
+}
+
+/// Write `tape` to `path` in the format of `source`, an existing tape.
+fn write_like(path: &Path, tape: &ReplayTape, source: &[u8]) -> Result<(), String> {
+    let bytes = match ReplayFormat::of(source) {
+        ReplayFormat::Ttr2 => tape.encode(),
+        ReplayFormat::Ttr3 => tape.encode_binary(binary_keyframe_interval(source)?),
+    };
+    std::fs::write(path, bytes).map_err(|error| error.to_string())
+}
+
+pub fn read_tape(path: &Path) -> Result<ReplayTape, String> {
     let bytes = std::fs::read(path).map_err(|error| error.to_string())?;
     ReplayTape::decode(&bytes)
 }
@@ -0,0 +1,174 @@
+//! Text report for `tui-tetris replay diff`.
+//!
+//! Two tapes of the same seed and ruleset are compared step by step with
+//! [`first_divergence`]; the report names the first step whose input or state
+//! hash differs, draws both boards after that step side by side, and closes
+//! with how far score and lines drifted apart by the end of each tape.
+
+use std::fmt::Write;
+
+use tetris_core::core::{GameSnapshot, get_shape};
+use tetris_core::types::{BOARD_HEIGHT, BOARD_WIDTH};
+use tetris_session::engine::replay::{ReplayTape, first_divergence};
+use tetris_session::engine::session::StepInput;
+
+use crate::replay_export::{local_actions, remote_commands};
+
+/// Compare `left` against `right`, labelling them with their file names.
+pub fn diff_tapes(
+    left: &ReplayTape,
+    left_name: &str,
+    right: &ReplayTape,
+    right_name: &str,
+) -> Result<String, String> {
+    left.check_compatible(right)?;
+    let mut report = String::new();
+    let Some(mismatch) = first_divergence(left, right) else {
+        let (left_steps, right_steps) = (left.records().len(), right.records().len());
+        if left_steps == right_steps {
+            writeln!(report, "tapes agree for all {left_steps} steps").unwrap();
+        } else {
+            let (shorter, longer) = if left_steps < right_steps {
+                (left_name, right_name)
+            } else {
+                (right_name, left_name)
+            };
+            writeln!(
+                report,
+                "tapes agree for {} steps; {shorter} ends there, {longer} continues to step {}",
+                left_steps.min(right_steps),
+                left_steps.max(right_steps)
+            )
+            .unwrap();
+        }
+        write_summary(&mut report, left, left_name, right, right_name);
+        return Ok(report);
+    };
+
+    let step = mismatch.step;
+    let (left_input, right_input) = (&left.records()[step].input, &right.records()[step].input);
+    let cause = if left_input != right_input {
+        "inputs differ"
+    } else {
+        "state hashes differ"
+    };
+    writeln!(report, "first divergence at step {step}: {cause}").unwrap();
+    writeln!(
+        report,
+        "  {left_name}: hash {} input {}",
+        mismatch.expected,
+        describe_input(left_input)
+    )
+    .unwrap();
+    writeln!(
+        report,
+        "  {right_name}: hash {} input {}",
+        mismatch.actual,
+        describe_input(right_input)
+    )
+    .unwrap();
+    writeln!(report).unwrap();
+
+    let left_prefix = left.minimal_failure_prefix(&mismatch);
+    let right_prefix = right.minimal_failure_prefix(&mismatch);
+    write_boards(
+        &mut report,
+        left_prefix.final_snapshot(),
+        left_name,
+        right_prefix.final_snapshot(),
+        right_name,
+    );
+    writeln!(report).unwrap();
+    write_summary(&mut report, left, left_name, right, right_name);
+    Ok(report)
+}
+
+/// Inputs in the form `local [hardDrop] remote [place 3 east hold]`.
+fn describe_input(input: &StepInput) -> String {
+    if input.local.is_empty() && input.remote.is_empty() {
+        return "idle".into();
+    }
+    format!(
+        "local [{}] remote [{}]",
+        local_actions(input),
+        remote_commands(input)
+    )
+}
+
+/// Both boards, `#` for locked cells and `@` for the active piece. Rows that
+/// differ are marked with `<>` between the boards.
+fn write_boards(
+    report: &mut String,
+    left: &GameSnapshot,
+    left_name: &str,
+    right: &GameSnapshot,
+    right_name: &str,
+) {
+    let width = BOARD_WIDTH as usize;
+    let (left_rows, right_rows) = (board_rows(left), board_rows(right));
+    writeln!(
+        report,
+        "{:<width$}    {}",
+        truncate(left_name, width),
+        truncate(right_name, width)
+    )
+    .unwrap();
+    for (left_row, right_row) in left_rows.iter().zip(&right_rows) {
+        let marker = if left_row == right_row { "  " } else { "<>" };
+        writeln!(report, "{left_row} {marker} {right_row}").unwrap();
+    }
+}
+
+fn board_rows(snapshot: &GameSnapshot) -> Vec<String> {
+    let mut rows: Vec<Vec<char>> = snapshot
+        .board
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| if cell == 0 { '.' } else { '#' })
+                .collect()
+        })
+        .collect();
+    if let Some(active) = snapshot.active {
+        for &(dx, dy) in get_shape(active.kind, active.rotation).iter() {
+            let (x, y) = (active.x + dx, active.y + dy);
+            if (0..BOARD_WIDTH as i8).contains(&x) && (0..BOARD_HEIGHT as i8).contains(&y) {
+                rows[y as usize][x as usize] = '@';
+            }
+        }
+    }
+    rows.into_iter().map(String::from_iter).collect()
+}
+
+fn truncate(name: &str, width: usize) -> String {
+    name.chars().take(width).collect()
+}
+
+fn write_summary(
+    report: &mut String,
+    left: &ReplayTape,
+    left_name: &str,
+    right: &ReplayTape,
+    right_name: &str,
+) {
+    let (left_end, right_end) = (left.final_snapshot(), right.final_snapshot());
+    for (name, tape, end) in [(left_name, left, left_end), (right_name, right, right_end)] {
+        writeln!(
+            report,
+            "{name}: {} steps, score {}, lines {}, level {}{}",
+            tape.records().len(),
+            end.score,
+            end.lines,
+            end.level,
+            if end.game_over { ", game over" } else { "" }
+        )
+        .unwrap();
+    }
+    writeln!(
+        report,
+        "final difference ({right_name} - {left_name}): score {:+}, lines {:+}",
+        right_end.score as i64 - left_end.score as i64,
+        right_end.lines as i64 - left_end.lines as i64
+    )
+    .unwrap();
+}
@@ -0,0 +1,285 @@
+//! Tabular export of replay tapes for `tui-tetris replay export`.
+//!
+//! The tape's inputs are re-simulated under its ruleset and every step, or
+//! every locked piece, becomes one flat row with the same columns in both
+//! formats: a JSON object per line, or CSV with a header. Recorded state hashes
+//! are not checked; run `replay verify` first when that matters.
+
+use std::io::{self, Write};
+
+use serde_json::{Map, Value};
+use tetris_core::core::{ActiveSnapshot, GameSnapshot};
+use tetris_core::types::{BOARD_WIDTH, CoreLastEvent, GameAction};
+use tetris_session::engine::replay::ReplayTape;
+use tetris_session::engine::session::{GameCommand, StepInput};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportRows {
+    /// One row per tape step.
+    Step,
+    /// One row per locked piece.
+    Piece,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub rows: ExportRows,
+    /// Add the locked board after each row as a `board` column.
+    pub board: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Jsonl,
+            rows: ExportRows::Step,
+            board: false,
+        }
+    }
+}
+
+const STEP_COLUMNS: &[&str] = &[
+    "step",
+    "local",
+    "remote",
+    "active_kind",
+    "active_x",
+    "active_y",
+    "active_rotation",
+    "locked",
+    "placed_kind",
+    "placed_x",
+    "placed_y",
+    "placed_rotation",
+    "lines_cleared",
+    "line_clear_score",
+    "tspin",
+    "combo",
+    "back_to_back",
+    "score",
+    "lines",
+    "level",
+    "game_over",
+    "board_hash",
+];
+
+const PIECE_COLUMNS: &[&str] = &[
+    "piece",
+    "step",
+    "piece_steps",
+    "placed_kind",
+    "placed_x",
+    "placed_y",
+    "placed_rotation",
+    "lines_cleared",
+    "line_clear_score",
+    "tspin",
+    "combo",
+    "back_to_back",
+    "score",
+    "lines",
+    "level",
+    "game_over",
+    "board_hash",
+];
+
+/// Write `tape` to `out` and return the number of rows written.
+pub fn export_tape(
+    tape: &ReplayTape,
+    options: ExportOptions,
+    out: &mut impl Write,
+) -> io::Result<u64> {
+    let mut columns = match options.rows {
+        ExportRows::Step => STEP_COLUMNS.to_vec(),
+        ExportRows::Piece => PIECE_COLUMNS.to_vec(),
+    };
+    if options.board {
+        columns.push("board");
+    }
+    if options.format == ExportFormat::Csv {
+        writeln!(out, "{}", columns.join(","))?;
+    }
+
+    let mut session = tape.ruleset().session(tape.seed());
+    let mut rows = 0;
+    let mut pieces = 0;
+    let mut piece_start = 0;
+    let mut locks = Vec::new();
+    for record in tape.records() {
+        locks.clear();
+        session.transition_with_locks(&record.input, |lock| locks.push(lock));
+        let snapshot = session.snapshot();
+
+        let mut step_rows = Vec::new();
+        match options.rows {
+            ExportRows::Step => {
+                let mut row = Map::new();
+                let last = locks.last();
+                row.insert("step".into(), record.step.into());
+                row.insert("local".into(), local_actions(&record.input).into());
+                row.insert("remote".into(), remote_commands(&record.input).into());
+                insert_piece(&mut row, "active", snapshot.active);
+                row.insert("locked".into(), last.is_some().into());
+                insert_piece(&mut row, "placed", last.map(|lock| lock.piece));
+                insert_event(&mut row, last.map(|lock| lock.event));
+                step_rows.push(row);
+            }
+            ExportRows::Piece => {
+                for lock in &locks {
+                    let mut row = Map::new();
+                    row.insert("piece".into(), pieces.into());
+                    row.insert("step".into(), record.step.into());
+                    row.insert("piece_steps".into(), (record.step + 1 - piece_start).into());
+                    insert_piece(&mut row, "placed", Some(lock.piece));
+                    insert_event(&mut row, Some(lock.event));
+                    step_rows.push(row);
+                    pieces += 1;
+                    piece_start = record.step + 1;
+                }
+            }
+        }
+
+        // Every row of a step reports the totals after the whole step.
+        for mut row in step_rows {
+            row.insert("score".into(), snapshot.score.into());
+            row.insert("lines".into(), snapshot.lines.into());
+            row.insert("level".into(), snapshot.level.into());
+            row.insert("game_over".into(), snapshot.game_over.into());
+            row.insert("board_hash".into(), snapshot.board_hash.into());
+            if options.board {
+                row.insert("board".into(), board_text(snapshot).into());
+            }
+            match options.format {
+                ExportFormat::Jsonl => writeln!(out, "{}", Value::Object(row))?,
+                ExportFormat::Csv => {
+                    let cells: Vec<String> = columns
+                        .iter()
+                        .map(|column| csv_cell(row.get(*column).unwrap_or(&Value::Null)))
+                        .collect();
+                    writeln!(out, "{}", cells.join(","))?;
+                }
+            }
+            rows += 1;
+        }
+    }
+    Ok(rows)
+}
+
+fn insert_piece(row: &mut Map<String, Value>, prefix: &str, piece: Option<ActiveSnapshot>) {
+    let piece = piece.as_ref();
+    row.insert(
+        format!("{prefix}_kind"),
+        piece.map(|piece| piece.kind.as_str()).into(),
+    );
+    row.insert(format!("{prefix}_x"), piece.map(|piece| piece.x).into());
+    row.insert(format!("{prefix}_y"), piece.map(|piece| piece.y).into());
+    row.insert(
+        format!("{prefix}_rotation"),
+        piece.map(|piece| piece.rotation.as_str()).into(),
+    );
+}
+
+fn insert_event(row: &mut Map<String, Value>, event: Option<CoreLastEvent>) {
+    let event = event.as_ref();
+    row.insert(
+        "lines_cleared".into(),
+        event.map_or(0, |event| event.lines_cleared).into(),
+    );
+    row.insert(
+        "line_clear_score".into(),
+        event.map_or(0, |event| event.line_clear_score).into(),
+    );
+    row.insert(
+        "tspin".into(),
+        event
+            .and_then(|event| event.tspin)
+            .and_then(|tspin| tspin.as_str())
+            .into(),
+    );
+    row.insert("combo".into(), event.map(|event| event.combo).into());
+    row.insert(
+        "back_to_back".into(),
+        event.map(|event| event.back_to_back).into(),
+    );
+}
+
+/// Local actions, comma separated.
+pub(crate) fn local_actions(input: &StepInput) -> String {
+    input
+        .local
+        .iter()
+        .map(GameAction::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Remote commands separated by `;`, as `moveLeft,hardDrop`,
+/// `restart seed 7`, or `place 3 east hold`.
+pub(crate) fn remote_commands(input: &StepInput) -> String {
+    input
+        .remote
+        .iter()
+        .map(|command| match command {
+            GameCommand::Actions {
+                actions,
+                restart_seed,
+            } => {
+                let actions = actions
+                    .iter()
+                    .map(GameAction::as_str)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                match restart_seed {
+                    Some(seed) => format!("{actions} seed {seed}"),
+                    None => actions,
+                }
+            }
+            GameCommand::Place {
+                x,
+                rotation,
+                use_hold,
+            } => format!(
+                "place {x} {}{}",
+                rotation.as_str(),
+                if *use_hold { " hold" } else { "" }
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Locked cells top row first, rows separated by `/`; `.` is empty, piece
+/// letters are filled cells.
+fn board_text(snapshot: &GameSnapshot) -> String {
+    let mut text = String::with_capacity(snapshot.board.len() * (BOARD_WIDTH as usize + 1));
+    for (y, row) in snapshot.board.iter().enumerate() {
+        if y > 0 {
+            text.push('/');
+        }
+        text.extend(
+            row.iter()
+                .map(|&cell| b".IOTSZJL".get(cell as usize).map_or('?', |&c| c as char)),
+        );
+    }
+    text
+}
+
+fn csv_cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
@@ -0,0 +1,164 @@
+//! Replay playback state for `tui-tetris replay play`.
+//!
+//! The viewer owns a [`ReplayCursor`] and a playback clock. The terminal loop
+//! feeds it key commands and elapsed time and renders its snapshot and
+//! [`ReplayStatusView`], plus a title line from the tape's metadata and the
+//! latest annotation; it performs no I/O itself.
+
+use std::time::Duration;
+
+use tetris_core::core::GameSnapshot;
+use tetris_core::types::TICK_MS;
+use tetris_session::engine::fixed_step::FixedStepClock;
+use tetris_session::engine::replay::{
+    META_DATE, META_MODE, META_PLAYER, META_PLAYER_VERSION, ReplayAnnotation, ReplayTape,
+};
+use tetris_session::engine::replay_cursor::ReplayCursor;
+use tetris_terminal::input::ReplayInputCommand;
+use tetris_terminal::term::ReplayStatusView;
+
+/// Playback speeds as `numerator/denominator` multipliers of real time.
+pub const REPLAY_SPEEDS: [(u16, u16); 7] =
+    [(1, 4), (1, 2), (1, 1), (2, 1), (4, 1), (8, 1), (16, 1)];
+const REAL_TIME: usize = 2;
+/// Fast playback applies up to this many steps per frame.
+const MAX_CATCH_UP_STEPS: u32 = 64;
+
+#[derive(Debug)]
+pub struct ReplayViewer {
+    cursor: ReplayCursor,
+    playing: bool,
+    speed: usize,
+    jump_target: Option<u64>,
+    clock: FixedStepClock,
+}
+
+impl ReplayViewer {
+    /// Start paused at step 0, at real-time speed.
+    pub fn new(tape: ReplayTape) -> Self {
+        Self {
+            cursor: ReplayCursor::new(tape),
+            playing: false,
+            speed: REAL_TIME,
+            jump_target: None,
+            clock: playback_clock(REAL_TIME),
+        }
+    }
+
+    pub fn cursor(&self) -> &ReplayCursor {
+        &self.cursor
+    }
+
+    pub fn snapshot(&self) -> &GameSnapshot {
+        self.cursor.session().snapshot()
+    }
+
+    /// Apply one key command; `false` once the viewer should close.
+    pub fn handle(&mut self, command: ReplayInputCommand) -> bool {
+        match command {
+            ReplayInputCommand::TogglePlay => {
+                if self.cursor.at_end() {
+                    self.cursor.seek(0);
+                }
+                self.playing = !self.playing;
+                self.clock = playback_clock(self.speed);
+            }
+            ReplayInputCommand::Faster => {
+                self.set_speed((self.speed + 1).min(REPLAY_SPEEDS.len() - 1))
+            }
+            ReplayInputCommand::Slower => self.set_speed(self.speed.saturating_sub(1)),
+            ReplayInputCommand::StepForward => {
+                self.playing = false;
+                self.cursor.step_forward();
+            }
+            ReplayInputCommand::StepBack => {
+                self.playing = false;
+                self.cursor.step_back();
+            }
+            ReplayInputCommand::Start => self.cursor.seek(0),
+            ReplayInputCommand::End => self.cursor.seek(u64::MAX),
+            ReplayInputCommand::Digit(digit) => {
+                let target = self.jump_target.unwrap_or(0);
+                self.jump_target = Some(target.saturating_mul(10).saturating_add(digit as u64));
+            }
+            ReplayInputCommand::DeleteDigit => {
+                self.jump_target = self.jump_target.map(|target| target / 10);
+            }
+            ReplayInputCommand::Jump => {
+                if let Some(target) = self.jump_target.take() {
+                    self.cursor.seek(target);
+                }
+            }
+            ReplayInputCommand::CancelJump => self.jump_target = None,
+            ReplayInputCommand::Quit => return false,
+        }
+        true
+    }
+
+    /// Advance playback by `elapsed` wall time; pauses at the end of the tape.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if !self.playing {
+            return;
+        }
+        for _ in 0..self.clock.advance(elapsed) {
+            if !self.cursor.step_forward() {
+                self.playing = false;
+                break;
+            }
+        }
+    }
+
+    /// Wall time until playback needs another [`ReplayViewer::advance`].
+    pub fn until_next_step(&self) -> Option<Duration> {
+        self.playing.then(|| self.clock.until_next_step())
+    }
+
+    /// Who played, how, and when, from the tape's metadata.
+    pub fn title(&self) -> Option<String> {
+        let metadata = self.cursor.tape().metadata();
+        let player =
+            metadata
+                .get(META_PLAYER)
+                .map(|player| match metadata.get(META_PLAYER_VERSION) {
+                    Some(version) => format!("{player} {version}"),
+                    None => player.to_string(),
+                });
+        let parts: Vec<String> = player
+            .into_iter()
+            .chain(
+                [META_MODE, META_DATE]
+                    .into_iter()
+                    .filter_map(|key| metadata.get(key).map(str::to_string)),
+            )
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+
+    /// The last annotation at or before the current step.
+    pub fn annotation(&self) -> Option<&ReplayAnnotation> {
+        let annotations = self.cursor.tape().annotations();
+        let shown = annotations.partition_point(|note| note.step <= self.cursor.position());
+        shown.checked_sub(1).map(|index| &annotations[index])
+    }
+
+    pub fn status(&self) -> ReplayStatusView {
+        ReplayStatusView {
+            step: self.cursor.position(),
+            total_steps: self.cursor.len(),
+            playing: self.playing,
+            speed: REPLAY_SPEEDS[self.speed],
+            jump_target: self.jump_target,
+        }
+    }
+
+    fn set_speed(&mut self, speed: usize) {
+        self.speed = speed;
+        self.clock = playback_clock(speed);
+    }
+}
+
+fn playback_clock(speed: usize) -> FixedStepClock {
+    let (numerator, denominator) = REPLAY_SPEEDS[speed];
+    let tick_us = TICK_MS as u64 * 1_000 * denominator as u64 / numerator as u64;
+    FixedStepClock::new(Duration::from_micros(tick_us), MAX_CATCH_UP_STEPS)
+}
@@ -0,0 +1,681 @@
+//! Wire log analysis for `tui-tetris wirelog`.
+//!
+//! `TETRIS_AI_LOG_PATH` writes one envelope per line,
+//! `{"t_us":..,"client":..,"dir":"in"|"out","msg":{..}}`. Logs from builds that
+//! wrote bare messages still parse: every line belongs to client 0, has no
+//! time, and its direction follows from the message type.
+//!
+//! Requests pair with replies by sequence number: a welcome carries its hello's
+//! `seq`, errors the failing request's `seq`, and acks and room states their
+//! `correlation_seq`. The log is sampled and best effort, so a request without
+//! a reply is reported rather than treated as an error.
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde_json::Value;
+use tetris_adapter::adapter::Adapter;
+use tetris_adapter::adapter::game_loop::step_session;
+use tetris_adapter::adapter::observation_schedule::ObservationSchedule;
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_core::types::TICK_MS;
+use tetris_session::engine::session::SessionRuntime;
+
+/// Message types clients send; everything else is server output.
+const INBOUND_TYPES: &[&str] = &[
+    "hello",
+    "command",
+    "control",
+    "step",
+    "subscribe",
+    "resync",
+    "room",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WireEntry {
+    /// 1-based line in the log.
+    pub line: usize,
+    /// Unix time in microseconds; `None` in bare logs.
+    pub at_us: Option<u64>,
+    pub client: u64,
+    pub inbound: bool,
+    pub message: Value,
+}
+
+impl WireEntry {
+    pub fn kind(&self) -> &str {
+        self.message["type"].as_str().unwrap_or("?")
+    }
+
+    pub fn seq(&self) -> Option<u64> {
+        self.message["seq"].as_u64()
+    }
+
+    /// The request `seq` this entry answers, for replies.
+    pub fn reply_to(&self) -> Option<u64> {
+        match self.kind() {
+            "welcome" | "error" => self.seq(),
+            "ack" | "room_state" => self.message["correlation_seq"].as_u64(),
+            _ => None,
+        }
+    }
+
+    /// The reply outcome compared by `wirelog redrive`: `ack ok`,
+    /// `error not_controller`, `welcome controller`, `room_state lobby`.
+    pub fn outcome(&self) -> String {
+        let detail = match self.kind() {
+            "ack" => &self.message["status"],
+            "error" => &self.message["code"],
+            "welcome" => &self.message["role"],
+            "room_state" => &self.message["room"],
+            _ => &Value::Null,
+        };
+        match detail.as_str() {
+            Some(detail) => format!("{} {detail}", self.kind()),
+            None => self.kind().to_string(),
+        }
+    }
+}
+
+pub fn parse_wire_log(text: &str) -> Result<Vec<WireEntry>, String> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value =
+            serde_json::from_str(line).map_err(|error| format!("line {line_number}: {error}"))?;
+        let entry = match value.get("msg") {
+            Some(message) => WireEntry {
+                line: line_number,
+                at_us: value["t_us"].as_u64(),
+                client: value["client"]
+                    .as_u64()
+                    .ok_or_else(|| format!("line {line_number}: missing client"))?,
+                inbound: match value["dir"].as_str() {
+                    Some("in") => true,
+                    Some("out") => false,
+                    _ => return Err(format!("line {line_number}: invalid dir")),
+                },
+                message: message.clone(),
+            },
+            None => WireEntry {
+                line: line_number,
+                at_us: None,
+                client: 0,
+                inbound: value["type"]
+                    .as_str()
+                    .is_some_and(|kind| INBOUND_TYPES.contains(&kind)),
+                message: value,
+            },
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+pub fn read_wire_log(path: &Path) -> Result<Vec<WireEntry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    parse_wire_log(&text)
+}
+
+/// Entries grouped by client, each in log order.
+pub fn client_timelines(entries: &[WireEntry]) -> BTreeMap<u64, Vec<&WireEntry>> {
+    let mut timelines: BTreeMap<u64, Vec<&WireEntry>> = BTreeMap::new();
+    for entry in entries {
+        timelines.entry(entry.client).or_default().push(entry);
+    }
+    timelines
+}
+
+/// Each request of `timeline` with its first reply, if the log has one.
+pub fn pair_replies<'a>(timeline: &[&'a WireEntry]) -> Vec<(&'a WireEntry, Option<&'a WireEntry>)> {
+    let mut pairs: Vec<(&WireEntry, Option<&WireEntry>)> = Vec::new();
+    let mut open: HashMap<u64, usize> = HashMap::new();
+    for &entry in timeline {
+        if entry.inbound {
+            if let Some(seq) = entry.seq() {
+                open.insert(seq, pairs.len());
+            }
+            pairs.push((entry, None));
+        } else if let Some(index) = entry.reply_to().and_then(|seq| open.remove(&seq)) {
+            pairs[index].1 = Some(entry);
+        }
+    }
+    pairs
+}
+
+/// Request-to-reply latency over every answered, timestamped request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub min_us: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+    pub mean_us: u64,
+}
+
+impl LatencyStats {
+    pub fn from_samples(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Self {
+            count: samples.len(),
+            min_us: samples[0],
+            p50_us: percentile(50),
+            p90_us: percentile(90),
+            p99_us: percentile(99),
+            max_us: samples[samples.len() - 1],
+            mean_us: samples.iter().sum::<u64>() / samples.len() as u64,
+        }
+    }
+}
+
+/// Latencies by request type, over all clients.
+pub fn reply_latencies(entries: &[WireEntry]) -> BTreeMap<String, LatencyStats> {
+    let mut samples: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    for timeline in client_timelines(entries).values() {
+        for (request, reply) in pair_replies(timeline) {
+            if let (Some(sent), Some(replied)) =
+                (request.at_us, reply.and_then(|reply| reply.at_us))
+            {
+                samples
+                    .entry(request.kind().to_string())
+                    .or_default()
+                    .push(replied.saturating_sub(sent));
+            }
+        }
+    }
+    samples
+        .into_iter()
+        .map(|(kind, samples)| (kind, LatencyStats::from_samples(samples)))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WirelogCommand {
+    /// Per-client counts, roles, and errors.
+    Summary { path: PathBuf },
+    /// Every entry in order, optionally for one client.
+    Timeline {
+        path: PathBuf,
+        client: Option<u64>,
+        observations: bool,
+    },
+    /// Request-to-reply latency by request type.
+    Latency { path: PathBuf },
+    /// Send the logged requests to a server and compare replies.
+    Redrive {
+        path: PathBuf,
+        target: RedriveTarget,
+        /// Replaces tokens the log redacted.
+        token: Option<String>,
+        timeout_ms: u64,
+    },
+}
+
+/// Where `wirelog redrive` sends requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedriveTarget {
+    /// A fresh in-process adapter; `None` takes the seed from the log's first
+    /// observation, else 1.
+    Fresh { seed: Option<u32> },
+    /// A server that is already running, given as `host:port`.
+    Attach { address: String },
+}
+
+pub fn parse_wirelog_args(args: &[String]) -> Result<Option<WirelogCommand>, String> {
+    if args.first().map(String::as_str) != Some("wirelog") {
+        return Ok(None);
+    }
+    let operation = args
+        .get(1)
+        .map(String::as_str)
+        .ok_or("usage: tui-tetris wirelog <summary|timeline|latency|redrive> <log>")?;
+    let path = args
+        .get(2)
+        .map(PathBuf::from)
+        .ok_or("missing wire log path")?;
+    match operation {
+        "summary" if args.len() == 3 => Ok(Some(WirelogCommand::Summary { path })),
+        "latency" if args.len() == 3 => Ok(Some(WirelogCommand::Latency { path })),
+        "summary" | "latency" => Err("unexpected wirelog arguments".into()),
+        "timeline" => {
+            let mut client = None;
+            let mut observations = true;
+            let mut index = 3;
+            while index < args.len() {
+                if args[index] == "--no-observations" {
+                    observations = false;
+                    index += 1;
+                    continue;
+                }
+                let value = args.get(index + 1).ok_or("missing wirelog option value")?;
+                match args[index].as_str() {
+                    "--client" => client = Some(value.parse().map_err(|_| "invalid --client")?),
+                    option => return Err(format!("unknown wirelog option: {option}")),
+                }
+                index += 2;
+            }
+            Ok(Some(WirelogCommand::Timeline {
+                path,
+                client,
+                observations,
+            }))
+        }
+        "redrive" => {
+            let (mut seed, mut attach) = (None, None);
+            let mut token = None;
+            let mut timeout_ms = 2_000;
+            let mut index = 3;
+            while index < args.len() {
+                let value = args.get(index + 1).ok_or("missing wirelog option value")?;
+                match args[index].as_str() {
+                    "--seed" => seed = Some(value.parse().map_err(|_| "invalid --seed")?),
+                    "--attach" => attach = Some(value.clone()),
+                    "--token" => token = Some(value.clone()),
+                    "--timeout-ms" => {
+                        timeout_ms = value.parse().map_err(|_| "invalid --timeout-ms")?
+                    }
+                    option => return Err(format!("unknown wirelog option: {option}")),
+                }
+                index += 2;
+            }
+            let target = match (attach, seed) {
+                (Some(_), Some(_)) => {
+                    return Err("--seed needs a fresh server, not --attach".into());
+                }
+                (Some(address), None) => RedriveTarget::Attach { address },
+                (None, seed) => RedriveTarget::Fresh { seed },
+            };
+            Ok(Some(WirelogCommand::Redrive {
+                path,
+                target,
+                token,
+                timeout_ms,
+            }))
+        }
+        _ => Err(format!("unknown wirelog operation: {operation}")),
+    }
+}
+
+pub fn run_wirelog_command(command: WirelogCommand) -> Result<String, String> {
+    match command {
+        WirelogCommand::Summary { path } => Ok(summary(&read_wire_log(&path)?)),
+        WirelogCommand::Timeline {
+            path,
+            client,
+            observations,
+        } => Ok(timeline(&read_wire_log(&path)?, client, observations)),
+        WirelogCommand::Latency { path } => Ok(latency_report(&read_wire_log(&path)?)),
+        WirelogCommand::Redrive {
+            path,
+            target,
+            token,
+            timeout_ms,
+        } => {
+            let entries = read_wire_log(&path)?;
+            let timeout = Duration::from_millis(timeout_ms);
+            match target {
+                RedriveTarget::Fresh { seed } => {
+                    let seed = seed.or_else(|| logged_seed(&entries)).unwrap_or(1);
+                    let server = RedriveServer::start(seed)?;
+                    let address = server.addr();
+                    let stream = |_client| {
+                        TcpStream::connect(address)
+                            .map_err(|error| format!("connect {address} failed: {error}"))
+                    };
+                    redrive(&entries, stream, token.as_deref(), timeout)
+                }
+                RedriveTarget::Attach { address } => {
+                    let stream = |_client| {
+                        TcpStream::connect(address.as_str())
+                            .map_err(|error| format!("connect {address} failed: {error}"))
+                    };
+                    redrive(&entries, stream, token.as_deref(), timeout)
+                }
+            }
+        }
+    }
+}
+
+pub fn summary(entries: &[WireEntry]) -> String {
+    let timelines = client_timelines(entries);
+    let mut report = format!("{} entries, {} clients", entries.len(), timelines.len());
+    for (client, timeline) in &timelines {
+        let name = timeline
+            .iter()
+            .find(|entry| entry.kind() == "hello")
+            .and_then(|hello| hello.message["client"]["name"].as_str())
+            .unwrap_or("?");
+        let role = timeline
+            .iter()
+            .rev()
+            .find(|entry| entry.kind() == "welcome")
+            .and_then(|welcome| welcome.message["role"].as_str())
+            .unwrap_or("?");
+        let mut counts: BTreeMap<(bool, &str), usize> = BTreeMap::new();
+        let mut errors: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in timeline {
+            *counts.entry((entry.inbound, entry.kind())).or_default() += 1;
+            if let Some(code) = entry
+                .message
+                .get("code")
+                .and_then(Value::as_str)
+                .filter(|_| entry.kind() == "error")
+            {
+                *errors.entry(code).or_default() += 1;
+            }
+        }
+        let unanswered = pair_replies(timeline)
+            .iter()
+            .filter(|(request, reply)| request.seq().is_some() && reply.is_none())
+            .count();
+        report.push_str(&format!("\nclient {client} ({name}, {role})"));
+        for (inbound, label) in [(true, "sent"), (false, "received")] {
+            let listed: Vec<String> = counts
+                .iter()
+                .filter(|((direction, _), _)| *direction == inbound)
+                .map(|((_, kind), count)| format!("{kind} {count}"))
+                .collect();
+            if !listed.is_empty() {
+                report.push_str(&format!("\n  {label}: {}", listed.join(", ")));
+            }
+        }
+        if !errors.is_empty() {
+            let listed: Vec<String> = errors
+                .iter()
+                .map(|(code, count)| format!("{code} {count}"))
+                .collect();
+            report.push_str(&format!("\n  errors: {}", listed.join(", ")));
+        }
+        if unanswered > 0 {
+            report.push_str(&format!("\n  unanswered requests: {unanswered}"));
+        }
+    }
+    report
+}
+
+/// One line per entry: time since the earliest entry, client, direction, and
+/// a short description.
+///
+/// Writers interleave, so timestamps are not ordered by line.
+pub fn timeline(entries: &[WireEntry], client: Option<u64>, observations: bool) -> String {
+    let start = entries.iter().filter_map(|entry| entry.at_us).min();
+    let mut lines = Vec::new();
+    for entry in entries {
+        if client.is_some_and(|client| client != entry.client)
+            || (!observations && entry.kind() == "observation")
+        {
+            continue;
+        }
+        let at = match (entry.at_us, start) {
+            (Some(at), Some(start)) => format!("{:>10.3}ms", (at - start) as f64 / 1_000.0),
+            _ => format!("{:>10}", format!("#{}", entry.line)),
+        };
+        lines.push(format!(
+            "{at} client {} {} {}",
+            entry.client,
+            if entry.inbound { "->" } else { "<-" },
+            describe(entry)
+        ));
+    }
+    lines.join("\n")
+}
+
+fn describe(entry: &WireEntry) -> String {
+    let message = &entry.message;
+    let mut text = entry.kind().to_string();
+    if let Some(seq) = entry.seq() {
+        text.push_str(&format!(" seq {seq}"));
+    }
+    let detail = match entry.kind() {
+        "hello" => message["client"]["name"].as_str().map(str::to_string),
+        "command" => Some(match message.get("place") {
+            Some(place) => format!("place {place}"),
+            None => message["actions"].to_string(),
+        }),
+        "control" | "room" => message["action"].as_str().map(str::to_string),
+        "welcome" => Some(format!(
+            "client_id {} role {}",
+            message["client_id"], message["role"]
+        )),
+        "ack" => Some(format!(
+            "{} for {}",
+            message["status"].as_str().unwrap_or("?"),
+            message["correlation_seq"]
+        )),
+        "error" => Some(format!(
+            "{}: {}",
+            message["code"].as_str().unwrap_or("?"),
+            message["message"].as_str().unwrap_or("")
+        )),
+        "observation" => Some(format!("step {}", message["logical_step"])),
+        "control_state" => Some(format!("controller {}", message["controller_id"])),
+        _ => None,
+    };
+    if let Some(detail) = detail {
+        text.push(' ');
+        text.push_str(&detail);
+    }
+    text
+}
+
+pub fn latency_report(entries: &[WireEntry]) -> String {
+    let latencies = reply_latencies(entries);
+    if latencies.is_empty() {
+        return "no timestamped request/reply pairs".into();
+    }
+    let ms = |us: u64| us as f64 / 1_000.0;
+    let mut report =
+        String::from("type       count     min     p50     p90     p99     max    mean (ms)");
+    for (kind, stats) in latencies {
+        report.push_str(&format!(
+            "\n{kind:<10} {:>5} {:>7.3} {:>7.3} {:>7.3} {:>7.3} {:>7.3} {:>7.3}",
+            stats.count,
+            ms(stats.min_us),
+            ms(stats.p50_us),
+            ms(stats.p90_us),
+            ms(stats.p99_us),
+            ms(stats.max_us),
+            ms(stats.mean_us)
+        ));
+    }
+    report
+}
+
+/// The game seed of the first logged observation.
+fn logged_seed(entries: &[WireEntry]) -> Option<u32> {
+    entries
+        .iter()
+        .filter(|entry| entry.kind() == "observation")
+        .find_map(|entry| entry.message["seed"].as_u64())
+        .and_then(|seed| u32::try_from(seed).ok())
+}
+
+/// A headless adapter on an ephemeral loopback port that steps its own game
+/// until dropped, so a redrive does not depend on whatever else is listening.
+pub struct RedriveServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    game: Option<JoinHandle<()>>,
+}
+
+impl RedriveServer {
+    /// Start a default-configured adapter playing `seed`.
+    pub fn start(seed: u32) -> Result<Self, String> {
+        let adapter = Adapter::start(ServerConfig {
+            port: 0,
+            ..ServerConfig::default()
+        })
+        .map_err(|error| format!("failed to start redrive adapter: {error}"))?;
+        let addr = adapter
+            .listen_addr()
+            .ok_or("redrive adapter has no TCP address")?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let game = std::thread::spawn(move || {
+            let mut adapter = Some(adapter);
+            let mut session = SessionRuntime::new(seed);
+            let mut observations = ObservationSchedule::new(session.game(), 20);
+            while !stopped.load(Ordering::Relaxed) {
+                step_session(&mut adapter, &mut session, &mut observations, &[], true);
+                std::thread::sleep(Duration::from_millis(TICK_MS as u64));
+            }
+        });
+        Ok(Self {
+            addr,
+            stop,
+            game: Some(game),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for RedriveServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(game) = self.game.take() {
+            let _ = game.join();
+        }
+    }
+}
+
+/// Send every logged request in log order, each client over its own
+/// connection from `connect`, and compare each reply the log recorded with
+/// the one the server gives now.
+///
+/// Hellos are downgraded to JSON lines, and `token` replaces redacted tokens.
+/// Requests whose reply the log does not have are sent without waiting.
+pub fn redrive(
+    entries: &[WireEntry],
+    mut connect: impl FnMut(u64) -> Result<TcpStream, String>,
+    token: Option<&str>,
+    timeout: Duration,
+) -> Result<String, String> {
+    let mut recorded: HashMap<(u64, usize), &WireEntry> = HashMap::new();
+    for timeline in client_timelines(entries).values() {
+        for (request, reply) in pair_replies(timeline) {
+            if let Some(reply) = reply {
+                recorded.insert((request.client, request.line), reply);
+            }
+        }
+    }
+
+    let mut connections: BTreeMap<u64, (TcpStream, BufReader<TcpStream>)> = BTreeMap::new();
+    let (mut sent, mut matched) = (0, 0);
+    let mut differences = Vec::new();
+    for request in entries.iter().filter(|entry| entry.inbound) {
+        let (stream, reader) = match connections.entry(request.client) {
+            Entry::Occupied(connection) => connection.into_mut(),
+            Entry::Vacant(slot) => {
+                let stream = connect(request.client)?;
+                stream
+                    .set_read_timeout(Some(timeout))
+                    .map_err(|error| error.to_string())?;
+                let reader = BufReader::new(stream.try_clone().map_err(|error| error.to_string())?);
+                slot.insert((stream, reader))
+            }
+        };
+        let mut message = request.message.clone();
+        if request.kind() == "hello" {
+            message["formats"] = serde_json::json!(["json"]);
+            if let Some(token) = token
+                && message.get("token").is_some()
+            {
+                message["token"] = token.into();
+            }
+        }
+        writeln!(stream, "{message}").map_err(|error| error.to_string())?;
+        sent += 1;
+
+        let Some(expected) = recorded.get(&(request.client, request.line)) else {
+            continue;
+        };
+        let actual = read_reply(reader, request.seq())?;
+        let actual = actual
+            .as_ref()
+            .map_or_else(|| "no reply".to_string(), WireEntry::outcome);
+        if actual == expected.outcome() {
+            matched += 1;
+        } else {
+            differences.push(format!(
+                "  line {} client {} {} seq {}: logged {}, now {actual}",
+                request.line,
+                request.client,
+                request.kind(),
+                request.seq().map_or("-".into(), |seq| seq.to_string()),
+                expected.outcome()
+            ));
+        }
+    }
+
+    let compared = matched + differences.len();
+    let mut report = format!(
+        "redrove {sent} requests from {} clients; {matched} of {compared} logged replies match",
+        connections.len()
+    );
+    if !differences.is_empty() {
+        report.push_str(&format!(
+            "\n{} differ:\n{}",
+            differences.len(),
+            differences.join("\n")
+        ));
+    }
+    Ok(report)
+}
+
+/// The next reply to `seq`, skipping other output, or `None` on timeout or
+/// disconnect.
+fn read_reply(
+    reader: &mut BufReader<TcpStream>,
+    seq: Option<u64>,
+) -> Result<Option<WireEntry>, String> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let entry = WireEntry {
+            line: 0,
+            at_us: None,
+            client: 0,
+            inbound: false,
+            message,
+        };
+        if entry.reply_to().is_some() && entry.reply_to() == seq {
+            return Ok(Some(entry));
+        }
+    }
+}
@@ -1478,6 +1478,7 @@ async fn acceptance_controller_disconnect_does_not_auto_promote_observer_role()
             client_count: 0,
             controller_id: None,
             streaming_count: 0,
+            lockstep: false,
         });
 
     let server_handle = tokio::spawn(async move {
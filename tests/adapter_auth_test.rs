@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_adapter_protocol::protocol::{RequestedRole, create_hello};
+
+mod support;
+use support::{ClientLines, read_json_line, spawn_server};
+
+fn auth_config() -> ServerConfig {
+    ServerConfig {
+        auth_token: Some("lab-secret".to_string()),
+        observer_token: Some("watch-only".to_string()),
+        ..support::server_config()
+    }
+}
+
+async fn hello_with_token(
+    addr: std::net::SocketAddr,
+    token: Option<&str>,
+    role: RequestedRole,
+) -> (
+    ClientLines,
+    tokio::net::tcp::OwnedWriteHalf,
+    serde_json::Value,
+) {
+    let (mut lines, mut writer) = support::connect(addr).await;
+    let mut hello = create_hello(1, "lab", "3.0.0");
+    hello.requested.stream_observations = false;
+    hello.requested.role = Some(role);
+    hello.token = token.map(str::to_string);
+    support::write_json_line(&mut writer, &hello).await;
+    let reply = read_json_line(&mut lines).await;
+    (lines, writer, reply)
+}
+
+#[tokio::test]
+async fn missing_or_wrong_tokens_are_refused_and_disconnected() {
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(auth_config(), 8).await;
+
+    for token in [None, Some("lab-secre"), Some("")] {
+        let (mut lines, _writer, reply) =
+            hello_with_token(addr, token, RequestedRole::Controller).await;
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["code"], "unauthorized");
+        assert_eq!(reply["seq"], 1);
+        let closed = tokio::time::timeout(Duration::from_secs(2), lines.next_line())
+            .await
+            .expect("refused client was not disconnected");
+        assert!(matches!(closed, Ok(None) | Err(_)));
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn observer_tokens_never_gain_control() {
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(auth_config(), 8).await;
+
+    // An observer-only client asking for control is still an observer, even
+    // with the controller seat free.
+    let (mut watcher, mut watcher_writer, welcome) =
+        hello_with_token(addr, Some("watch-only"), RequestedRole::Controller).await;
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["role"], "observer");
+    assert!(welcome["controller_id"].is_null());
+
+    support::write_json_line(
+        &mut watcher_writer,
+        &serde_json::json!({"type":"control","seq":2,"ts":1,"action":"claim"}),
+    )
+    .await;
+    let error = read_json_line(&mut watcher).await;
+    assert_eq!(error["code"], "unauthorized");
+    assert_eq!(error["seq"], 2);
+
+    let (_bot, bot_writer, welcome) =
+        hello_with_token(addr, Some("lab-secret"), RequestedRole::Auto).await;
+    assert_eq!(welcome["role"], "controller");
+
+    // The controller leaving does not promote the observer-only client.
+    drop(bot_writer);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    support::write_json_line(
+        &mut watcher_writer,
+        &serde_json::json!({"type":"command","seq":3,"ts":1,"mode":"action","actions":["hardDrop"]}),
+    )
+    .await;
+    let error = read_json_line(&mut watcher).await;
+    assert_eq!(error["code"], "not_controller");
+
+    server.abort();
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::OwnedReadHalf;
+
+use tetris_adapter_protocol::binary::{BinaryMessage, decode_frame};
+use tetris_adapter_protocol::protocol::{CapabilityFeature, ErrorCode, FormatsList, create_hello};
+
+mod support;
+use support::spawn_server;
+
+async fn read_frame(reader: &mut BufReader<OwnedReadHalf>) -> BinaryMessage {
+    let mut frame = vec![0; 4];
+    tokio::time::timeout(Duration::from_secs(3), async {
+        reader.read_exact(&mut frame).await.expect("frame length");
+        let len = u32::from_le_bytes(frame[..4].try_into().unwrap()) as usize;
+        frame.resize(4 + len, 0);
+        reader
+            .read_exact(&mut frame[4..])
+            .await
+            .expect("frame body");
+    })
+    .await
+    .expect("timeout waiting for adapter frame");
+    let (message, size) = decode_frame(&frame).expect("adapter sent an invalid frame");
+    assert_eq!(size, frame.len());
+    message
+}
+
+#[tokio::test]
+async fn binary_client_receives_frames_after_json_welcome() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+    let (reader, mut writer) = TcpStream::connect(addr).await.unwrap().into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut hello = create_hello(1, "binary", "3.0.0");
+    hello.formats = FormatsList {
+        json: true,
+        binary: true,
+    };
+    let hello_json = serde_json::to_value(&hello).unwrap();
+    assert_eq!(hello_json["formats"], serde_json::json!(["json", "binary"]));
+    support::write_json_line(&mut writer, &hello_json).await;
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await.unwrap();
+    let welcome: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["format"], "binary");
+    assert!(
+        welcome["capabilities"]["formats"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("binary"))
+    );
+
+    let BinaryMessage::Observation(snapshot) = read_frame(&mut reader).await else {
+        panic!("expected snapshot observation frame");
+    };
+    assert_eq!(snapshot.seq, 1);
+    assert!(snapshot.active.is_some());
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"command","seq":2,"ts":1,"mode":"action","actions":["hardDrop"]}),
+    )
+    .await;
+    let BinaryMessage::Ack(ack) = read_frame(&mut reader).await else {
+        panic!("expected ack frame");
+    };
+    assert_eq!(ack.seq, 2);
+    let BinaryMessage::Observation(observation) = read_frame(&mut reader).await else {
+        panic!("expected observation frame");
+    };
+    assert_eq!(Some(observation.logical_step), ack.applied_step);
+    assert!(observation.events.0.iter().any(|event| event.locked));
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"command","seq":2,"ts":1,"mode":"action","actions":["hardDrop"]}),
+    )
+    .await;
+    let BinaryMessage::Error(error) = read_frame(&mut reader).await else {
+        panic!("expected error frame");
+    };
+    assert_eq!(error.seq, 2);
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"subscribe","seq":3,"ts":1,"subscription":{"fields":["state_hash"]}}),
+    )
+    .await;
+    let BinaryMessage::Error(error) = read_frame(&mut reader).await else {
+        panic!("expected error frame");
+    };
+    assert_eq!(error.seq, 3);
+    assert_eq!(error.code, ErrorCode::InvalidCommand);
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn json_only_client_keeps_json_lines() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+
+    support::write_json_line(&mut writer, &create_hello(1, "json", "3.0.0")).await;
+    let welcome = support::read_json_line(&mut lines).await;
+    assert_eq!(welcome["format"], "json");
+    assert_eq!(
+        support::read_json_line(&mut lines).await["type"],
+        "observation"
+    );
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn binary_hello_with_a_field_filter_is_rejected() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+
+    let mut hello = create_hello(1, "binary-fields", "3.0.0");
+    hello.formats = FormatsList {
+        json: true,
+        binary: true,
+    };
+    hello.requested.subscription.fields = Some(vec![CapabilityFeature::StateHash]);
+    support::write_json_line(&mut writer, &hello).await;
+    let error = support::read_json_line(&mut lines).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["code"], "invalid_command");
+
+    engine.abort();
+    server.abort();
+}
@@ -0,0 +1,148 @@
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_adapter_protocol::protocol::{ControlPromotionOrder, RequestedRole};
+use tokio::net::tcp::OwnedWriteHalf;
+
+mod support;
+use support::{ClientLines, agent_hello, read_json_line, spawn_server};
+
+async fn control(writer: &mut OwnedWriteHalf, seq: u64, action: &str, to: Option<u64>) {
+    let mut message = serde_json::json!({"type":"control","seq":seq,"ts":1,"action":action});
+    if let Some(to) = to {
+        message["to_client_id"] = to.into();
+    }
+    support::write_json_line(writer, &message).await;
+}
+
+async fn control_state(lines: &mut ClientLines, reason: &str) -> serde_json::Value {
+    let state = read_json_line(lines).await;
+    assert_eq!(state["type"], "control_state", "{state}");
+    assert_eq!(state["reason"], reason, "{state}");
+    state
+}
+
+#[tokio::test]
+async fn fifo_queue_release_and_handoff_are_announced_to_every_client() {
+    let config = ServerConfig {
+        promotion_order: ControlPromotionOrder::Fifo,
+        ..support::server_config()
+    };
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(config, 8).await;
+
+    let (mut a, mut a_writer, welcome) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    assert_eq!(welcome["role"], "controller");
+    let policy = &welcome["capabilities"]["control_policy"];
+    assert_eq!(policy["promotion_order"], "fifo");
+    assert_eq!(policy["handoff"], true);
+    assert!(policy.get("lease_steps").is_none());
+
+    // An observer only waits for control once it claims.
+    let (mut b, mut b_writer, welcome) = agent_hello(addr, RequestedRole::Observer, |_| {}).await;
+    assert_eq!(welcome["role"], "observer");
+    let (mut c, mut c_writer, welcome) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    assert_eq!(welcome["role"], "observer");
+    for lines in [&mut a, &mut b] {
+        let state = control_state(lines, "hello").await;
+        assert_eq!(state["controller_id"], 1);
+        assert_eq!(state["waiting"], serde_json::json!([3]));
+    }
+
+    control(&mut b_writer, 2, "claim", None).await;
+    assert_eq!(read_json_line(&mut b).await["type"], "ack");
+    for lines in [&mut a, &mut b, &mut c] {
+        let state = control_state(lines, "claim").await;
+        assert_eq!(state["waiting"], serde_json::json!([3, 2]));
+    }
+
+    // Release passes control to the longest-waiting client.
+    control(&mut a_writer, 2, "release", None).await;
+    assert_eq!(read_json_line(&mut a).await["type"], "ack");
+    for lines in [&mut a, &mut b, &mut c] {
+        let state = control_state(lines, "release").await;
+        assert_eq!(state["controller_id"], 3);
+        assert_eq!(state["waiting"], serde_json::json!([2]));
+    }
+
+    control(&mut a_writer, 3, "handoff", Some(2)).await;
+    assert_eq!(read_json_line(&mut a).await["code"], "not_controller");
+    control(&mut c_writer, 2, "handoff", Some(99)).await;
+    assert_eq!(read_json_line(&mut c).await["code"], "invalid_command");
+
+    // The former controller goes to the back of the queue.
+    control(&mut c_writer, 3, "handoff", Some(1)).await;
+    assert_eq!(read_json_line(&mut c).await["type"], "ack");
+    for lines in [&mut a, &mut b, &mut c] {
+        let state = control_state(lines, "handoff").await;
+        assert_eq!(state["controller_id"], 1);
+        assert_eq!(state["waiting"], serde_json::json!([2, 3]));
+        assert_eq!(state["seq"], 5);
+    }
+
+    drop(b_writer);
+    drop(b);
+    for lines in [&mut a, &mut c] {
+        let state = control_state(lines, "disconnect").await;
+        assert_eq!(state["controller_id"], 1);
+        assert_eq!(state["waiting"], serde_json::json!([3]));
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn idle_leases_pass_control_by_token_priority() {
+    let config = ServerConfig {
+        auth_token: Some("plain".to_string()),
+        priority_tokens: vec![("gold".to_string(), 10), ("silver".to_string(), 5)],
+        promotion_order: ControlPromotionOrder::TokenPriority,
+        lease_steps: Some(30),
+        ..support::server_config()
+    };
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(config, 8).await;
+
+    // Leases count logical steps, so the test uses a server-hosted room.
+    let (mut host, mut host_writer, welcome) = agent_hello(addr, RequestedRole::Auto, |hello| {
+        hello.token = Some("silver".into())
+    })
+    .await;
+    assert_eq!(
+        welcome["capabilities"]["control_policy"]["promotion_order"],
+        "token_priority"
+    );
+    assert_eq!(welcome["capabilities"]["control_policy"]["lease_steps"], 30);
+    support::write_json_line(
+        &mut host_writer,
+        &serde_json::json!({"type":"room","seq":2,"ts":1,"action":"create","name":"arena"}),
+    )
+    .await;
+    // Joining the new room applies the hello role policy before the reply.
+    let state = control_state(&mut host, "hello").await;
+    assert_eq!(state["controller_id"], 1);
+    let state = read_json_line(&mut host).await;
+    assert_eq!(state["type"], "room_state");
+    assert_eq!(state["role"], "controller");
+
+    let (_low, _low_writer, _) = agent_hello(addr, RequestedRole::Auto, |hello| {
+        hello.token = Some("plain".into());
+        hello.requested.room = Some("arena".into());
+    })
+    .await;
+    let state = control_state(&mut host, "hello").await;
+    assert_eq!(state["waiting"], serde_json::json!([2]));
+    let (mut gold, _gold_writer, _) = agent_hello(addr, RequestedRole::Auto, |hello| {
+        hello.token = Some("gold".into());
+        hello.requested.room = Some("arena".into());
+    })
+    .await;
+    let state = control_state(&mut host, "hello").await;
+    assert_eq!(state["waiting"], serde_json::json!([3, 2]));
+
+    // The idle host loses control to the highest priority, then still ranks
+    // above the plain token.
+    for lines in [&mut host, &mut gold] {
+        let state = control_state(lines, "lease_expired").await;
+        assert_eq!(state["controller_id"], 3);
+        assert_eq!(state["waiting"], serde_json::json!([1, 2]));
+    }
+
+    server.abort();
+}
@@ -0,0 +1,116 @@
+use tetris_adapter_protocol::delta::DeltaDecoder;
+use tetris_adapter_protocol::protocol::{ObservationMessage, create_hello};
+
+mod support;
+use support::{ClientLines, read_json_line, read_line, spawn_server};
+
+/// Read one observation line, returning its raw JSON and the rebuilt message.
+async fn read_observation(
+    lines: &mut ClientLines,
+    decoder: &mut DeltaDecoder,
+) -> (serde_json::Value, ObservationMessage) {
+    let line = read_line(lines).await;
+    let raw: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(raw["type"], "observation", "{raw}");
+    let observation = decoder.apply(&line).expect("delta stream reconstructs");
+    (raw, observation)
+}
+
+#[tokio::test]
+async fn delta_observer_reconstructs_state_verified_by_keyframe_hash() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(11, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+
+    let mut hello = create_hello(1, "delta", "3.0.0");
+    hello.requested.delta_observations = true;
+    support::write_json_line(&mut writer, &hello).await;
+    let welcome = read_json_line(&mut lines).await;
+    assert_eq!(welcome["delta_observations"], true);
+    assert_eq!(welcome["capabilities"]["delta_observations"], true);
+
+    let mut decoder = DeltaDecoder::new();
+    let (raw, _) = read_observation(&mut lines, &mut decoder).await;
+    assert_eq!(raw["keyframe"], true);
+    assert!(raw.get("board").is_some());
+
+    let mut seq = 1;
+    let mut unchanged = 0;
+    let mut row_deltas = 0;
+    let mut last = None;
+    for actions in std::iter::repeat_n(["hardDrop", "moveLeft"], 6).flatten() {
+        seq += 1;
+        support::write_json_line(
+            &mut writer,
+            &serde_json::json!({"type":"command","seq":seq,"ts":1,"mode":"action","actions":[actions]}),
+        )
+        .await;
+        let ack = read_json_line(&mut lines).await;
+        assert_eq!(ack["type"], "ack", "{ack}");
+        let (raw, observation) = read_observation(&mut lines, &mut decoder).await;
+        assert_eq!(
+            serde_json::to_value(observation.state_hash).unwrap(),
+            ack["state_hash"]
+        );
+        if raw.get("board").is_none() {
+            if raw.get("board_delta").is_some() {
+                row_deltas += 1;
+            } else {
+                unchanged += 1;
+            }
+        }
+        last = Some(observation);
+    }
+    assert!(unchanged > 0, "moves without a lock omit the board");
+    assert!(row_deltas > 0, "locks send changed rows only");
+
+    seq += 1;
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"resync","seq":seq,"ts":1}),
+    )
+    .await;
+    let ack = read_json_line(&mut lines).await;
+    assert_eq!(ack["type"], "ack");
+    assert_eq!(ack["correlation_seq"], seq);
+    let (raw, keyframe) = read_observation(&mut lines, &mut decoder).await;
+    assert_eq!(raw["keyframe"], true);
+
+    // The script ends on an event-free move, so an equal state hash means the
+    // same game state: the reconstructed board must match the keyframe.
+    let last = last.unwrap();
+    assert_eq!(keyframe.state_hash, last.state_hash);
+    assert_eq!(keyframe.board.cells, last.board.cells);
+    assert_eq!(keyframe.board_id, last.board_id);
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn resync_without_delta_observations_is_rejected() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(11, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+
+    support::write_json_line(&mut writer, &create_hello(1, "full", "3.0.0")).await;
+    assert_eq!(
+        read_json_line(&mut lines).await["delta_observations"],
+        false
+    );
+    let snapshot = read_json_line(&mut lines).await;
+    assert!(snapshot.get("keyframe").is_none());
+    assert!(snapshot.get("board").is_some());
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"resync","seq":2,"ts":1}),
+    )
+    .await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "invalid_command");
+    assert_eq!(error["seq"], 2);
+
+    engine.abort();
+    server.abort();
+}
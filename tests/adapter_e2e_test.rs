@@ -68,7 +68,7 @@ fn adapter_start_returns_the_actual_ephemeral_address() {
     })
     .unwrap();
 
-    let address = adapter.listen_addr();
+    let address = adapter.listen_addr().expect("tcp adapter");
     assert_ne!(address.port(), 0);
 }
 
@@ -185,7 +185,7 @@ async fn adapter_wire_logging_writes_raw_frames() {
     // hello
     let hello = create_hello(1, "wire-log-test", "3.0.0");
     let hello_line = serde_json::to_string(&hello).unwrap();
-    support::write_raw_line(&mut write_half, hello_line).await;
+    support::write_raw_line(&mut write_half, hello_line.clone()).await;
 
     let welcome_line = tokio::time::timeout(Duration::from_secs(2), lines.next_line())
         .await
@@ -205,14 +205,21 @@ async fn adapter_wire_logging_writes_raw_frames() {
             && contents.contains("\"type\":\"hello\"")
             && contents.contains("\"type\":\"welcome\"")
         {
-            // Ensure raw JSON lines only (no prefixes).
+            // One JSON envelope per line around the raw message.
             for line in contents.lines() {
                 if line.trim().is_empty() {
                     continue;
                 }
-                assert!(line.starts_with('{'));
-                assert!(line.ends_with('}'));
+                let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                assert!(entry["t_us"].as_u64().unwrap() > 0);
+                assert!(entry["client"].is_u64());
+                let expected = match entry["msg"]["type"].as_str() {
+                    Some("hello") => "in",
+                    _ => "out",
+                };
+                assert_eq!(entry["dir"], expected, "{line}");
             }
+            assert!(contents.contains(&format!(r#""msg":{hello_line}}}"#)));
             break;
         }
 
@@ -451,6 +458,7 @@ async fn adapter_emits_status_updates_on_connect_and_controller() {
         client_count: 0,
         controller_id: None,
         streaming_count: 0,
+        lockstep: false,
     });
 
     let server_handle = tokio::spawn(async move {
@@ -588,7 +596,7 @@ async fn adapter_hello_snapshot_request_waits_for_bounded_queue_capacity() {
 fn production_session_replies_through_the_originating_client_mailbox() {
     let config = support::server_config_with_capacity(8);
     let mut adapter = Some(Adapter::start(config).unwrap());
-    let addr = adapter.as_ref().unwrap().listen_addr();
+    let addr = adapter.as_ref().unwrap().listen_addr().unwrap();
     let mut stream = std::net::TcpStream::connect(addr).unwrap();
     stream
         .set_read_timeout(Some(Duration::from_secs(2)))
@@ -614,10 +622,26 @@ fn production_session_replies_through_the_originating_client_mailbox() {
         .unwrap();
     stream.write_all(b"\n").unwrap();
     stream.flush().unwrap();
-    std::thread::sleep(Duration::from_millis(10));
-    step_session(&mut adapter, &mut session, &mut observations, &[], true);
+    // The client task forwards the command asynchronously; step until the
+    // session applies it instead of guessing how long forwarding takes.
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while step_session(&mut adapter, &mut session, &mut observations, &[], true)
+        .command_outcomes
+        .is_empty()
+    {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "command never reached the session"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
 
-    let ack = read_std_json_line(&mut reader);
+    let ack = loop {
+        let message = read_std_json_line(&mut reader);
+        if message["type"] != "observation" {
+            break message;
+        }
+    };
     assert_eq!(ack["type"], "ack");
     assert_eq!(ack["seq"], 2);
 }
@@ -855,6 +879,7 @@ async fn controller_disconnect_promotes_next_client() {
         client_count: 0,
         controller_id: None,
         streaming_count: 0,
+        lockstep: false,
     });
 
     let server_handle = tokio::spawn(async move {
@@ -0,0 +1,358 @@
+use std::io::{BufRead as _, Write as _};
+use std::time::{Duration, Instant};
+
+use tetris_adapter::adapter::Adapter;
+use tetris_adapter::adapter::game_loop::step_session;
+use tetris_adapter::adapter::observation_schedule::ObservationSchedule;
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_adapter_protocol::protocol::{CommandMode, MAX_LOCKSTEP_STEPS, create_hello};
+use tetris_core::types::GameAction;
+use tetris_session::engine::replay::{
+    META_DATE, META_MODE, META_PLAYER, META_PLAYER_VERSION, ReplayTape, replay_and_verify,
+};
+use tetris_session::engine::session::{SessionRuntime, StepInput};
+
+mod support;
+use support::{ClientLines, read_json_line, spawn_server};
+
+fn read_std_json_line(reader: &mut std::io::BufReader<std::net::TcpStream>) -> serde_json::Value {
+    let mut line = String::new();
+    reader.read_line(&mut line).expect("adapter line");
+    serde_json::from_str(&line).expect("adapter returned invalid JSON")
+}
+
+fn write_std_line(stream: &mut std::net::TcpStream, value: &serde_json::Value) {
+    stream
+        .write_all(serde_json::to_string(value).unwrap().as_bytes())
+        .unwrap();
+    stream.write_all(b"\n").unwrap();
+    stream.flush().unwrap();
+}
+
+/// Run the shared step until it answers one request or the deadline passes.
+fn step_until_answered(
+    adapter: &mut Option<Adapter>,
+    session: &mut SessionRuntime,
+    observations: &mut ObservationSchedule,
+) {
+    let before = session.logical_step();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while session.logical_step() == before {
+        assert!(Instant::now() < deadline, "lockstep request never applied");
+        step_session(adapter, session, observations, &[], true);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Wait until the broker granted lockstep; the mode change is queued to the
+/// game loop by then, which switches clocks on its next step.
+fn wait_for_lockstep_status(adapter: &mut Option<Adapter>) {
+    let adapter = adapter.as_mut().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !adapter
+        .try_recv_status()
+        .is_some_and(|status| status.lockstep)
+    {
+        assert!(Instant::now() < deadline, "lockstep status never published");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn lockstep_controller_owns_the_session_clock() {
+    let config = support::server_config_with_capacity(8);
+    let mut adapter = Some(Adapter::start(config).unwrap());
+    let addr = adapter.as_ref().unwrap().listen_addr().unwrap();
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+
+    let mut hello = create_hello(1, "lockstep", "3.0.0");
+    hello.requested.lockstep = true;
+    write_std_line(&mut stream, &serde_json::to_value(&hello).unwrap());
+    let welcome = read_std_json_line(&mut reader);
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["lockstep"], true);
+    assert_eq!(welcome["capabilities"]["lockstep"], true);
+
+    let mut session = SessionRuntime::new(1);
+    let mut observations = ObservationSchedule::new(session.game(), 20);
+    wait_for_lockstep_status(&mut adapter);
+
+    // Wall-clock steps and local input do not move a lockstep session.
+    let piece_id = session.game().piece_id();
+    for _ in 0..20 {
+        step_session(
+            &mut adapter,
+            &mut session,
+            &mut observations,
+            &[GameAction::HardDrop],
+            true,
+        );
+    }
+    assert!(adapter.as_ref().unwrap().lockstep());
+    assert_eq!(session.logical_step(), 0);
+    assert_eq!(session.game().piece_id(), piece_id);
+    let snapshot = read_std_json_line(&mut reader);
+    assert_eq!(snapshot["type"], "observation");
+    assert_eq!(snapshot["logical_step"], 0);
+
+    write_std_line(
+        &mut stream,
+        &serde_json::json!({"type":"step","seq":2,"ts":1,"n":5}),
+    );
+    step_until_answered(&mut adapter, &mut session, &mut observations);
+    let ack = read_std_json_line(&mut reader);
+    assert_eq!(ack["type"], "ack");
+    assert_eq!(ack["seq"], 2);
+    assert_eq!(ack["applied_step"], 5);
+    let observation = read_std_json_line(&mut reader);
+    assert_eq!(observation["type"], "observation");
+    assert_eq!(observation["logical_step"], 5);
+    assert_eq!(observation["state_hash"], ack["state_hash"]);
+
+    write_std_line(
+        &mut stream,
+        &serde_json::json!({"type":"command","seq":3,"ts":1,"mode":"action","actions":["hardDrop"]}),
+    );
+    step_until_answered(&mut adapter, &mut session, &mut observations);
+    let ack = read_std_json_line(&mut reader);
+    assert_eq!(ack["seq"], 3);
+    assert_eq!(ack["applied_step"], 6);
+    let observation = read_std_json_line(&mut reader);
+    assert_eq!(observation["logical_step"], 6);
+    assert_eq!(observation["events"][0]["locked"], true);
+    assert_eq!(session.logical_step(), 6);
+}
+
+#[test]
+fn adapter_records_every_applied_step_to_a_verifiable_tape() {
+    let path = std::env::temp_dir().join(format!(
+        "tui-tetris-adapter-record-{}.ttr",
+        std::process::id()
+    ));
+    let mut config = ServerConfig {
+        record_path: Some(path.clone()),
+        ..support::server_config_with_capacity(8)
+    };
+    config.record_metadata.set(META_PLAYER, "recorded-bot");
+    config.record_metadata.set(META_PLAYER_VERSION, "3.0.0");
+    let mut adapter = Some(Adapter::start(config).unwrap());
+    let addr = adapter.as_ref().unwrap().listen_addr().unwrap();
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+
+    let mut hello = create_hello(1, "recorded", "3.0.0");
+    hello.requested.lockstep = true;
+    write_std_line(&mut stream, &serde_json::to_value(&hello).unwrap());
+    assert_eq!(read_std_json_line(&mut reader)["type"], "welcome");
+
+    let mut session = SessionRuntime::new(3);
+    let mut observations = ObservationSchedule::new(session.game(), 20);
+    wait_for_lockstep_status(&mut adapter);
+    step_session(&mut adapter, &mut session, &mut observations, &[], true);
+    assert_eq!(read_std_json_line(&mut reader)["type"], "observation");
+
+    let requests = [
+        serde_json::json!({"type":"step","seq":2,"ts":1,"n":4}),
+        serde_json::json!({"type":"command","seq":3,"ts":1,"mode":"action","actions":["hardDrop"]}),
+        serde_json::json!({"type":"command","seq":4,"ts":1,"mode":"action","actions":["restart"],"restart":{"seed":11}}),
+        serde_json::json!({"type":"command","seq":5,"ts":1,"mode":"place","place":{"x":3,"rotation":"east","useHold":true}}),
+    ];
+    for request in &requests {
+        write_std_line(&mut stream, request);
+        step_until_answered(&mut adapter, &mut session, &mut observations);
+        let ack = read_std_json_line(&mut reader);
+        assert_eq!(ack["type"], "ack", "{ack}");
+        assert_eq!(read_std_json_line(&mut reader)["type"], "observation");
+    }
+
+    // Steps are flushed as they are applied; the adapter is still running.
+    let tape = ReplayTape::decode(&std::fs::read(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(tape.seed(), 3);
+    assert_eq!(tape.metadata().get(META_MODE), Some("adapter"));
+    assert_eq!(tape.metadata().get(META_PLAYER), Some("recorded-bot"));
+    assert_eq!(tape.metadata().get(META_PLAYER_VERSION), Some("3.0.0"));
+    assert!(tape.metadata().get(META_DATE).is_some());
+    assert_eq!(tape.records().len() as u64, session.logical_step());
+    let replayed = replay_and_verify(&tape).expect("recorded tape verifies");
+    assert_eq!(replayed.snapshot(), session.snapshot());
+}
+
+#[test]
+fn recording_a_session_that_already_stepped_reports_why_it_did_not_start() {
+    let path = std::env::temp_dir().join(format!(
+        "tui-tetris-adapter-late-record-{}.ttr",
+        std::process::id()
+    ));
+    let config = ServerConfig {
+        record_path: Some(path.clone()),
+        ..support::server_config_with_capacity(8)
+    };
+    let mut adapter = Some(Adapter::start(config).unwrap());
+    let mut session = SessionRuntime::new(3);
+    let mut observations = ObservationSchedule::new(session.game(), 20);
+    session.transition(&StepInput::default());
+    session.transition(&StepInput::default());
+
+    step_session(&mut adapter, &mut session, &mut observations, &[], true);
+    let error = adapter.as_mut().unwrap().take_recording_error();
+    let recorded = std::fs::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(
+        error
+            .as_deref()
+            .is_some_and(|error| error.contains("step 2")),
+        "{error:?}"
+    );
+    assert!(recorded.is_empty());
+    step_session(&mut adapter, &mut session, &mut observations, &[], true);
+    assert_eq!(adapter.as_mut().unwrap().take_recording_error(), None);
+}
+
+#[test]
+fn lockstep_is_granted_to_the_controller_and_ends_with_its_control() {
+    let config = support::server_config_with_capacity(8);
+    let mut adapter = Some(Adapter::start(config).unwrap());
+    let addr = adapter.as_ref().unwrap().listen_addr().unwrap();
+    let connect = |name: &str| {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let reader = std::io::BufReader::new(stream.try_clone().unwrap());
+        let mut hello = create_hello(1, name, "3.0.0");
+        hello.requested.lockstep = true;
+        hello.requested.stream_observations = false;
+        write_std_line(&mut stream, &serde_json::to_value(&hello).unwrap());
+        (stream, reader)
+    };
+
+    let (mut controller, mut controller_reader) = connect("lockstep-controller");
+    assert_eq!(read_std_json_line(&mut controller_reader)["lockstep"], true);
+    let (observer, mut observer_reader) = connect("lockstep-observer");
+    let welcome = read_std_json_line(&mut observer_reader);
+    assert_eq!(welcome["role"], "observer");
+    assert_eq!(welcome["lockstep"], false);
+    drop(observer);
+
+    let mut session = SessionRuntime::new(1);
+    let mut observations = ObservationSchedule::new(session.game(), 20);
+    wait_for_lockstep_status(&mut adapter);
+    step_session(&mut adapter, &mut session, &mut observations, &[], false);
+    assert!(adapter.as_ref().unwrap().lockstep());
+    assert_eq!(session.logical_step(), 0);
+
+    write_std_line(
+        &mut controller,
+        &serde_json::json!({"type":"control","seq":2,"ts":1,"action":"release"}),
+    );
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while adapter.as_ref().unwrap().lockstep() {
+        assert!(Instant::now() < deadline, "lockstep never ended");
+        step_session(&mut adapter, &mut session, &mut observations, &[], false);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let released_at = session.logical_step();
+    step_session(&mut adapter, &mut session, &mut observations, &[], false);
+    assert_eq!(session.logical_step(), released_at + 1);
+}
+
+async fn lockstep_client(
+    addr: std::net::SocketAddr,
+) -> (ClientLines, tokio::net::tcp::OwnedWriteHalf) {
+    let (mut lines, mut writer) = support::connect(addr).await;
+    let mut hello = create_hello(1, "lockstep-driver", "3.0.0");
+    hello.requested.lockstep = true;
+    hello.requested.command_mode = CommandMode::Place;
+    support::write_json_line(&mut writer, &hello).await;
+    assert_eq!(read_json_line(&mut lines).await["lockstep"], true);
+    assert_eq!(read_json_line(&mut lines).await["type"], "observation");
+    (lines, writer)
+}
+
+/// Replay a fixed script with irregular client think time and collect the
+/// `(logical_step, state_hash)` of every reply observation.
+async fn scripted_trajectory(think_ms: &[u64]) -> Vec<(u64, String)> {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+    let (mut lines, mut writer) = lockstep_client(addr).await;
+
+    let mut trajectory = Vec::new();
+    for (index, &think) in think_ms.iter().enumerate() {
+        tokio::time::sleep(Duration::from_millis(think)).await;
+        let seq = index as u64 + 2;
+        let message = if index % 2 == 0 {
+            serde_json::json!({"type":"step","seq":seq,"ts":1,"n":7})
+        } else {
+            serde_json::json!({"type":"command","seq":seq,"ts":1,"mode":"action","actions":["rotateCw","hardDrop"]})
+        };
+        support::write_json_line(&mut writer, &message).await;
+        let ack = read_json_line(&mut lines).await;
+        assert_eq!(ack["type"], "ack", "{ack}");
+        assert_eq!(ack["seq"], seq);
+        let observation = read_json_line(&mut lines).await;
+        assert_eq!(observation["logical_step"], ack["applied_step"]);
+        trajectory.push((
+            observation["logical_step"].as_u64().unwrap(),
+            observation["state_hash"].as_str().unwrap().to_string(),
+        ));
+    }
+
+    engine.abort();
+    server.abort();
+    trajectory
+}
+
+#[tokio::test]
+async fn lockstep_trajectory_is_independent_of_agent_latency() {
+    let fast = scripted_trajectory(&[0; 8]).await;
+    let slow = scripted_trajectory(&[0, 30, 5, 60, 0, 20, 45, 10]).await;
+    assert_eq!(fast, slow);
+    assert_eq!(fast.last().unwrap().0, 4 * 7 + 4);
+}
+
+#[tokio::test]
+async fn step_is_rejected_outside_lockstep_and_out_of_range() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+
+    let (mut lines, mut writer) = support::connect(addr).await;
+    let hello = create_hello(1, "realtime", "3.0.0");
+    support::write_json_line(&mut writer, &hello).await;
+    assert_eq!(read_json_line(&mut lines).await["lockstep"], false);
+    assert_eq!(read_json_line(&mut lines).await["type"], "observation");
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"step","seq":2,"ts":1,"n":1}),
+    )
+    .await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "invalid_command");
+    assert_eq!(error["seq"], 2);
+    engine.abort();
+    server.abort();
+
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(7, cmd_rx));
+    let (mut lines, mut writer) = lockstep_client(addr).await;
+    for (seq, n) in [(2, 0), (3, MAX_LOCKSTEP_STEPS + 1)] {
+        support::write_json_line(
+            &mut writer,
+            &serde_json::json!({"type":"step","seq":seq,"ts":1,"n":n}),
+        )
+        .await;
+        let error = read_json_line(&mut lines).await;
+        assert_eq!(error["code"], "invalid_command");
+        assert_eq!(error["seq"], seq);
+    }
+
+    engine.abort();
+    server.abort();
+}
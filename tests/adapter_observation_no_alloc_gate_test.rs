@@ -2,6 +2,8 @@ use std::alloc::{GlobalAlloc, Layout, System};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use tetris_adapter::adapter::server::build_observation;
+use tetris_adapter_protocol::binary::write_frame;
+use tetris_adapter_protocol::delta::DeltaEncoder;
 use tetris_core::core::GameState;
 
 struct CountingAlloc;
@@ -58,6 +60,7 @@ fn adapter_observation_build_and_serialize_is_allocation_free() {
     // Pre-allocate a buffer large enough for observation JSON.
     let mut buf: Vec<u8> = Vec::with_capacity(16 * 1024);
     let mut seq: u64 = 1;
+    let mut delta = DeltaEncoder::default();
 
     // Warm-up.
     if gs.board_id() != last_board_id {
@@ -68,6 +71,10 @@ fn adapter_observation_build_and_serialize_is_allocation_free() {
     let obs0 = build_observation(seq, 0, &snap, &[]);
     buf.clear();
     serde_json::to_writer(&mut buf, &obs0).unwrap();
+    buf.clear();
+    write_frame(&obs0, &mut buf);
+    buf.clear();
+    serde_json::to_writer(&mut buf, &delta.encode(&obs0)).unwrap();
 
     let allocs = with_alloc_counting(|| {
         for _ in 0..200 {
@@ -80,6 +87,10 @@ fn adapter_observation_build_and_serialize_is_allocation_free() {
             let obs = build_observation(seq, 0, &snap, &[]);
             buf.clear();
             serde_json::to_writer(&mut buf, &obs).unwrap();
+            buf.clear();
+            write_frame(&obs, &mut buf);
+            buf.clear();
+            serde_json::to_writer(&mut buf, &delta.encode(&obs)).unwrap();
         }
     });
 
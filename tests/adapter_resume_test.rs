@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tetris_adapter::adapter::OutboundMessage;
+use tetris_adapter::adapter::server::{ServerConfig, build_observation};
+use tetris_adapter_protocol::protocol::{HelloMessage, RequestedRole, ResumeRequest};
+use tetris_core::core::GameState;
+use tokio::net::tcp::OwnedWriteHalf;
+
+mod support;
+use support::{agent_hello, read_json_line, spawn_server};
+
+fn resumable_config(grace_ms: u64) -> ServerConfig {
+    ServerConfig {
+        resume_grace_ms: Some(grace_ms),
+        ..support::server_config()
+    }
+}
+
+fn resume(welcome: &serde_json::Value, since_step: Option<u64>) -> Option<ResumeRequest> {
+    Some(ResumeRequest {
+        token: welcome["resume_token"].as_str().unwrap().to_string(),
+        since_step,
+    })
+}
+
+async fn claim(writer: &mut OwnedWriteHalf, seq: u64) {
+    let message = serde_json::json!({"type":"control","seq":seq,"ts":1,"action":"claim"});
+    support::write_json_line(writer, &message).await;
+}
+
+#[tokio::test]
+async fn resumed_controller_keeps_its_id_role_and_seq_window() {
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(resumable_config(5_000), 8).await;
+
+    let (mut lines, mut writer, first) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    assert_eq!(first["role"], "controller");
+    assert_eq!(first["capabilities"]["resume_grace_ms"], 5_000);
+    assert_eq!(first["resume_token"].as_str().unwrap().len(), 32);
+    claim(&mut writer, 5).await;
+    assert_eq!(read_json_line(&mut lines).await["type"], "ack");
+    drop((lines, writer));
+
+    // The seat is held for the detached controller.
+    let (_other, _other_writer, welcome) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    assert_eq!(welcome["role"], "observer");
+    assert_eq!(welcome["controller_id"], 1);
+
+    let (mut lines, mut writer, welcome) = agent_hello(addr, RequestedRole::Auto, |hello| {
+        hello.resume = resume(&first, None)
+    })
+    .await;
+    assert_eq!(welcome["resumed"], true);
+    assert_eq!(welcome["client_id"], 1);
+    assert_eq!(welcome["role"], "controller");
+    assert_eq!(welcome["resume_token"], first["resume_token"]);
+
+    claim(&mut writer, 5).await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "invalid_command", "{error}");
+    claim(&mut writer, 6).await;
+    assert_eq!(read_json_line(&mut lines).await["type"], "ack");
+
+    // An unknown token starts a fresh session.
+    let stale = Some(ResumeRequest {
+        token: "0".repeat(32),
+        since_step: None,
+    });
+    let (_fresh, _fresh_writer, welcome) =
+        agent_hello(addr, RequestedRole::Auto, |hello| hello.resume = stale).await;
+    assert!(welcome.get("resumed").is_none());
+    assert_eq!(welcome["client_id"], 4);
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn resumed_observer_receives_the_observations_it_missed() {
+    let (server, addr, _cmd_rx, out_tx) = spawn_server(resumable_config(5_000), 8).await;
+    let mut game = GameState::new(1);
+    game.start();
+    let snapshot = game.snapshot();
+    let broadcast = |step: u64| {
+        out_tx
+            .send(OutboundMessage::BroadcastObservationArc {
+                obs: Arc::new(build_observation(step, step, &snapshot, &[])),
+            })
+            .unwrap();
+    };
+
+    let (mut lines, writer, first) = agent_hello(addr, RequestedRole::Observer, |hello| {
+        hello.requested.stream_observations = true
+    })
+    .await;
+    broadcast(1);
+    assert_eq!(read_json_line(&mut lines).await["logical_step"], 1);
+    drop((lines, writer));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    for step in 2..=4 {
+        broadcast(step);
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let (mut lines, _writer, welcome) = agent_hello(addr, RequestedRole::Observer, |hello| {
+        hello.resume = resume(&first, Some(1))
+    })
+    .await;
+    assert_eq!(welcome["resumed"], true);
+    for step in 2..=4 {
+        let obs = read_json_line(&mut lines).await;
+        assert_eq!(obs["type"], "observation");
+        assert_eq!(obs["logical_step"], step);
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn expired_sessions_release_control_and_cannot_resume() {
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(resumable_config(100), 8).await;
+
+    let (lines, writer, first) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    let (mut waiting, _waiting_writer, _) = agent_hello(addr, RequestedRole::Auto, |_| {}).await;
+    drop((lines, writer));
+
+    let state = read_json_line(&mut waiting).await;
+    assert_eq!(state["type"], "control_state", "{state}");
+    assert_eq!(state["reason"], "disconnect");
+    assert_eq!(state["controller_id"], 2);
+
+    let (_late, _late_writer, welcome) = agent_hello(addr, RequestedRole::Auto, |hello| {
+        hello.resume = resume(&first, None)
+    })
+    .await;
+    assert!(welcome.get("resumed").is_none());
+    assert_eq!(welcome["client_id"], 3);
+    assert_eq!(welcome["role"], "observer");
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn resuming_with_an_observer_token_gives_up_control() {
+    let config = ServerConfig {
+        auth_token: Some("full".to_string()),
+        observer_token: Some("watch".to_string()),
+        ..resumable_config(5_000)
+    };
+    let (server, addr, _cmd_rx, _out_tx) = spawn_server(config, 8).await;
+
+    let with_token = |token: &str, resume: Option<ResumeRequest>| {
+        let token = Some(token.to_string());
+        move |hello: &mut HelloMessage| {
+            hello.requested.control_events = false;
+            hello.token = token;
+            hello.resume = resume;
+        }
+    };
+    let (lines, writer, first) =
+        agent_hello(addr, RequestedRole::Controller, with_token("full", None)).await;
+    assert_eq!(first["role"], "controller");
+    drop((lines, writer));
+    let (_waiting, _waiting_writer, welcome) =
+        agent_hello(addr, RequestedRole::Controller, with_token("full", None)).await;
+    assert_eq!(welcome["role"], "observer");
+    let waiting_id = welcome["client_id"].clone();
+
+    let resumed = with_token("watch", resume(&first, None));
+    let (mut lines, mut writer, welcome) =
+        agent_hello(addr, RequestedRole::Controller, resumed).await;
+    assert_eq!(welcome["resumed"], true);
+    assert_eq!(welcome["client_id"], first["client_id"]);
+    assert_eq!(welcome["role"], "observer");
+    assert_eq!(welcome["controller_id"], waiting_id);
+
+    claim(&mut writer, 2).await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "unauthorized", "{error}");
+    let drop_piece = serde_json::json!({
+        "type": "command", "seq": 3, "ts": 1, "mode": "action", "actions": ["hardDrop"]
+    });
+    support::write_json_line(&mut writer, &drop_piece).await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "not_controller", "{error}");
+
+    server.abort();
+}
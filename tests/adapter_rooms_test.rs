@@ -0,0 +1,126 @@
+use tetris_adapter_protocol::protocol::create_hello;
+
+mod support;
+use support::{ClientLines, read_json_line, spawn_server};
+
+/// Read lines until one of type `ty` arrives, skipping streamed observations.
+async fn next_of_type(lines: &mut ClientLines, ty: &str) -> serde_json::Value {
+    loop {
+        let value = read_json_line(lines).await;
+        if value["type"] == ty {
+            return value;
+        }
+        assert_eq!(value["type"], "observation", "unexpected {value}");
+    }
+}
+
+fn room(seq: u64, action: &str, name: Option<&str>) -> serde_json::Value {
+    let mut message = serde_json::json!({"type":"room","seq":seq,"ts":1,"action":action});
+    if let Some(name) = name {
+        message["name"] = name.into();
+    }
+    message
+}
+
+#[tokio::test]
+async fn clients_create_join_list_and_leave_rooms() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(3, cmd_rx));
+
+    let (mut host, mut host_writer) = support::connect(addr).await;
+    support::write_json_line(&mut host_writer, &create_hello(1, "host", "3.0.0")).await;
+    let welcome = read_json_line(&mut host).await;
+    assert_eq!(welcome["game_id"], "tui-tetris");
+    assert_eq!(welcome["capabilities"]["rooms"], true);
+
+    let mut create = room(2, "create", Some("arena"));
+    create["seed"] = 7.into();
+    support::write_json_line(&mut host_writer, &create).await;
+    let state = next_of_type(&mut host, "room_state").await;
+    assert_eq!(state["correlation_seq"], 2);
+    assert_eq!(state["room"], "arena");
+    assert_eq!(state["role"], "controller");
+
+    // The hosted game is driven by the server, not the host process.
+    support::write_json_line(
+        &mut host_writer,
+        &serde_json::json!({"type":"command","seq":3,"ts":1,"mode":"action","actions":["hardDrop"]}),
+    )
+    .await;
+    assert_eq!(next_of_type(&mut host, "ack").await["correlation_seq"], 3);
+
+    let (mut guest, mut guest_writer) = support::connect(addr).await;
+    let mut hello = create_hello(1, "guest", "3.0.0");
+    hello.requested.room = Some("arena".to_string());
+    support::write_json_line(&mut guest_writer, &hello).await;
+    let welcome = read_json_line(&mut guest).await;
+    assert_eq!(welcome["game_id"], "arena");
+    assert_eq!(welcome["role"], "observer");
+
+    support::write_json_line(&mut guest_writer, &room(2, "list", None)).await;
+    let listed = next_of_type(&mut guest, "room_state").await;
+    let rooms = listed["rooms"].as_array().unwrap();
+    assert_eq!(rooms.len(), 2);
+    assert_eq!(rooms[0]["name"], "tui-tetris");
+    assert_eq!(rooms[0]["clients"], 0);
+    assert_eq!(rooms[1]["name"], "arena");
+    assert_eq!(rooms[1]["seed"], 7);
+    assert_eq!(rooms[1]["clients"], 2);
+
+    // The host leaves; the guest is promoted in the arena.
+    support::write_json_line(&mut host_writer, &room(4, "leave", None)).await;
+    let state = next_of_type(&mut host, "room_state").await;
+    assert_eq!(state["room"], "tui-tetris");
+    assert_eq!(state["role"], "controller");
+
+    support::write_json_line(&mut guest_writer, &room(3, "join", Some("arena"))).await;
+    let state = next_of_type(&mut guest, "room_state").await;
+    assert_eq!(state["role"], "controller");
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn room_requests_are_validated() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(3, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+    support::write_json_line(&mut writer, &create_hello(1, "agent", "3.0.0")).await;
+    assert_eq!(read_json_line(&mut lines).await["type"], "welcome");
+
+    for (seq, message) in [
+        (2, room(2, "join", Some("missing"))),
+        (3, room(3, "create", Some("bad name"))),
+        (4, room(4, "create", None)),
+        (5, room(5, "leave", None)),
+    ] {
+        support::write_json_line(&mut writer, &message).await;
+        let error = next_of_type(&mut lines, "error").await;
+        assert_eq!(error["code"], "invalid_command");
+        assert_eq!(error["seq"], seq);
+    }
+
+    support::write_json_line(&mut writer, &room(6, "create", Some("duel"))).await;
+    assert_eq!(next_of_type(&mut lines, "room_state").await["room"], "duel");
+    support::write_json_line(&mut writer, &room(7, "create", Some("duel"))).await;
+    let error = next_of_type(&mut lines, "error").await;
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("already exists")
+    );
+
+    // The room closes once its last client leaves.
+    support::write_json_line(&mut writer, &room(8, "leave", None)).await;
+    assert_eq!(
+        next_of_type(&mut lines, "room_state").await["room"],
+        "tui-tetris"
+    );
+    support::write_json_line(&mut writer, &room(9, "join", Some("duel"))).await;
+    assert_eq!(next_of_type(&mut lines, "error").await["seq"], 9);
+
+    engine.abort();
+    server.abort();
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tetris_adapter::adapter::OutboundMessage;
+use tetris_adapter::adapter::server::build_observation;
+use tetris_adapter_protocol::protocol::{CapabilityFeature, Subscription, create_hello};
+use tetris_core::core::GameState;
+
+mod support;
+use support::{ClientLines, read_json_line, spawn_server};
+
+async fn subscribed_client(
+    addr: std::net::SocketAddr,
+    name: &str,
+    subscription: Subscription,
+) -> (ClientLines, tokio::net::tcp::OwnedWriteHalf) {
+    let (mut lines, mut writer) = support::connect(addr).await;
+    let mut hello = create_hello(1, name, "3.0.0");
+    hello.requested.role = Some(tetris_adapter_protocol::protocol::RequestedRole::Observer);
+    hello.requested.subscription = subscription;
+    support::write_json_line(&mut writer, &hello).await;
+    assert_eq!(read_json_line(&mut lines).await["type"], "welcome");
+    (lines, writer)
+}
+
+/// Collect observation seqs until the stream stays quiet for `idle`.
+async fn drain_seqs(lines: &mut ClientLines, idle: Duration) -> Vec<u64> {
+    let mut seqs = Vec::new();
+    while let Ok(line) = tokio::time::timeout(idle, lines.next_line()).await {
+        let line = line.unwrap().expect("adapter closed");
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["type"], "observation");
+        seqs.push(value["seq"].as_u64().unwrap());
+    }
+    seqs
+}
+
+#[tokio::test]
+async fn field_filter_omits_unselected_features() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(3, cmd_rx));
+    let subscription = Subscription {
+        fields: Some(vec![CapabilityFeature::StateHash, CapabilityFeature::Score]),
+        ..Subscription::default()
+    };
+    let (mut lines, _writer) = subscribed_client(addr, "filtered", subscription).await;
+
+    let snapshot = read_json_line(&mut lines).await;
+    assert_eq!(snapshot["type"], "observation");
+    for kept in [
+        "seq",
+        "board",
+        "active",
+        "state_hash",
+        "score",
+        "level",
+        "lines",
+    ] {
+        assert!(snapshot.get(kept).is_some(), "{kept} is kept");
+    }
+    for dropped in ["timers", "next_queue", "events", "logical_step", "board_id"] {
+        assert!(snapshot.get(dropped).is_none(), "{dropped} is filtered");
+    }
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn events_only_subscription_skips_observations_without_a_lock() {
+    let (server, addr, cmd_rx, _out_tx) = spawn_server(support::server_config(), 8).await;
+    let engine = tokio::spawn(support::driver_task(3, cmd_rx));
+    let (mut lines, mut writer) = support::connect(addr).await;
+    support::write_json_line(&mut writer, &create_hello(1, "agent", "3.0.0")).await;
+    assert_eq!(read_json_line(&mut lines).await["role"], "controller");
+    assert_eq!(read_json_line(&mut lines).await["type"], "observation");
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"subscribe","seq":2,"ts":1,"subscription":{"events_only":true}}),
+    )
+    .await;
+    let ack = read_json_line(&mut lines).await;
+    assert_eq!(ack["type"], "ack");
+    assert_eq!(ack["correlation_seq"], 2);
+    // The first observation after subscribing is always delivered.
+    assert_eq!(read_json_line(&mut lines).await["type"], "observation");
+
+    for (seq, action) in [(3, "moveLeft"), (4, "hardDrop")] {
+        support::write_json_line(
+            &mut writer,
+            &serde_json::json!({"type":"command","seq":seq,"ts":1,"mode":"action","actions":[action]}),
+        )
+        .await;
+        let ack = read_json_line(&mut lines).await;
+        assert_eq!(ack["type"], "ack", "moveLeft produced no observation");
+        assert_eq!(ack["seq"], seq);
+    }
+    let locked = read_json_line(&mut lines).await;
+    assert_eq!(locked["type"], "observation");
+    assert_eq!(locked["events"][0]["locked"], true);
+
+    support::write_json_line(
+        &mut writer,
+        &serde_json::json!({"type":"subscribe","seq":5,"ts":1,"subscription":{"hz":0}}),
+    )
+    .await;
+    let error = read_json_line(&mut lines).await;
+    assert_eq!(error["code"], "invalid_command");
+    assert_eq!(error["seq"], 5);
+
+    engine.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn rate_limits_apply_per_client_and_keep_the_latest_state() {
+    let (server, addr, _cmd_rx, out_tx) = spawn_server(support::server_config(), 8).await;
+    let slow = Subscription {
+        hz: Some(4),
+        ..Subscription::default()
+    };
+    let (mut slow_lines, _slow_writer) = subscribed_client(addr, "slow", slow).await;
+    let (mut fast_lines, _fast_writer) =
+        subscribed_client(addr, "fast", Subscription::default()).await;
+
+    let mut game = GameState::new(1);
+    game.start();
+    let snapshot = game.snapshot();
+    for seq in 1..=30 {
+        out_tx
+            .send(OutboundMessage::BroadcastObservationArc {
+                obs: Arc::new(build_observation(seq, seq, &snapshot, &[])),
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let slow = drain_seqs(&mut slow_lines, Duration::from_millis(600)).await;
+    let fast = drain_seqs(&mut fast_lines, Duration::from_millis(200)).await;
+    assert!(slow.len() <= 4, "4 Hz over ~300ms: {slow:?}");
+    assert!(fast.len() > slow.len(), "unlimited client: {fast:?}");
+    assert_eq!(slow.last(), Some(&30), "deferred delivery keeps the latest");
+    assert_eq!(fast.last(), Some(&30));
+
+    server.abort();
+}
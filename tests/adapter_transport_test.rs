@@ -0,0 +1,78 @@
+use std::io::{BufReader, Write as _};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use tetris_adapter::adapter::Adapter;
+use tetris_adapter::adapter::server::{Endpoint, ServerConfig, Transport};
+use tetris_adapter_protocol::protocol::create_hello;
+
+fn hello_line() -> String {
+    let mut line = serde_json::to_string(&create_hello(1, "pipe", "3.0.0")).unwrap();
+    line.push('\n');
+    line
+}
+
+fn read_json(reader: &mut impl std::io::BufRead) -> serde_json::Value {
+    let mut line = String::new();
+    reader.read_line(&mut line).expect("adapter line");
+    serde_json::from_str(&line).expect("adapter returned invalid JSON")
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_socket_transport_serves_the_same_protocol() {
+    let path = std::env::temp_dir().join(format!("tui-tetris-{}.sock", std::process::id()));
+    let adapter = Adapter::start(ServerConfig {
+        transport: Transport::Unix(path.clone()),
+        ..ServerConfig::default()
+    })
+    .unwrap();
+    assert_eq!(adapter.endpoint(), &Endpoint::Unix(path.clone()));
+    assert_eq!(adapter.listen_addr(), None);
+
+    let mut stream = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    stream.write_all(hello_line().as_bytes()).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let welcome = read_json(&mut reader);
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["role"], "controller");
+
+    drop(adapter);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn headless_stdio_serves_one_client_and_exits_when_it_leaves() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tui-tetris"))
+        .args(["headless", "--stdio", "--seed", "4"])
+        .env_remove("TETRIS_AI_DISABLED")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    stdin.write_all(hello_line().as_bytes()).unwrap();
+    let welcome = read_json(&mut stdout);
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["client_id"], 1);
+    let snapshot = read_json(&mut stdout);
+    assert_eq!(snapshot["type"], "observation");
+    assert_eq!(snapshot["seed"], 4);
+
+    drop(stdin);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        assert!(Instant::now() < deadline, "headless stdio did not exit");
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    assert!(status.success());
+}
@@ -0,0 +1,226 @@
+use std::io::{BufRead as _, BufReader, Read as _, Write as _};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use tetris_adapter::adapter::Adapter;
+use tetris_adapter::adapter::game_loop::step_session;
+use tetris_adapter::adapter::observation_schedule::ObservationSchedule;
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_adapter::adapter::websocket::accept_key;
+use tetris_adapter_protocol::protocol::create_hello;
+use tetris_session::engine::session::SessionRuntime;
+
+const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+/// Minimal in-process WebSocket client: masked text frames out, server
+/// frames in.
+struct WsClient {
+    reader: BufReader<TcpStream>,
+    stream: TcpStream,
+}
+
+impl WsClient {
+    fn connect(addr: SocketAddr) -> Self {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        write!(
+            stream,
+            "GET /adapter HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Key: {KEY}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let head = read_head(&mut reader);
+        assert!(head.starts_with("HTTP/1.1 101"), "{head}");
+        assert!(head.contains(&format!("Sec-WebSocket-Accept: {}", accept_key(KEY))));
+        Self { reader, stream }
+    }
+
+    fn send_frame(&mut self, first: u8, payload: &[u8]) {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![first];
+        if payload.len() < 126 {
+            frame.push(0x80 | payload.len() as u8);
+        } else {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream.write_all(&frame).unwrap();
+    }
+
+    fn send_json(&mut self, value: &serde_json::Value) {
+        self.send_frame(0x81, value.to_string().as_bytes());
+    }
+
+    fn read_frame(&mut self) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        self.reader.read_exact(&mut header).unwrap();
+        assert_eq!(header[1] & 0x80, 0, "server frames are unmasked");
+        let len = match header[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                self.reader.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                self.reader.read_exact(&mut len).unwrap();
+                u64::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        self.reader.read_exact(&mut payload).unwrap();
+        (header[0] & 0x0F, payload)
+    }
+
+    fn read_json(&mut self) -> serde_json::Value {
+        let (opcode, payload) = self.read_frame();
+        assert_eq!(opcode, 0x1, "expected a text frame");
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    /// Read messages until one of type `ty`, skipping streamed observations.
+    fn next_of_type(&mut self, ty: &str) -> serde_json::Value {
+        loop {
+            let value = self.read_json();
+            if value["type"] == ty {
+                return value;
+            }
+            assert_eq!(value["type"], "observation", "unexpected {value}");
+        }
+    }
+}
+
+fn read_head(reader: &mut impl std::io::BufRead) -> String {
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        assert!(
+            reader.read_line(&mut head).unwrap() > 0,
+            "connection closed"
+        );
+    }
+    head
+}
+
+/// Start an adapter with a WebSocket listener and drive its game on a thread.
+fn start_adapter() -> (SocketAddr, SocketAddr, Arc<AtomicBool>, JoinHandle<()>) {
+    let adapter = Adapter::start(ServerConfig {
+        port: 0,
+        websocket_port: Some(0),
+        ..ServerConfig::default()
+    })
+    .unwrap();
+    let tcp = adapter.listen_addr().unwrap();
+    let websocket = adapter.websocket_addr().expect("websocket listener");
+    let stop = Arc::new(AtomicBool::new(false));
+    let running = Arc::clone(&stop);
+    let game = std::thread::spawn(move || {
+        let mut adapter = Some(adapter);
+        let mut session = SessionRuntime::new(1);
+        let mut observations = ObservationSchedule::new(session.game(), 20);
+        while !running.load(Ordering::Relaxed) {
+            step_session(&mut adapter, &mut session, &mut observations, &[], true);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    });
+    (tcp, websocket, stop, game)
+}
+
+#[test]
+fn websocket_clients_share_roles_with_tcp_clients() {
+    let (tcp, websocket, stop, game) = start_adapter();
+
+    let mut controller = TcpStream::connect(tcp).unwrap();
+    controller
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut hello = serde_json::to_string(&create_hello(1, "bot", "3.0.0")).unwrap();
+    hello.push('\n');
+    controller.write_all(hello.as_bytes()).unwrap();
+    let mut line = String::new();
+    BufReader::new(controller.try_clone().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    assert!(line.contains("\"role\":\"controller\""));
+
+    // A fragmented hello asking for binary still gets JSON text frames.
+    let mut browser = WsClient::connect(websocket);
+    let mut hello = serde_json::to_value(create_hello(1, "dashboard", "3.0.0")).unwrap();
+    hello["formats"] = serde_json::json!(["json", "binary"]);
+    let text = hello.to_string();
+    let (first, rest) = text.as_bytes().split_at(text.len() / 2);
+    browser.send_frame(0x01, first);
+    browser.send_frame(0x80, rest);
+    let welcome = browser.read_json();
+    assert_eq!(welcome["type"], "welcome");
+    assert_eq!(welcome["role"], "observer");
+    assert_eq!(
+        welcome["capabilities"]["formats"],
+        serde_json::json!(["json"])
+    );
+    assert!(welcome.get("format").is_none_or(|format| format == "json"));
+    assert_eq!(browser.read_json()["type"], "observation");
+
+    browser.send_json(&serde_json::json!({
+        "type": "command", "seq": 2, "ts": 1, "mode": "action", "actions": ["hardDrop"]
+    }));
+    let error = browser.next_of_type("error");
+    assert_eq!(error["code"], "not_controller");
+    assert_eq!(error["seq"], 2);
+
+    // Dropping the TCP controller promotes the browser.
+    drop(controller);
+    std::thread::sleep(Duration::from_millis(50));
+    browser.send_json(&serde_json::json!({
+        "type": "command", "seq": 3, "ts": 1, "mode": "action", "actions": ["hardDrop"]
+    }));
+    let ack = browser.next_of_type("ack");
+    assert_eq!(ack["seq"], 3);
+
+    stop.store(true, Ordering::Relaxed);
+    game.join().unwrap();
+}
+
+#[test]
+fn websocket_listener_answers_pings_and_rejects_bad_upgrades() {
+    let (_tcp, websocket, stop, game) = start_adapter();
+
+    let mut browser = WsClient::connect(websocket);
+    browser.send_frame(0x89, b"hi");
+    assert_eq!(browser.read_frame(), (0xA, b"hi".to_vec()));
+    browser.send_frame(0x82, b"{}");
+    let (opcode, payload) = browser.read_frame();
+    assert_eq!(opcode, 0x8);
+    assert_eq!(payload, 1003u16.to_be_bytes());
+
+    let mut plain = TcpStream::connect(websocket).unwrap();
+    plain
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    plain
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let head = read_head(&mut BufReader::new(plain));
+    assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+
+    // A request head past 8 KiB is refused even if no line ever ends.
+    let mut endless = TcpStream::connect(websocket).unwrap();
+    endless
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut request = b"GET / HTTP/1.1\r\nX-Filler: ".to_vec();
+    request.resize(9 * 1024, b'a');
+    endless.write_all(&request).unwrap();
+    let head = read_head(&mut BufReader::new(endless));
+    assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+
+    stop.store(true, Ordering::Relaxed);
+    game.join().unwrap();
+}
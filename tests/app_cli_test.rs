@@ -1,4 +1,8 @@
-use tui_tetris::app_cli::{AppCommand, HeadlessConfig, parse_app_args, run_batch_headless};
+use std::path::PathBuf;
+
+use tui_tetris::app_cli::{
+    AppCommand, HeadlessConfig, PlayConfig, parse_app_args, parse_play_args, run_batch_headless,
+};
 
 #[test]
 fn headless_and_diagnostic_commands_are_explicit() {
@@ -14,6 +18,7 @@ fn headless_and_diagnostic_commands_are_explicit() {
         Some(AppCommand::Headless(HeadlessConfig {
             seed: 5,
             steps: Some(12),
+            stdio: false,
         }))
     );
     assert_eq!(
@@ -22,11 +27,39 @@ fn headless_and_diagnostic_commands_are_explicit() {
     );
 }
 
+#[test]
+fn stdio_headless_mode_is_a_flag() {
+    assert_eq!(
+        parse_app_args(&[
+            "headless".into(),
+            "--stdio".into(),
+            "--seed".into(),
+            "3".into()
+        ])
+        .unwrap(),
+        Some(AppCommand::Headless(HeadlessConfig {
+            seed: 3,
+            steps: None,
+            stdio: true,
+        }))
+    );
+    assert!(
+        parse_app_args(&[
+            "headless".into(),
+            "--stdio".into(),
+            "--steps".into(),
+            "5".into()
+        ])
+        .is_err()
+    );
+}
+
 #[test]
 fn finite_headless_mode_is_deterministic_and_terminates() {
     let config = HeadlessConfig {
         seed: 8,
         steps: Some(100),
+        stdio: false,
     };
     let first = run_batch_headless(config).unwrap();
     let second = run_batch_headless(config).unwrap();
@@ -34,3 +67,24 @@ fn finite_headless_mode_is_deterministic_and_terminates() {
     assert!(first.contains("steps=100"));
     assert!(first.contains("state_hash="));
 }
+
+#[test]
+fn interactive_game_accepts_only_a_record_path_and_player() {
+    assert_eq!(parse_play_args(&[]).unwrap(), PlayConfig::default());
+    assert_eq!(
+        parse_play_args(&["--record".into(), "best.ttr".into()]).unwrap(),
+        PlayConfig {
+            record: Some(PathBuf::from("best.ttr")),
+            player: None,
+        }
+    );
+    assert_eq!(
+        parse_play_args(&["--player".into(), "ada".into()]).unwrap(),
+        PlayConfig {
+            record: None,
+            player: Some("ada".into()),
+        }
+    );
+    assert!(parse_play_args(&["--record".into()]).is_err());
+    assert!(parse_play_args(&["--seed".into(), "3".into()]).is_err());
+}
@@ -134,6 +134,7 @@ fn workspace_crates_own_their_sources_without_cross_tree_path_indirection() {
         "crates/tetris-adapter-protocol/src",
         "crates/tetris-adapter/src",
         "crates/tetris-terminal/src",
+        "crates/tetris-ffi/src",
     ] {
         for path in rust_sources(Path::new(manifest_root)) {
             let source = fs::read_to_string(&path).unwrap();
@@ -153,6 +154,7 @@ fn workspace_members_do_not_reexport_dependency_layers() {
         "crates/tetris-adapter-protocol/src",
         "crates/tetris-adapter/src",
         "crates/tetris-terminal/src",
+        "crates/tetris-ffi/src",
     ] {
         for path in rust_sources(Path::new(manifest_root)) {
             let source = fs::read_to_string(&path).unwrap();
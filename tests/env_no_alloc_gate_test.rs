@@ -0,0 +1,92 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tetris_core::types::Rotation;
+use tetris_session::engine::env::{ActionSpace, EnvAction, ScoreDeltaReward, TetrisEnv};
+use tetris_session::engine::place::Placement;
+use tetris_session::engine::vec_env::{OBS_LEN, VecEnv, VecStepResult};
+
+struct CountingAlloc;
+
+static COUNT_ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOC_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe {
+            if COUNT_ENABLED.load(Ordering::Relaxed) {
+                let _ = layout;
+                ALLOC_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            System.alloc(layout)
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe {
+            if COUNT_ENABLED.load(Ordering::Relaxed) {
+                let _ = (layout, new_size);
+                ALLOC_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+fn with_alloc_counting<F: FnOnce()>(f: F) -> usize {
+    ALLOC_COUNT.store(0, Ordering::Relaxed);
+    COUNT_ENABLED.store(true, Ordering::Relaxed);
+    f();
+    COUNT_ENABLED.store(false, Ordering::Relaxed);
+    ALLOC_COUNT.load(Ordering::Relaxed)
+}
+
+// One test per binary: the counting allocator is process-global, so parallel
+// tests would count each other's allocations.
+#[test]
+fn env_and_vec_env_steps_do_not_allocate() {
+    let mut ticks = TetrisEnv::new(1, ActionSpace::Tick, ScoreDeltaReward);
+    let mut places = TetrisEnv::new(1, ActionSpace::Placement, ScoreDeltaReward);
+    let _ = ticks.step_index(1);
+    let _ = places.step_index(0);
+
+    let seeds: Vec<u32> = (1..=8).collect();
+    let mut vec_env = VecEnv::new(&seeds, ActionSpace::Tick, 3, ScoreDeltaReward);
+    let mut obs = vec![0.0; seeds.len() * OBS_LEN];
+    let mut results = vec![VecStepResult::default(); seeds.len()];
+    let mut actions = vec![0; seeds.len()];
+    vec_env.reset(&mut obs);
+    vec_env.step(&actions, &mut obs, &mut results);
+
+    let allocs = with_alloc_counting(|| {
+        for index in 0..400 {
+            let step = ticks.step_index(index % 8);
+            if step.terminated {
+                ticks.reset(2);
+            }
+            let step = places.step(EnvAction::Place(Placement {
+                x: (index % 8) as i8 + 1,
+                rotation: Rotation::North,
+                use_hold: index % 3 == 0,
+            }));
+            if step.terminated {
+                places.reset(2);
+            }
+
+            // Hard drops top out quickly, so auto-reset paths are exercised too.
+            for (env, action) in actions.iter_mut().enumerate() {
+                *action = (index + env) % 8;
+            }
+            vec_env.step(&actions, &mut obs, &mut results);
+        }
+    });
+
+    assert_eq!(allocs, 0);
+}
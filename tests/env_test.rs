@@ -0,0 +1,94 @@
+use tetris_core::types::GameAction;
+use tetris_session::engine::env::{
+    ActionSpace, EnvAction, LinesReward, ScoreDeltaReward, StepInfo, SurvivalReward, TICK_ACTIONS,
+    TetrisEnv,
+};
+use tetris_session::engine::planner::{Planner, PlannerConfig};
+
+#[test]
+fn reset_starts_a_deterministic_episode() {
+    let mut env = TetrisEnv::new(3, ActionSpace::Tick, LinesReward);
+    let first = *env.reset(9);
+    for _ in 0..20 {
+        env.step(EnvAction::Tick(Some(GameAction::MoveLeft)));
+    }
+    assert_eq!(*env.reset(9), first);
+    assert_eq!(first.seed, 9);
+    assert_eq!(ActionSpace::Tick.size(), TICK_ACTIONS.len());
+}
+
+#[test]
+fn tick_space_applies_one_raw_action_per_logical_step() {
+    let mut env = TetrisEnv::new(1, ActionSpace::Tick, ScoreDeltaReward);
+    let before = env.session().logical_step();
+    let step = env.step(EnvAction::Tick(Some(GameAction::HardDrop)));
+    assert_eq!(step.info.pieces_locked, 1);
+    assert!(step.info.score_delta > 0);
+    assert_eq!(step.reward, step.info.score_delta as f32);
+    assert!(!step.terminated && !step.truncated);
+    assert_eq!(env.session().logical_step(), before + 1);
+}
+
+#[test]
+fn placement_space_accepts_planner_placements_and_reports_lines() {
+    let mut env = TetrisEnv::new(1, ActionSpace::Placement, LinesReward);
+    let mut planner = Planner::new(PlannerConfig::default());
+    let mut total = 0.0;
+    for _ in 0..60 {
+        let plan = planner.plan(env.game()).unwrap();
+        let step = env.step(EnvAction::Place(plan.placement));
+        assert!(!step.info.invalid_action);
+        assert_eq!(step.info.pieces_locked, 1);
+        total += step.reward;
+    }
+    assert_eq!(total, env.game().lines() as f32);
+    assert!(total > 0.0);
+}
+
+#[test]
+fn mismatched_and_unknown_actions_are_flagged_but_still_advance() {
+    let mut env = TetrisEnv::new(1, ActionSpace::Placement, LinesReward);
+    let step = env.step(EnvAction::Tick(Some(GameAction::HardDrop)));
+    assert!(step.info.invalid_action);
+    assert_eq!(step.info.pieces_locked, 0);
+    assert!(env.step_index(usize::MAX).info.invalid_action);
+    assert_eq!(env.session().logical_step(), 2);
+}
+
+#[test]
+fn episodes_terminate_on_top_out_and_truncate_at_the_step_limit() {
+    let mut env =
+        TetrisEnv::new(1, ActionSpace::Tick, SurvivalReward::default()).with_max_episode_steps(3);
+    let rewards: Vec<_> = (0..3)
+        .map(|_| {
+            let step = env.step_index(0);
+            (step.reward, step.truncated)
+        })
+        .collect();
+    assert_eq!(rewards, [(1.0, false), (1.0, false), (1.0, true)]);
+
+    let mut env = TetrisEnv::new(1, ActionSpace::Tick, SurvivalReward::default());
+    let hard_drop = TICK_ACTIONS
+        .iter()
+        .position(|action| *action == Some(GameAction::HardDrop))
+        .unwrap();
+    let terminal = (0..200)
+        .map(|_| {
+            let step = env.step_index(hard_drop);
+            (step.reward, step.terminated)
+        })
+        .find(|(_, terminated)| *terminated)
+        .expect("stacking hard drops tops out");
+    assert_eq!(terminal.0, -1.0);
+}
+
+#[test]
+fn closures_are_reward_functions() {
+    let mut env = TetrisEnv::new(1, ActionSpace::Tick, |info: &StepInfo, _: bool| {
+        info.pieces_locked as f32 * 0.5
+    });
+    assert_eq!(
+        env.step(EnvAction::Tick(Some(GameAction::HardDrop))).reward,
+        0.5
+    );
+}
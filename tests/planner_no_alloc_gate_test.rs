@@ -0,0 +1,63 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tetris_session::engine::planner::{Planner, PlannerConfig};
+use tetris_session::engine::session::{SessionRuntime, StepInput};
+struct CountingAlloc;
+
+static COUNT_ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOC_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe {
+            if COUNT_ENABLED.load(Ordering::Relaxed) {
+                let _ = layout;
+                ALLOC_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            System.alloc(layout)
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe {
+            if COUNT_ENABLED.load(Ordering::Relaxed) {
+                let _ = (layout, new_size);
+                ALLOC_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+fn with_alloc_counting<F: FnOnce()>(f: F) -> usize {
+    ALLOC_COUNT.store(0, Ordering::Relaxed);
+    COUNT_ENABLED.store(true, Ordering::Relaxed);
+    f();
+    COUNT_ENABLED.store(false, Ordering::Relaxed);
+    ALLOC_COUNT.load(Ordering::Relaxed)
+}
+
+#[test]
+fn warmed_planner_search_does_not_allocate() {
+    let mut session = SessionRuntime::new(7);
+    let mut planner = Planner::new(PlannerConfig::default());
+
+    // Warm-up: one full decision outside counting.
+    let plan = planner.plan(session.game()).unwrap();
+    session.transition(&StepInput::default().with_remote(plan.placement.into()));
+
+    let allocs = with_alloc_counting(|| {
+        for _ in 0..8 {
+            let _ = planner.plan(session.game());
+        }
+    });
+    assert_eq!(allocs, 0, "planner search allocated {allocs} times");
+}
@@ -0,0 +1,118 @@
+use tetris_session::engine::place::placement_targets;
+use tetris_session::engine::planner::{Planner, PlannerConfig};
+use tetris_session::engine::session::{SessionRuntime, StepInput};
+use tui_tetris::bot_cli::{BotConfig, parse_bot_args, play_bot, run_bot};
+
+#[test]
+fn planner_decisions_are_deterministic_and_accepted_by_the_session() {
+    let mut session = SessionRuntime::new(11);
+    let mut planner = Planner::new(PlannerConfig::default());
+    let mut twin = Planner::new(PlannerConfig::default());
+    for _ in 0..40 {
+        let plan = planner.plan(session.game()).expect("active piece");
+        assert_eq!(twin.plan(session.game()), Some(plan));
+        let transition =
+            session.transition(&StepInput::default().with_remote(plan.placement.into()));
+        assert_eq!(transition.command_outcomes[0], Ok(()));
+    }
+    assert!(!session.game().game_over());
+}
+
+#[test]
+fn planner_respects_the_step_budget_after_the_root_ply() {
+    let session = SessionRuntime::new(3);
+    let root_targets = placement_targets(session.game(), true).len() as u64;
+    let mut planner = Planner::new(PlannerConfig {
+        depth: 4,
+        step_budget: 1,
+        placements_per_step: 4,
+        ..PlannerConfig::default()
+    });
+    // The root ply is always expanded so a legal placement is still returned.
+    let plan = planner.plan(session.game()).unwrap();
+    assert_eq!(plan.depth_reached, 1);
+    assert_eq!(plan.placements, root_targets);
+    assert_eq!(plan.steps_used, root_targets.div_ceil(4));
+
+    let mut unbounded = Planner::new(PlannerConfig {
+        depth: 2,
+        step_budget: u64::MAX,
+        ..PlannerConfig::default()
+    });
+    let plan = unbounded.plan(session.game()).unwrap();
+    assert_eq!(plan.depth_reached, 2);
+    assert_eq!(plan.steps_used, 1);
+}
+
+#[test]
+fn the_bot_keeps_the_game_stepping_while_it_plans() {
+    let run = |placements_per_step| {
+        play_bot(BotConfig {
+            seed: 2,
+            pieces: 5,
+            planner: PlannerConfig {
+                placements_per_step,
+                ..PlannerConfig::default()
+            },
+        })
+    };
+    let fast = run(2_048);
+    assert_eq!((fast.pieces, fast.logical_steps), (5, 5));
+    let slow = run(64);
+    assert_eq!(slow.pieces, 5);
+    assert!(
+        slow.logical_steps > 10,
+        "slow planning took {} steps",
+        slow.logical_steps
+    );
+}
+
+#[test]
+fn lookahead_beam_outplays_the_greedy_baseline() {
+    let run = |planner| {
+        play_bot(BotConfig {
+            seed: 1,
+            pieces: 300,
+            planner,
+        })
+    };
+    let greedy = run(PlannerConfig::greedy());
+    let beam = run(PlannerConfig::default());
+    assert!(!beam.game_over);
+    assert!(
+        beam.lines >= greedy.lines,
+        "beam {beam:?} greedy {greedy:?}"
+    );
+}
+
+#[test]
+fn bot_command_parses_options_and_reports_a_stable_summary() {
+    let config = parse_bot_args(&[
+        "bot".into(),
+        "--seed".into(),
+        "5".into(),
+        "--pieces".into(),
+        "20".into(),
+        "--beam".into(),
+        "4".into(),
+        "--depth".into(),
+        "2".into(),
+        "--no-hold".into(),
+        "--budget".into(),
+        "3".into(),
+    ])
+    .unwrap()
+    .unwrap();
+    assert_eq!(config.seed, 5);
+    assert_eq!(config.pieces, 20);
+    assert_eq!(config.planner.beam_width, 4);
+    assert_eq!(config.planner.depth, 2);
+    assert!(!config.planner.use_hold);
+    assert_eq!(config.planner.step_budget, 3);
+    assert_eq!(parse_bot_args(&["replay".into()]).unwrap(), None);
+    assert!(parse_bot_args(&["bot".into(), "--depth".into(), "9".into()]).is_err());
+
+    let summary = run_bot(config);
+    assert!(summary.starts_with("seed=5 pieces=20 "), "{summary}");
+    assert_eq!(summary, run_bot(config));
+}
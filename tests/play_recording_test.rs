@@ -0,0 +1,105 @@
+use tetris_core::types::GameAction;
+use tetris_session::engine::replay::{
+    META_FINAL_SCORE, META_MODE, META_PLAYER, ReplayTape, replay_and_verify,
+};
+use tetris_session::engine::session::{GameCommand, SessionRuntime, StepInput};
+use tui_tetris::recording::{PlayRecording, interactive_metadata};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("tui-tetris-{name}-{}.ttr", std::process::id()))
+}
+
+fn play(recording: &mut PlayRecording, session: &mut SessionRuntime, input: StepInput) {
+    let transition = session.transition(&input);
+    recording.record(session, &input, &transition).unwrap();
+}
+
+#[test]
+fn streamed_play_is_verifiable_after_every_step_and_matches_the_saved_tape() {
+    let stream_path = temp_path("play-stream");
+    let save_path = temp_path("play-save");
+    let mut session = SessionRuntime::new(4);
+    let mut recording =
+        PlayRecording::streaming_to(4, &interactive_metadata("ada"), &stream_path).unwrap();
+
+    let inputs = [
+        StepInput::default().with_local(GameAction::MoveLeft),
+        StepInput::default(),
+        StepInput::default()
+            .with_remote(GameCommand::action(GameAction::RotateCw))
+            .with_local(GameAction::HardDrop),
+        StepInput::default().with_local(GameAction::Restart),
+        StepInput::default().with_local(GameAction::Hold),
+    ];
+    for input in inputs {
+        play(&mut recording, &mut session, input);
+        let streamed = ReplayTape::decode(&std::fs::read(&stream_path).unwrap()).unwrap();
+        assert_eq!(streamed.records().len() as u64, recording.steps());
+        replay_and_verify(&streamed).expect("streamed prefix verifies");
+    }
+
+    recording.save(&save_path).unwrap();
+    let saved = std::fs::read(&save_path).unwrap();
+    let streamed = std::fs::read(&stream_path).unwrap();
+    let _ = std::fs::remove_file(&stream_path);
+    let _ = std::fs::remove_file(&save_path);
+    let streamed = ReplayTape::decode(&streamed).unwrap();
+    assert_eq!(streamed.metadata().get(META_PLAYER), Some("ada"));
+    assert_eq!(streamed.metadata().get(META_MODE), Some("interactive"));
+
+    // The saved tape is the streamed one plus its final score.
+    let tape = ReplayTape::decode(&saved).unwrap();
+    assert_eq!(tape.records(), streamed.records());
+    assert_eq!(
+        tape.metadata().get(META_FINAL_SCORE),
+        Some(session.snapshot().score.to_string().as_str())
+    );
+    assert_eq!(tape.metadata().get(META_PLAYER), Some("ada"));
+    assert_eq!(tape.seed(), 4);
+    assert_eq!(tape.records()[2].input.remote.len(), 1);
+    assert_eq!(
+        tape.records()[2].input.local.as_slice(),
+        [GameAction::HardDrop]
+    );
+    let replayed = replay_and_verify(&tape).unwrap();
+    assert_eq!(replayed.snapshot(), session.snapshot());
+}
+
+#[test]
+fn save_path_names_the_seed_and_step() {
+    let mut session = SessionRuntime::new(9);
+    let mut recording = PlayRecording::new(9, &interactive_metadata("ada"));
+    play(&mut recording, &mut session, StepInput::default());
+    assert_eq!(
+        recording.default_save_path(),
+        std::path::PathBuf::from("tui-tetris-9-1.ttr")
+    );
+}
+
+#[test]
+fn branched_play_streams_the_source_prefix_then_new_steps() {
+    let path = temp_path("play-branch");
+    let source = ReplayTape::record(
+        6,
+        (0..40).map(|step| match step % 10 {
+            9 => StepInput::default().with_local(GameAction::HardDrop),
+            _ => StepInput::default(),
+        }),
+    );
+    let prefix = source.truncated(25);
+    let mut session = source.replay_to(25);
+    let mut recording = PlayRecording::branching(&prefix, &path).unwrap();
+    assert_eq!(recording.steps(), 25);
+    assert_eq!(recording.seed(), 6);
+
+    play(
+        &mut recording,
+        &mut session,
+        StepInput::default().with_local(GameAction::Hold),
+    );
+    let branched = ReplayTape::decode(&std::fs::read(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(branched.records()[..25], source.records()[..25]);
+    assert_eq!(branched.records().len(), 26);
+    replay_and_verify(&branched).unwrap();
+}
@@ -1,6 +1,11 @@
 use std::path::PathBuf;
 
-use tui_tetris::replay_cli::{ReplayCommand, parse_replay_args, run_replay_command};
+use tetris_core::types::{GameAction, Rules};
+use tetris_session::engine::replay::{ReplayTape, Ruleset};
+use tetris_session::engine::session::StepInput;
+use tui_tetris::replay_cli::{ReplayCommand, ReplayFormat, parse_replay_args, run_replay_command};
+use tui_tetris::replay_diff::diff_tapes;
+use tui_tetris::replay_export::{ExportFormat, ExportOptions, ExportRows};
 
 fn temp_replay() -> PathBuf {
     std::env::temp_dir().join(format!("tui-tetris-replay-{}.ttr", std::process::id()))
@@ -31,6 +36,38 @@ fn replay_subcommands_have_an_explicit_stable_surface() {
         parse_replay_args(&["replay".into(), "verify".into(), path.display().to_string()]).unwrap(),
         Some(ReplayCommand::Verify { path: path.clone() })
     );
+    assert_eq!(
+        parse_replay_args(&["replay".into(), "play".into(), path.display().to_string()]).unwrap(),
+        Some(ReplayCommand::Play { path: path.clone() })
+    );
+    assert_eq!(
+        parse_replay_args(&[
+            "replay".into(),
+            "convert".into(),
+            "in.ttr".into(),
+            "out.ttr".into(),
+            "--format".into(),
+            "ttr2".into(),
+        ])
+        .unwrap(),
+        Some(ReplayCommand::Convert {
+            path: "in.ttr".into(),
+            output: "out.ttr".into(),
+            format: ReplayFormat::Ttr2,
+            keyframe_interval: 4096,
+        })
+    );
+    assert!(
+        parse_replay_args(&[
+            "replay".into(),
+            "convert".into(),
+            "in.ttr".into(),
+            "out.ttr".into(),
+            "--format".into(),
+            "json".into(),
+        ])
+        .is_err()
+    );
 }
 
 #[test]
@@ -54,3 +91,418 @@ fn record_verify_and_inspect_form_a_complete_cli_loop() {
     assert!(inspected.contains("ruleset:"));
     std::fs::remove_file(path).unwrap();
 }
+
+#[test]
+fn convert_switches_between_text_and_binary_tapes() {
+    let text = std::env::temp_dir().join(format!("tui-tetris-convert-{}.ttr", std::process::id()));
+    let binary = text.with_extension("ttr3");
+    run_replay_command(ReplayCommand::Record {
+        path: text.clone(),
+        seed: 5,
+        steps: 500,
+    })
+    .unwrap();
+    let converted = run_replay_command(ReplayCommand::Convert {
+        path: text.clone(),
+        output: binary.clone(),
+        format: ReplayFormat::Ttr3,
+        keyframe_interval: 100,
+    })
+    .unwrap();
+    assert!(converted.contains("converted 500 steps to ttr3"));
+    assert!(std::fs::metadata(&binary).unwrap().len() < std::fs::metadata(&text).unwrap().len());
+
+    let inspected = run_replay_command(ReplayCommand::Inspect {
+        path: binary.clone(),
+    })
+    .unwrap();
+    assert!(inspected.contains("format: ttr3"));
+    assert!(inspected.contains("steps: 500"));
+    run_replay_command(ReplayCommand::Verify {
+        path: binary.clone(),
+    })
+    .unwrap();
+
+    run_replay_command(ReplayCommand::Convert {
+        path: binary.clone(),
+        output: text.clone(),
+        format: ReplayFormat::Ttr2,
+        keyframe_interval: 0,
+    })
+    .unwrap();
+    assert!(std::fs::read(&text).unwrap().starts_with(b"TTR2"));
+    std::fs::remove_file(text).unwrap();
+    std::fs::remove_file(binary).unwrap();
+}
+
+#[test]
+fn diff_reports_the_first_divergent_step_with_both_boards() {
+    let dir = std::env::temp_dir();
+    let left = dir.join(format!("tui-tetris-diff-left-{}.ttr", std::process::id()));
+    let right = dir.join(format!("tui-tetris-diff-right-{}.ttr", std::process::id()));
+    let inputs = |divergent: GameAction| {
+        (0..120).map(move |step| match step {
+            5 => StepInput::default().with_local(GameAction::HardDrop),
+            40 => StepInput::default().with_local(divergent),
+            41 | 80 => StepInput::default().with_local(GameAction::HardDrop),
+            _ => StepInput::default(),
+        })
+    };
+    std::fs::write(
+        &left,
+        ReplayTape::record(3, inputs(GameAction::MoveLeft)).encode(),
+    )
+    .unwrap();
+    std::fs::write(
+        &right,
+        ReplayTape::record(3, inputs(GameAction::MoveRight)).encode(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        parse_replay_args(&[
+            "replay".into(),
+            "diff".into(),
+            left.display().to_string(),
+            right.display().to_string(),
+        ])
+        .unwrap(),
+        Some(ReplayCommand::Diff {
+            path: left.clone(),
+            other: right.clone(),
+        })
+    );
+    let report = run_replay_command(ReplayCommand::Diff {
+        path: left.clone(),
+        other: right.clone(),
+    })
+    .unwrap();
+    assert!(
+        report.contains("first divergence at step 40: inputs differ"),
+        "{report}"
+    );
+    assert!(report.contains("local [moveLeft]"));
+    assert!(report.contains("local [moveRight]"));
+    assert!(report.contains("<>"));
+    assert!(report.contains("final difference"));
+
+    let same = run_replay_command(ReplayCommand::Diff {
+        path: left.clone(),
+        other: left.clone(),
+    })
+    .unwrap();
+    assert!(same.starts_with("tapes agree for all 120 steps"));
+
+    std::fs::write(
+        &right,
+        ReplayTape::record(4, inputs(GameAction::MoveLeft)).encode(),
+    )
+    .unwrap();
+    assert!(
+        run_replay_command(ReplayCommand::Diff {
+            path: left.clone(),
+            other: right.clone(),
+        })
+        .is_err()
+    );
+    std::fs::remove_file(left).unwrap();
+    std::fs::remove_file(right).unwrap();
+}
+
+/// A retired ruleset with a longer lock delay.
+static RETIRED_RULES: Ruleset = Ruleset {
+    version: "test-retired-rules",
+    rules: Rules {
+        lock_delay_ms: 500,
+        ..Rules::GUIDELINE
+    },
+};
+
+#[test]
+fn diff_refuses_tapes_recorded_under_different_rulesets() {
+    let inputs = || (0..10).map(|_| StepInput::default().with_local(GameAction::HardDrop));
+    let current = ReplayTape::record(3, inputs());
+    let retired = ReplayTape::record_under(&RETIRED_RULES, 3, inputs());
+
+    let error = diff_tapes(&current, "current.ttr", &retired, "retired.ttr").unwrap_err();
+    assert!(error.contains("different rulesets"), "{error}");
+    assert!(error.contains("test-retired-rules"), "{error}");
+}
+
+#[test]
+fn export_parses_format_rows_board_and_output() {
+    let args = |extra: &[&str]| {
+        ["replay", "export", "game.ttr"]
+            .iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        parse_replay_args(&args(&[])).unwrap(),
+        Some(ReplayCommand::Export {
+            path: "game.ttr".into(),
+            options: ExportOptions::default(),
+            output: None,
+        })
+    );
+    assert_eq!(
+        parse_replay_args(&args(&[
+            "--format", "csv", "--board", "--rows", "piece", "--output", "out.csv"
+        ]))
+        .unwrap(),
+        Some(ReplayCommand::Export {
+            path: "game.ttr".into(),
+            options: ExportOptions {
+                format: ExportFormat::Csv,
+                rows: ExportRows::Piece,
+                board: true,
+            },
+            output: Some("out.csv".into()),
+        })
+    );
+    assert!(parse_replay_args(&args(&["--rows", "frame"])).is_err());
+}
+
+#[test]
+fn annotate_edits_tapes_in_place_and_inspect_lists_the_result() {
+    let text = std::env::temp_dir().join(format!("tui-tetris-annotate-{}.ttr", std::process::id()));
+    let binary = text.with_extension("ttr3");
+    run_replay_command(ReplayCommand::Record {
+        path: text.clone(),
+        seed: 8,
+        steps: 300,
+    })
+    .unwrap();
+    run_replay_command(ReplayCommand::Convert {
+        path: text.clone(),
+        output: binary.clone(),
+        format: ReplayFormat::Ttr3,
+        keyframe_interval: 64,
+    })
+    .unwrap();
+
+    let args = [
+        "replay",
+        "annotate",
+        "PATH",
+        "--set",
+        "player=greedy",
+        "--tag",
+        "eval",
+        "--note",
+        "120=tops out soon",
+    ];
+    for path in [&text, &binary] {
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| match *arg {
+                "PATH" => path.display().to_string(),
+                arg => arg.to_string(),
+            })
+            .collect();
+        let command = parse_replay_args(&args).unwrap().unwrap();
+        assert_eq!(
+            command,
+            ReplayCommand::Annotate {
+                path: path.clone(),
+                set: vec![("player".into(), "greedy".into())],
+                tags: vec!["eval".into()],
+                notes: vec![(120, "tops out soon".into())],
+            }
+        );
+        run_replay_command(command).unwrap();
+
+        let inspected = run_replay_command(ReplayCommand::Inspect { path: path.clone() }).unwrap();
+        assert!(inspected.contains("final_score: 0"), "{inspected}");
+        assert!(inspected.contains("\n  mode: headless"), "{inspected}");
+        assert!(inspected.contains("\n  player: greedy"));
+        assert!(inspected.contains("\n  tags: eval"));
+        assert!(inspected.contains("annotations:\n  120: tops out soon"));
+        run_replay_command(ReplayCommand::Verify { path: path.clone() }).unwrap();
+    }
+    assert!(std::fs::read(&binary).unwrap().starts_with(b"TTR3"));
+    assert!(
+        parse_replay_args(&[
+            "replay".into(),
+            "annotate".into(),
+            "x.ttr".into(),
+            "--note".into(),
+            "soon=text".into(),
+        ])
+        .is_err()
+    );
+    std::fs::remove_file(text).unwrap();
+    std::fs::remove_file(binary).unwrap();
+}
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+#[test]
+fn cut_splice_concat_and_branch_parse_their_operands() {
+    assert_eq!(
+        parse_replay_args(&args("replay cut a.ttr b.ttr --steps 40")).unwrap(),
+        Some(ReplayCommand::Cut {
+            path: "a.ttr".into(),
+            output: "b.ttr".into(),
+            steps: 40,
+        })
+    );
+    assert_eq!(
+        parse_replay_args(&args("replay splice a.ttr b.ttr c.ttr --at 12")).unwrap(),
+        Some(ReplayCommand::Splice {
+            path: "a.ttr".into(),
+            other: "b.ttr".into(),
+            output: "c.ttr".into(),
+            at: 12,
+        })
+    );
+    assert_eq!(
+        parse_replay_args(&args("replay concat a.ttr b.ttr c.ttr")).unwrap(),
+        Some(ReplayCommand::Concat {
+            path: "a.ttr".into(),
+            other: "b.ttr".into(),
+            output: "c.ttr".into(),
+        })
+    );
+    assert_eq!(
+        parse_replay_args(&args("replay branch a.ttr b.ttr --at 5")).unwrap(),
+        Some(ReplayCommand::Branch {
+            path: "a.ttr".into(),
+            output: "b.ttr".into(),
+            at: 5,
+            bot: None,
+        })
+    );
+    let Some(ReplayCommand::Branch { bot: Some(bot), .. }) =
+        parse_replay_args(&args("replay branch a.ttr b.ttr --at 5 --bot --pieces 9")).unwrap()
+    else {
+        panic!("--bot branches carry a bot config");
+    };
+    assert_eq!(bot.pieces, 9);
+    for bad in [
+        "replay cut a.ttr b.ttr",
+        "replay cut a.ttr b.ttr --at 4",
+        "replay splice a.ttr b.ttr --at 4",
+        "replay concat a.ttr b.ttr",
+        "replay branch a.ttr b.ttr --bot",
+    ] {
+        assert!(parse_replay_args(&args(bad)).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn edited_and_branched_tapes_verify_and_keep_their_source_prefix() {
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let [source, cut, spliced, joined, branch] = ["source", "cut", "spliced", "joined", "branch"]
+        .map(|name| dir.join(format!("tui-tetris-edit-{name}-{id}.ttr")));
+    run_replay_command(ReplayCommand::Record {
+        path: source.clone(),
+        seed: 5,
+        steps: 200,
+    })
+    .unwrap();
+    let original = ReplayTape::decode(&std::fs::read(&source).unwrap()).unwrap();
+
+    run_replay_command(ReplayCommand::Cut {
+        path: source.clone(),
+        output: cut.clone(),
+        steps: 80,
+    })
+    .unwrap();
+    run_replay_command(ReplayCommand::Splice {
+        path: cut.clone(),
+        other: source.clone(),
+        output: spliced.clone(),
+        at: 80,
+    })
+    .unwrap();
+    run_replay_command(ReplayCommand::Concat {
+        path: cut.clone(),
+        other: source.clone(),
+        output: joined.clone(),
+    })
+    .unwrap();
+    let cut_tape = ReplayTape::decode(&std::fs::read(&cut).unwrap()).unwrap();
+    assert_eq!(cut_tape.records(), &original.records()[..80]);
+    let spliced_tape = ReplayTape::decode(&std::fs::read(&spliced).unwrap()).unwrap();
+    assert_eq!(spliced_tape.records(), original.records());
+    let joined_tape = ReplayTape::decode(&std::fs::read(&joined).unwrap()).unwrap();
+    assert_eq!(joined_tape.records(), original.records());
+    for path in [&cut, &spliced, &joined] {
+        run_replay_command(ReplayCommand::Verify { path: path.clone() }).unwrap();
+    }
+
+    let report = run_replay_command(ReplayCommand::Branch {
+        path: source.clone(),
+        output: branch.clone(),
+        at: 80,
+        bot: parse_replay_args(&args("replay branch x y --at 80 --bot --pieces 6"))
+            .unwrap()
+            .and_then(|command| match command {
+                ReplayCommand::Branch { bot, .. } => bot,
+                _ => None,
+            }),
+    })
+    .unwrap();
+    assert!(report.contains("bot placed 6 pieces"), "{report}");
+    let branched = ReplayTape::decode(&std::fs::read(&branch).unwrap()).unwrap();
+    assert_eq!(branched.records()[..80], original.records()[..80]);
+    assert!(branched.records().len() > 80);
+    assert_eq!(branched.metadata().get("branch_step"), Some("80"));
+    assert_eq!(branched.metadata().get("mode"), Some("bot"));
+    run_replay_command(ReplayCommand::Verify {
+        path: branch.clone(),
+    })
+    .unwrap();
+
+    for path in [source, cut, spliced, joined, branch] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn migrate_rewrites_tapes_under_the_current_ruleset_and_reports_changes() {
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let (source, output) = (
+        dir.join(format!("tui-tetris-migrate-in-{id}.ttr")),
+        dir.join(format!("tui-tetris-migrate-out-{id}.ttr")),
+    );
+    assert_eq!(
+        parse_replay_args(&args("replay migrate a.ttr b.ttr")).unwrap(),
+        Some(ReplayCommand::Migrate {
+            path: "a.ttr".into(),
+            output: "b.ttr".into(),
+        })
+    );
+    assert!(parse_replay_args(&args("replay migrate a.ttr")).is_err());
+
+    run_replay_command(ReplayCommand::Record {
+        path: source.clone(),
+        seed: 3,
+        steps: 120,
+    })
+    .unwrap();
+    let report = run_replay_command(ReplayCommand::Migrate {
+        path: source.clone(),
+        output: output.clone(),
+    })
+    .unwrap();
+    assert!(report.starts_with("migrated 120 steps from "), "{report}");
+    assert!(report.ends_with("outcomes unchanged"), "{report}");
+    let migrated = ReplayTape::decode(&std::fs::read(&output).unwrap()).unwrap();
+    assert_eq!(
+        migrated.metadata().get("migrated_from"),
+        Some(migrated.ruleset_version())
+    );
+    run_replay_command(ReplayCommand::Verify {
+        path: output.clone(),
+    })
+    .unwrap();
+    std::fs::remove_file(source).unwrap();
+    std::fs::remove_file(output).unwrap();
+}
@@ -0,0 +1,146 @@
+use serde_json::Value;
+use tetris_core::core::get_shape;
+use tetris_core::types::{GameAction, PieceKind, Rotation};
+use tetris_session::engine::replay::ReplayTape;
+use tetris_session::engine::session::{GameCommand, StepInput};
+use tui_tetris::replay_export::{ExportFormat, ExportOptions, ExportRows, export_tape};
+
+fn tape() -> ReplayTape {
+    ReplayTape::record(
+        11,
+        (0..400).map(|step| match step % 25 {
+            3 => StepInput::default().with_local(GameAction::MoveLeft),
+            7 => StepInput::default().with_local(GameAction::HardDrop),
+            19 => StepInput::default().with_remote(GameCommand::Place {
+                x: 6,
+                rotation: Rotation::East,
+                use_hold: step % 2 == 0,
+            }),
+            _ => StepInput::default(),
+        }),
+    )
+}
+
+fn export(options: ExportOptions) -> String {
+    let mut out = Vec::new();
+    export_tape(&tape(), options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn step_rows_cover_every_step_in_both_formats() {
+    let jsonl = export(ExportOptions::default());
+    let rows: Vec<Value> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 400);
+    assert_eq!(rows[7]["step"], 7);
+    assert_eq!(rows[7]["local"], "hardDrop");
+    assert_eq!(rows[7]["locked"], true);
+    assert_eq!(rows[19]["remote"], "place 6 east");
+    assert!(rows[8]["placed_kind"].is_null());
+    let final_score = rows.last().unwrap()["score"].as_u64().unwrap();
+    assert_eq!(final_score, tape().final_snapshot().score as u64);
+
+    let csv = export(ExportOptions {
+        format: ExportFormat::Csv,
+        ..ExportOptions::default()
+    });
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(header[0], "step");
+    assert!(header.contains(&"board_hash"));
+    assert!(lines.all(|line| line.split(',').count() == header.len()));
+}
+
+#[test]
+fn piece_rows_place_each_locked_piece_on_the_board() {
+    let jsonl = export(ExportOptions {
+        rows: ExportRows::Piece,
+        board: true,
+        ..ExportOptions::default()
+    });
+    let rows: Vec<Value> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let locks = export(ExportOptions::default())
+        .lines()
+        .filter(|line| line.contains("\"locked\":true"))
+        .count();
+    assert!(locks > 10);
+    assert_eq!(rows.len(), locks);
+    for (index, row) in rows.iter().enumerate() {
+        assert_eq!(row["piece"], index);
+        if row["lines_cleared"] != 0 {
+            continue;
+        }
+        let kind: PieceKind = row["placed_kind"].as_str().unwrap().parse().unwrap();
+        let rotation: Rotation = row["placed_rotation"].as_str().unwrap().parse().unwrap();
+        let (x, y) = (
+            row["placed_x"].as_i64().unwrap(),
+            row["placed_y"].as_i64().unwrap(),
+        );
+        let board: Vec<&str> = row["board"].as_str().unwrap().split('/').collect();
+        let letter = kind.as_str().to_ascii_uppercase();
+        for (dx, dy) in get_shape(kind, rotation) {
+            let cell = board[(y + dy as i64) as usize].as_bytes()[(x + dx as i64) as usize];
+            assert_eq!(cell as char, letter.chars().next().unwrap());
+        }
+    }
+    assert_eq!(rows[1]["piece_steps"], 12);
+}
+
+#[test]
+fn a_step_that_locks_two_pieces_exports_a_row_for_each() {
+    let double = StepInput::default()
+        .with_remote(GameCommand::Place {
+            x: 0,
+            rotation: Rotation::North,
+            use_hold: false,
+        })
+        .with_local(GameAction::HardDrop);
+    let tape = ReplayTape::record(5, [StepInput::default(), double]);
+    let export = |rows| {
+        let mut out = Vec::new();
+        let options = ExportOptions {
+            rows,
+            board: true,
+            ..ExportOptions::default()
+        };
+        export_tape(&tape, options, &mut out).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let pieces = export(ExportRows::Piece);
+    assert_eq!(pieces.len(), 2);
+    let board: Vec<&str> = pieces[1]["board"].as_str().unwrap().split('/').collect();
+    for (index, row) in pieces.iter().enumerate() {
+        assert_eq!(row["piece"], index);
+        assert_eq!(row["step"], 1);
+        let kind: PieceKind = row["placed_kind"].as_str().unwrap().parse().unwrap();
+        let rotation: Rotation = row["placed_rotation"].as_str().unwrap().parse().unwrap();
+        let (x, y) = (
+            row["placed_x"].as_i64().unwrap(),
+            row["placed_y"].as_i64().unwrap(),
+        );
+        let letter = kind.as_str().to_ascii_uppercase().chars().next().unwrap();
+        for (dx, dy) in get_shape(kind, rotation) {
+            let cell = board[(y + dy as i64) as usize].as_bytes()[(x + dx as i64) as usize];
+            assert_eq!(cell as char, letter, "piece {index}");
+        }
+    }
+    assert_eq!(pieces[0]["placed_x"], 0);
+    assert_eq!(pieces[1]["piece_steps"], 0);
+
+    let steps = export(ExportRows::Step);
+    assert_eq!(steps[1]["locked"], true);
+    for column in ["placed_kind", "placed_x", "placed_y", "placed_rotation"] {
+        assert_eq!(steps[1][column], pieces[1][column], "{column}");
+    }
+}
@@ -1,7 +1,10 @@
-use tetris_core::types::{CoreLastEvent, GameAction, TSpinKind};
+use tetris_core::types::{CoreLastEvent, GameAction, Rotation, Rules, TSpinKind};
+use tetris_session::engine::planner::{Planner, PlannerConfig};
 use tetris_session::engine::replay::{
-    REPLAY_FORMAT_VERSION, RULESET_VERSION, ReplayMismatch, ReplayTape, StepRecord,
-    replay_and_verify, transition_hash,
+    CURRENT_RULESET, META_FINAL_SCORE, META_MIGRATED_FROM, META_PLAYER, META_TAGS,
+    REPLAY_FORMAT_VERSION, RULESET_VERSION, ReplayAnnotation, ReplayMismatch, ReplayRecorder,
+    ReplayTape, Ruleset, StepRecord, first_divergence, format_utc, migrate, replay_and_verify,
+    seek_binary, transition_hash,
 };
 use tetris_session::engine::session::{GameCommand, SessionRuntime, StepInput};
 
@@ -57,6 +60,367 @@ fn equal_command_trajectories_are_byte_stable() {
     assert_eq!(decoded, ReplayTape::record(7, sample_batches()));
 }
 
+#[test]
+fn streamed_recording_matches_the_recorded_tape_at_every_step() {
+    let mut session = SessionRuntime::new(7);
+    let mut recorder = ReplayRecorder::new(Vec::new(), 7).unwrap();
+    let mut complete = Vec::new();
+    for input in sample_batches() {
+        let transition = session.transition(&input);
+        recorder.record(&session, &input, &transition).unwrap();
+        complete.push(recorder.steps());
+        // Every flushed prefix is a verifiable tape.
+        let prefix = ReplayTape::decode(recorder.get_ref()).unwrap();
+        replay_and_verify(&prefix).expect("valid prefix");
+    }
+    assert_eq!(complete, [1, 2, 3]);
+    assert_eq!(
+        recorder.into_inner(),
+        ReplayTape::record(7, sample_batches()).encode()
+    );
+}
+
+/// Mostly idle steps with sparse local and remote input, like a long headless run.
+fn sparse_batches(steps: u64) -> Vec<StepInput> {
+    (0..steps)
+        .map(|step| match step % 97 {
+            0 => StepInput::default().with_local(GameAction::HardDrop),
+            31 => StepInput::default().with_remote(GameCommand::Place {
+                x: 3,
+                rotation: Rotation::East,
+                use_hold: step % 2 == 0,
+            }),
+            60 => StepInput::default().with_remote(GameCommand::Actions {
+                actions: [GameAction::MoveRight, GameAction::RotateCcw]
+                    .into_iter()
+                    .collect(),
+                restart_seed: (step > 500).then_some(77),
+            }),
+            _ => StepInput::default(),
+        })
+        .collect()
+}
+
+#[test]
+fn binary_tapes_round_trip_smaller_than_text() {
+    let tape = ReplayTape::record(23, sparse_batches(2_000));
+    for interval in [0, 300] {
+        let binary = tape.encode_binary(interval);
+        assert!(ReplayTape::is_binary(&binary));
+        assert!(binary.len() * 10 < tape.encode().len());
+        let decoded = ReplayTape::decode(&binary).unwrap();
+        assert_eq!(decoded, tape);
+        assert!(replay_and_verify(&decoded).is_ok());
+    }
+    // Text tapes still decode after the binary format was added.
+    assert_eq!(ReplayTape::decode(&tape.encode()).unwrap(), tape);
+}
+
+#[test]
+fn binary_seeks_from_keyframes_match_straight_playback() {
+    let inputs = sparse_batches(1_000);
+    let tape = ReplayTape::record(23, inputs.clone());
+    let binary = tape.encode_binary(128);
+    let mut session = SessionRuntime::new(23);
+    let mut straight = vec![*session.snapshot()];
+    for input in &inputs {
+        session.transition(input);
+        straight.push(*session.snapshot());
+    }
+    for step in [0, 1, 127, 128, 129, 500, 999, 1_000] {
+        let seeked = seek_binary(&binary, step).unwrap();
+        assert_eq!(seeked.logical_step(), step);
+        assert_eq!(seeked.snapshot(), &straight[step as usize]);
+    }
+    assert_eq!(
+        seek_binary(&binary, u64::MAX).unwrap().logical_step(),
+        1_000
+    );
+}
+
+#[test]
+fn truncated_binary_tapes_are_rejected() {
+    let binary = ReplayTape::record(23, sparse_batches(300)).encode_binary(100);
+    for len in [3, 20, binary.len() / 2, binary.len() - 1] {
+        assert!(ReplayTape::decode(&binary[..len]).is_err());
+    }
+}
+
+#[test]
+fn first_divergence_reports_input_and_hash_differences() {
+    let tape = ReplayTape::record(42, sample_batches());
+    assert_eq!(first_divergence(&tape, &tape), None);
+
+    let mut inputs = sample_batches();
+    inputs[1] = StepInput::default().with_local(GameAction::Hold);
+    let other = ReplayTape::record(42, inputs);
+    let mismatch = first_divergence(&tape, &other).unwrap();
+    assert_eq!(mismatch.step, 1);
+    assert_eq!(mismatch.expected, tape.records()[1].state_hash);
+    assert_eq!(mismatch.actual, other.records()[1].state_hash);
+
+    let mut corrupted = tape.clone();
+    let mut record = corrupted.records()[2].clone();
+    record.state_hash ^= 1;
+    corrupted.replace_record_for_test(2, record);
+    assert_eq!(first_divergence(&tape, &corrupted).unwrap().step, 2);
+
+    let prefix = ReplayTape::record(42, sample_batches().into_iter().take(2));
+    assert_eq!(first_divergence(&tape, &prefix), None);
+}
+
+fn annotated_tape(steps: u64) -> ReplayTape {
+    let mut tape = ReplayTape::record(23, sparse_batches(steps));
+    let metadata = tape.metadata_mut();
+    metadata.set(META_PLAYER, "greedy\tbot\\v2");
+    metadata.set(META_TAGS, "eval");
+    metadata.add_tag("nightly");
+    metadata.add_tag("eval");
+    tape.annotate(steps, "end");
+    tape.annotate(150, "first\nclear");
+    tape.annotate(0, "start");
+    tape.annotate(150, "second note");
+    tape
+}
+
+#[test]
+fn metadata_and_annotations_round_trip_in_both_formats() {
+    let tape = annotated_tape(400);
+    assert_eq!(
+        tape.metadata().tags().collect::<Vec<_>>(),
+        ["eval", "nightly"]
+    );
+    assert_eq!(
+        tape.annotations()
+            .iter()
+            .map(|note| (note.step, note.text.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (0, "start"),
+            (150, "first\nclear"),
+            (150, "second note"),
+            (400, "end")
+        ]
+    );
+    for bytes in [
+        tape.encode(),
+        tape.encode_binary(128),
+        tape.encode_binary(0),
+    ] {
+        let decoded = ReplayTape::decode(&bytes).unwrap();
+        assert_eq!(decoded, tape);
+        assert_eq!(decoded.metadata().get(META_PLAYER), Some("greedy\tbot\\v2"));
+    }
+    let text = String::from_utf8(tape.encode()).unwrap();
+    assert!(
+        text.lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("M\tplayer\tgreedy\\tbot")
+    );
+    assert!(text.lines().all(|line| line.split('\t').count() <= 5));
+
+    let binary = tape.encode_binary(128);
+    let mut session = SessionRuntime::new(23);
+    for record in &tape.records()[..300] {
+        session.transition(&record.input);
+    }
+    assert_eq!(
+        seek_binary(&binary, 300).unwrap().snapshot(),
+        session.snapshot()
+    );
+}
+
+#[test]
+fn text_tapes_accept_metadata_and_notes_between_steps() {
+    let tape = ReplayTape::record(42, sample_batches());
+    let text = String::from_utf8(tape.encode()).unwrap();
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.insert(1, "M\tmode\theadless");
+    let first_step_end = lines.iter().position(|line| *line == "E").unwrap();
+    lines.insert(first_step_end + 1, "N\t1\tafter the first step");
+    lines.push("M\tmode\tedited");
+    let decoded = ReplayTape::decode((lines.join("\n") + "\n").as_bytes()).unwrap();
+    assert_eq!(decoded.records(), tape.records());
+    assert_eq!(decoded.metadata().get("mode"), Some("edited"));
+    assert_eq!(
+        decoded.annotations(),
+        [ReplayAnnotation {
+            step: 1,
+            text: "after the first step".into()
+        }]
+    );
+    assert!(ReplayTape::decode(b"TTR2\ttui-guideline-2026.1\t1\nM\tbad\\q\tx\n").is_err());
+}
+
+#[test]
+fn prefixes_keep_metadata_and_annotations_inside_them() {
+    let tape = annotated_tape(400);
+    let prefix = tape.minimal_failure_prefix(&ReplayMismatch {
+        step: 199,
+        expected: 0,
+        actual: 0,
+    });
+    assert_eq!(prefix.metadata(), tape.metadata());
+    assert_eq!(prefix.annotations().len(), 3);
+}
+
+#[test]
+fn truncated_tapes_keep_recorded_hashes_and_resume_where_they_end() {
+    let tape = annotated_tape(400);
+    let cut = tape.truncated(150);
+    assert_eq!(cut.records(), &tape.records()[..150]);
+    assert_eq!(cut.annotations().len(), 3);
+    assert_eq!(tape.truncated(1_000), tape);
+    assert_eq!(
+        tape.replay_to(150).snapshot(),
+        cut.final_snapshot(),
+        "resuming at the cut reaches the cut tape's final state"
+    );
+
+    let mut branch = ReplayRecorder::continuing(Vec::new(), &cut).unwrap();
+    assert_eq!(branch.steps(), 150);
+    let mut session = tape.replay_to(150);
+    for record in &tape.records()[150..] {
+        let transition = session.transition(&record.input);
+        branch.record(&session, &record.input, &transition).unwrap();
+    }
+    let branch = ReplayTape::decode(branch.get_ref()).unwrap();
+    assert_eq!(branch.records(), tape.records());
+    replay_and_verify(&branch).unwrap();
+}
+
+#[test]
+fn spliced_and_concatenated_tapes_re_record_their_joined_inputs() {
+    let tape = annotated_tape(400);
+    let other = ReplayTape::record(23, sample_batches().into_iter().cycle().take(300));
+
+    let spliced = tape.spliced(200, &other).unwrap();
+    assert_eq!(spliced.records().len(), 300);
+    assert_eq!(spliced.records()[..200], tape.records()[..200]);
+    assert!(
+        spliced.records()[200..]
+            .iter()
+            .zip(&other.records()[200..])
+            .all(|(left, right)| left.input == right.input)
+    );
+    assert_eq!(spliced.metadata(), tape.metadata());
+    assert_eq!(spliced.annotations().len(), 3);
+    replay_and_verify(&spliced).unwrap();
+
+    let opening = tape.truncated(200);
+    let joined = opening.concatenated(&tape).unwrap();
+    assert_eq!(joined.records(), tape.records());
+    assert_eq!(joined.annotations(), tape.annotations());
+    replay_and_verify(&joined).unwrap();
+    // Replayed from the seed, `other` is not what followed the opening.
+    let error = opening.concatenated(&other).unwrap_err();
+    assert!(error.contains("does not continue"), "{error}");
+    let error = opening.concatenated(&tape.truncated(100)).unwrap_err();
+    assert!(error.contains("ends after 100 steps"), "{error}");
+
+    let foreign = ReplayTape::record(7, sample_batches());
+    assert!(tape.spliced(1, &foreign).is_err());
+    assert!(tape.concatenated(&foreign).is_err());
+}
+
+/// A retired ruleset that scored line clears on another table.
+static RETIRED_SCORING: Ruleset = Ruleset {
+    version: "test-retired-scoring",
+    rules: Rules {
+        line_scores: [0, 100, 300, 500, 800],
+        ..Rules::GUIDELINE
+    },
+};
+
+/// Planner placements chosen under the current ruleset.
+fn planned_inputs(seed: u32, pieces: usize) -> Vec<StepInput> {
+    let mut session = SessionRuntime::new(seed);
+    let mut planner = Planner::new(PlannerConfig::default());
+    let mut inputs = Vec::new();
+    while inputs.len() < pieces && !session.game().game_over() {
+        let input = planner
+            .plan(session.game())
+            .map_or_else(StepInput::default, |plan| {
+                StepInput::default().with_remote(plan.placement.into())
+            });
+        session.transition(&input);
+        inputs.push(input);
+    }
+    inputs
+}
+
+#[test]
+fn tapes_verify_under_their_own_ruleset_and_migrate_to_the_current_one() {
+    let inputs = planned_inputs(3, 60);
+    let mut old = ReplayTape::record_under(&RETIRED_SCORING, 3, inputs.clone());
+    old.metadata_mut().set(META_FINAL_SCORE, "1");
+    old.annotate(10, "opening");
+    assert_eq!(old.ruleset_version(), "test-retired-scoring");
+    replay_and_verify(&old).expect("verifies under the recorded ruleset");
+    let current = ReplayTape::record(3, inputs);
+    assert!(old.concatenated(&current).is_err());
+    assert_ne!(old.final_snapshot().score, current.final_snapshot().score);
+    // Hashes recorded under one table do not verify under the other.
+    let mut relabelled = current.clone();
+    for (index, record) in old.records().iter().enumerate() {
+        relabelled.replace_record_for_test(index, record.clone());
+    }
+    assert!(replay_and_verify(&relabelled).is_err());
+
+    let migration = migrate(&old, &CURRENT_RULESET);
+    assert_eq!(migration.tape.records(), current.records());
+    assert_eq!(migration.tape.ruleset_version(), RULESET_VERSION);
+    let first_clear = current
+        .records()
+        .iter()
+        .position(|record| {
+            let mut session = current.replay_to(record.step);
+            let transition = session.transition(&record.input);
+            transition
+                .events
+                .iter()
+                .any(|event| event.lines_cleared > 0)
+        })
+        .expect("the planner clears lines") as u64;
+    assert_eq!(migration.first_divergence, Some(first_clear));
+    assert!(!migration.changes.is_empty());
+    for change in &migration.changes {
+        let (before, after) = (change.before.unwrap(), change.after.unwrap());
+        assert_eq!(before.lines_cleared, after.lines_cleared);
+        assert_ne!(before.line_clear_score, after.line_clear_score);
+    }
+    assert!(
+        migration
+            .changes
+            .windows(2)
+            .all(|pair| pair[0].step < pair[1].step)
+    );
+    let metadata = migration.tape.metadata();
+    assert_eq!(
+        metadata.get(META_MIGRATED_FROM),
+        Some("test-retired-scoring")
+    );
+    assert_eq!(
+        metadata.get(META_FINAL_SCORE),
+        Some(current.final_snapshot().score.to_string().as_str())
+    );
+    assert_eq!(migration.tape.annotations(), old.annotations());
+    replay_and_verify(&migration.tape).unwrap();
+
+    let unchanged = migrate(&current, &CURRENT_RULESET);
+    assert_eq!(unchanged.first_divergence, None);
+    assert!(unchanged.changes.is_empty());
+}
+
+#[test]
+fn dates_format_as_utc_timestamps() {
+    let at = |secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    assert_eq!(format_utc(at(0)), "1970-01-01T00:00:00Z");
+    assert_eq!(format_utc(at(951_782_400)), "2000-02-29T00:00:00Z");
+    assert_eq!(format_utc(at(1_792_332_245)), "2026-10-18T14:04:05Z");
+}
+
 #[test]
 fn replay_header_versions_the_container_and_ruleset() {
     let tape = ReplayTape::record(7, sample_batches());
@@ -67,10 +431,11 @@ fn replay_header_versions_the_container_and_ruleset() {
     assert_eq!(tape.ruleset_version(), RULESET_VERSION);
 
     let incompatible = encoded.replacen(RULESET_VERSION, "incompatible-rules", 1);
+    let error = ReplayTape::decode(incompatible.as_bytes()).unwrap_err();
+    assert!(error.contains("unsupported ruleset"));
     assert!(
-        ReplayTape::decode(incompatible.as_bytes())
-            .unwrap_err()
-            .contains("unsupported ruleset")
+        error.contains(&format!("known: {RULESET_VERSION}")),
+        "{error}"
     );
 }
 
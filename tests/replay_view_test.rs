@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use tetris_core::types::{GameAction, TICK_MS};
+use tetris_session::engine::replay::{META_DATE, META_MODE, META_PLAYER, ReplayTape};
+use tetris_session::engine::session::StepInput;
+use tetris_terminal::input::ReplayInputCommand;
+use tui_tetris::replay_view::ReplayViewer;
+
+fn tape(steps: u64) -> ReplayTape {
+    ReplayTape::record(
+        3,
+        (0..steps).map(|step| {
+            if step % 30 == 0 {
+                StepInput::default().with_local(GameAction::HardDrop)
+            } else {
+                StepInput::default()
+            }
+        }),
+    )
+}
+
+fn ticks(count: u64) -> Duration {
+    Duration::from_millis(TICK_MS as u64 * count)
+}
+
+#[test]
+fn playback_follows_the_speed_multiplier_and_pauses_at_the_end() {
+    let mut viewer = ReplayViewer::new(tape(100));
+    viewer.advance(ticks(10));
+    assert_eq!(viewer.status().step, 0, "the viewer opens paused");
+
+    viewer.handle(ReplayInputCommand::TogglePlay);
+    viewer.advance(ticks(10));
+    assert_eq!(viewer.status().step, 10);
+
+    viewer.handle(ReplayInputCommand::Faster);
+    assert_eq!(viewer.status().speed, (2, 1));
+    viewer.advance(ticks(10));
+    assert_eq!(viewer.status().step, 30);
+
+    viewer.handle(ReplayInputCommand::Slower);
+    viewer.handle(ReplayInputCommand::Slower);
+    assert_eq!(viewer.status().speed, (1, 2));
+    viewer.advance(ticks(10));
+    assert_eq!(viewer.status().step, 35);
+
+    viewer.handle(ReplayInputCommand::End);
+    viewer.advance(ticks(10));
+    assert_eq!(viewer.status().step, 100);
+    assert!(!viewer.status().playing);
+    assert_eq!(viewer.snapshot(), tape(100).final_snapshot());
+}
+
+#[test]
+fn stepping_and_jumping_move_the_cursor_exactly() {
+    let mut viewer = ReplayViewer::new(tape(400));
+    viewer.handle(ReplayInputCommand::TogglePlay);
+    viewer.handle(ReplayInputCommand::StepForward);
+    assert!(!viewer.status().playing, "single steps pause playback");
+    assert_eq!(viewer.status().step, 1);
+
+    for digit in [3, 0, 9, 9] {
+        viewer.handle(ReplayInputCommand::Digit(digit));
+    }
+    viewer.handle(ReplayInputCommand::DeleteDigit);
+    assert_eq!(viewer.status().jump_target, Some(309));
+    viewer.handle(ReplayInputCommand::Jump);
+    assert_eq!(viewer.status().step, 309);
+    assert_eq!(viewer.status().jump_target, None);
+
+    let at_309 = *viewer.snapshot();
+    viewer.handle(ReplayInputCommand::StepForward);
+    viewer.handle(ReplayInputCommand::StepBack);
+    assert_eq!(viewer.snapshot(), &at_309);
+
+    viewer.handle(ReplayInputCommand::Start);
+    assert_eq!(viewer.status().step, 0);
+    assert!(!viewer.handle(ReplayInputCommand::Quit));
+}
+
+#[test]
+fn title_and_annotation_follow_the_tape_metadata_and_position() {
+    assert_eq!(ReplayViewer::new(tape(10)).title(), None);
+
+    let mut annotated = tape(100);
+    annotated
+        .metadata_mut()
+        .set(META_DATE, "2026-10-18T09:00:00Z");
+    annotated.metadata_mut().set(META_PLAYER, "ada");
+    annotated.metadata_mut().set(META_MODE, "interactive");
+    annotated.annotate(40, "misdrop");
+    annotated.annotate(10, "opening");
+    let mut viewer = ReplayViewer::new(annotated);
+    assert_eq!(
+        viewer.title().as_deref(),
+        Some("ada · interactive · 2026-10-18T09:00:00Z")
+    );
+
+    assert_eq!(viewer.annotation(), None);
+    for (step, expected) in [
+        (10, Some("opening")),
+        (39, Some("opening")),
+        (40, Some("misdrop")),
+    ] {
+        for digit in step.to_string().bytes() {
+            viewer.handle(ReplayInputCommand::Digit(digit - b'0'));
+        }
+        viewer.handle(ReplayInputCommand::Jump);
+        assert_eq!(viewer.annotation().map(|note| note.text.as_str()), expected);
+    }
+    viewer.handle(ReplayInputCommand::Start);
+    assert_eq!(viewer.annotation(), None);
+}
@@ -9,8 +9,10 @@ use tokio::net::TcpStream;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::sync::{mpsc, oneshot};
 
+use tetris_adapter::adapter::game_loop::SessionProtocolDriver;
 use tetris_adapter::adapter::server::{ServerConfig, run_server};
 use tetris_adapter::adapter::{InboundCommand, OutboundMessage};
+use tetris_adapter_protocol::protocol::{HelloMessage, RequestedRole, create_hello};
 
 pub type ClientLines = tokio::io::Lines<BufReader<OwnedReadHalf>>;
 
@@ -99,3 +101,32 @@ pub async fn spawn_server(
 
     (server, address, command_rx, outbound_tx)
 }
+
+/// Apply every queued request to a session of `seed` through the production
+/// protocol driver, until the server drops its command sender.
+pub async fn driver_task(seed: u32, mut command_rx: mpsc::Receiver<InboundCommand>) {
+    let mut driver = SessionProtocolDriver::new(seed, 20);
+    while let Some(inbound) = command_rx.recv().await {
+        driver.handle(inbound);
+    }
+}
+
+/// Connect as `agent` requesting `role` and control events, without streamed
+/// observations, after `customize` adjusts the hello. Returns the client's
+/// lines, writer, and welcome.
+pub async fn agent_hello(
+    address: SocketAddr,
+    role: RequestedRole,
+    customize: impl FnOnce(&mut HelloMessage),
+) -> (ClientLines, OwnedWriteHalf, serde_json::Value) {
+    let (mut lines, mut writer) = connect(address).await;
+    let mut hello = create_hello(1, "agent", "3.0.0");
+    hello.requested.stream_observations = false;
+    hello.requested.role = Some(role);
+    hello.requested.control_events = true;
+    customize(&mut hello);
+    write_json_line(&mut writer, &hello).await;
+    let welcome = read_json_line(&mut lines).await;
+    assert_eq!(welcome["type"], "welcome", "{welcome}");
+    (lines, writer, welcome)
+}
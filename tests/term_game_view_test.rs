@@ -1,6 +1,8 @@
-use tetris_core::core::GameState;
-use tetris_core::types::PieceKind;
-use tetris_terminal::term::{AdapterStatusView, AnchorY, GameView, Viewport};
+use tetris_core::core::{ActiveSnapshot, GameState};
+use tetris_core::types::{PieceKind, Rotation};
+use tetris_terminal::term::{
+    AdapterStatusView, AnchorY, FrameBuffer, GameView, GameViewModel, ReplayStatusView, Viewport,
+};
 
 #[test]
 fn term_view_renders_border_corners() {
@@ -39,6 +41,33 @@ fn term_view_renders_locked_cell_as_two_chars_wide() {
     assert_eq!(fb.get(x0 + 1, y0).unwrap().ch, '█');
 }
 
+#[test]
+fn term_view_draws_hint_as_a_second_ghost_with_a_distinct_style() {
+    let mut snap = GameState::new(1).snapshot();
+    snap.active = None;
+    snap.ghost_y = None;
+    let hint = ActiveSnapshot {
+        kind: PieceKind::O,
+        rotation: Rotation::North,
+        x: 0,
+        y: 18,
+    };
+
+    let view = GameView::default();
+    let vp = Viewport::new(22, 22);
+    let mut fb = FrameBuffer::new(22, 22);
+    let model = GameViewModel::new(snap, None).with_hint(Some(hint));
+    view.render_model_into(&model, vp, &mut fb);
+
+    let hinted: Vec<_> = fb.cells().iter().filter(|cell| cell.ch == '▒').collect();
+    assert_eq!(hinted.len(), 4 * 2);
+    assert!(hinted.iter().all(|cell| !cell.style.dim));
+
+    // Without a hint the same frame carries no hint cells.
+    view.render_model_into(&GameViewModel::new(snap, None), vp, &mut fb);
+    assert!(fb.cells().iter().all(|cell| cell.ch != '▒'));
+}
+
 #[test]
 fn term_view_draws_side_panel_when_wide_enough() {
     let mut gs = GameState::new(1);
@@ -88,6 +117,40 @@ fn term_view_hides_ai_panel_without_adapter_status() {
     assert!(!all.contains("PID"));
 }
 
+#[test]
+fn term_view_draws_replay_progress_in_side_panel() {
+    let mut gs = GameState::new(1);
+    gs.start();
+    let replay = ReplayStatusView {
+        step: 250,
+        total_steps: 1000,
+        playing: false,
+        speed: (1, 2),
+        jump_target: Some(42),
+    };
+    let model = GameViewModel::new(gs.snapshot(), None).with_replay(Some(replay));
+    let mut fb = FrameBuffer::new(60, 30);
+    GameView::default().render_model_into(&model, Viewport::new(60, 30), &mut fb);
+
+    let lines: Vec<String> = (0..fb.height())
+        .map(|y| (0..fb.width()).map(|x| fb.get(x, y).unwrap().ch).collect())
+        .collect();
+    let all = lines.join("\n");
+    assert!(all.contains("REPLAY"));
+    assert!(all.contains("250/1000"));
+    assert!(all.contains("PAUSE x1/2"));
+    assert!(all.contains("GO 42"));
+    let line = lines.iter().find(|line| line.contains('[')).expect("bar");
+    let bar = &line[line.find('[').unwrap()..line.find(']').unwrap()];
+    let filled = bar.chars().filter(|&ch| ch == '█').count();
+    let empty = bar.chars().filter(|&ch| ch == '·').count();
+    assert_eq!(
+        filled,
+        (filled + empty) / 4,
+        "a quarter of the bar is filled"
+    );
+}
+
 #[test]
 fn term_view_centers_board_by_default_on_tall_viewports() {
     let state = GameState::new(1);
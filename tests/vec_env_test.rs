@@ -0,0 +1,93 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use tetris_session::engine::env::{ActionSpace, LinesReward, StepInfo, TetrisEnv};
+use tetris_session::engine::vec_env::{OBS_LEN, VecEnv, VecStepResult, write_observation};
+
+fn run_vec(threads: usize, steps: usize) -> (Vec<f32>, Vec<VecStepResult>) {
+    let seeds = [3, 5, 7, 11, 13];
+    let mut env =
+        VecEnv::with_max_episode_steps(&seeds, ActionSpace::Tick, threads, LinesReward, Some(40));
+    let mut obs = vec![0.0; seeds.len() * OBS_LEN];
+    let mut results = vec![VecStepResult::default(); seeds.len()];
+    let mut history = Vec::new();
+    env.reset(&mut obs);
+    for step in 0..steps {
+        let actions: Vec<usize> = (0..seeds.len()).map(|env| (step + env) % 8).collect();
+        env.step(&actions, &mut obs, &mut results);
+        history.extend_from_slice(&results);
+    }
+    (obs, history)
+}
+
+#[test]
+fn vec_env_matches_independent_envs_regardless_of_thread_count() {
+    let (obs_1, history_1) = run_vec(1, 120);
+    let (obs_4, history_4) = run_vec(4, 120);
+    assert_eq!(obs_1, obs_4);
+    assert_eq!(history_1, history_4);
+
+    // The first environment replays exactly like a standalone TetrisEnv.
+    let mut single = TetrisEnv::new(3, ActionSpace::Tick, LinesReward).with_max_episode_steps(40);
+    let mut expected = vec![0.0; OBS_LEN];
+    for step in 0..30 {
+        let result = single.step_index(step % 8);
+        assert_eq!(result.info, history_1[step * 5].info);
+        write_observation(result.observation, &mut expected);
+    }
+    let (obs_30, _) = run_vec(2, 30);
+    assert_eq!(&obs_30[..OBS_LEN], &expected[..]);
+}
+
+#[test]
+fn finished_episodes_auto_reset_with_an_advanced_seed() {
+    let seeds = [1, 2];
+    let mut env =
+        VecEnv::with_max_episode_steps(&seeds, ActionSpace::Tick, 2, LinesReward, Some(3));
+    assert_eq!(env.num_envs(), 2);
+    assert_eq!(env.num_threads(), 2);
+    let mut obs = vec![0.0; seeds.len() * OBS_LEN];
+    let mut results = vec![VecStepResult::default(); seeds.len()];
+    env.reset(&mut obs);
+    let first = obs.clone();
+    for _ in 0..3 {
+        env.step(&[0, 0], &mut obs, &mut results);
+    }
+    assert!(results.iter().all(|result| result.truncated));
+    assert_eq!(results[0].info.episode_step, 3);
+
+    // Env 0 now runs seed 1 + 2 = 3, env 1 runs seed 4.
+    let mut reset = vec![0.0; OBS_LEN];
+    write_observation(
+        TetrisEnv::new(3, ActionSpace::Tick, LinesReward).observation(),
+        &mut reset,
+    );
+    assert_eq!(&obs[..OBS_LEN], &reset[..]);
+    assert_ne!(obs, first);
+}
+
+#[test]
+fn a_panicking_worker_fails_the_step_instead_of_deadlocking() {
+    let reward = |info: &StepInfo, _terminated: bool| {
+        assert!(info.episode_step < 2, "reward exploded");
+        0.0
+    };
+    let mut env = VecEnv::new(&[1, 2, 3], ActionSpace::Tick, 3, reward);
+    let mut obs = vec![0.0; 3 * OBS_LEN];
+    let mut results = vec![VecStepResult::default(); 3];
+    env.step(&[0, 0, 0], &mut obs, &mut results);
+
+    let mut step = || {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            env.step(&[0, 0, 0], &mut obs, &mut results)
+        }))
+        .unwrap_err()
+    };
+    assert_eq!(step().downcast_ref::<&str>(), Some(&"reward exploded"));
+    let later = step();
+    assert!(
+        later
+            .downcast_ref::<&str>()
+            .unwrap()
+            .contains("panicked in an earlier step")
+    );
+}
@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use tetris_adapter::adapter::server::ServerConfig;
+use tetris_adapter_protocol::protocol::{RequestedRole, create_hello};
+use tui_tetris::wirelog::{
+    LatencyStats, RedriveServer, RedriveTarget, WirelogCommand, client_timelines, pair_replies,
+    parse_wire_log, parse_wirelog_args, redrive, reply_latencies, summary, timeline,
+};
+
+mod support;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+async fn read_until(lines: &mut support::ClientLines, kind: &str) -> serde_json::Value {
+    loop {
+        let message = support::read_json_line(lines).await;
+        if message["type"] == kind {
+            return message;
+        }
+    }
+}
+
+async fn spawn_game(config: ServerConfig) -> (tokio::task::JoinHandle<()>, std::net::SocketAddr) {
+    let (server, address, cmd_rx, out_tx) = support::spawn_server(config, 8).await;
+    tokio::spawn(async move {
+        let _out_tx = out_tx;
+        support::driver_task(1, cmd_rx).await;
+    });
+    (server, address)
+}
+
+const ENVELOPES: &str = r#"
+{"t_us":1000,"client":1,"dir":"in","msg":{"type":"hello","seq":1,"client":{"name":"bot-a"}}}
+{"t_us":1400,"client":1,"dir":"out","msg":{"type":"welcome","seq":1,"client_id":1,"role":"controller"}}
+{"t_us":2000,"client":2,"dir":"in","msg":{"type":"hello","seq":1,"client":{"name":"bot-b"}}}
+{"t_us":2100,"client":2,"dir":"out","msg":{"type":"welcome","seq":1,"client_id":2,"role":"observer"}}
+{"t_us":3000,"client":2,"dir":"in","msg":{"type":"command","seq":2,"actions":["hardDrop"]}}
+{"t_us":3050,"client":2,"dir":"out","msg":{"type":"error","seq":2,"code":"not_controller","message":"no"}}
+{"t_us":3100,"client":1,"dir":"in","msg":{"type":"command","seq":2,"actions":["hardDrop"]}}
+{"t_us":3300,"client":1,"dir":"out","msg":{"type":"observation","seq":3,"logical_step":4}}
+{"t_us":4100,"client":1,"dir":"out","msg":{"type":"ack","seq":4,"status":"ok","correlation_seq":2}}
+{"t_us":5000,"client":1,"dir":"in","msg":{"type":"control","seq":3,"action":"release"}}
+"#;
+
+#[test]
+fn envelopes_split_into_client_timelines_with_paired_replies() {
+    let entries = parse_wire_log(ENVELOPES).unwrap();
+    assert_eq!(entries.len(), 10);
+    assert_eq!(entries[0].line, 2);
+    let timelines = client_timelines(&entries);
+    assert_eq!(timelines.keys().copied().collect::<Vec<_>>(), [1, 2]);
+
+    let pairs = pair_replies(&timelines[&1]);
+    let outcomes: Vec<_> = pairs
+        .iter()
+        .map(|(request, reply)| (request.kind(), reply.map(|reply| reply.outcome())))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            ("hello", Some("welcome controller".to_string())),
+            ("command", Some("ack ok".to_string())),
+            ("control", None),
+        ]
+    );
+
+    let latencies = reply_latencies(&entries);
+    assert_eq!(latencies["hello"].count, 2);
+    assert_eq!(latencies["hello"].min_us, 100);
+    assert_eq!(latencies["hello"].max_us, 400);
+    assert_eq!(latencies["command"].count, 2);
+    assert_eq!(latencies["command"].p50_us, 50);
+    assert_eq!(latencies["command"].max_us, 1_000);
+
+    let report = summary(&entries);
+    assert!(report.starts_with("10 entries, 2 clients"), "{report}");
+    assert!(report.contains("client 1 (bot-a, controller)"), "{report}");
+    assert!(report.contains("client 2 (bot-b, observer)"), "{report}");
+    assert!(report.contains("errors: not_controller 1"), "{report}");
+    assert!(report.contains("unanswered requests: 1"), "{report}");
+
+    let client_two = timeline(&entries, Some(2), true);
+    assert_eq!(client_two.lines().count(), 4);
+    assert!(
+        client_two.contains("<- error seq 2 not_controller: no"),
+        "{client_two}"
+    );
+    assert!(!timeline(&entries, None, false).contains("observation"));
+}
+
+#[test]
+fn timeline_measures_from_the_earliest_timestamp() {
+    let entries = parse_wire_log(
+        r#"
+{"t_us":2000,"client":1,"dir":"out","msg":{"type":"welcome","seq":1,"client_id":1,"role":"controller"}}
+{"t_us":1500,"client":1,"dir":"in","msg":{"type":"hello","seq":1}}
+"#,
+    )
+    .unwrap();
+    let lines: Vec<_> = timeline(&entries, None, true)
+        .lines()
+        .map(str::trim_start)
+        .map(str::to_string)
+        .collect();
+    assert!(
+        lines[0].starts_with("0.500ms client 1 <- welcome"),
+        "{lines:?}"
+    );
+    assert!(
+        lines[1].starts_with("0.000ms client 1 -> hello"),
+        "{lines:?}"
+    );
+}
+
+#[test]
+fn bare_message_logs_still_parse() {
+    let entries = parse_wire_log(
+        "{\"type\":\"hello\",\"seq\":1}\n{\"type\":\"welcome\",\"seq\":1,\"role\":\"controller\"}\n",
+    )
+    .unwrap();
+    assert!(entries[0].inbound && !entries[1].inbound);
+    assert!(
+        entries
+            .iter()
+            .all(|entry| entry.client == 0 && entry.at_us.is_none())
+    );
+    assert!(reply_latencies(&entries).is_empty());
+    assert!(parse_wire_log("{\"msg\":{}}").is_err());
+    assert!(
+        parse_wire_log("not json")
+            .unwrap_err()
+            .starts_with("line 1:")
+    );
+}
+
+#[test]
+fn latency_percentiles_pick_from_sorted_samples() {
+    let stats = LatencyStats::from_samples((1..=100).rev().collect());
+    assert_eq!((stats.min_us, stats.max_us), (1, 100));
+    assert_eq!((stats.p50_us, stats.p90_us, stats.p99_us), (50, 90, 99));
+    assert_eq!(stats.mean_us, 50);
+    assert_eq!(LatencyStats::from_samples(Vec::new()).count, 0);
+}
+
+#[test]
+fn wirelog_subcommands_parse_their_options() {
+    assert_eq!(
+        parse_wirelog_args(&args("wirelog summary a.log")).unwrap(),
+        Some(WirelogCommand::Summary {
+            path: "a.log".into()
+        })
+    );
+    assert_eq!(
+        parse_wirelog_args(&args("wirelog timeline a.log --client 3 --no-observations")).unwrap(),
+        Some(WirelogCommand::Timeline {
+            path: "a.log".into(),
+            client: Some(3),
+            observations: false,
+        })
+    );
+    assert_eq!(
+        parse_wirelog_args(&args("wirelog redrive a.log --seed 9")).unwrap(),
+        Some(WirelogCommand::Redrive {
+            path: "a.log".into(),
+            target: RedriveTarget::Fresh { seed: Some(9) },
+            token: None,
+            timeout_ms: 2_000,
+        })
+    );
+    assert_eq!(
+        parse_wirelog_args(&args(
+            "wirelog redrive a.log --attach 127.0.0.1:9000 --token t"
+        ))
+        .unwrap(),
+        Some(WirelogCommand::Redrive {
+            path: "a.log".into(),
+            target: RedriveTarget::Attach {
+                address: "127.0.0.1:9000".into()
+            },
+            token: Some("t".into()),
+            timeout_ms: 2_000,
+        })
+    );
+    assert!(parse_wirelog_args(&args("wirelog redrive a.log --attach h:1 --seed 2")).is_err());
+    assert_eq!(
+        parse_wirelog_args(&args("replay verify a.ttr")).unwrap(),
+        None
+    );
+    assert!(parse_wirelog_args(&args("wirelog latency a.log extra")).is_err());
+    assert!(parse_wirelog_args(&args("wirelog tail a.log")).is_err());
+}
+
+#[tokio::test]
+async fn logged_sessions_redrive_against_a_fresh_server_with_matching_replies() {
+    let log_path =
+        std::env::temp_dir().join(format!("tui-tetris-wirelog-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&log_path);
+    let (logged_server, address) = spawn_game(ServerConfig {
+        log_path: Some(log_path.to_string_lossy().to_string()),
+        ..support::server_config()
+    })
+    .await;
+
+    let (mut controller_lines, mut controller) = support::connect(address).await;
+    let mut hello = create_hello(1, "wirelog-controller", "3.0.0");
+    hello.requested.role = Some(RequestedRole::Controller);
+    support::write_json_line(&mut controller, &hello).await;
+    read_until(&mut controller_lines, "welcome").await;
+
+    let (mut observer_lines, mut observer) = support::connect(address).await;
+    let mut hello = create_hello(1, "wirelog-observer", "3.0.0");
+    hello.requested.role = Some(RequestedRole::Observer);
+    support::write_json_line(&mut observer, &hello).await;
+    read_until(&mut observer_lines, "welcome").await;
+
+    let drop = serde_json::json!({
+        "type": "command", "seq": 2, "ts": 1, "mode": "action", "actions": ["hardDrop"]
+    });
+    support::write_json_line(&mut observer, &drop).await;
+    let error = read_until(&mut observer_lines, "error").await;
+    assert_eq!(error["code"], "not_controller");
+    support::write_json_line(&mut controller, &drop).await;
+    read_until(&mut controller_lines, "ack").await;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    let entries = loop {
+        let text = std::fs::read_to_string(&log_path).unwrap_or_default();
+        let entries = parse_wire_log(&text).unwrap();
+        if entries.iter().filter(|entry| entry.kind() == "ack").count() == 1 {
+            break entries;
+        }
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "wire log incomplete: {text}"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    logged_server.abort();
+    let _ = std::fs::remove_file(&log_path);
+
+    let report = summary(&entries);
+    assert!(
+        report.contains("(wirelog-controller, controller)"),
+        "{report}"
+    );
+    assert!(report.contains("(wirelog-observer, observer)"), "{report}");
+    assert!(report.contains("errors: not_controller 1"), "{report}");
+    assert_eq!(reply_latencies(&entries)["command"].count, 2);
+
+    let report = tokio::task::spawn_blocking(move || {
+        let fresh = RedriveServer::start(1)?;
+        redrive(
+            &entries,
+            |_| std::net::TcpStream::connect(fresh.addr()).map_err(|error| error.to_string()),
+            None,
+            Duration::from_secs(2),
+        )
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        report,
+        "redrove 4 requests from 2 clients; 4 of 4 logged replies match"
+    );
+}